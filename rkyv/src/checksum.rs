@@ -0,0 +1,170 @@
+//! Checksums for detecting corrupted archives.
+//!
+//! Validation only catches corruption that makes an archive structurally invalid, so a flipped bit
+//! in an integer or float goes unnoticed. Archives can be written with a
+//! [`ChecksumSerializer`](crate::ser::adapters::ChecksumSerializer), which appends a checksum of
+//! the archive in a trailer. The checksum is then verified with [`verify_checksum`] or
+//! [`check_archived_root_with_checksum`](crate::validation::check_archived_root_with_checksum)
+//! before the archive is used.
+//!
+//! Checksums are computed with a [`StreamingHasher`]. [`Crc32c`] is always available and uses the
+//! hardware-accelerated `crc32c` crate when the `crc32c` feature is enabled. [`Xxh3`] is available
+//! with the `xxh3` feature.
+
+use core::{convert::TryInto, fmt, hash::Hasher};
+
+/// The size of the checksum trailer at the end of an archive.
+///
+/// The trailer holds the result of [`Hasher::finish`] as a little-endian `u64`.
+pub const CHECKSUM_SIZE: usize = 8;
+
+/// A [`Hasher`] whose result only depends on the bytes written to it, and not on how they were
+/// split across calls to [`write`](Hasher::write).
+///
+/// Checksums are computed from many small writes while an archive is serialized, but verified with
+/// a single write of the whole archive, so only streaming hashers give matching results. Many
+/// general-purpose hashers, like `SeaHasher`, hash each write separately. Others, like the standard
+/// library's `DefaultHasher`, may change between releases and can't verify stored archives.
+pub trait StreamingHasher: Hasher {}
+
+/// A [`Hasher`] that computes the CRC-32C (Castagnoli) checksum of the bytes written to it.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Crc32c {
+    crc: u32,
+}
+
+#[cfg(not(feature = "crc32c"))]
+const CRC32C_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0x82f6_3b78
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+impl Hasher for Crc32c {
+    #[inline]
+    fn finish(&self) -> u64 {
+        self.crc as u64
+    }
+
+    #[cfg(feature = "crc32c")]
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) {
+        self.crc = crc32c::crc32c_append(self.crc, bytes);
+    }
+
+    #[cfg(not(feature = "crc32c"))]
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) {
+        let mut crc = !self.crc;
+        for &byte in bytes {
+            crc = CRC32C_TABLE[((crc ^ byte as u32) & 0xff) as usize] ^ (crc >> 8);
+        }
+        self.crc = !crc;
+    }
+}
+
+impl StreamingHasher for Crc32c {}
+
+/// A [`Hasher`] that computes the 64-bit XXH3 hash of the bytes written to it.
+#[cfg(feature = "xxh3")]
+pub use xxhash_rust::xxh3::Xxh3;
+
+#[cfg(feature = "xxh3")]
+impl StreamingHasher for Xxh3 {}
+
+/// An error that can occur while verifying the checksum of an archive.
+#[derive(Debug)]
+pub enum ChecksumError {
+    /// The buffer was too short to contain a checksum trailer
+    MissingTrailer {
+        /// The length of the buffer
+        len: usize,
+    },
+    /// The checksum of the archive did not match the checksum in the trailer
+    ChecksumMismatch {
+        /// The checksum stored in the trailer
+        expected: u64,
+        /// The checksum computed from the archive
+        actual: u64,
+    },
+}
+
+impl fmt::Display for ChecksumError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChecksumError::MissingTrailer { len } => write!(
+                f,
+                "buffer of length {} is too short to contain a checksum trailer",
+                len
+            ),
+            ChecksumError::ChecksumMismatch { expected, actual } => write!(
+                f,
+                "checksum mismatch: expected {:#018x} but computed {:#018x}",
+                expected, actual
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ChecksumError {}
+
+/// Verifies the checksum trailer at the end of the given buffer with a hasher of type `H`.
+///
+/// On success, returns the archive without its trailer. The archive can then be accessed with
+/// [`archived_root`](crate::archived_root) as usual.
+///
+/// ## Examples
+/// ```
+/// use rkyv::{
+///     archived_root,
+///     checksum::{verify_checksum, ChecksumError, Crc32c},
+///     ser::{adapters::ChecksumSerializer, serializers::AlignedSerializer, Serializer},
+///     AlignedVec,
+/// };
+///
+/// let mut serializer =
+///     ChecksumSerializer::<_, Crc32c>::new(AlignedSerializer::new(AlignedVec::new()));
+/// serializer.serialize_value(&42u32).unwrap();
+/// let mut buf = serializer.finish().unwrap().into_inner();
+///
+/// let archive = verify_checksum::<Crc32c>(buf.as_slice()).unwrap();
+/// assert_eq!(unsafe { *archived_root::<u32>(archive) }, 42);
+///
+/// buf[0] ^= 1;
+/// match verify_checksum::<Crc32c>(buf.as_slice()) {
+///     Err(ChecksumError::ChecksumMismatch { .. }) => (),
+///     result => panic!("expected checksum mismatch, got {:?}", result),
+/// }
+/// ```
+pub fn verify_checksum<H: StreamingHasher + Default>(buf: &[u8]) -> Result<&[u8], ChecksumError> {
+    if buf.len() < CHECKSUM_SIZE {
+        return Err(ChecksumError::MissingTrailer { len: buf.len() });
+    }
+
+    let (archive, trailer) = buf.split_at(buf.len() - CHECKSUM_SIZE);
+    let expected = u64::from_le_bytes(trailer.try_into().unwrap());
+    let mut hasher = H::default();
+    hasher.write(archive);
+    let actual = hasher.finish();
+
+    if actual == expected {
+        Ok(archive)
+    } else {
+        Err(ChecksumError::ChecksumMismatch { expected, actual })
+    }
+}
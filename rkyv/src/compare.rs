@@ -0,0 +1,92 @@
+//! Lookups in hash maps with either archived or unarchived keys.
+//!
+//! `#[archive(compare(Hash))]` implements [`ArchiveKey`] for a type and its archived counterpart,
+//! and implements `Borrow<dyn ArchiveKey<T>>` for both of them. Maps keyed by either type can then
+//! be searched with the other:
+//!
+//! ```
+//! use rkyv::{
+//!     archived_root,
+//!     compare::ArchiveKey,
+//!     ser::{serializers::AlignedSerializer, Serializer},
+//!     AlignedVec, Archive, Serialize,
+//! };
+//! use std::collections::HashMap;
+//!
+//! #[derive(Archive, Serialize, Hash, PartialEq, Eq)]
+//! #[archive(compare(PartialEq, Eq, Hash))]
+//! struct Key {
+//!     name: String,
+//!     id: u32,
+//! }
+//!
+//! let key = Key {
+//!     name: "hello".to_string(),
+//!     id: 42,
+//! };
+//! let mut serializer = AlignedSerializer::new(AlignedVec::new());
+//! serializer.serialize_value(&key).unwrap();
+//! let buf = serializer.into_inner();
+//! let archived = unsafe { archived_root::<Key>(buf.as_ref()) };
+//!
+//! let mut map = HashMap::new();
+//! map.insert(key, "world");
+//! assert_eq!(map.get(archived as &dyn ArchiveKey<Key>), Some(&"world"));
+//! ```
+
+use crate::{Archive, Archived};
+use core::hash::{Hash, Hasher};
+
+/// A reference to a value or to its archived counterpart.
+pub enum KeyRef<'a, T: Archive> {
+    /// An unarchived value
+    Unarchived(&'a T),
+    /// An archived value
+    Archived(&'a Archived<T>),
+}
+
+/// A type that can look up entries in hash maps keyed by `T` or `Archived<T>`.
+///
+/// `dyn ArchiveKey<T>` hashes values the same way whether they're archived or not, and compares
+/// them with the `PartialEq` implementations between `T` and `Archived<T>`.
+pub trait ArchiveKey<T: Archive> {
+    /// Gets the value to look up.
+    fn key(&self) -> KeyRef<'_, T>;
+}
+
+impl<T> Hash for dyn ArchiveKey<T> + '_
+where
+    T: Archive + Hash,
+    Archived<T>: Hash,
+{
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self.key() {
+            KeyRef::Unarchived(value) => value.hash(state),
+            KeyRef::Archived(value) => value.hash(state),
+        }
+    }
+}
+
+impl<T> PartialEq for dyn ArchiveKey<T> + '_
+where
+    T: Archive + PartialEq,
+    Archived<T>: PartialEq + PartialEq<T>,
+{
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        match (self.key(), other.key()) {
+            (KeyRef::Unarchived(a), KeyRef::Unarchived(b)) => a == b,
+            (KeyRef::Unarchived(a), KeyRef::Archived(b)) => b == a,
+            (KeyRef::Archived(a), KeyRef::Unarchived(b)) => a == b,
+            (KeyRef::Archived(a), KeyRef::Archived(b)) => a == b,
+        }
+    }
+}
+
+impl<T> Eq for dyn ArchiveKey<T> + '_
+where
+    T: Archive + Eq,
+    Archived<T>: Eq + PartialEq<T>,
+{
+}
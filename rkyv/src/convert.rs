@@ -0,0 +1,171 @@
+//! Building archives from serde input.
+//!
+//! These functions drive rkyv serialization from any serde [`Deserializer`], so data authored in
+//! a format like JSON can be archived without writing a conversion for every type. The types only
+//! need to implement both `serde::Deserialize` and rkyv's [`Serialize`].
+//!
+//! Resolving an archived value needs both its resolver and the native value, so each value is
+//! fully deserialized into its native type before it's serialized. [`serialize_from`] buffers the
+//! whole input this way. [`write_frames_from`] streams a top-level sequence instead, so only one
+//! element has to be held in memory at a time.
+
+use crate::{
+    frame::{ArchiveFrameWriter, FrameError, FrameSerializer, FrameSerializerError},
+    ser::Serializer,
+    Serialize,
+};
+use core::{fmt, marker::PhantomData};
+use serde::de::{self, Deserialize, Deserializer, SeqAccess, Visitor};
+use std::{error::Error, io::Write};
+
+/// An error that can occur while converting serde input into an archive.
+#[derive(Debug)]
+pub enum ConvertError<D, S> {
+    /// The input failed to deserialize
+    Deserialize(D),
+    /// The deserialized value failed to serialize
+    Serialize(S),
+}
+
+impl<D: fmt::Display, S: fmt::Display> fmt::Display for ConvertError<D, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConvertError::Deserialize(e) => write!(f, "failed to deserialize input: {}", e),
+            ConvertError::Serialize(e) => write!(f, "failed to serialize value: {}", e),
+        }
+    }
+}
+
+impl<D: Error + 'static, S: Error + 'static> Error for ConvertError<D, S> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ConvertError::Deserialize(e) => Some(e as &dyn Error),
+            ConvertError::Serialize(e) => Some(e as &dyn Error),
+        }
+    }
+}
+
+/// Deserializes a `T` from the given serde deserializer and serializes it with the given
+/// serializer, returning the position of the serialized value.
+///
+/// This doesn't stream: the whole value is deserialized into memory before any of it is
+/// serialized, so converting an input takes about as much memory as holding its native value. Use
+/// [`write_frames_from`] to convert a large top-level sequence one element at a time.
+///
+/// # Examples
+/// ```
+/// use rkyv::{
+///     archived_root,
+///     convert::serialize_from,
+///     ser::{serializers::AllocSerializer, Serializer},
+/// };
+/// use std::collections::HashMap;
+///
+/// let json = r#"{ "sword": [1, 2], "shield": [3] }"#;
+///
+/// let mut serializer = AllocSerializer::<256>::default();
+/// serialize_from::<HashMap<String, Vec<u32>>, _, _>(
+///     &mut serde_json::Deserializer::from_str(json),
+///     &mut serializer,
+/// )
+/// .unwrap();
+/// let buf = serializer.into_serializer().into_inner();
+///
+/// let archived = unsafe { archived_root::<HashMap<String, Vec<u32>>>(buf.as_ref()) };
+/// assert_eq!(archived.get("sword").unwrap().as_slice(), &[1, 2]);
+/// assert_eq!(archived.get("shield").unwrap().as_slice(), &[3]);
+/// ```
+pub fn serialize_from<'de, T, D, S>(
+    deserializer: D,
+    serializer: &mut S,
+) -> Result<usize, ConvertError<D::Error, S::Error>>
+where
+    T: Deserialize<'de> + Serialize<S>,
+    D: Deserializer<'de>,
+    S: Serializer + ?Sized,
+{
+    let value = T::deserialize(deserializer).map_err(ConvertError::Deserialize)?;
+    serializer
+        .serialize_value(&value)
+        .map_err(ConvertError::Serialize)
+}
+
+struct FrameVisitor<'a, T, W> {
+    writer: &'a mut ArchiveFrameWriter<W>,
+    error: &'a mut Option<FrameError<FrameSerializerError>>,
+    _phantom: PhantomData<T>,
+}
+
+impl<'de, T, W> Visitor<'de> for FrameVisitor<'_, T, W>
+where
+    T: Deserialize<'de> + Serialize<FrameSerializer>,
+    W: Write,
+{
+    type Value = usize;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "a sequence")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<usize, A::Error> {
+        let mut count = 0;
+        while let Some(value) = seq.next_element::<T>()? {
+            if let Err(e) = self.writer.write(&value) {
+                // Keep the frame error so it can be returned instead of the deserializer error
+                *self.error = Some(e);
+                return Err(de::Error::custom("failed to write frame"));
+            }
+            count += 1;
+        }
+        Ok(count)
+    }
+}
+
+/// Deserializes a sequence of `T` from the given serde deserializer and writes each element to
+/// the frame writer as its own frame, returning the number of frames written.
+///
+/// Each element is written as soon as it's deserialized, so a large sequence can be converted
+/// without holding all of it in memory at once.
+///
+/// # Examples
+/// ```
+/// use rkyv::{
+///     convert::write_frames_from,
+///     frame::{ArchiveFrameReader, ArchiveFrameWriter},
+/// };
+///
+/// let json = r#"["first", "second", "third"]"#;
+///
+/// let mut writer = ArchiveFrameWriter::new(Vec::new());
+/// let count = write_frames_from::<String, _, _>(
+///     &mut serde_json::Deserializer::from_str(json),
+///     &mut writer,
+/// )
+/// .unwrap();
+/// assert_eq!(count, 3);
+///
+/// let stream = writer.into_inner();
+/// let mut reader = ArchiveFrameReader::new(stream.as_slice());
+/// let first = unsafe { reader.read_unchecked::<String>() }.unwrap().unwrap();
+/// assert_eq!(first.as_str(), "first");
+/// ```
+pub fn write_frames_from<'de, T, D, W>(
+    deserializer: D,
+    writer: &mut ArchiveFrameWriter<W>,
+) -> Result<usize, ConvertError<D::Error, FrameError<FrameSerializerError>>>
+where
+    T: Deserialize<'de> + Serialize<FrameSerializer>,
+    D: Deserializer<'de>,
+    W: Write,
+{
+    let mut error = None;
+    let result = deserializer.deserialize_seq(FrameVisitor::<T, W> {
+        writer,
+        error: &mut error,
+        _phantom: PhantomData,
+    });
+    match (result, error) {
+        (_, Some(e)) => Err(ConvertError::Serialize(e)),
+        (result, None) => result.map_err(ConvertError::Deserialize),
+    }
+}
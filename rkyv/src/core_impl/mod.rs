@@ -3,11 +3,11 @@
 use crate::{
     de::Deserializer, offset_of, project_struct, ser::Serializer, Archive, ArchiveCopy,
     ArchivePointee, ArchiveUnsized, Archived, ArchivedIsize, ArchivedMetadata, ArchivedUsize,
-    Deserialize, DeserializeUnsized, Fallible, Serialize, SerializeUnsized,
+    CopyOptimization, Deserialize, DeserializeInto, DeserializeUnsized, Fallible, Serialize,
+    SerializeUnsized,
 };
-
-#[cfg(all(not(feature = "std"), feature = "specialization"))]
-use ::alloc::vec::Vec;
+#[cfg(any(feature = "std", feature = "specialization"))]
+use crate::{de::PathSegment, ScratchVec};
 
 #[cfg(rkyv_atomic)]
 use core::sync::atomic::{
@@ -29,6 +29,8 @@ use core::{
 use ptr_meta::Pointee;
 
 pub mod range;
+#[cfg(feature = "serde")]
+mod serde;
 pub mod time;
 
 impl<T> ArchivePointee for T {
@@ -56,7 +58,7 @@ impl<T: Archive> ArchiveUnsized for T {
 impl<T: Serialize<S>, S: Serializer + ?Sized> SerializeUnsized<S> for T {
     #[inline]
     fn serialize_unsized(&self, serializer: &mut S) -> Result<usize, S::Error> {
-        serializer.serialize_value(self)
+        serializer.nested(1, |serializer| serializer.serialize_value(self))
     }
 
     #[inline]
@@ -71,10 +73,12 @@ where
 {
     #[inline]
     unsafe fn deserialize_unsized(&self, deserializer: &mut D) -> Result<*mut (), D::Error> {
-        let ptr = deserializer.alloc(alloc::Layout::new::<T>())?.cast::<T>();
-        let deserialized = self.deserialize(deserializer)?;
-        ptr.write(deserialized);
-        Ok(ptr.cast())
+        deserializer.nested(1, |deserializer| {
+            let ptr = deserializer.alloc(alloc::Layout::new::<T>())?.cast::<T>();
+            let deserialized = self.deserialize(deserializer)?;
+            ptr.write(deserialized);
+            Ok(ptr.cast())
+        })
     }
 
     #[inline]
@@ -107,8 +111,18 @@ impl<T: ?Sized, D: Fallible + ?Sized> Deserialize<PhantomData<T>, D> for Phantom
     }
 }
 
+impl<T: ?Sized, D: Fallible + ?Sized> DeserializeInto<PhantomData<T>, D> for PhantomData<T> {
+    #[inline]
+    fn deserialize_into(&self, _: &mut PhantomData<T>, _: &mut D) -> Result<(), D::Error> {
+        Ok(())
+    }
+}
+
 macro_rules! impl_primitive {
     ($type:ty) => {
+        impl_primitive!($type, CopyOptimization::disable());
+    };
+    ($type:ty, $copy_optimization:expr) => {
         impl Archive for $type
         where
             $type: Copy,
@@ -116,6 +130,8 @@ macro_rules! impl_primitive {
             type Archived = Self;
             type Resolver = ();
 
+            const COPY_OPTIMIZATION: CopyOptimization<Self> = $copy_optimization;
+
             #[inline]
             fn resolve(&self, _: usize, _: Self::Resolver, out: &mut MaybeUninit<Self::Archived>) {
                 unsafe {
@@ -142,23 +158,34 @@ macro_rules! impl_primitive {
                 Ok(*self)
             }
         }
+
+        impl<D: Fallible + ?Sized> DeserializeInto<$type, D> for $type
+        where
+            $type: Copy,
+        {
+            #[inline]
+            fn deserialize_into(&self, out: &mut $type, _: &mut D) -> Result<(), D::Error> {
+                *out = *self;
+                Ok(())
+            }
+        }
     };
 }
 
-impl_primitive!(());
+impl_primitive!((), unsafe { CopyOptimization::enable() });
 impl_primitive!(bool);
-impl_primitive!(i8);
-impl_primitive!(i16);
-impl_primitive!(i32);
-impl_primitive!(i64);
-impl_primitive!(i128);
-impl_primitive!(u8);
-impl_primitive!(u16);
-impl_primitive!(u32);
-impl_primitive!(u64);
-impl_primitive!(u128);
-impl_primitive!(f32);
-impl_primitive!(f64);
+impl_primitive!(i8, unsafe { CopyOptimization::enable() });
+impl_primitive!(i16, unsafe { CopyOptimization::enable() });
+impl_primitive!(i32, unsafe { CopyOptimization::enable() });
+impl_primitive!(i64, unsafe { CopyOptimization::enable() });
+impl_primitive!(i128, unsafe { CopyOptimization::enable() });
+impl_primitive!(u8, unsafe { CopyOptimization::enable() });
+impl_primitive!(u16, unsafe { CopyOptimization::enable() });
+impl_primitive!(u32, unsafe { CopyOptimization::enable() });
+impl_primitive!(u64, unsafe { CopyOptimization::enable() });
+impl_primitive!(u128, unsafe { CopyOptimization::enable() });
+impl_primitive!(f32, unsafe { CopyOptimization::enable() });
+impl_primitive!(f64, unsafe { CopyOptimization::enable() });
 impl_primitive!(char);
 impl_primitive!(NonZeroI8);
 impl_primitive!(NonZeroI16);
@@ -197,6 +224,14 @@ impl<D: Fallible + ?Sized> Deserialize<usize, D> for ArchivedUsize {
     }
 }
 
+impl<D: Fallible + ?Sized> DeserializeInto<usize, D> for ArchivedUsize {
+    #[inline]
+    fn deserialize_into(&self, out: &mut usize, _: &mut D) -> Result<(), D::Error> {
+        *out = *self as usize;
+        Ok(())
+    }
+}
+
 impl Archive for isize {
     type Archived = ArchivedIsize;
     type Resolver = ();
@@ -223,6 +258,14 @@ impl<D: Fallible + ?Sized> Deserialize<isize, D> for ArchivedIsize {
     }
 }
 
+impl<D: Fallible + ?Sized> DeserializeInto<isize, D> for ArchivedIsize {
+    #[inline]
+    fn deserialize_into(&self, out: &mut isize, _: &mut D) -> Result<(), D::Error> {
+        *out = *self as isize;
+        Ok(())
+    }
+}
+
 /// The resolver for atomic types.
 pub struct AtomicResolver;
 
@@ -260,6 +303,14 @@ macro_rules! impl_atomic {
                 Ok(<$type>::new(self.load(atomic::Ordering::Relaxed)))
             }
         }
+
+        impl<D: Fallible + ?Sized> DeserializeInto<$type, D> for $type {
+            #[inline]
+            fn deserialize_into(&self, out: &mut $type, _: &mut D) -> Result<(), D::Error> {
+                *out.get_mut() = self.load(atomic::Ordering::Relaxed);
+                Ok(())
+            }
+        }
     };
 }
 
@@ -329,6 +380,19 @@ macro_rules! impl_tuple {
             }
         }
 
+        impl<D: Fallible + ?Sized, $($type: Archive),+> DeserializeInto<($($type,)+), D> for ($($type::Archived,)+)
+        where
+            $($type::Archived: DeserializeInto<$type, D>,)+
+        {
+            #[inline]
+            fn deserialize_into(&self, out: &mut ($($type,)+), deserializer: &mut D) -> Result<(), D::Error> {
+                let rev = ($(&self.$index,)+);
+                let out_rev = ($(&mut out.$index,)+);
+                $(rev.$index.deserialize_into(out_rev.$index, deserializer)?;)+
+                Ok(())
+            }
+        }
+
         peel_tuple! { $($type $index,)+ }
     };
 }
@@ -397,6 +461,19 @@ macro_rules! impl_array {
             }
         }
 
+        impl<T: Archive, D: Fallible + ?Sized> DeserializeInto<[T; $len], D> for [T::Archived; $len]
+        where
+            T::Archived: DeserializeInto<T, D>,
+        {
+            #[inline]
+            fn deserialize_into(&self, out: &mut [T; $len], deserializer: &mut D) -> Result<(), D::Error> {
+                for (value, out) in self.iter().zip(out.iter_mut()) {
+                    value.deserialize_into(out, deserializer)?;
+                }
+                Ok(())
+            }
+        }
+
         impl_array! { $($rest,)* }
     };
 }
@@ -462,6 +539,21 @@ where
     }
 }
 
+#[cfg(feature = "const_generics")]
+impl<T: Archive, D: Fallible + ?Sized, const N: usize> DeserializeInto<[T; N], D>
+    for [T::Archived; N]
+where
+    T::Archived: DeserializeInto<T, D>,
+{
+    #[inline]
+    fn deserialize_into(&self, out: &mut [T; N], deserializer: &mut D) -> Result<(), D::Error> {
+        for (value, out) in self.iter().zip(out.iter_mut()) {
+            value.deserialize_into(out, deserializer)?;
+        }
+        Ok(())
+    }
+}
+
 impl<T: Archive> ArchiveUnsized for [T] {
     type Archived = [T::Archived];
 
@@ -491,22 +583,44 @@ impl<T> ArchivePointee for [T] {
 }
 
 #[cfg(any(not(feature = "std"), feature = "specialization"))]
+#[inline]
+fn serialize_copy_slice<T: ArchiveCopy, S: Serializer + ?Sized>(
+    slice: &[T],
+    serializer: &mut S,
+) -> Result<usize, S::Error> {
+    if slice.is_empty() || core::mem::size_of::<T>() == 0 {
+        Ok(0)
+    } else {
+        unsafe {
+            let bytes = core::slice::from_raw_parts(
+                slice.as_ptr().cast::<u8>(),
+                core::mem::size_of_val(slice),
+            );
+            serializer.nested(slice.len(), |serializer| {
+                serializer.write_plain_bytes(bytes, core::mem::align_of::<T>())
+            })
+        }
+    }
+}
+
+#[cfg(all(not(feature = "std"), not(feature = "specialization")))]
 impl<T: ArchiveCopy + Serialize<S>, S: Serializer + ?Sized> SerializeUnsized<S> for [T] {
     #[inline]
     fn serialize_unsized(&self, serializer: &mut S) -> Result<usize, S::Error> {
-        if self.is_empty() || core::mem::size_of::<T::Archived>() == 0 {
-            Ok(0)
-        } else {
-            unsafe {
-                let bytes = core::slice::from_raw_parts(
-                    (self.as_ptr() as *const T).cast::<u8>(),
-                    self.len() * core::mem::size_of::<T>(),
-                );
-                let result = serializer.align_for::<T>()?;
-                serializer.write(bytes)?;
-                Ok(result)
-            }
-        }
+        serialize_copy_slice(self, serializer)
+    }
+
+    #[inline]
+    fn serialize_metadata(&self, _: &mut S) -> Result<Self::MetadataResolver, S::Error> {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "specialization")]
+impl<T: ArchiveCopy + Serialize<S>, S: Serializer + ?Sized> SerializeUnsized<S> for [T] {
+    #[inline]
+    fn serialize_unsized(&self, serializer: &mut S) -> Result<usize, S::Error> {
+        serialize_copy_slice(self, serializer)
     }
 
     #[inline]
@@ -522,18 +636,27 @@ impl<T: Serialize<S>, S: Serializer + ?Sized> SerializeUnsized<S> for [T] {
         fn serialize_unsized(&self, serializer: &mut S) -> Result<usize, S::Error> {
             if self.is_empty() || core::mem::size_of::<T::Archived>() == 0 {
                 Ok(0)
+            } else if T::COPY_OPTIMIZATION.is_enabled() {
+                serializer.nested(self.len(), |serializer| unsafe {
+                    let bytes = core::slice::from_raw_parts(
+                        self.as_ptr().cast::<u8>(),
+                        core::mem::size_of_val(self),
+                    );
+                    serializer.write_plain_bytes(bytes, core::mem::align_of::<T::Archived>())
+                })
             } else {
-                let mut resolvers = Vec::with_capacity(self.len());
-                for value in self {
-                    resolvers.push(value.serialize(serializer)?);
-                }
-                let result = serializer.align_for::<T::Archived>()?;
-                unsafe {
-                    for (i, resolver) in resolvers.drain(..).enumerate() {
-                        serializer.resolve_aligned(&self[i], resolver)?;
-                    }
-                }
-                Ok(result)
+                serializer.nested(self.len(), |serializer| unsafe {
+                    ScratchVec::with(serializer, self.len(), |serializer, resolvers| {
+                        for value in self {
+                            resolvers.push(value.serialize(serializer)?);
+                        }
+                        let result = serializer.align_for::<T::Archived>()?;
+                        for (value, resolver) in self.iter().zip(resolvers.drain()) {
+                            serializer.resolve_aligned(value, resolver)?;
+                        }
+                        Ok(result)
+                    })
+                })
             }
         }
     }
@@ -552,15 +675,18 @@ impl<T: Deserialize<T, D> + ArchiveCopy, D: Deserializer + ?Sized> DeserializeUn
 {
     #[inline]
     unsafe fn deserialize_unsized(&self, deserializer: &mut D) -> Result<*mut (), D::Error> {
-        if self.is_empty() || core::mem::size_of::<T>() == 0 {
-            Ok(ptr::NonNull::dangling().as_ptr())
-        } else {
-            let result = deserializer
-                .alloc(alloc::Layout::array::<T>(self.len()).unwrap())?
-                .cast::<T>();
-            ptr::copy_nonoverlapping(self.as_ptr(), result, self.len());
+        deserializer.nested(self.len(), |deserializer| {
+            let result = if self.is_empty() || core::mem::size_of::<T>() == 0 {
+                ptr::NonNull::dangling().as_ptr()
+            } else {
+                let result = deserializer
+                    .alloc(alloc::Layout::array::<T>(self.len()).unwrap())?
+                    .cast::<T>();
+                ptr::copy_nonoverlapping(self.as_ptr(), result, self.len());
+                result
+            };
             Ok(result.cast())
-        }
+        })
     }
 
     #[inline]
@@ -576,17 +702,23 @@ impl<T: Deserialize<U, D>, U: Archive<Archived = T>, D: Deserializer + ?Sized>
     #[inline]
     default! {
         unsafe fn deserialize_unsized(&self, deserializer: &mut D) -> Result<*mut (), D::Error> {
-            if self.is_empty() || core::mem::size_of::<U>() == 0 {
-                Ok(ptr::NonNull::dangling().as_ptr())
-            } else {
-                let result = deserializer
-                    .alloc(alloc::Layout::array::<U>(self.len()).unwrap())?
-                    .cast::<U>();
-                for (i, item) in self.iter().enumerate() {
-                    result.add(i).write(item.deserialize(deserializer)?);
-                }
+            deserializer.nested(self.len(), |deserializer| {
+                let result = if self.is_empty() || core::mem::size_of::<U>() == 0 {
+                    ptr::NonNull::dangling().as_ptr()
+                } else {
+                    let result = deserializer
+                        .alloc(alloc::Layout::array::<U>(self.len()).unwrap())?
+                        .cast::<U>();
+                    for (i, item) in self.iter().enumerate() {
+                        deserializer.enter_path(PathSegment::Index(i));
+                        let value = item.deserialize(deserializer);
+                        deserializer.exit_path();
+                        result.add(i).write(value?);
+                    }
+                    result
+                };
                 Ok(result.cast())
-            }
+            })
         }
     }
 
@@ -629,9 +761,9 @@ impl ArchivePointee for str {
 impl<S: Serializer + ?Sized> SerializeUnsized<S> for str {
     #[inline]
     fn serialize_unsized(&self, serializer: &mut S) -> Result<usize, S::Error> {
-        let result = serializer.pos();
-        serializer.write(self.as_bytes())?;
-        Ok(result)
+        serializer.nested(self.len(), |serializer| {
+            serializer.write_plain_bytes(self.as_bytes(), 1)
+        })
     }
 
     #[inline]
@@ -643,9 +775,11 @@ impl<S: Serializer + ?Sized> SerializeUnsized<S> for str {
 impl<D: Deserializer + ?Sized> DeserializeUnsized<str, D> for <str as ArchiveUnsized>::Archived {
     #[inline]
     unsafe fn deserialize_unsized(&self, deserializer: &mut D) -> Result<*mut (), D::Error> {
-        let bytes = deserializer.alloc(alloc::Layout::array::<u8>(self.len()).unwrap())?;
-        ptr::copy_nonoverlapping(self.as_ptr(), bytes, self.len());
-        Ok(bytes.cast())
+        deserializer.nested(self.len(), |deserializer| {
+            let bytes = deserializer.alloc(alloc::Layout::array::<u8>(self.len()).unwrap())?;
+            ptr::copy_nonoverlapping(self.as_ptr(), bytes, self.len());
+            Ok(bytes.cast())
+        })
     }
 
     #[inline]
@@ -794,6 +928,22 @@ where
     }
 }
 
+impl<T: Archive, D: Fallible + ?Sized> DeserializeInto<Option<T>, D> for Archived<Option<T>>
+where
+    T::Archived: DeserializeInto<T, D>,
+{
+    #[inline]
+    fn deserialize_into(&self, out: &mut Option<T>, deserializer: &mut D) -> Result<(), D::Error> {
+        match (self, out) {
+            (ArchivedOption::Some(value), Some(out)) => value.deserialize_into(out, deserializer),
+            (archived, out) => {
+                *out = archived.deserialize(deserializer)?;
+                Ok(())
+            }
+        }
+    }
+}
+
 impl<T: Eq> Eq for ArchivedOption<T> {}
 
 impl<T: Hash> Hash for ArchivedOption<T> {
@@ -1,6 +1,6 @@
 //! [`Archive`] implementations for ranges.
 
-use crate::{Archive, ArchiveCopy, Archived, Deserialize, Fallible, Serialize};
+use crate::{Archive, ArchiveCopy, Archived, Deserialize, DeserializeInto, Fallible, Serialize};
 use core::{
     cmp, fmt,
     mem::MaybeUninit,
@@ -31,6 +31,13 @@ impl<D: Fallible + ?Sized> Deserialize<RangeFull, D> for RangeFull {
     }
 }
 
+impl<D: Fallible + ?Sized> DeserializeInto<RangeFull, D> for RangeFull {
+    #[inline]
+    fn deserialize_into(&self, _: &mut RangeFull, _: &mut D) -> Result<(), D::Error> {
+        Ok(())
+    }
+}
+
 /// An archived [`Range`].
 #[derive(Clone, Default, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "validation", derive(bytecheck::CheckBytes))]
@@ -127,6 +134,17 @@ where
     }
 }
 
+impl<T: Archive, D: Fallible + ?Sized> DeserializeInto<Range<T>, D> for Archived<Range<T>>
+where
+    T::Archived: DeserializeInto<T, D>,
+{
+    #[inline]
+    fn deserialize_into(&self, out: &mut Range<T>, deserializer: &mut D) -> Result<(), D::Error> {
+        self.start.deserialize_into(&mut out.start, deserializer)?;
+        self.end.deserialize_into(&mut out.end, deserializer)
+    }
+}
+
 /// An archived [`RangeInclusive`].
 #[derive(Clone, Default, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "validation", derive(bytecheck::CheckBytes))]
@@ -224,3 +242,20 @@ where
         ))
     }
 }
+
+impl<T: Archive, D: Fallible + ?Sized> DeserializeInto<RangeInclusive<T>, D>
+    for Archived<RangeInclusive<T>>
+where
+    T::Archived: DeserializeInto<T, D>,
+{
+    #[inline]
+    fn deserialize_into(
+        &self,
+        out: &mut RangeInclusive<T>,
+        deserializer: &mut D,
+    ) -> Result<(), D::Error> {
+        // The bounds of an inclusive range can't be borrowed mutably
+        *out = self.deserialize(deserializer)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,39 @@
+//! [`Serialize`](serde::Serialize) implementations for archived core types.
+//!
+//! Each archived type serializes the same way that serde serializes its unarchived counterpart.
+
+use super::{
+    range::{ArchivedRange, ArchivedRangeInclusive},
+    time::ArchivedDuration,
+    ArchivedOption,
+};
+use core::time::Duration;
+use serde::{Serialize, Serializer};
+
+impl<T: Serialize> Serialize for ArchivedOption<T> {
+    #[inline]
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.as_ref().serialize(serializer)
+    }
+}
+
+impl<T: Serialize> Serialize for ArchivedRange<T> {
+    #[inline]
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (&self.start..&self.end).serialize(serializer)
+    }
+}
+
+impl<T: Serialize> Serialize for ArchivedRangeInclusive<T> {
+    #[inline]
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (&self.start..=&self.end).serialize(serializer)
+    }
+}
+
+impl Serialize for ArchivedDuration {
+    #[inline]
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Duration::new(self.as_secs(), self.subsec_nanos()).serialize(serializer)
+    }
+}
@@ -1,7 +1,9 @@
 //! [`Archive`] implementations for times.
 
-use crate::{offset_of, project_struct, Archive, Deserialize, Fallible, Serialize};
-use core::{mem::MaybeUninit, time::Duration};
+use crate::{
+    offset_of, project_struct, Archive, Deserialize, DeserializeInto, Fallible, Serialize,
+};
+use core::{fmt, mem::MaybeUninit, time::Duration};
 
 /// An archived [`Duration`](core::time::Duration).
 #[derive(Clone, Copy, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
@@ -96,6 +98,13 @@ impl ArchivedDuration {
     }
 }
 
+impl fmt::Debug for ArchivedDuration {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&Duration::new(self.secs, self.nanos), f)
+    }
+}
+
 impl Archive for Duration {
     type Archived = ArchivedDuration;
     type Resolver = ();
@@ -130,3 +139,11 @@ impl<D: Fallible + ?Sized> Deserialize<Duration, D> for ArchivedDuration {
         Ok(Duration::new(self.secs, self.nanos))
     }
 }
+
+impl<D: Fallible + ?Sized> DeserializeInto<Duration, D> for ArchivedDuration {
+    #[inline]
+    fn deserialize_into(&self, out: &mut Duration, _: &mut D) -> Result<(), D::Error> {
+        *out = Duration::new(self.secs, self.nanos);
+        Ok(())
+    }
+}
@@ -1,11 +1,15 @@
 //! Adapters wrap deserializers and add support for deserializer traits.
 
+#[cfg(feature = "arena")]
+use crate::de::ArenaDeserializer;
 use crate::{
-    de::{Deserializer, SharedDeserializer, SharedPointer},
+    de::{move_to_global, Deserializer, PathSegment, SharedDeserializer, SharedPointer},
     ArchiveUnsized, DeserializeUnsized, Fallible,
 };
-use core::alloc;
-use std::collections::HashMap;
+#[cfg(feature = "arena")]
+use bumpalo::Bump;
+use core::{alloc, fmt};
+use std::{collections::HashMap, error::Error};
 
 /// An adapter that adds shared deserialization support to a deserializer.
 pub struct SharedDeserializerAdapter<D> {
@@ -39,6 +43,49 @@ impl<D: Deserializer> Deserializer for SharedDeserializerAdapter<D> {
     unsafe fn alloc(&mut self, layout: alloc::Layout) -> Result<*mut u8, Self::Error> {
         self.inner.alloc(layout)
     }
+
+    #[inline]
+    unsafe fn dealloc(&mut self, ptr: *mut u8, layout: alloc::Layout) {
+        self.inner.dealloc(ptr, layout)
+    }
+
+    #[inline]
+    fn reserve(&mut self, layout: alloc::Layout) -> Result<(), Self::Error> {
+        self.inner.reserve(layout)
+    }
+
+    #[inline]
+    fn allocates_globally(&self) -> bool {
+        self.inner.allocates_globally()
+    }
+
+    #[inline]
+    fn enter_nested(&mut self, len: usize) -> Result<(), Self::Error> {
+        self.inner.enter_nested(len)
+    }
+
+    #[inline]
+    fn exit_nested(&mut self) {
+        self.inner.exit_nested()
+    }
+
+    #[inline]
+    fn enter_path(&mut self, segment: PathSegment) {
+        self.inner.enter_path(segment)
+    }
+
+    #[inline]
+    fn exit_path(&mut self) {
+        self.inner.exit_path()
+    }
+}
+
+#[cfg(feature = "arena")]
+unsafe impl<'a, D: ArenaDeserializer<'a>> ArenaDeserializer<'a> for SharedDeserializerAdapter<D> {
+    #[inline]
+    fn arena(&self) -> &'a Bump {
+        self.inner.arena()
+    }
 }
 
 impl<D: Deserializer> SharedDeserializer for SharedDeserializerAdapter<D> {
@@ -63,8 +110,11 @@ impl<D: Deserializer> SharedDeserializer for SharedDeserializerAdapter<D> {
                 metadata,
             ))
         } else {
-            let deserialized_data = unsafe { value.deserialize_unsized(self)? };
-            let shared_ptr = to_shared(ptr_meta::from_raw_parts_mut(deserialized_data, metadata));
+            let deserialized_data = unsafe {
+                let data_address = value.deserialize_unsized(self)?;
+                move_to_global(self, ptr_meta::from_raw_parts_mut(data_address, metadata))
+            };
+            let shared_ptr = to_shared(deserialized_data);
             let data_address = shared_ptr.data_address();
 
             self.shared_pointers
@@ -73,3 +123,488 @@ impl<D: Deserializer> SharedDeserializer for SharedDeserializerAdapter<D> {
         }
     }
 }
+
+/// The error type returned by a [`LimitedDeserializer`].
+#[derive(Debug)]
+pub enum LimitedDeserializerError<E> {
+    /// An error occurred while deserializing
+    Inner(E),
+    /// Deserializing would have allocated more memory than the memory budget allows
+    AllocLimitExceeded {
+        /// The maximum number of bytes that may be allocated
+        limit: usize,
+        /// The total number of bytes that would have been allocated
+        requested: usize,
+    },
+    /// Deserializing would have created more values behind pointers than the element budget allows
+    ElementLimitExceeded {
+        /// The maximum number of values that may be deserialized behind pointers
+        limit: usize,
+        /// The total number of values that would have been deserialized behind pointers
+        count: usize,
+    },
+    /// Values were nested more deeply than the depth limit
+    DepthLimitExceeded {
+        /// The maximum nesting depth
+        limit: usize,
+    },
+}
+
+impl<E: fmt::Display> fmt::Display for LimitedDeserializerError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LimitedDeserializerError::Inner(e) => write!(f, "{}", e),
+            LimitedDeserializerError::AllocLimitExceeded { limit, requested } => write!(
+                f,
+                "allocation limit exceeded: limit is {} bytes but {} bytes were requested",
+                limit, requested
+            ),
+            LimitedDeserializerError::ElementLimitExceeded { limit, count } => write!(
+                f,
+                "element limit exceeded: limit is {} elements but {} were requested",
+                limit, count
+            ),
+            LimitedDeserializerError::DepthLimitExceeded { limit } => {
+                write!(f, "nesting depth limit of {} exceeded", limit)
+            }
+        }
+    }
+}
+
+impl<E: Error + 'static> Error for LimitedDeserializerError<E> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LimitedDeserializerError::Inner(e) => Some(e as &dyn Error),
+            _ => None,
+        }
+    }
+}
+
+/// An adapter that limits the memory allocated and the number of values created while
+/// deserializing.
+///
+/// Validation guarantees that an archive is well-formed, but a small archive can still expand into
+/// a huge amount of memory when it's deserialized. For example, a slice of zero-sized types can
+/// have any length, and many pointers can share the same data. This adapter tracks the total number
+/// of bytes requested through [`alloc`](Deserializer::alloc) and returns an error once they exceed
+/// the memory budget. It can optionally also limit the total number of values behind pointers
+/// (slice elements, string bytes, and boxed values) and how deeply they can be nested.
+///
+/// Shared pointers can be deserialized by wrapping this adapter in a
+/// [`SharedDeserializerAdapter`]. Each shared value then only counts against the budgets once.
+///
+/// ## Examples
+/// ```
+/// use rkyv::{
+///     archived_root,
+///     de::{
+///         adapters::{LimitedDeserializer, LimitedDeserializerError, SharedDeserializerAdapter},
+///         deserializers::AllocDeserializer,
+///     },
+///     ser::{serializers::AllocSerializer, Serializer},
+///     Deserialize,
+/// };
+///
+/// let mut serializer = AllocSerializer::<256>::default();
+/// serializer.serialize_value(&vec![(); 1_000_000]).unwrap();
+/// let buf = serializer.into_serializer().into_inner();
+/// let archived = unsafe { archived_root::<Vec<()>>(buf.as_ref()) };
+///
+/// let mut deserializer = SharedDeserializerAdapter::new(
+///     LimitedDeserializer::new(AllocDeserializer, 1024).with_max_elements(1000),
+/// );
+/// let result: Result<Vec<()>, _> = archived.deserialize(&mut deserializer);
+/// match result {
+///     Err(LimitedDeserializerError::ElementLimitExceeded { limit: 1000, count: 1_000_000 }) => (),
+///     result => panic!("expected element limit error, got {:?}", result),
+/// }
+/// ```
+pub struct LimitedDeserializer<D> {
+    inner: D,
+    max_alloc: usize,
+    allocated: usize,
+    max_elements: Option<usize>,
+    elements: usize,
+    max_depth: Option<usize>,
+    depth: usize,
+}
+
+impl<D> LimitedDeserializer<D> {
+    /// Wraps the given deserializer and limits the memory it may allocate to `max_alloc` bytes.
+    #[inline]
+    pub fn new(inner: D, max_alloc: usize) -> Self {
+        Self {
+            inner,
+            max_alloc,
+            allocated: 0,
+            max_elements: None,
+            elements: 0,
+            max_depth: None,
+            depth: 0,
+        }
+    }
+
+    /// Limits the total number of values behind pointers that may be deserialized to
+    /// `max_elements`.
+    #[inline]
+    pub fn with_max_elements(mut self, max_elements: usize) -> Self {
+        self.max_elements = Some(max_elements);
+        self
+    }
+
+    /// Limits how many pointers deep values can be nested to `max_depth`.
+    #[inline]
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    /// Returns the total number of bytes allocated so far.
+    #[inline]
+    pub fn allocated(&self) -> usize {
+        self.allocated
+    }
+
+    /// Returns the total number of values behind pointers deserialized so far.
+    #[inline]
+    pub fn elements(&self) -> usize {
+        self.elements
+    }
+
+    /// Consumes the adapter and returns the underlying deserializer.
+    #[inline]
+    pub fn into_inner(self) -> D {
+        self.inner
+    }
+}
+
+impl<D: Fallible> LimitedDeserializer<D> {
+    /// Returns the total number of bytes allocated after allocating `layout`, if it's within the
+    /// memory budget.
+    #[inline]
+    fn charge(&self, layout: alloc::Layout) -> Result<usize, LimitedDeserializerError<D::Error>> {
+        let requested = self.allocated.saturating_add(layout.size());
+        if requested > self.max_alloc {
+            Err(LimitedDeserializerError::AllocLimitExceeded {
+                limit: self.max_alloc,
+                requested,
+            })
+        } else {
+            Ok(requested)
+        }
+    }
+}
+
+impl<D: Fallible> Fallible for LimitedDeserializer<D> {
+    type Error = LimitedDeserializerError<D::Error>;
+}
+
+impl<D: Deserializer> Deserializer for LimitedDeserializer<D> {
+    #[inline]
+    unsafe fn alloc(&mut self, layout: alloc::Layout) -> Result<*mut u8, Self::Error> {
+        let requested = self.charge(layout)?;
+        let result = self
+            .inner
+            .alloc(layout)
+            .map_err(LimitedDeserializerError::Inner)?;
+        self.allocated = requested;
+        Ok(result)
+    }
+
+    #[inline]
+    unsafe fn dealloc(&mut self, ptr: *mut u8, layout: alloc::Layout) {
+        self.inner.dealloc(ptr, layout)
+    }
+
+    #[inline]
+    fn reserve(&mut self, layout: alloc::Layout) -> Result<(), Self::Error> {
+        let requested = self.charge(layout)?;
+        self.inner
+            .reserve(layout)
+            .map_err(LimitedDeserializerError::Inner)?;
+        self.allocated = requested;
+        Ok(())
+    }
+
+    #[inline]
+    fn allocates_globally(&self) -> bool {
+        self.inner.allocates_globally()
+    }
+
+    #[inline]
+    fn enter_nested(&mut self, len: usize) -> Result<(), Self::Error> {
+        let count = self.elements.saturating_add(len);
+        if let Some(limit) = self.max_elements {
+            if count > limit {
+                return Err(LimitedDeserializerError::ElementLimitExceeded { limit, count });
+            }
+        }
+        if let Some(limit) = self.max_depth {
+            if self.depth >= limit {
+                return Err(LimitedDeserializerError::DepthLimitExceeded { limit });
+            }
+        }
+        self.inner
+            .enter_nested(len)
+            .map_err(LimitedDeserializerError::Inner)?;
+        self.elements = count;
+        self.depth += 1;
+        Ok(())
+    }
+
+    #[inline]
+    fn exit_nested(&mut self) {
+        self.depth -= 1;
+        self.inner.exit_nested()
+    }
+
+    #[inline]
+    fn enter_path(&mut self, segment: PathSegment) {
+        self.inner.enter_path(segment)
+    }
+
+    #[inline]
+    fn exit_path(&mut self) {
+        self.inner.exit_path()
+    }
+}
+
+#[cfg(feature = "arena")]
+unsafe impl<'a, D: ArenaDeserializer<'a>> ArenaDeserializer<'a> for LimitedDeserializer<D> {
+    #[inline]
+    fn arena(&self) -> &'a Bump {
+        self.inner.arena()
+    }
+}
+
+/// The path to the value that was being deserialized when an error occurred.
+///
+/// Paths are displayed like Rust expressions that access the value from the root, for example
+/// `Player.inventory[12].name`. Only the type of the root value is displayed, but the types of all
+/// of the values along the path are available from [`segments`](DeserializePath::segments).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DeserializePath {
+    segments: Vec<PathSegment>,
+}
+
+impl DeserializePath {
+    /// Returns the segments of the path, starting from the root.
+    #[inline]
+    pub fn segments(&self) -> &[PathSegment] {
+        &self.segments
+    }
+
+    /// Returns `true` if the path has no segments.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+}
+
+/// Writes path segments like a Rust expression that accesses the value from the root.
+pub(crate) fn write_path(segments: &[PathSegment], f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let mut is_start = true;
+    for segment in segments.iter() {
+        match segment {
+            PathSegment::Type(name) if is_start => write!(f, "{}", name)?,
+            PathSegment::Type(_) => continue,
+            PathSegment::Variant(name) if is_start => write!(f, "{}", name)?,
+            PathSegment::Variant(name) => write!(f, "::{}", name)?,
+            PathSegment::Field(name) if is_start => write!(f, "{}", name)?,
+            PathSegment::Field(name) => write!(f, ".{}", name)?,
+            PathSegment::Index(index) => write!(f, "[{}]", index)?,
+        }
+        is_start = false;
+    }
+    Ok(())
+}
+
+impl fmt::Display for DeserializePath {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_path(&self.segments, f)
+    }
+}
+
+/// The error type returned by a [`PathDeserializer`].
+#[derive(Debug)]
+pub struct PathDeserializerError<E> {
+    path: DeserializePath,
+    inner: E,
+}
+
+impl<E> PathDeserializerError<E> {
+    /// Returns the path to the value that was being deserialized when the error occurred.
+    #[inline]
+    pub fn path(&self) -> &DeserializePath {
+        &self.path
+    }
+
+    /// Returns a reference to the error that occurred.
+    #[inline]
+    pub fn inner(&self) -> &E {
+        &self.inner
+    }
+
+    /// Consumes the error and returns the error that occurred.
+    #[inline]
+    pub fn into_inner(self) -> E {
+        self.inner
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for PathDeserializerError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.inner)
+        } else {
+            write!(f, "{} at {}", self.inner, self.path)
+        }
+    }
+}
+
+impl<E: Error + 'static> Error for PathDeserializerError<E> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.inner as &dyn Error)
+    }
+}
+
+/// An adapter that records where errors occur while deserializing.
+///
+/// The adapter keeps a stack of the [path segments](PathSegment) reported to
+/// [`enter_path`](Deserializer::enter_path) and attaches them to any error returned by the
+/// underlying deserializer. Slices always report the index of each element, but structs and enums
+/// only report their type and field names if they derive `Deserialize` with
+/// `#[archive(track_path)]`.
+///
+/// Shared pointers can be deserialized by wrapping this adapter in a
+/// [`SharedDeserializerAdapter`].
+///
+/// ## Examples
+/// ```
+/// use rkyv::{
+///     archived_root,
+///     de::{
+///         adapters::{LimitedDeserializer, PathDeserializer},
+///         deserializers::AllocDeserializer,
+///     },
+///     ser::{serializers::AllocSerializer, Serializer},
+///     Archive,
+///     Deserialize,
+///     Serialize,
+/// };
+///
+/// #[derive(Archive, Serialize, Deserialize)]
+/// #[archive(track_path)]
+/// struct Item {
+///     name: String,
+/// }
+///
+/// #[derive(Archive, Serialize, Deserialize)]
+/// #[archive(track_path)]
+/// struct Player {
+///     inventory: Vec<Item>,
+/// }
+///
+/// let mut inventory = (0..12)
+///     .map(|_| Item { name: String::new() })
+///     .collect::<Vec<_>>();
+/// inventory.push(Item { name: "a very long name".repeat(100) });
+///
+/// let mut serializer = AllocSerializer::<256>::default();
+/// serializer.serialize_value(&Player { inventory }).unwrap();
+/// let buf = serializer.into_serializer().into_inner();
+/// let archived = unsafe { archived_root::<Player>(buf.as_ref()) };
+///
+/// let mut deserializer = PathDeserializer::new(LimitedDeserializer::new(AllocDeserializer, 1024));
+/// let result: Result<Player, _> = archived.deserialize(&mut deserializer);
+/// let error = result.err().unwrap();
+/// assert_eq!(error.path().to_string(), "Player.inventory[12].name");
+/// ```
+pub struct PathDeserializer<D> {
+    inner: D,
+    path: Vec<PathSegment>,
+}
+
+impl<D> PathDeserializer<D> {
+    /// Wraps the given deserializer and records the path to any errors it returns.
+    #[inline]
+    pub fn new(inner: D) -> Self {
+        Self {
+            inner,
+            path: Vec::new(),
+        }
+    }
+
+    /// Consumes the adapter and returns the underlying deserializer.
+    #[inline]
+    pub fn into_inner(self) -> D {
+        self.inner
+    }
+
+    #[inline]
+    fn error<E>(&self, inner: E) -> PathDeserializerError<E> {
+        PathDeserializerError {
+            path: DeserializePath {
+                segments: self.path.clone(),
+            },
+            inner,
+        }
+    }
+}
+
+impl<D: Fallible> Fallible for PathDeserializer<D> {
+    type Error = PathDeserializerError<D::Error>;
+}
+
+impl<D: Deserializer> Deserializer for PathDeserializer<D> {
+    #[inline]
+    unsafe fn alloc(&mut self, layout: alloc::Layout) -> Result<*mut u8, Self::Error> {
+        self.inner.alloc(layout).map_err(|e| self.error(e))
+    }
+
+    #[inline]
+    unsafe fn dealloc(&mut self, ptr: *mut u8, layout: alloc::Layout) {
+        self.inner.dealloc(ptr, layout)
+    }
+
+    #[inline]
+    fn reserve(&mut self, layout: alloc::Layout) -> Result<(), Self::Error> {
+        self.inner.reserve(layout).map_err(|e| self.error(e))
+    }
+
+    #[inline]
+    fn allocates_globally(&self) -> bool {
+        self.inner.allocates_globally()
+    }
+
+    #[inline]
+    fn enter_nested(&mut self, len: usize) -> Result<(), Self::Error> {
+        self.inner.enter_nested(len).map_err(|e| self.error(e))
+    }
+
+    #[inline]
+    fn exit_nested(&mut self) {
+        self.inner.exit_nested()
+    }
+
+    #[inline]
+    fn enter_path(&mut self, segment: PathSegment) {
+        self.path.push(segment);
+        self.inner.enter_path(segment)
+    }
+
+    #[inline]
+    fn exit_path(&mut self) {
+        self.path.pop();
+        self.inner.exit_path()
+    }
+}
+
+#[cfg(feature = "arena")]
+unsafe impl<'a, D: ArenaDeserializer<'a>> ArenaDeserializer<'a> for PathDeserializer<D> {
+    #[inline]
+    fn arena(&self) -> &'a Bump {
+        self.inner.arena()
+    }
+}
@@ -1,7 +1,11 @@
 //! Deserializers that can be used standalone and provide basic capabilities.
 
+#[cfg(feature = "arena")]
+use crate::de::ArenaDeserializer;
 #[cfg(feature = "std")]
 use crate::{de::Deserializer, Fallible, Unreachable};
+#[cfg(feature = "arena")]
+use bumpalo::Bump;
 #[cfg(feature = "std")]
 use core::alloc;
 
@@ -21,3 +25,95 @@ impl Deserializer for AllocDeserializer {
         Ok(std::alloc::alloc(layout))
     }
 }
+
+/// A deserializer that allocates from a [`Bump`] arena.
+///
+/// Deserializing large object graphs with [`AllocDeserializer`] calls the global allocator for
+/// every allocation. This deserializer carves allocations out of an arena instead, which is much
+/// faster. Arena-aware types like `bumpalo::boxed::Box` and `bumpalo::collections::Vec` keep
+/// their memory in the arena, so dropping a whole deserialized tree of them costs a single free
+/// when the arena is dropped. Other types like `Box` and `Vec` still work, but have their values
+/// moved into the global allocator after deserializing.
+///
+/// ## Examples
+/// ```
+/// use bumpalo::{boxed::Box, collections::{String, Vec}, Bump};
+/// use rkyv::{
+///     archived_root,
+///     de::deserializers::BumpDeserializer,
+///     ser::{serializers::AllocSerializer, Serializer},
+///     Archive,
+///     Deserialize,
+///     Serialize,
+/// };
+///
+/// #[derive(Archive, Serialize, Deserialize)]
+/// struct Monster<'a> {
+///     name: String<'a>,
+///     inventory: Vec<'a, String<'a>>,
+///     position: Box<'a, [f32; 3]>,
+/// }
+///
+/// let arena = Bump::new();
+/// let mut inventory = Vec::new_in(&arena);
+/// inventory.push(String::from_str_in("sword", &arena));
+/// inventory.push(String::from_str_in("shield", &arena));
+/// let value = Monster {
+///     name: String::from_str_in("orc", &arena),
+///     inventory,
+///     position: Box::new_in([1.0, 2.0, 3.0], &arena),
+/// };
+///
+/// let mut serializer = AllocSerializer::<256>::default();
+/// serializer.serialize_value(&value).unwrap();
+/// let buf = serializer.into_serializer().into_inner();
+/// let archived = unsafe { archived_root::<Monster>(buf.as_ref()) };
+///
+/// let deserialized_arena = Bump::new();
+/// let deserialized: Monster = archived
+///     .deserialize(&mut BumpDeserializer::new(&deserialized_arena))
+///     .unwrap();
+/// assert_eq!(deserialized.name, "orc");
+/// assert_eq!(deserialized.inventory, ["sword", "shield"]);
+/// assert_eq!(*deserialized.position, [1.0, 2.0, 3.0]);
+/// ```
+#[cfg(feature = "arena")]
+#[derive(Debug)]
+pub struct BumpDeserializer<'a> {
+    arena: &'a Bump,
+}
+
+#[cfg(feature = "arena")]
+impl<'a> BumpDeserializer<'a> {
+    /// Creates a new deserializer that allocates from the given arena.
+    #[inline]
+    pub fn new(arena: &'a Bump) -> Self {
+        Self { arena }
+    }
+}
+
+#[cfg(feature = "arena")]
+impl Fallible for BumpDeserializer<'_> {
+    type Error = Unreachable;
+}
+
+#[cfg(feature = "arena")]
+impl Deserializer for BumpDeserializer<'_> {
+    #[inline]
+    unsafe fn alloc(&mut self, layout: alloc::Layout) -> Result<*mut u8, Self::Error> {
+        Ok(self.arena.alloc_layout(layout).as_ptr())
+    }
+
+    #[inline]
+    fn allocates_globally(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(feature = "arena")]
+unsafe impl<'a> ArenaDeserializer<'a> for BumpDeserializer<'a> {
+    #[inline]
+    fn arena(&self) -> &'a Bump {
+        self.arena
+    }
+}
@@ -5,7 +5,13 @@ pub mod adapters;
 pub mod deserializers;
 
 use crate::{ArchiveUnsized, DeserializeUnsized, Fallible};
+#[cfg(feature = "arena")]
+use bumpalo::Bump;
 use core::alloc;
+#[cfg(feature = "std")]
+use core::ptr;
+#[cfg(feature = "std")]
+use ptr_meta::Pointee;
 
 /// A context that provides a memory allocator.
 ///
@@ -15,9 +21,165 @@ pub trait Deserializer: Fallible {
     ///
     /// # Safety
     ///
-    /// The caller must guarantee that the memory returned by this function is deallocated by the
-    /// global allocator.
+    /// If the deserializer [allocates globally](Deserializer::allocates_globally), the caller must
+    /// guarantee that the memory returned by this function is deallocated by the global allocator.
+    /// Otherwise, the caller must guarantee that the memory is never deallocated except through
+    /// [`dealloc`](Deserializer::dealloc).
     unsafe fn alloc(&mut self, layout: alloc::Layout) -> Result<*mut u8, Self::Error>;
+
+    /// Frees memory returned by [`alloc`](Deserializer::alloc) that was never handed out, like when
+    /// deserializing the values that were going to be placed in it failed.
+    ///
+    /// The default implementation frees the memory with the global allocator if the deserializer
+    /// [allocates globally](Deserializer::allocates_globally) and does nothing otherwise.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must have been returned by a call to [`alloc`](Deserializer::alloc) on this
+    /// deserializer with the same `layout`, and must not be used after this call.
+    #[inline]
+    unsafe fn dealloc(&mut self, ptr: *mut u8, layout: alloc::Layout) {
+        #[cfg(feature = "std")]
+        if self.allocates_globally() {
+            std::alloc::dealloc(ptr, layout);
+        }
+        #[cfg(not(feature = "std"))]
+        let _ = (ptr, layout);
+    }
+
+    /// Called by builtin types before they allocate memory for their values themselves instead of
+    /// with [`alloc`](Deserializer::alloc), like hash maps do.
+    ///
+    /// This lets deserializers that limit how much memory is allocated account for that memory too.
+    #[inline]
+    fn reserve(&mut self, layout: alloc::Layout) -> Result<(), Self::Error> {
+        let _ = layout;
+        Ok(())
+    }
+
+    /// Returns whether the memory returned by [`alloc`](Deserializer::alloc) is owned by the global
+    /// allocator.
+    ///
+    /// Deserializers that allocate from somewhere else, like an arena, must return `false`. Types
+    /// like `Box` that free their memory with the global allocator will then move their values into
+    /// globally-allocated memory after deserializing them.
+    #[inline]
+    fn allocates_globally(&self) -> bool {
+        true
+    }
+
+    /// Called by builtin types before they deserialize the values behind a pointer.
+    ///
+    /// `len` is the number of values that are pointed to: the length of slices, the number of
+    /// bytes in strings, and 1 for single values. Every successful call is matched by a call to
+    /// [`exit_nested`](Deserializer::exit_nested) after the values are deserialized, even if
+    /// deserializing them fails, which lets deserializers track and limit how many values are
+    /// deserialized and how deeply they're nested. [`nested`](Deserializer::nested) makes both
+    /// calls.
+    #[inline]
+    fn enter_nested(&mut self, len: usize) -> Result<(), Self::Error> {
+        let _ = len;
+        Ok(())
+    }
+
+    /// Called by builtin types after they deserialize the values behind a pointer.
+    #[inline]
+    fn exit_nested(&mut self) {}
+
+    /// Deserializes `len` values behind a pointer with `f`, calling
+    /// [`enter_nested`](Deserializer::enter_nested) before and
+    /// [`exit_nested`](Deserializer::exit_nested) after even if `f` fails.
+    #[inline]
+    fn nested<T, F>(&mut self, len: usize, f: F) -> Result<T, Self::Error>
+    where
+        F: FnOnce(&mut Self) -> Result<T, Self::Error>,
+    {
+        self.enter_nested(len)?;
+        let result = f(self);
+        self.exit_nested();
+        result
+    }
+
+    /// Called before deserializing a part of a value that has a location, like a field or an
+    /// element of a slice.
+    ///
+    /// Slices report the index of each element, and types that derive `Deserialize` with
+    /// `#[archive(track_path)]` report their type name, the variant for enums, and the name of
+    /// each field. Every call is matched by a call to [`exit_path`](Deserializer::exit_path), even
+    /// if the part fails to deserialize, so deserializers can keep a stack of segments to describe
+    /// where an error occurred.
+    #[inline]
+    fn enter_path(&mut self, segment: PathSegment) {
+        let _ = segment;
+    }
+
+    /// Called after deserializing a part of a value that has a location.
+    #[inline]
+    fn exit_path(&mut self) {}
+}
+
+/// A segment of the path to a value that is being deserialized or checked.
+///
+/// See [`Deserializer::enter_path`] for more information.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PathSegment {
+    /// A value of the named type
+    Type(&'static str),
+    /// The named variant of an enum
+    Variant(&'static str),
+    /// The named field of a struct or enum variant, tuple fields are named by their index
+    Field(&'static str),
+    /// The element at the given index of a slice
+    Index(usize),
+}
+
+/// A context that allocates from a bump arena.
+///
+/// Arena-aware types like `bumpalo::boxed::Box`, `bumpalo::collections::Vec`, and
+/// `bumpalo::collections::String` require this kind of context to deserialize. Since they never
+/// free their memory, dropping a deserialized tree of them only runs destructors and the memory is
+/// released all at once when the arena is dropped or reset.
+///
+/// # Safety
+///
+/// The memory returned by [`alloc`](Deserializer::alloc) must be allocated from the arena returned
+/// by [`arena`](ArenaDeserializer::arena), and [`allocates_globally`](Deserializer::allocates_globally)
+/// must return `false`.
+#[cfg(feature = "arena")]
+pub unsafe trait ArenaDeserializer<'a>: Deserializer {
+    /// Returns the arena that this deserializer allocates from.
+    fn arena(&self) -> &'a Bump;
+}
+
+/// Moves a value allocated by the given deserializer into memory owned by the global allocator.
+///
+/// If the deserializer already [allocates globally](Deserializer::allocates_globally), the pointer
+/// is returned unchanged.
+///
+/// # Safety
+///
+/// `ptr` must point to an initialized value that was allocated by `deserializer`. If the value is
+/// moved, it must not be used or dropped through `ptr` afterward.
+#[cfg(feature = "std")]
+pub unsafe fn move_to_global<T: Pointee + ?Sized, D: Deserializer + ?Sized>(
+    deserializer: &D,
+    ptr: *mut T,
+) -> *mut T {
+    if deserializer.allocates_globally() {
+        return ptr;
+    }
+
+    let layout = alloc::Layout::for_value(&*ptr);
+    if layout.size() == 0 {
+        return ptr;
+    }
+
+    let result = std::alloc::alloc(layout);
+    if result.is_null() {
+        std::alloc::handle_alloc_error(layout);
+    }
+    ptr::copy_nonoverlapping(ptr.cast::<u8>(), result, layout.size());
+    ptr_meta::from_raw_parts_mut(result.cast(), ptr_meta::metadata(ptr))
 }
 
 /// A deserializable shared pointer type.
@@ -0,0 +1,492 @@
+//! Length-delimited framing for sending archives over byte streams.
+//!
+//! Each frame starts with a header of [`FRAME_HEADER_SIZE`] bytes that holds the length of the
+//! archive and the alignment it requires, both as little-endian `u32`s. The archive follows the
+//! header with its root object at the end, as usual.
+//!
+//! Byte streams don't preserve alignment, so an [`ArchiveFrameReader`] reads the archive of every
+//! frame into an [`AlignedVec`] that is reused between frames. With the `tokio_codec` feature,
+//! [`ArchiveFrameCodec`] encodes and decodes frames with `tokio_util::codec`.
+
+#[cfg(feature = "validation")]
+use crate::validation::{check_archived_value, CheckTypeError, DefaultArchiveValidator};
+use crate::{
+    archived_value,
+    ser::{
+        serializers::{AlignedSerializer, AllocScratch, CompositeSerializer, SharedSerializeMap},
+        Serializer,
+    },
+    AlignedVec, Archive, Fallible, Serialize, Unreachable,
+};
+#[cfg(feature = "validation")]
+use bytecheck::CheckBytes;
+use core::{convert::TryInto, fmt, mem};
+use std::{
+    error::Error,
+    io::{self, Read, Write},
+};
+
+/// The size of the header at the start of every frame.
+pub const FRAME_HEADER_SIZE: usize = 8;
+
+/// The largest archive that can be written in a single frame.
+pub const MAX_FRAME_LEN: usize = u32::MAX as usize;
+
+/// The largest archive that frame readers and codecs accept by default.
+///
+/// Frame headers come from the stream, so readers limit how much memory a single header can ask
+/// for. Use `with_max_len` to accept larger frames.
+pub const DEFAULT_MAX_FRAME_LEN: usize = 8 * 1024 * 1024;
+
+/// The number of bytes that frame readers read before growing their buffers.
+const READ_CHUNK_SIZE: usize = 64 * 1024;
+
+/// The serializer used to write the archives in frames.
+pub type FrameSerializer =
+    CompositeSerializer<AlignedSerializer<AlignedVec>, AllocScratch, SharedSerializeMap>;
+
+/// The error type returned when serializing values into frames.
+pub type FrameSerializerError = <FrameSerializer as Fallible>::Error;
+
+/// The error type returned when reading and validating frames for a root object of type `T`.
+#[cfg(feature = "validation")]
+pub type CheckFrameError<T> =
+    FrameError<CheckTypeError<<T as Archive>::Archived, DefaultArchiveValidator>>;
+
+/// An error that can occur while writing or reading frames.
+#[derive(Debug)]
+pub enum FrameError<E> {
+    /// An error occurred while writing to or reading from the stream
+    Io(io::Error),
+    /// The archive in the frame was longer than the maximum frame length
+    FrameTooLarge {
+        /// The length of the archive
+        len: usize,
+        /// The maximum length of an archive
+        max_len: usize,
+    },
+    /// The archive in the frame was too short to contain its root object
+    FrameTooSmall {
+        /// The length of the archive
+        len: usize,
+        /// The size of the root object
+        min_len: usize,
+    },
+    /// The archive in the frame requires a greater alignment than is supported
+    UnsupportedAlignment {
+        /// The alignment required by the archive
+        align: usize,
+    },
+    /// An error occurred while serializing or validating the archive
+    ArchiveError(E),
+}
+
+impl<E> From<io::Error> for FrameError<E> {
+    #[inline]
+    fn from(e: io::Error) -> Self {
+        FrameError::Io(e)
+    }
+}
+
+#[cfg(feature = "validation")]
+impl FrameError<Unreachable> {
+    #[inline]
+    fn widen<E>(self) -> FrameError<E> {
+        match self {
+            FrameError::Io(e) => FrameError::Io(e),
+            FrameError::FrameTooLarge { len, max_len } => {
+                FrameError::FrameTooLarge { len, max_len }
+            }
+            FrameError::FrameTooSmall { len, min_len } => {
+                FrameError::FrameTooSmall { len, min_len }
+            }
+            FrameError::UnsupportedAlignment { align } => {
+                FrameError::UnsupportedAlignment { align }
+            }
+            FrameError::ArchiveError(e) => match e {},
+        }
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for FrameError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FrameError::Io(e) => write!(f, "io error: {}", e),
+            FrameError::FrameTooLarge { len, max_len } => write!(
+                f,
+                "frame of length {} exceeds the maximum frame length of {}",
+                len, max_len
+            ),
+            FrameError::FrameTooSmall { len, min_len } => write!(
+                f,
+                "frame of length {} is too small to contain a root object of size {}",
+                len, min_len
+            ),
+            FrameError::UnsupportedAlignment { align } => write!(
+                f,
+                "frame requires an alignment of {} but at most {} is supported",
+                align,
+                AlignedVec::ALIGNMENT
+            ),
+            FrameError::ArchiveError(e) => write!(f, "archive error: {}", e),
+        }
+    }
+}
+
+impl<E: Error + 'static> Error for FrameError<E> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            FrameError::Io(e) => Some(e as &dyn Error),
+            FrameError::ArchiveError(e) => Some(e as &dyn Error),
+            _ => None,
+        }
+    }
+}
+
+/// Serializes the given value into the buffer, reusing its memory.
+fn serialize_frame<T: Serialize<FrameSerializer>>(
+    buffer: &mut AlignedVec,
+    value: &T,
+) -> Result<[u8; FRAME_HEADER_SIZE], FrameError<FrameSerializerError>> {
+    let mut vec = mem::take(buffer);
+    vec.clear();
+    let mut serializer = CompositeSerializer::new(
+        AlignedSerializer::new(vec),
+        AllocScratch::new(),
+        SharedSerializeMap::new(),
+    );
+    serializer
+        .serialize_value(value)
+        .map_err(FrameError::ArchiveError)?;
+    *buffer = serializer.into_serializer().into_inner();
+
+    if buffer.len() > MAX_FRAME_LEN {
+        return Err(FrameError::FrameTooLarge {
+            len: buffer.len(),
+            max_len: MAX_FRAME_LEN,
+        });
+    }
+    let mut header = [0; FRAME_HEADER_SIZE];
+    header[..4].copy_from_slice(&(buffer.len() as u32).to_le_bytes());
+    header[4..].copy_from_slice(&(AlignedVec::ALIGNMENT as u32).to_le_bytes());
+    Ok(header)
+}
+
+/// Parses a frame header and returns the length of the archive that follows it.
+fn parse_header(
+    header: &[u8; FRAME_HEADER_SIZE],
+    max_len: usize,
+) -> Result<usize, FrameError<Unreachable>> {
+    let len = u32::from_le_bytes(header[..4].try_into().unwrap()) as usize;
+    let align = u32::from_le_bytes(header[4..].try_into().unwrap()) as usize;
+    if !align.is_power_of_two() || align > AlignedVec::ALIGNMENT {
+        Err(FrameError::UnsupportedAlignment { align })
+    } else if len > max_len {
+        Err(FrameError::FrameTooLarge { len, max_len })
+    } else {
+        Ok(len)
+    }
+}
+
+/// Returns the position of the root object of type `T` in an archive of length `len`.
+fn root_pos<T: Archive>(len: usize) -> Result<usize, FrameError<Unreachable>> {
+    let min_len = mem::size_of::<T::Archived>();
+    len.checked_sub(min_len)
+        .ok_or(FrameError::FrameTooSmall { len, min_len })
+}
+
+/// Serializes values and writes them to a stream as frames.
+///
+/// The memory used to serialize values is reused between frames.
+///
+/// ## Examples
+/// ```
+/// use rkyv::{
+///     frame::{ArchiveFrameReader, ArchiveFrameWriter},
+///     Archive,
+///     Serialize,
+/// };
+///
+/// #[derive(Archive, Serialize)]
+/// struct Message {
+///     id: u32,
+///     text: String,
+/// }
+///
+/// let mut writer = ArchiveFrameWriter::new(Vec::new());
+/// writer.write(&Message { id: 1, text: "hello".to_string() }).unwrap();
+/// writer.write(&Message { id: 2, text: "world".to_string() }).unwrap();
+/// let stream = writer.into_inner();
+///
+/// let mut reader = ArchiveFrameReader::new(stream.as_slice());
+/// let first = unsafe { reader.read_unchecked::<Message>() }.unwrap().unwrap();
+/// assert_eq!(first.id, 1);
+/// assert_eq!(first.text, "hello");
+/// let second = unsafe { reader.read_unchecked::<Message>() }.unwrap().unwrap();
+/// assert_eq!(second.id, 2);
+/// assert_eq!(second.text, "world");
+/// assert!(unsafe { reader.read_unchecked::<Message>() }.unwrap().is_none());
+/// ```
+pub struct ArchiveFrameWriter<W> {
+    inner: W,
+    buffer: AlignedVec,
+}
+
+impl<W> ArchiveFrameWriter<W> {
+    /// Creates a new frame writer that writes to the given stream.
+    #[inline]
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            buffer: AlignedVec::new(),
+        }
+    }
+
+    /// Returns a reference to the underlying stream.
+    #[inline]
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the underlying stream.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Consumes the frame writer and returns the underlying stream.
+    #[inline]
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: Write> ArchiveFrameWriter<W> {
+    /// Serializes the given value and writes it to the stream as a frame.
+    pub fn write<T: Serialize<FrameSerializer>>(
+        &mut self,
+        value: &T,
+    ) -> Result<(), FrameError<FrameSerializerError>> {
+        let header = serialize_frame(&mut self.buffer, value)?;
+        self.inner.write_all(&header)?;
+        self.inner.write_all(&self.buffer)?;
+        Ok(())
+    }
+
+    /// Flushes the underlying stream.
+    #[inline]
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Reads frames from a stream and provides access to their archives.
+///
+/// The archive of the most recently read frame is kept in an [`AlignedVec`] that is reused for the
+/// next frame, so it can be accessed until the next frame is read.
+///
+/// See [`ArchiveFrameWriter`] for an example.
+pub struct ArchiveFrameReader<R> {
+    inner: R,
+    buffer: AlignedVec,
+    max_len: usize,
+}
+
+impl<R> ArchiveFrameReader<R> {
+    /// Creates a new frame reader that reads from the given stream.
+    #[inline]
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            buffer: AlignedVec::new(),
+            max_len: DEFAULT_MAX_FRAME_LEN,
+        }
+    }
+
+    /// Limits the length of the archives in frames to `max_len`.
+    ///
+    /// Frames that are longer are rejected before any memory is allocated for them. Defaults to
+    /// [`DEFAULT_MAX_FRAME_LEN`].
+    #[inline]
+    pub fn with_max_len(mut self, max_len: usize) -> Self {
+        self.max_len = max_len;
+        self
+    }
+
+    /// Returns a reference to the underlying stream.
+    #[inline]
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the underlying stream.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    /// Consumes the frame reader and returns the underlying stream.
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: Read> ArchiveFrameReader<R> {
+    /// Reads the next frame and returns its archive.
+    ///
+    /// Returns `None` if the stream ended cleanly before the next frame.
+    pub fn read_frame(&mut self) -> Result<Option<&[u8]>, FrameError<Unreachable>> {
+        let mut header = [0; FRAME_HEADER_SIZE];
+        let mut read = 0;
+        while read < FRAME_HEADER_SIZE {
+            match self.inner.read(&mut header[read..]) {
+                Ok(0) if read == 0 => return Ok(None),
+                Ok(0) => return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
+                Ok(len) => read += len,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => (),
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        // The buffer only grows as bytes arrive, so a header can't make it allocate much more memory
+        // than the stream actually sends
+        let len = parse_header(&header, self.max_len)?;
+        self.buffer.clear();
+        while self.buffer.len() < len {
+            let start = self.buffer.len();
+            let end = len.min(start + start.max(READ_CHUNK_SIZE));
+            self.buffer.resize(end, 0);
+            match self.inner.read(&mut self.buffer.as_mut_slice()[start..end]) {
+                Ok(0) => return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
+                Ok(read) => self.buffer.resize(start + read, 0),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => self.buffer.resize(start, 0),
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(Some(&self.buffer))
+    }
+
+    /// Reads the next frame and validates its archive for a root object of the given type.
+    ///
+    /// Returns `None` if the stream ended cleanly before the next frame.
+    #[cfg(feature = "validation")]
+    pub fn read<T: Archive>(&mut self) -> Result<Option<&T::Archived>, CheckFrameError<T>>
+    where
+        T::Archived: CheckBytes<DefaultArchiveValidator>,
+    {
+        match self.read_frame().map_err(FrameError::widen)? {
+            None => Ok(None),
+            Some(archive) => {
+                let pos = root_pos::<T>(archive.len()).map_err(FrameError::widen)?;
+                check_archived_value::<T>(archive, pos)
+                    .map(Some)
+                    .map_err(FrameError::ArchiveError)
+            }
+        }
+    }
+
+    /// Reads the next frame and gets the root object of its archive without validating it.
+    ///
+    /// Returns `None` if the stream ended cleanly before the next frame.
+    ///
+    /// # Safety
+    ///
+    /// The caller must guarantee that the frame contains a valid archive of `T`. See
+    /// [`archived_root`](crate::archived_root) for more details.
+    pub unsafe fn read_unchecked<T: Archive>(
+        &mut self,
+    ) -> Result<Option<&T::Archived>, FrameError<Unreachable>> {
+        match self.read_frame()? {
+            None => Ok(None),
+            Some(archive) => {
+                let pos = root_pos::<T>(archive.len())?;
+                Ok(Some(archived_value::<T>(archive, pos)))
+            }
+        }
+    }
+}
+
+#[cfg(feature = "tokio_codec")]
+mod codec {
+    use super::*;
+    use bytes::{Buf, BufMut, BytesMut};
+    use tokio_util::codec::{Decoder, Encoder};
+
+    /// A `tokio_util` codec that encodes values as frames and decodes frames into archives.
+    ///
+    /// Decoded frames are returned as [`AlignedVec`]s so their archives can be accessed with
+    /// [`check_archived_root`](crate::check_archived_root) or
+    /// [`archived_root`](crate::archived_root).
+    #[derive(Debug)]
+    pub struct ArchiveFrameCodec {
+        buffer: AlignedVec,
+        max_len: usize,
+    }
+
+    impl ArchiveFrameCodec {
+        /// Creates a new frame codec.
+        #[inline]
+        pub fn new() -> Self {
+            Self {
+                buffer: AlignedVec::new(),
+                max_len: DEFAULT_MAX_FRAME_LEN,
+            }
+        }
+
+        /// Limits the length of the archives in decoded frames to `max_len`.
+        ///
+        /// Defaults to [`DEFAULT_MAX_FRAME_LEN`].
+        #[inline]
+        pub fn with_max_len(mut self, max_len: usize) -> Self {
+            self.max_len = max_len;
+            self
+        }
+    }
+
+    impl Default for ArchiveFrameCodec {
+        #[inline]
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl<T: Serialize<FrameSerializer>> Encoder<&T> for ArchiveFrameCodec {
+        type Error = FrameError<FrameSerializerError>;
+
+        fn encode(&mut self, item: &T, dst: &mut BytesMut) -> Result<(), Self::Error> {
+            let header = serialize_frame(&mut self.buffer, item)?;
+            dst.reserve(FRAME_HEADER_SIZE + self.buffer.len());
+            dst.put_slice(&header);
+            dst.put_slice(&self.buffer);
+            Ok(())
+        }
+    }
+
+    impl Decoder for ArchiveFrameCodec {
+        type Item = AlignedVec;
+        type Error = FrameError<Unreachable>;
+
+        fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+            if src.len() < FRAME_HEADER_SIZE {
+                return Ok(None);
+            }
+
+            let header = src[..FRAME_HEADER_SIZE].try_into().unwrap();
+            let len = parse_header(&header, self.max_len)?;
+            if src.len() < FRAME_HEADER_SIZE + len {
+                src.reserve(FRAME_HEADER_SIZE + len - src.len());
+                return Ok(None);
+            }
+
+            src.advance(FRAME_HEADER_SIZE);
+            let mut archive = AlignedVec::with_capacity(len);
+            archive.extend_from_slice(&src[..len]);
+            src.advance(len);
+            Ok(Some(archive))
+        }
+    }
+}
+
+#[cfg(feature = "tokio_codec")]
+pub use self::codec::ArchiveFrameCodec;
@@ -0,0 +1,210 @@
+//! A wrapper for subtrees that can be deserialized on demand.
+//!
+//! The archived value of a [`Lazy`] can be read directly from the [`ArchivedLazy`] without
+//! deserializing it, or loaded into an unloaded `Lazy` later with [`Lazy::load`]. Deserializing a
+//! `Lazy` as part of its parent loads the value as well, so that serializing the result writes the
+//! same subtree back out.
+//!
+//! ## Examples
+//! ```
+//! use rkyv::{
+//!     archived_root,
+//!     de::deserializers::AllocDeserializer,
+//!     lazy::Lazy,
+//!     ser::{serializers::AllocSerializer, Serializer},
+//!     Archive,
+//!     Deserialize,
+//!     Serialize,
+//! };
+//!
+//! #[derive(Archive, Serialize, Deserialize)]
+//! struct Document {
+//!     title: String,
+//!     body: Lazy<Vec<String>>,
+//! }
+//!
+//! let value = Document {
+//!     title: "Notes".to_string(),
+//!     body: Lazy::new(vec!["first".to_string(), "second".to_string()]),
+//! };
+//!
+//! let mut serializer = AllocSerializer::<256>::default();
+//! serializer.serialize_value(&value).expect("failed to serialize value");
+//! let bytes = serializer.into_serializer().into_inner();
+//!
+//! let archived = unsafe { archived_root::<Document>(&bytes[..]) };
+//! assert_eq!(archived.body.get().unwrap()[0], "first");
+//!
+//! let mut body = Lazy::<Vec<String>>::unloaded();
+//! assert!(!body.is_loaded());
+//! let loaded = body.load(&archived.body, &mut AllocDeserializer).unwrap();
+//! assert_eq!(loaded.unwrap().len(), 2);
+//! assert!(body.is_loaded());
+//!
+//! let document: Document = archived.deserialize(&mut AllocDeserializer).unwrap();
+//! assert_eq!(document.body.get().unwrap().len(), 2);
+//! ```
+
+use crate::{
+    core_impl::ArchivedOption, offset_of, Archive, Deserialize, DeserializeInto, Fallible,
+    Serialize,
+};
+use core::mem::MaybeUninit;
+
+/// A value that is skipped when deserializing and can be loaded from its archive on demand.
+///
+/// A `Lazy` is either loaded and holds a value, or unloaded and holds nothing. Serializing an
+/// unloaded `Lazy` archives it as unloaded. Deserializing a `Lazy` always loads the archived value
+/// if there is one, so round trips never drop data.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Lazy<T> {
+    value: Option<T>,
+}
+
+impl<T> Lazy<T> {
+    /// Creates a loaded `Lazy` holding the given value.
+    #[inline]
+    pub fn new(value: T) -> Self {
+        Self { value: Some(value) }
+    }
+
+    /// Creates an unloaded `Lazy`.
+    #[inline]
+    pub fn unloaded() -> Self {
+        Self { value: None }
+    }
+
+    /// Returns `true` if the value has been loaded.
+    #[inline]
+    pub fn is_loaded(&self) -> bool {
+        self.value.is_some()
+    }
+
+    /// Returns a reference to the value if it has been loaded.
+    #[inline]
+    pub fn get(&self) -> Option<&T> {
+        self.value.as_ref()
+    }
+
+    /// Returns a mutable reference to the value if it has been loaded.
+    #[inline]
+    pub fn get_mut(&mut self) -> Option<&mut T> {
+        self.value.as_mut()
+    }
+
+    /// Takes the value out of the `Lazy`, leaving it unloaded.
+    #[inline]
+    pub fn unload(&mut self) -> Option<T> {
+        self.value.take()
+    }
+
+    /// Consumes the `Lazy`, returning the value if it has been loaded.
+    #[inline]
+    pub fn into_inner(self) -> Option<T> {
+        self.value
+    }
+}
+
+impl<T: Archive> Lazy<T> {
+    /// Loads the value from its archived counterpart if it isn't already loaded, then returns a
+    /// mutable reference to it.
+    ///
+    /// Returns `None` if the archived value was serialized while unloaded.
+    pub fn load<D: Fallible + ?Sized>(
+        &mut self,
+        archived: &ArchivedLazy<T::Archived>,
+        deserializer: &mut D,
+    ) -> Result<Option<&mut T>, D::Error>
+    where
+        T::Archived: Deserialize<T, D>,
+    {
+        if self.value.is_none() {
+            self.value = archived.0.deserialize(deserializer)?;
+        }
+        Ok(self.value.as_mut())
+    }
+}
+
+impl<T> Default for Lazy<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::unloaded()
+    }
+}
+
+impl<T> From<T> for Lazy<T> {
+    #[inline]
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+/// An archived [`Lazy`].
+///
+/// The archived value can be accessed directly with [`get`](ArchivedLazy::get) without
+/// deserializing it.
+#[derive(Debug)]
+#[cfg_attr(feature = "validation", derive(bytecheck::CheckBytes))]
+#[repr(transparent)]
+pub struct ArchivedLazy<T>(ArchivedOption<T>);
+
+impl<T> ArchivedLazy<T> {
+    /// Returns a reference to the archived value, or `None` if the `Lazy` was serialized while
+    /// unloaded.
+    #[inline]
+    pub fn get(&self) -> Option<&T> {
+        self.0.as_ref()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T: serde::Serialize> serde::Serialize for ArchivedLazy<T> {
+    #[inline]
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.get().serialize(serializer)
+    }
+}
+
+impl<T: Archive> Archive for Lazy<T> {
+    type Archived = ArchivedLazy<T::Archived>;
+    type Resolver = Option<T::Resolver>;
+
+    #[inline]
+    fn resolve(&self, pos: usize, resolver: Self::Resolver, out: &mut MaybeUninit<Self::Archived>) {
+        self.value.resolve(
+            pos + offset_of!(Self::Archived, 0),
+            resolver,
+            project_struct!(out: Self::Archived => 0),
+        );
+    }
+}
+
+impl<T: Serialize<S>, S: Fallible + ?Sized> Serialize<S> for Lazy<T> {
+    #[inline]
+    fn serialize(&self, serializer: &mut S) -> Result<Self::Resolver, S::Error> {
+        self.value.serialize(serializer)
+    }
+}
+
+impl<T: Archive, D: Fallible + ?Sized> Deserialize<Lazy<T>, D> for ArchivedLazy<T::Archived>
+where
+    T::Archived: Deserialize<T, D>,
+{
+    #[inline]
+    fn deserialize(&self, deserializer: &mut D) -> Result<Lazy<T>, D::Error> {
+        Ok(Lazy {
+            value: self.0.deserialize(deserializer)?,
+        })
+    }
+}
+
+impl<T: Archive, D: Fallible + ?Sized> DeserializeInto<Lazy<T>, D> for ArchivedLazy<T::Archived>
+where
+    T::Archived: Deserialize<T, D>,
+{
+    #[inline]
+    fn deserialize_into(&self, out: &mut Lazy<T>, deserializer: &mut D) -> Result<(), D::Error> {
+        out.value = self.0.deserialize(deserializer)?;
+        Ok(())
+    }
+}
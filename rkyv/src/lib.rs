@@ -47,8 +47,14 @@
 //!
 //! ## Features
 //!
+//! - `arena`: Enables deserializing into arena-allocated types from `bumpalo`
 //! - `const_generics`: Improves the trait implementations for arrays with support for all lengths
 //!   (enabled by default)
+//! - `crc32c`: Computes CRC-32C archive checksums with the hardware-accelerated `crc32c` crate
+//! - `futures_io`: Enables asynchronously writing archives to `futures_io::AsyncWrite` sinks
+//! - `mmap`: Enables serializing to and loading archives from memory-mapped files
+//! - `serde`: Implements `serde::Serialize` for archived types so they can be written to any serde
+//!   format without deserializing them first, and enables building archives from serde input
 //! - `size_64`: Archives `*size` as `*64` instead of `*32`. This is for large archive support
 //! - `specialization`: Enables support for the unstable specialization feature for increased
 //!   performance for a few specific cases
@@ -56,7 +62,11 @@
 //! - `strict`: Guarantees that types will have the same representations across platforms and
 //!   compilations. This is already the case in practice, but this feature provides a guarantee. It
 //!   additionally provides C type compatibility.
-//! - `validation`: Enables validation support through `bytecheck`
+//! - `tokio_codec`: Enables a `tokio_util` codec for archive frames
+//! - `tokio_io`: Enables asynchronously writing archives to `tokio::io::AsyncWrite` sinks
+//! - `validation`: Enables validation support through `bytecheck` and makes the `Archive` derive
+//!   implement `CheckBytes` for archived types
+//! - `xxh3`: Enables the XXH3 hasher for archive checksums
 //!
 //! ## Examples
 //!
@@ -66,14 +76,18 @@
 #![cfg_attr(feature = "specialization", feature(min_specialization))]
 #![cfg_attr(feature = "specialization", feature(rustc_attrs))]
 
-#[cfg(all(not(feature = "std"), feature = "specialization"))]
-extern crate alloc;
-
 #[doc(hidden)]
 #[macro_use]
 pub mod macros;
+pub mod checksum;
+pub mod compare;
+#[cfg(all(feature = "serde", feature = "std"))]
+pub mod convert;
 pub mod core_impl;
 pub mod de;
+#[cfg(feature = "std")]
+pub mod frame;
+pub mod lazy;
 pub mod ser;
 #[cfg(feature = "std")]
 pub mod std_impl;
@@ -87,12 +101,23 @@ use core::{
     mem::MaybeUninit,
 };
 
+#[cfg(feature = "validation")]
+#[doc(hidden)]
+pub use bytecheck;
 pub use memoffset::{offset_of, offset_of_tuple};
+#[doc(hidden)]
+pub use ptr_meta;
 use ptr_meta::Pointee;
-pub use rkyv_derive::{Archive, Deserialize, Serialize};
+pub use rkyv_derive::{Archive, Deserialize, DeserializeInto, Serialize};
+#[cfg(feature = "serde")]
+#[doc(hidden)]
+pub use serde;
 pub use util::*;
 #[cfg(feature = "validation")]
-pub use validation::{check_archived_root, check_archived_value};
+pub use validation::{
+    check_archived_root, check_archived_root_with_path, check_archived_value,
+    check_archived_value_with_path, from_bytes,
+};
 
 /// Contains the error type for traits with methods that can fail
 pub trait Fallible {
@@ -114,7 +139,7 @@ impl fmt::Display for Unreachable {
 impl std::error::Error for Unreachable {}
 
 /// A fallible type that cannot produce errors
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct Infallible;
 
 impl Fallible for Infallible {
@@ -298,6 +323,16 @@ pub trait Archive {
     /// they must remain whatever value they currently have. This is so that uninitialized memory
     /// doesn't get leaked to the final archive.
     fn resolve(&self, pos: usize, resolver: Self::Resolver, out: &mut MaybeUninit<Self::Archived>);
+
+    /// Whether slices of this type may be archived by copying their bytes directly.
+    ///
+    /// When enabled, slices of this type are serialized with
+    /// [`write_plain_bytes`](ser::Serializer::write_plain_bytes) instead of serializing and
+    /// resolving each element. This is what lets serializers like
+    /// [`DedupSerializer`](ser::serializers::DedupSerializer) and
+    /// [`VectoredSerializer`](ser::serializers::VectoredSerializer) see the contents of a
+    /// `Vec<u8>` as a single block of bytes.
+    const COPY_OPTIMIZATION: CopyOptimization<Self> = CopyOptimization::disable();
 }
 
 /// Converts a type to its archived form.
@@ -317,6 +352,63 @@ pub trait Deserialize<T: Archive<Archived = Self>, D: Fallible + ?Sized> {
     fn deserialize(&self, deserializer: &mut D) -> Result<T, D::Error>;
 }
 
+/// Deserializes an archived value into an existing value, reusing its allocations.
+///
+/// Where [`Deserialize`] always creates a new value, `DeserializeInto` overwrites an existing one.
+/// Containers like `Vec`, `String` and `HashMap` keep their capacity and deserialize into the
+/// elements they already hold, so repeatedly deserializing similar values into the same place
+/// avoids most allocations.
+///
+/// This can be derived with [`DeserializeInto`](macro@DeserializeInto).
+///
+/// ## Examples
+/// ```
+/// use rkyv::{
+///     archived_root,
+///     de::deserializers::AllocDeserializer,
+///     ser::{serializers::AllocSerializer, Serializer},
+///     Archive,
+///     Deserialize,
+///     DeserializeInto,
+///     Serialize,
+/// };
+///
+/// #[derive(Archive, Serialize, Deserialize, DeserializeInto)]
+/// struct World {
+///     tick: u64,
+///     names: Vec<String>,
+/// }
+///
+/// let mut world = World {
+///     tick: 0,
+///     names: Vec::with_capacity(16),
+/// };
+///
+/// for tick in 1..=3 {
+///     let snapshot = World {
+///         tick,
+///         names: (0..tick).map(|i| format!("entity #{}", i)).collect(),
+///     };
+///     let mut serializer = AllocSerializer::<256>::default();
+///     serializer.serialize_value(&snapshot).unwrap();
+///     let buf = serializer.into_serializer().into_inner();
+///     let archived = unsafe { archived_root::<World>(buf.as_ref()) };
+///
+///     let names_ptr = world.names.as_ptr();
+///     archived.deserialize_into(&mut world, &mut AllocDeserializer).unwrap();
+///     assert_eq!(world.tick, tick);
+///     assert_eq!(world.names, snapshot.names);
+///     // The names vec was reused instead of allocating a new one
+///     assert_eq!(world.names.as_ptr(), names_ptr);
+/// }
+/// ```
+pub trait DeserializeInto<T: Archive<Archived = Self>, D: Fallible + ?Sized>:
+    Deserialize<T, D>
+{
+    /// Deserializes into the given value using the given deserializer.
+    fn deserialize_into(&self, out: &mut T, deserializer: &mut D) -> Result<(), D::Error>;
+}
+
 /// A counterpart of [`Archive`] that's suitable for unsized types.
 ///
 /// Instead of archiving its value directly, `ArchiveUnsized` archives a [`RelPtr`] to its archived
@@ -325,8 +417,8 @@ pub trait Deserialize<T: Archive<Archived = Self>, D: Fallible + ?Sized> {
 /// `ArchiveUnsized` is automatically implemented for all types that implement [`Archive`].
 ///
 /// `ArchiveUnsized` is already implemented for slices and string slices, and the `rkyv_dyn` crate
-/// can be used to archive trait objects. Other unsized types must manually implement
-/// `ArchiveUnsized`.
+/// can be used to archive trait objects. Structs that end in a slice or string slice can derive it
+/// with [`Archive`](macro@Archive). Other unsized types must manually implement `ArchiveUnsized`.
 ///
 /// ## Examples
 ///
@@ -596,6 +688,36 @@ pub trait DeserializeUnsized<T: ArchiveUnsized<Archived = Self> + ?Sized, D: Fal
 #[cfg_attr(feature = "specialization", rustc_unsafe_specialization_marker)]
 pub unsafe trait ArchiveCopy: Archive<Archived = Self> + Copy {}
 
+/// Whether a type can be archived by copying its bytes.
+///
+/// See [`Archive::COPY_OPTIMIZATION`].
+pub struct CopyOptimization<T: ?Sized>(bool, PhantomData<T>);
+
+impl<T: ?Sized> CopyOptimization<T> {
+    /// Returns a `CopyOptimization` that is disabled.
+    #[inline]
+    pub const fn disable() -> Self {
+        Self(false, PhantomData)
+    }
+
+    /// Returns a `CopyOptimization` that is enabled.
+    ///
+    /// # Safety
+    ///
+    /// `T` must be its own archived type, must not contain any padding bytes, and checking an
+    /// archived `T` must never fail.
+    #[inline]
+    pub const unsafe fn enable() -> Self {
+        Self(true, PhantomData)
+    }
+
+    /// Returns whether the optimization is enabled.
+    #[inline]
+    pub const fn is_enabled(&self) -> bool {
+        self.0
+    }
+}
+
 /// The type used for sizes in archived types.
 #[cfg(not(feature = "size_64"))]
 pub type ArchivedUsize = u32;
@@ -1,10 +1,12 @@
 //! Adapters wrap serializers and add support for serializer traits.
 
 use crate::{
-    ser::{Serializer, SharedSerializer},
-    Archive, Fallible, SerializeUnsized,
+    checksum::StreamingHasher,
+    ser::{ScratchSpace, SeekSerializer, Serializer, SharedSerializeRegistry, SharedSerializer},
+    Archive, ArchiveUnsized, Fallible, RelPtr, SerializeUnsized,
 };
-use std::collections::HashMap;
+use core::{alloc::Layout, fmt, mem, ptr::NonNull};
+use std::{collections::HashMap, error::Error};
 
 /// An adapter that adds shared serialization support to a serializer.
 pub struct SharedSerializerAdapter<S> {
@@ -59,6 +61,31 @@ impl<S: Serializer> Serializer for SharedSerializerAdapter<S> {
         self.inner.align_for::<T>()
     }
 
+    #[inline]
+    fn write_plain_bytes(&mut self, bytes: &[u8], align: usize) -> Result<usize, Self::Error> {
+        self.inner.write_plain_bytes(bytes, align)
+    }
+
+    #[inline]
+    fn enter_nested(&mut self, len: usize) -> Result<(), Self::Error> {
+        self.inner.enter_nested(len)
+    }
+
+    #[inline]
+    fn exit_nested(&mut self) {
+        self.inner.exit_nested()
+    }
+
+    #[inline]
+    unsafe fn push_scratch(&mut self, layout: Layout) -> Result<NonNull<[u8]>, Self::Error> {
+        self.inner.push_scratch(layout)
+    }
+
+    #[inline]
+    unsafe fn pop_scratch(&mut self, ptr: NonNull<u8>, layout: Layout) -> Result<(), Self::Error> {
+        self.inner.pop_scratch(ptr, layout)
+    }
+
     #[inline]
     unsafe fn resolve_aligned<T: Archive + ?Sized>(
         &mut self,
@@ -67,6 +94,24 @@ impl<S: Serializer> Serializer for SharedSerializerAdapter<S> {
     ) -> Result<usize, Self::Error> {
         self.inner.resolve_aligned(value, resolver)
     }
+
+    #[inline]
+    unsafe fn resolve_unsized_aligned<T: ArchiveUnsized + ?Sized>(
+        &mut self,
+        value: &T,
+        to: usize,
+        metadata_resolver: T::MetadataResolver,
+    ) -> Result<usize, Self::Error> {
+        self.inner
+            .resolve_unsized_aligned(value, to, metadata_resolver)
+    }
+}
+
+impl<S: SeekSerializer> SeekSerializer for SharedSerializerAdapter<S> {
+    #[inline]
+    fn seek(&mut self, pos: usize) -> Result<(), Self::Error> {
+        self.inner.seek(pos)
+    }
 }
 
 impl<S: Serializer> SharedSerializer for SharedSerializerAdapter<S> {
@@ -84,3 +129,850 @@ impl<S: Serializer> SharedSerializer for SharedSerializerAdapter<S> {
         }
     }
 }
+
+/// An adapter that makes a serializer allocate scratch space from a [`ScratchSpace`] instead of the
+/// global allocator.
+///
+/// ## Examples
+/// ```
+/// use rkyv::{
+///     archived_root,
+///     ser::{
+///         adapters::ScratchSpaceAdapter,
+///         serializers::{AlignedSerializer, AllocScratch, BufferScratch, FallbackScratch},
+///         Serializer,
+///     },
+///     AlignedVec,
+/// };
+///
+/// let mut serializer = ScratchSpaceAdapter::new(
+///     AlignedSerializer::new(AlignedVec::new()),
+///     FallbackScratch::new(BufferScratch::new([0u8; 256]), AllocScratch::new()),
+/// );
+/// let value = vec!["hello".to_string(), "world".to_string()];
+/// serializer.serialize_value(&value).expect("failed to archive value");
+/// let buf = serializer.into_inner().into_inner();
+/// let archived = unsafe { archived_root::<Vec<String>>(buf.as_ref()) };
+/// assert_eq!(archived.as_slice(), value.as_slice());
+/// ```
+pub struct ScratchSpaceAdapter<S, C> {
+    inner: S,
+    scratch_space: C,
+}
+
+impl<S, C> ScratchSpaceAdapter<S, C> {
+    /// Wraps the given serializer and allocates its scratch space from the given scratch space.
+    #[inline]
+    pub fn new(inner: S, scratch_space: C) -> Self {
+        Self {
+            inner,
+            scratch_space,
+        }
+    }
+
+    /// Consumes the adapter and returns the underlying serializer.
+    #[inline]
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    /// Consumes the adapter and returns the underlying serializer and scratch space.
+    #[inline]
+    pub fn into_parts(self) -> (S, C) {
+        (self.inner, self.scratch_space)
+    }
+}
+
+/// The error type returned by a [`ScratchSpaceAdapter`].
+#[derive(Debug)]
+pub enum ScratchSpaceAdapterError<S, C> {
+    /// An error occurred while serializing
+    Inner(S),
+    /// An error occurred while allocating scratch space
+    ScratchSpace(C),
+}
+
+impl<S: fmt::Display, C: fmt::Display> fmt::Display for ScratchSpaceAdapterError<S, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScratchSpaceAdapterError::Inner(e) => write!(f, "{}", e),
+            ScratchSpaceAdapterError::ScratchSpace(e) => write!(f, "scratch space error: {}", e),
+        }
+    }
+}
+
+impl<S: Error + 'static, C: Error + 'static> Error for ScratchSpaceAdapterError<S, C> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ScratchSpaceAdapterError::Inner(e) => Some(e as &dyn Error),
+            ScratchSpaceAdapterError::ScratchSpace(e) => Some(e as &dyn Error),
+        }
+    }
+}
+
+impl<S: Fallible, C: Fallible> Fallible for ScratchSpaceAdapter<S, C> {
+    type Error = ScratchSpaceAdapterError<S::Error, C::Error>;
+}
+
+impl<S: Serializer, C: ScratchSpace> Serializer for ScratchSpaceAdapter<S, C> {
+    #[inline]
+    fn pos(&self) -> usize {
+        self.inner.pos()
+    }
+
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.inner
+            .write(bytes)
+            .map_err(ScratchSpaceAdapterError::Inner)
+    }
+
+    #[inline]
+    fn pad(&mut self, padding: usize) -> Result<(), Self::Error> {
+        self.inner
+            .pad(padding)
+            .map_err(ScratchSpaceAdapterError::Inner)
+    }
+
+    #[inline]
+    fn align(&mut self, align: usize) -> Result<usize, Self::Error> {
+        self.inner
+            .align(align)
+            .map_err(ScratchSpaceAdapterError::Inner)
+    }
+
+    #[inline]
+    fn align_for<T>(&mut self) -> Result<usize, Self::Error> {
+        self.inner
+            .align_for::<T>()
+            .map_err(ScratchSpaceAdapterError::Inner)
+    }
+
+    #[inline]
+    fn write_plain_bytes(&mut self, bytes: &[u8], align: usize) -> Result<usize, Self::Error> {
+        self.inner
+            .write_plain_bytes(bytes, align)
+            .map_err(ScratchSpaceAdapterError::Inner)
+    }
+
+    #[inline]
+    fn enter_nested(&mut self, len: usize) -> Result<(), Self::Error> {
+        self.inner
+            .enter_nested(len)
+            .map_err(ScratchSpaceAdapterError::Inner)
+    }
+
+    #[inline]
+    fn exit_nested(&mut self) {
+        self.inner.exit_nested()
+    }
+
+    #[inline]
+    unsafe fn push_scratch(&mut self, layout: Layout) -> Result<NonNull<[u8]>, Self::Error> {
+        self.scratch_space
+            .push_scratch(layout)
+            .map_err(ScratchSpaceAdapterError::ScratchSpace)
+    }
+
+    #[inline]
+    unsafe fn pop_scratch(&mut self, ptr: NonNull<u8>, layout: Layout) -> Result<(), Self::Error> {
+        self.scratch_space
+            .pop_scratch(ptr, layout)
+            .map_err(ScratchSpaceAdapterError::ScratchSpace)
+    }
+
+    #[inline]
+    unsafe fn resolve_aligned<T: Archive + ?Sized>(
+        &mut self,
+        value: &T,
+        resolver: T::Resolver,
+    ) -> Result<usize, Self::Error> {
+        self.inner
+            .resolve_aligned(value, resolver)
+            .map_err(ScratchSpaceAdapterError::Inner)
+    }
+
+    #[inline]
+    unsafe fn resolve_unsized_aligned<T: ArchiveUnsized + ?Sized>(
+        &mut self,
+        value: &T,
+        to: usize,
+        metadata_resolver: T::MetadataResolver,
+    ) -> Result<usize, Self::Error> {
+        self.inner
+            .resolve_unsized_aligned(value, to, metadata_resolver)
+            .map_err(ScratchSpaceAdapterError::Inner)
+    }
+}
+
+impl<S: SeekSerializer, C: ScratchSpace> SeekSerializer for ScratchSpaceAdapter<S, C> {
+    #[inline]
+    fn seek(&mut self, pos: usize) -> Result<(), Self::Error> {
+        self.inner
+            .seek(pos)
+            .map_err(ScratchSpaceAdapterError::Inner)
+    }
+}
+
+/// An adapter that deduplicates blocks of plain bytes by their contents.
+///
+/// Whenever a string or a slice of copy values is written as a single block, the adapter checks
+/// whether identical bytes were already written and points to that copy instead of writing them
+/// again. This saves space when the same values are repeated many times.
+///
+/// Slices are written as single blocks when their element type enables
+/// [`COPY_OPTIMIZATION`](crate::Archive::COPY_OPTIMIZATION), which primitive numbers do. With the
+/// `specialization` feature, slices of all [`ArchiveCopy`](crate::ArchiveCopy) types are too.
+///
+/// Values in the resulting archive may share their bytes, so it must never be accessed mutably:
+/// modifying one value through [`archived_root_mut`](crate::archived_root_mut) or methods like
+/// [`ArchivedString::str_pin`](crate::std_impl::ArchivedString::str_pin) would modify every value
+/// that shares its bytes, and could create aliasing mutable references. The default validator
+/// rejects shared bytes, so check these archives with a
+/// [`DefaultArchiveDedupValidator`](crate::validation::DefaultArchiveDedupValidator) instead.
+///
+/// ## Examples
+/// ```
+/// use rkyv::{
+///     archived_root,
+///     ser::{adapters::DedupSerializer, serializers::AllocSerializer, Serializer},
+/// };
+///
+/// let value = vec!["hello".to_string(), "world".to_string(), "hello".to_string()];
+///
+/// let mut serializer = DedupSerializer::new(AllocSerializer::<256>::default());
+/// serializer.serialize_value(&value).expect("failed to archive value");
+/// let buf = serializer.into_inner().into_serializer().into_inner();
+///
+/// let archived = unsafe { archived_root::<Vec<String>>(buf.as_ref()) };
+/// assert_eq!(archived.as_slice(), value.as_slice());
+/// assert_eq!(archived[0].as_ptr(), archived[2].as_ptr());
+/// ```
+///
+/// With the `validation` feature, deduplicated archives can be checked with a
+/// [`DefaultArchiveDedupValidator`](crate::validation::DefaultArchiveDedupValidator):
+/// ```
+/// # #[cfg(feature = "validation")]
+/// # {
+/// use rkyv::{
+///     check_archived_root,
+///     ser::{adapters::DedupSerializer, serializers::AllocSerializer, Serializer},
+///     validation::{
+///         check_archived_root_with_context, ArchiveBoundsValidator, ArchiveDedupValidator,
+///         ArchiveValidator, DefaultArchiveDedupValidator, SharedArchiveValidator,
+///     },
+/// };
+///
+/// let value = vec!["hello".to_string(), "world".to_string(), "hello".to_string()];
+///
+/// let mut serializer = DedupSerializer::new(AllocSerializer::<256>::default());
+/// serializer.serialize_value(&value).expect("failed to archive value");
+/// let buf = serializer.into_inner().into_serializer().into_inner();
+///
+/// assert!(check_archived_root::<Vec<String>>(buf.as_ref()).is_err());
+///
+/// let mut validator = SharedArchiveValidator::new(ArchiveDedupValidator::new(
+///     ArchiveValidator::new(ArchiveBoundsValidator::new(buf.as_ref())),
+/// ));
+/// let archived = check_archived_root_with_context::<Vec<String>, DefaultArchiveDedupValidator>(
+///     buf.as_ref(),
+///     &mut validator,
+/// )
+/// .unwrap();
+/// assert_eq!(archived.as_slice(), value.as_slice());
+/// # }
+/// ```
+pub struct DedupSerializer<S> {
+    inner: S,
+    blocks: HashMap<Box<[u8]>, usize>,
+}
+
+impl<S> DedupSerializer<S> {
+    /// Wraps the given serializer and adds deduplication of plain byte blocks.
+    #[inline]
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            blocks: HashMap::new(),
+        }
+    }
+
+    /// Consumes the adapter and returns the underlying serializer.
+    #[inline]
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S: Fallible> Fallible for DedupSerializer<S> {
+    type Error = S::Error;
+}
+
+impl<S: Serializer> Serializer for DedupSerializer<S> {
+    #[inline]
+    fn pos(&self) -> usize {
+        self.inner.pos()
+    }
+
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.inner.write(bytes)
+    }
+
+    #[inline]
+    fn pad(&mut self, padding: usize) -> Result<(), Self::Error> {
+        self.inner.pad(padding)
+    }
+
+    #[inline]
+    fn align(&mut self, align: usize) -> Result<usize, Self::Error> {
+        self.inner.align(align)
+    }
+
+    #[inline]
+    fn align_for<T>(&mut self) -> Result<usize, Self::Error> {
+        self.inner.align_for::<T>()
+    }
+
+    fn write_plain_bytes(&mut self, bytes: &[u8], align: usize) -> Result<usize, Self::Error> {
+        if bytes.is_empty() {
+            return self.inner.write_plain_bytes(bytes, align);
+        }
+
+        // An existing block can only be reused if it's sufficiently aligned
+        if let Some(&pos) = self.blocks.get(bytes) {
+            if pos & (align - 1) == 0 {
+                return Ok(pos);
+            }
+        }
+
+        let pos = self.inner.write_plain_bytes(bytes, align)?;
+        if !self.blocks.contains_key(bytes) {
+            self.blocks.insert(bytes.into(), pos);
+        }
+        Ok(pos)
+    }
+
+    #[inline]
+    fn enter_nested(&mut self, len: usize) -> Result<(), Self::Error> {
+        self.inner.enter_nested(len)
+    }
+
+    #[inline]
+    fn exit_nested(&mut self) {
+        self.inner.exit_nested()
+    }
+
+    #[inline]
+    unsafe fn push_scratch(&mut self, layout: Layout) -> Result<NonNull<[u8]>, Self::Error> {
+        self.inner.push_scratch(layout)
+    }
+
+    #[inline]
+    unsafe fn pop_scratch(&mut self, ptr: NonNull<u8>, layout: Layout) -> Result<(), Self::Error> {
+        self.inner.pop_scratch(ptr, layout)
+    }
+
+    #[inline]
+    unsafe fn resolve_aligned<T: Archive + ?Sized>(
+        &mut self,
+        value: &T,
+        resolver: T::Resolver,
+    ) -> Result<usize, Self::Error> {
+        self.inner.resolve_aligned(value, resolver)
+    }
+
+    #[inline]
+    unsafe fn resolve_unsized_aligned<T: ArchiveUnsized + ?Sized>(
+        &mut self,
+        value: &T,
+        to: usize,
+        metadata_resolver: T::MetadataResolver,
+    ) -> Result<usize, Self::Error> {
+        self.inner
+            .resolve_unsized_aligned(value, to, metadata_resolver)
+    }
+}
+
+impl<S: SeekSerializer> SeekSerializer for DedupSerializer<S> {
+    #[inline]
+    fn seek(&mut self, pos: usize) -> Result<(), Self::Error> {
+        self.inner.seek(pos)
+    }
+}
+
+impl<S: SharedSerializeRegistry> SharedSerializeRegistry for DedupSerializer<S> {
+    #[inline]
+    fn get_shared_ptr(&self, value: *const u8) -> Option<usize> {
+        self.inner.get_shared_ptr(value)
+    }
+
+    #[inline]
+    fn add_shared_ptr(&mut self, value: *const u8, pos: usize) -> Result<(), Self::Error> {
+        self.inner.add_shared_ptr(value, pos)
+    }
+}
+
+impl<S: Serializer + SharedSerializeRegistry> SharedSerializer for DedupSerializer<S> {
+    fn serialize_shared<T: SerializeUnsized<Self> + ?Sized>(
+        &mut self,
+        value: &T,
+    ) -> Result<usize, Self::Error> {
+        let key = (value as *const T).cast::<u8>();
+        if let Some(pos) = self.inner.get_shared_ptr(key) {
+            Ok(pos)
+        } else {
+            let pos = value.serialize_unsized(self)?;
+            self.inner.add_shared_ptr(key, pos)?;
+            Ok(pos)
+        }
+    }
+}
+
+/// The error type returned by a [`LimitSerializer`].
+#[derive(Debug)]
+pub enum LimitSerializerError<E> {
+    /// An error occurred while serializing
+    Inner(E),
+    /// The archive would have grown past the size limit
+    SizeLimitExceeded {
+        /// The maximum size of the archive
+        limit: usize,
+        /// The size the archive would have grown to
+        size: usize,
+    },
+    /// A slice or string was longer than the slice length limit
+    SliceLengthLimitExceeded {
+        /// The maximum length of a slice
+        limit: usize,
+        /// The length of the slice
+        len: usize,
+    },
+    /// Values were nested more deeply than the depth limit
+    DepthLimitExceeded {
+        /// The maximum nesting depth
+        limit: usize,
+    },
+}
+
+impl<E: fmt::Display> fmt::Display for LimitSerializerError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LimitSerializerError::Inner(e) => write!(f, "{}", e),
+            LimitSerializerError::SizeLimitExceeded { limit, size } => write!(
+                f,
+                "archive size limit exceeded: limit is {} bytes but the archive would be {} bytes",
+                limit, size
+            ),
+            LimitSerializerError::SliceLengthLimitExceeded { limit, len } => write!(
+                f,
+                "slice length limit exceeded: the limit is {} but the slice has length {}",
+                limit, len
+            ),
+            LimitSerializerError::DepthLimitExceeded { limit } => {
+                write!(f, "nesting depth limit of {} exceeded", limit)
+            }
+        }
+    }
+}
+
+impl<E: Error + 'static> Error for LimitSerializerError<E> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LimitSerializerError::Inner(e) => Some(e as &dyn Error),
+            _ => None,
+        }
+    }
+}
+
+/// An adapter that limits the size of archives and the values in them.
+///
+/// The adapter returns an error before the archive grows past its size limit. It can optionally
+/// also limit the length of individual slices and strings, and how deeply values behind pointers
+/// can be nested. This bounds the resources used when serializing untrusted input.
+///
+/// ## Examples
+/// ```
+/// use rkyv::ser::{
+///     adapters::{LimitSerializer, LimitSerializerError},
+///     serializers::AllocSerializer,
+///     Serializer,
+/// };
+///
+/// let mut serializer = LimitSerializer::new(AllocSerializer::<256>::default(), 1024)
+///     .with_max_slice_len(4);
+/// match serializer.serialize_value(&vec![1, 2, 3, 4, 5]) {
+///     Err(LimitSerializerError::SliceLengthLimitExceeded { limit: 4, len: 5 }) => (),
+///     result => panic!("expected slice length limit error, got {:?}", result),
+/// }
+/// ```
+pub struct LimitSerializer<S> {
+    inner: S,
+    max_size: usize,
+    max_slice_len: Option<usize>,
+    max_depth: Option<usize>,
+    depth: usize,
+}
+
+impl<S> LimitSerializer<S> {
+    /// Wraps the given serializer and limits the archive to `max_size` bytes.
+    #[inline]
+    pub fn new(inner: S, max_size: usize) -> Self {
+        Self {
+            inner,
+            max_size,
+            max_slice_len: None,
+            max_depth: None,
+            depth: 0,
+        }
+    }
+
+    /// Limits the length of every slice and the number of bytes in every string to `max_len`.
+    #[inline]
+    pub fn with_max_slice_len(mut self, max_len: usize) -> Self {
+        self.max_slice_len = Some(max_len);
+        self
+    }
+
+    /// Limits how many pointers deep values can be nested to `max_depth`.
+    #[inline]
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    /// Consumes the adapter and returns the underlying serializer.
+    #[inline]
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S: Serializer> LimitSerializer<S> {
+    #[inline]
+    fn reserve(&self, len: usize) -> Result<(), LimitSerializerError<S::Error>> {
+        let size = self.inner.pos().saturating_add(len);
+        if size > self.max_size {
+            Err(LimitSerializerError::SizeLimitExceeded {
+                limit: self.max_size,
+                size,
+            })
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl<S: Fallible> Fallible for LimitSerializer<S> {
+    type Error = LimitSerializerError<S::Error>;
+}
+
+impl<S: Serializer> Serializer for LimitSerializer<S> {
+    #[inline]
+    fn pos(&self) -> usize {
+        self.inner.pos()
+    }
+
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.reserve(bytes.len())?;
+        self.inner.write(bytes).map_err(LimitSerializerError::Inner)
+    }
+
+    #[inline]
+    fn pad(&mut self, padding: usize) -> Result<(), Self::Error> {
+        self.reserve(padding)?;
+        self.inner.pad(padding).map_err(LimitSerializerError::Inner)
+    }
+
+    #[inline]
+    fn write_plain_bytes(&mut self, bytes: &[u8], align: usize) -> Result<usize, Self::Error> {
+        // The inner serializer may not write the bytes, but it can't be told ahead of time
+        self.reserve(align - 1 + bytes.len())?;
+        self.inner
+            .write_plain_bytes(bytes, align)
+            .map_err(LimitSerializerError::Inner)
+    }
+
+    #[inline]
+    fn enter_nested(&mut self, len: usize) -> Result<(), Self::Error> {
+        if let Some(limit) = self.max_slice_len {
+            if len > limit {
+                return Err(LimitSerializerError::SliceLengthLimitExceeded { limit, len });
+            }
+        }
+        if let Some(limit) = self.max_depth {
+            if self.depth >= limit {
+                return Err(LimitSerializerError::DepthLimitExceeded { limit });
+            }
+        }
+        self.inner
+            .enter_nested(len)
+            .map_err(LimitSerializerError::Inner)?;
+        self.depth += 1;
+        Ok(())
+    }
+
+    #[inline]
+    fn exit_nested(&mut self) {
+        self.depth -= 1;
+        self.inner.exit_nested()
+    }
+
+    #[inline]
+    unsafe fn push_scratch(&mut self, layout: Layout) -> Result<NonNull<[u8]>, Self::Error> {
+        self.inner
+            .push_scratch(layout)
+            .map_err(LimitSerializerError::Inner)
+    }
+
+    #[inline]
+    unsafe fn pop_scratch(&mut self, ptr: NonNull<u8>, layout: Layout) -> Result<(), Self::Error> {
+        self.inner
+            .pop_scratch(ptr, layout)
+            .map_err(LimitSerializerError::Inner)
+    }
+
+    #[inline]
+    unsafe fn resolve_aligned<T: Archive + ?Sized>(
+        &mut self,
+        value: &T,
+        resolver: T::Resolver,
+    ) -> Result<usize, Self::Error> {
+        self.reserve(mem::size_of::<T::Archived>())?;
+        self.inner
+            .resolve_aligned(value, resolver)
+            .map_err(LimitSerializerError::Inner)
+    }
+
+    #[inline]
+    unsafe fn resolve_unsized_aligned<T: ArchiveUnsized + ?Sized>(
+        &mut self,
+        value: &T,
+        to: usize,
+        metadata_resolver: T::MetadataResolver,
+    ) -> Result<usize, Self::Error> {
+        self.reserve(mem::size_of::<RelPtr<T::Archived>>())?;
+        self.inner
+            .resolve_unsized_aligned(value, to, metadata_resolver)
+            .map_err(LimitSerializerError::Inner)
+    }
+}
+
+impl<S: SeekSerializer> SeekSerializer for LimitSerializer<S> {
+    #[inline]
+    fn seek(&mut self, pos: usize) -> Result<(), Self::Error> {
+        if pos > self.max_size {
+            return Err(LimitSerializerError::SizeLimitExceeded {
+                limit: self.max_size,
+                size: pos,
+            });
+        }
+        self.inner.seek(pos).map_err(LimitSerializerError::Inner)
+    }
+}
+
+impl<S: SharedSerializeRegistry> SharedSerializeRegistry for LimitSerializer<S> {
+    #[inline]
+    fn get_shared_ptr(&self, value: *const u8) -> Option<usize> {
+        self.inner.get_shared_ptr(value)
+    }
+
+    #[inline]
+    fn add_shared_ptr(&mut self, value: *const u8, pos: usize) -> Result<(), Self::Error> {
+        self.inner
+            .add_shared_ptr(value, pos)
+            .map_err(LimitSerializerError::Inner)
+    }
+}
+
+impl<S: Serializer + SharedSerializeRegistry> SharedSerializer for LimitSerializer<S> {
+    fn serialize_shared<T: SerializeUnsized<Self> + ?Sized>(
+        &mut self,
+        value: &T,
+    ) -> Result<usize, Self::Error> {
+        let key = (value as *const T).cast::<u8>();
+        if let Some(pos) = self.inner.get_shared_ptr(key) {
+            Ok(pos)
+        } else {
+            let pos = value.serialize_unsized(self)?;
+            self.add_shared_ptr(key, pos)?;
+            Ok(pos)
+        }
+    }
+}
+
+/// An adapter that computes a checksum of the archive as it's written.
+///
+/// Every byte that is written to the inner serializer is fed to a [`StreamingHasher`] of type `H`.
+/// When the
+/// archive is complete, [`finish`](ChecksumSerializer::finish) appends the checksum to the archive
+/// in a trailer of [`CHECKSUM_SIZE`](crate::checksum::CHECKSUM_SIZE) bytes. The checksum can be
+/// verified with [`verify_checksum`](crate::checksum::verify_checksum) or
+/// `check_archived_root_with_checksum`.
+///
+/// Because the checksum is computed in a single pass, the inner serializer can write its output
+/// straight to a stream like a [`WriteSerializer`](crate::ser::serializers::WriteSerializer) does.
+/// For the same reason, this adapter can't seek. Other adapters like
+/// [`DedupSerializer`] should wrap this adapter rather than the other way around, so that the
+/// checksum only sees the bytes that are actually written.
+///
+/// ## Examples
+/// ```
+/// use rkyv::{
+///     archived_root,
+///     checksum::{verify_checksum, Crc32c},
+///     ser::{adapters::ChecksumSerializer, serializers::WriteSerializer, Serializer},
+///     Aligned,
+///     Archive,
+///     Serialize,
+/// };
+///
+/// #[derive(Archive, Serialize)]
+/// struct Example {
+///     name: String,
+///     value: f64,
+/// }
+///
+/// let mut serializer = ChecksumSerializer::<_, Crc32c>::new(WriteSerializer::new(Vec::new()));
+/// serializer.serialize_value(&Example {
+///     name: "pi".to_string(),
+///     value: 3.1415926,
+/// }).unwrap();
+/// let bytes = serializer.finish().unwrap().into_inner();
+///
+/// let mut buf = Aligned([0u8; 256]);
+/// buf.as_mut()[..bytes.len()].copy_from_slice(&bytes);
+/// let archive = verify_checksum::<Crc32c>(&buf.as_ref()[..bytes.len()]).unwrap();
+/// let archived = unsafe { archived_root::<Example>(archive) };
+/// assert_eq!(archived.name, "pi");
+/// assert_eq!(archived.value, 3.1415926);
+/// ```
+pub struct ChecksumSerializer<S, H> {
+    inner: S,
+    hasher: H,
+}
+
+impl<S, H: Default> ChecksumSerializer<S, H> {
+    /// Wraps the given serializer and computes a checksum with a default hasher.
+    #[inline]
+    pub fn new(inner: S) -> Self {
+        Self::with_hasher(inner, H::default())
+    }
+}
+
+impl<S, H> ChecksumSerializer<S, H> {
+    /// Wraps the given serializer and computes a checksum with the given hasher.
+    ///
+    /// The hasher must be created the same way as the hasher that verifies the checksum.
+    #[inline]
+    pub fn with_hasher(inner: S, hasher: H) -> Self {
+        Self { inner, hasher }
+    }
+
+    /// Consumes the adapter and returns the underlying serializer without writing the checksum.
+    #[inline]
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S: Serializer, H: StreamingHasher> ChecksumSerializer<S, H> {
+    /// Returns the checksum of the bytes that have been written so far.
+    #[inline]
+    pub fn checksum(&self) -> u64 {
+        self.hasher.finish()
+    }
+
+    /// Writes the checksum trailer and returns the underlying serializer.
+    #[inline]
+    pub fn finish(mut self) -> Result<S, S::Error> {
+        let checksum = self.checksum();
+        self.inner.write(&checksum.to_le_bytes())?;
+        Ok(self.inner)
+    }
+}
+
+impl<S: Fallible, H> Fallible for ChecksumSerializer<S, H> {
+    type Error = S::Error;
+}
+
+// Alignment, plain bytes and resolved values are all written through `pad` and `write`, so only
+// those two are forwarded directly to the inner serializer.
+impl<S: Serializer, H: StreamingHasher> Serializer for ChecksumSerializer<S, H> {
+    #[inline]
+    fn pos(&self) -> usize {
+        self.inner.pos()
+    }
+
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.inner.write(bytes)?;
+        self.hasher.write(bytes);
+        Ok(())
+    }
+
+    #[inline]
+    fn pad(&mut self, padding: usize) -> Result<(), Self::Error> {
+        const ZEROES: [u8; 32] = [0; 32];
+
+        self.inner.pad(padding)?;
+        let mut remaining = padding;
+        while remaining > 0 {
+            let len = remaining.min(ZEROES.len());
+            self.hasher.write(&ZEROES[..len]);
+            remaining -= len;
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn enter_nested(&mut self, len: usize) -> Result<(), Self::Error> {
+        self.inner.enter_nested(len)
+    }
+
+    #[inline]
+    fn exit_nested(&mut self) {
+        self.inner.exit_nested()
+    }
+
+    #[inline]
+    unsafe fn push_scratch(&mut self, layout: Layout) -> Result<NonNull<[u8]>, Self::Error> {
+        self.inner.push_scratch(layout)
+    }
+
+    #[inline]
+    unsafe fn pop_scratch(&mut self, ptr: NonNull<u8>, layout: Layout) -> Result<(), Self::Error> {
+        self.inner.pop_scratch(ptr, layout)
+    }
+}
+
+impl<S: SharedSerializeRegistry, H> SharedSerializeRegistry for ChecksumSerializer<S, H> {
+    #[inline]
+    fn get_shared_ptr(&self, value: *const u8) -> Option<usize> {
+        self.inner.get_shared_ptr(value)
+    }
+
+    #[inline]
+    fn add_shared_ptr(&mut self, value: *const u8, pos: usize) -> Result<(), Self::Error> {
+        self.inner.add_shared_ptr(value, pos)
+    }
+}
+
+impl<S: Serializer + SharedSerializeRegistry, H: StreamingHasher> SharedSerializer
+    for ChecksumSerializer<S, H>
+{
+    fn serialize_shared<T: SerializeUnsized<Self> + ?Sized>(
+        &mut self,
+        value: &T,
+    ) -> Result<usize, Self::Error> {
+        let key = (value as *const T).cast::<u8>();
+        if let Some(pos) = self.inner.get_shared_ptr(key) {
+            Ok(pos)
+        } else {
+            let pos = value.serialize_unsized(self)?;
+            self.inner.add_shared_ptr(key, pos)?;
+            Ok(pos)
+        }
+    }
+}
@@ -4,11 +4,13 @@
 pub mod adapters;
 pub mod serializers;
 
+#[cfg(feature = "std")]
+use crate::Infallible;
 use crate::{
     Archive, ArchivePointee, ArchiveUnsized, Archived, Fallible, RelPtr, Serialize,
     SerializeUnsized,
 };
-use core::{mem, slice};
+use core::{alloc::Layout, mem, ptr::NonNull, slice};
 
 /// A byte sink that knows where it is.
 ///
@@ -50,6 +52,81 @@ pub trait Serializer: Fallible {
         self.align(mem::align_of::<T>())
     }
 
+    /// Aligns the serializer to the given alignment and writes a block of bytes that does not
+    /// contain any pointers.
+    ///
+    /// Returns the position of the block. Serializers like
+    /// [`DedupSerializer`](adapters::DedupSerializer) may return the position of an identical block
+    /// that was written earlier instead of writing it again, so several values may end up sharing
+    /// the same bytes. See `DedupSerializer` for how archives with shared bytes have to be
+    /// accessed.
+    #[inline]
+    fn write_plain_bytes(&mut self, bytes: &[u8], align: usize) -> Result<usize, Self::Error> {
+        let pos = self.align(align)?;
+        self.write(bytes)?;
+        Ok(pos)
+    }
+
+    /// Called by builtin types before they serialize the values behind a pointer.
+    ///
+    /// `len` is the number of values that are pointed to: the length of slices, the number of
+    /// bytes in strings, and 1 for single values. Every successful call is matched by a call to
+    /// [`exit_nested`](Serializer::exit_nested) after the values are serialized, even if
+    /// serializing them fails, which lets serializers track and limit how deeply values are
+    /// nested. [`nested`](Serializer::nested) makes both calls.
+    #[inline]
+    fn enter_nested(&mut self, len: usize) -> Result<(), Self::Error> {
+        let _ = len;
+        Ok(())
+    }
+
+    /// Called by builtin types after they serialize the values behind a pointer.
+    #[inline]
+    fn exit_nested(&mut self) {}
+
+    /// Serializes `len` values behind a pointer with `f`, calling
+    /// [`enter_nested`](Serializer::enter_nested) before and
+    /// [`exit_nested`](Serializer::exit_nested) after even if `f` fails.
+    #[inline]
+    fn nested<T, F>(&mut self, len: usize, f: F) -> Result<T, Self::Error>
+    where
+        F: FnOnce(&mut Self) -> Result<T, Self::Error>,
+    {
+        self.enter_nested(len)?;
+        let result = f(self);
+        self.exit_nested();
+        result
+    }
+
+    /// Allocates scratch space of the requested size.
+    ///
+    /// Builtin types use scratch space to hold temporary data (e.g. resolvers) while they
+    /// serialize. By default, scratch space is allocated with the global allocator. Serializers
+    /// that have a [`ScratchSpace`] can override this and [`pop_scratch`](Serializer::pop_scratch)
+    /// to allocate from it instead.
+    ///
+    /// # Safety
+    ///
+    /// `layout` must have a non-zero size.
+    #[cfg(feature = "std")]
+    #[inline]
+    unsafe fn push_scratch(&mut self, layout: Layout) -> Result<NonNull<[u8]>, Self::Error> {
+        Ok(alloc_scratch(layout))
+    }
+
+    /// Deallocates scratch space allocated with [`push_scratch`](Serializer::push_scratch).
+    ///
+    /// # Safety
+    ///
+    /// - `ptr` must be the scratch memory last allocated with `push_scratch`.
+    /// - `layout` must be the same layout that was used to allocate that block of memory.
+    #[cfg(feature = "std")]
+    #[inline]
+    unsafe fn pop_scratch(&mut self, ptr: NonNull<u8>, layout: Layout) -> Result<(), Self::Error> {
+        dealloc_scratch(ptr, layout);
+        Ok(())
+    }
+
     /// Resolves the given value with its resolver and writes the archived type.
     ///
     /// Returns the position of the written archived type.
@@ -174,3 +251,75 @@ pub trait SharedSerializer: Serializer {
         value: &T,
     ) -> Result<usize, Self::Error>;
 }
+
+/// A registry that keeps track of the positions of shared values that have already been
+/// serialized.
+///
+/// A serializer can implement [`SharedSerializer`] on top of a registry by looking up each shared
+/// value before serializing it. See [`CompositeSerializer`](serializers::CompositeSerializer) for
+/// an example.
+pub trait SharedSerializeRegistry: Fallible {
+    /// Returns the position of the shared value at the given address if it has already been
+    /// serialized.
+    fn get_shared_ptr(&self, value: *const u8) -> Option<usize>;
+
+    /// Records that the shared value at the given address was serialized at the given position.
+    fn add_shared_ptr(&mut self, value: *const u8, pos: usize) -> Result<(), Self::Error>;
+}
+
+/// An allocator for scratch space.
+///
+/// Scratch space is temporary memory used while serializing (e.g. to hold resolvers until they can
+/// be written). Allocations are made and released in stack order, which lets them be backed by a
+/// fixed buffer so that serialization doesn't have to allocate in the steady state.
+///
+/// Serializers allocate scratch space from the global allocator unless they are paired with a
+/// scratch space, for example with a [`CompositeSerializer`](serializers::CompositeSerializer) or a
+/// [`ScratchSpaceAdapter`](adapters::ScratchSpaceAdapter).
+pub trait ScratchSpace: Fallible {
+    /// Allocates scratch space of the requested size.
+    ///
+    /// # Safety
+    ///
+    /// `layout` must have a non-zero size.
+    unsafe fn push_scratch(&mut self, layout: Layout) -> Result<NonNull<[u8]>, Self::Error>;
+
+    /// Deallocates previously allocated scratch space.
+    ///
+    /// # Safety
+    ///
+    /// - `ptr` must be the scratch memory last allocated with `push_scratch`.
+    /// - `layout` must be the same layout that was used to allocate that block of memory.
+    unsafe fn pop_scratch(&mut self, ptr: NonNull<u8>, layout: Layout) -> Result<(), Self::Error>;
+}
+
+#[cfg(feature = "std")]
+#[inline]
+unsafe fn alloc_scratch(layout: Layout) -> NonNull<[u8]> {
+    let result = std::alloc::alloc(layout);
+    if result.is_null() {
+        std::alloc::handle_alloc_error(layout);
+    }
+    NonNull::new_unchecked(core::ptr::slice_from_raw_parts_mut(result, layout.size()))
+}
+
+#[cfg(feature = "std")]
+#[inline]
+unsafe fn dealloc_scratch(ptr: NonNull<u8>, layout: Layout) {
+    std::alloc::dealloc(ptr.as_ptr(), layout);
+}
+
+/// Allocates scratch space with the global allocator.
+#[cfg(feature = "std")]
+impl ScratchSpace for Infallible {
+    #[inline]
+    unsafe fn push_scratch(&mut self, layout: Layout) -> Result<NonNull<[u8]>, Self::Error> {
+        Ok(alloc_scratch(layout))
+    }
+
+    #[inline]
+    unsafe fn pop_scratch(&mut self, ptr: NonNull<u8>, layout: Layout) -> Result<(), Self::Error> {
+        dealloc_scratch(ptr, layout);
+        Ok(())
+    }
+}
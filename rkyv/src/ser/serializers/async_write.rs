@@ -0,0 +1,403 @@
+use crate::{
+    ser::{
+        serializers::{CompositeSerializer, CompositeSerializerError},
+        ScratchSpace, Serializer,
+    },
+    Fallible, Serialize,
+};
+use core::{
+    future::Future,
+    pin::Pin,
+    ptr,
+    task::{Context, Poll, RawWaker, RawWakerVTable, Waker},
+};
+use std::io;
+
+/// A byte sink that can be written to asynchronously.
+///
+/// With the `futures_io` feature, this is implemented for all types that implement
+/// [`futures_io::AsyncWrite`]. With the `tokio_io` feature, types that implement
+/// [`tokio::io::AsyncWrite`] can be wrapped in a [`TokioSink`] to implement it.
+pub trait AsyncSink {
+    /// Attempts to write bytes from `buf` into the sink, returning how many bytes were written.
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>>;
+
+    /// Attempts to flush the sink, ensuring that all written bytes reach their destination.
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>>;
+}
+
+#[cfg(feature = "futures_io")]
+impl<W: futures_io::AsyncWrite + ?Sized> AsyncSink for W {
+    #[inline]
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        futures_io::AsyncWrite::poll_write(self, cx, buf)
+    }
+
+    #[inline]
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        futures_io::AsyncWrite::poll_flush(self, cx)
+    }
+}
+
+/// Wraps a [`tokio::io::AsyncWrite`] and equips it with [`AsyncSink`].
+#[cfg(feature = "tokio_io")]
+#[derive(Debug)]
+pub struct TokioSink<W>(pub W);
+
+#[cfg(feature = "tokio_io")]
+impl<W> TokioSink<W> {
+    /// Consumes the sink and returns the wrapped writer.
+    #[inline]
+    pub fn into_inner(self) -> W {
+        self.0
+    }
+}
+
+#[cfg(feature = "tokio_io")]
+impl<W: tokio::io::AsyncWrite + Unpin> AsyncSink for TokioSink<W> {
+    #[inline]
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        tokio::io::AsyncWrite::poll_write(Pin::new(&mut self.get_mut().0), cx, buf)
+    }
+
+    #[inline]
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        tokio::io::AsyncWrite::poll_flush(Pin::new(&mut self.get_mut().0), cx)
+    }
+}
+
+/// Buffers archived bytes and flushes them to an [`AsyncSink`].
+///
+/// Serializing is synchronous, so bytes are collected in a buffer as they're written. The buffer is
+/// written to the sink when the serializer is [flushed](AsyncWriteSerializer::flush), after which
+/// its memory is reused. Archived bytes never change after they're written, so only the bytes
+/// that haven't reached the sink yet are ever held in memory.
+///
+/// Once more than a [threshold](AsyncWriteSerializer::with_flush_threshold) of bytes are buffered,
+/// writing to the serializer also writes buffered bytes to the sink for as long as the sink is
+/// ready to accept them. This keeps the buffer small while serializing large values to fast sinks.
+/// Slow sinks that aren't ready get the remaining bytes when the serializer is flushed, or when
+/// waiting for the serializer to be [ready](AsyncWriteSerializer::ready).
+///
+/// Serializing a value can't wait for the sink, so all of a value's bytes may end up buffered if
+/// the sink is slower than serialization. To bound the buffer while writing many values, wait for
+/// the serializer to be ready before serializing each one.
+///
+/// Use [`serialize_value_async`](AsyncWriteSerializer::serialize_value_async) to wait until the
+/// serializer is ready, then serialize a value and flush it in one step. Types that need scratch space or shared memory support can be
+/// serialized by wrapping this serializer in a [`CompositeSerializer`], which provides the same
+/// methods.
+///
+/// ## Examples
+/// ```
+/// # #[cfg(feature = "futures_io")]
+/// # {
+/// use rkyv::{
+///     archived_value,
+///     ser::serializers::{AllocScratch, AsyncWriteSerializer, CompositeSerializer},
+///     AlignedVec,
+///     Archive,
+///     Infallible,
+///     Serialize,
+/// };
+///
+/// #[derive(Archive, Serialize)]
+/// struct Example {
+///     name: String,
+///     values: Vec<i32>,
+/// }
+///
+/// async fn write_example(sink: &mut Vec<u8>) -> usize {
+///     let mut serializer = CompositeSerializer::new(
+///         AsyncWriteSerializer::new(sink),
+///         AllocScratch::new(),
+///         Infallible,
+///     );
+///     serializer.serialize_value_async(&Example {
+///         name: "pi".to_string(),
+///         values: vec![3, 1, 4, 1, 5],
+///     }).await.unwrap()
+/// }
+///
+/// # // Writing to a `Vec` is always ready, so the future completes when it's first polled
+/// # fn block_on<F: core::future::Future>(future: F) -> F::Output {
+/// #     use core::{pin::Pin, task::{Context, Poll, RawWaker, RawWakerVTable, Waker}};
+/// #     fn clone(_: *const ()) -> RawWaker { RawWaker::new(core::ptr::null(), &VTABLE) }
+/// #     fn noop(_: *const ()) {}
+/// #     static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+/// #     let waker = unsafe { Waker::from_raw(clone(core::ptr::null())) };
+/// #     let mut future = Box::pin(future);
+/// #     match future.as_mut().poll(&mut Context::from_waker(&waker)) {
+/// #         Poll::Ready(output) => output,
+/// #         Poll::Pending => panic!("future was not ready"),
+/// #     }
+/// # }
+/// let mut sink = Vec::new();
+/// let pos = block_on(write_example(&mut sink));
+///
+/// let mut buf = AlignedVec::new();
+/// buf.extend_from_slice(&sink);
+/// let archived = unsafe { archived_value::<Example>(buf.as_ref(), pos) };
+/// assert_eq!(archived.name, "pi");
+/// assert_eq!(archived.values.as_slice(), &[3, 1, 4, 1, 5]);
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct AsyncWriteSerializer<W> {
+    inner: W,
+    buffer: Vec<u8>,
+    // The number of bytes at the start of the buffer that have already been written to the sink
+    written: usize,
+    flush_threshold: usize,
+    // The number of buffered bytes at which to try writing to the sink again
+    write_at: usize,
+    pos: usize,
+}
+
+impl<W> AsyncWriteSerializer<W> {
+    /// The default number of buffered bytes past which writes are passed on to the sink.
+    pub const DEFAULT_FLUSH_THRESHOLD: usize = 64 * 1024;
+
+    /// Creates a new serializer from a sink.
+    #[inline]
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            buffer: Vec::new(),
+            written: 0,
+            flush_threshold: Self::DEFAULT_FLUSH_THRESHOLD,
+            write_at: Self::DEFAULT_FLUSH_THRESHOLD,
+            pos: 0,
+        }
+    }
+
+    /// Passes writes on to the sink whenever more than `threshold` bytes are buffered.
+    ///
+    /// If the sink isn't ready, it's tried again after another `threshold` bytes are buffered.
+    #[inline]
+    pub fn with_flush_threshold(mut self, threshold: usize) -> Self {
+        self.flush_threshold = threshold;
+        self.write_at = threshold;
+        self
+    }
+
+    /// Returns the bytes that have been written but not yet passed on to the sink.
+    #[inline]
+    pub fn buffered(&self) -> &[u8] {
+        &self.buffer[self.written..]
+    }
+
+    /// Consumes the serializer and returns the sink.
+    ///
+    /// Any bytes that have not been flushed are discarded.
+    #[inline]
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: AsyncSink + Unpin> AsyncWriteSerializer<W> {
+    /// Writes buffered bytes to the sink until no more than `len` bytes are left in the buffer.
+    fn poll_write_buffered_to(&mut self, cx: &mut Context<'_>, len: usize) -> Poll<io::Result<()>> {
+        while self.buffered().len() > len {
+            match Pin::new(&mut self.inner).poll_write(cx, &self.buffer[self.written..]) {
+                Poll::Ready(Ok(0)) => {
+                    return Poll::Ready(Err(io::Error::new(
+                        io::ErrorKind::WriteZero,
+                        "failed to write buffered bytes to sink",
+                    )))
+                }
+                Poll::Ready(Ok(len)) => self.written += len,
+                Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    /// Writes buffered bytes to the sink until the buffer is empty.
+    fn poll_write_buffered(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.poll_write_buffered_to(cx, 0) {
+            Poll::Ready(Ok(())) => {
+                self.buffer.clear();
+                self.written = 0;
+                self.write_at = self.flush_threshold;
+                Poll::Ready(Ok(()))
+            }
+            poll => poll,
+        }
+    }
+
+    /// Drops the bytes that have been written to the sink once they make up most of the buffer, so
+    /// moving the rest is cheap.
+    fn compact(&mut self) {
+        if self.written > self.buffer.len() / 2 {
+            self.buffer.drain(..self.written);
+            self.written = 0;
+        }
+        self.write_at = self.buffered().len().saturating_add(self.flush_threshold);
+    }
+
+    /// Writes buffered bytes to the sink for as long as it's ready to accept them.
+    fn write_ready(&mut self) -> io::Result<()> {
+        let waker = noop_waker();
+        if let Poll::Ready(Err(e)) = self.poll_write_buffered(&mut Context::from_waker(&waker)) {
+            return Err(e);
+        }
+        self.compact();
+        Ok(())
+    }
+
+    /// Writes buffered bytes to the sink until no more than the flush threshold are buffered.
+    ///
+    /// Unlike [`flush`](AsyncWriteSerializer::flush), this doesn't wait for the sink to accept all
+    /// of the buffered bytes or flush the sink.
+    #[inline]
+    pub fn ready(&mut self) -> Ready<'_, W> {
+        Ready { serializer: self }
+    }
+
+    /// Writes all buffered bytes to the sink and flushes it.
+    #[inline]
+    pub fn flush(&mut self) -> Flush<'_, W> {
+        Flush { serializer: self }
+    }
+
+    /// Waits until the serializer is [ready](AsyncWriteSerializer::ready), then archives the given
+    /// value, flushes it to the sink, and returns the position of the root object.
+    pub async fn serialize_value_async<T: Serialize<Self>>(
+        &mut self,
+        value: &T,
+    ) -> io::Result<usize> {
+        self.ready().await?;
+        let pos = self.serialize_value(value)?;
+        self.flush().await?;
+        Ok(pos)
+    }
+}
+
+impl<W> Fallible for AsyncWriteSerializer<W> {
+    type Error = io::Error;
+}
+
+impl<W: AsyncSink + Unpin> Serializer for AsyncWriteSerializer<W> {
+    #[inline]
+    fn pos(&self) -> usize {
+        self.pos
+    }
+
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.buffer.extend_from_slice(bytes);
+        self.pos += bytes.len();
+        if self.buffered().len() > self.write_at {
+            self.write_ready()?;
+        }
+        Ok(())
+    }
+}
+
+// Writes made while serializing can't wait for the sink, so they're polled with a waker that does
+// nothing. A sink that isn't ready is polled again with a real waker when the serializer is flushed
+// or waited on.
+fn noop_waker() -> Waker {
+    fn clone(_: *const ()) -> RawWaker {
+        RawWaker::new(ptr::null(), &VTABLE)
+    }
+    fn noop(_: *const ()) {}
+    static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+
+    unsafe { Waker::from_raw(clone(ptr::null())) }
+}
+
+/// The future returned by [`AsyncWriteSerializer::ready`].
+#[derive(Debug)]
+#[must_use = "futures do nothing unless polled"]
+pub struct Ready<'a, W> {
+    serializer: &'a mut AsyncWriteSerializer<W>,
+}
+
+impl<W: AsyncSink + Unpin> Future for Ready<'_, W> {
+    type Output = io::Result<()>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let serializer = &mut *self.get_mut().serializer;
+        let threshold = serializer.flush_threshold;
+        match serializer.poll_write_buffered_to(cx, threshold) {
+            Poll::Ready(Ok(())) => {
+                serializer.compact();
+                Poll::Ready(Ok(()))
+            }
+            poll => poll,
+        }
+    }
+}
+
+/// The future returned by [`AsyncWriteSerializer::flush`].
+#[derive(Debug)]
+#[must_use = "futures do nothing unless polled"]
+pub struct Flush<'a, W> {
+    serializer: &'a mut AsyncWriteSerializer<W>,
+}
+
+impl<W: AsyncSink + Unpin> Future for Flush<'_, W> {
+    type Output = io::Result<()>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let serializer = &mut *self.get_mut().serializer;
+        match serializer.poll_write_buffered(cx) {
+            Poll::Ready(Ok(())) => Pin::new(&mut serializer.inner).poll_flush(cx),
+            poll => poll,
+        }
+    }
+}
+
+impl<W: AsyncSink + Unpin, C: ScratchSpace, H: Fallible>
+    CompositeSerializer<AsyncWriteSerializer<W>, C, H>
+{
+    /// Writes buffered bytes to the sink until no more than the flush threshold are buffered.
+    ///
+    /// See [`AsyncWriteSerializer::ready`] for more details.
+    #[inline]
+    pub fn ready(&mut self) -> Ready<'_, W> {
+        self.serializer.ready()
+    }
+
+    /// Writes all buffered bytes to the sink and flushes it.
+    ///
+    /// See [`AsyncWriteSerializer::flush`] for more details.
+    #[inline]
+    pub fn flush(&mut self) -> Flush<'_, W> {
+        self.serializer.flush()
+    }
+
+    /// Waits until the serializer is ready, then archives the given value, flushes it to the sink,
+    /// and returns the position of the root object.
+    ///
+    /// See [`AsyncWriteSerializer::serialize_value_async`] for more details.
+    pub async fn serialize_value_async<T: Serialize<Self>>(
+        &mut self,
+        value: &T,
+    ) -> Result<usize, CompositeSerializerError<io::Error, C::Error, H::Error>> {
+        self.ready()
+            .await
+            .map_err(CompositeSerializerError::SerializerError)?;
+        let pos = self.serialize_value(value)?;
+        self.flush()
+            .await
+            .map_err(CompositeSerializerError::SerializerError)?;
+        Ok(pos)
+    }
+}
@@ -0,0 +1,164 @@
+use crate::{
+    ser::{SeekSerializer, Serializer},
+    Fallible,
+};
+use memmap2::MmapMut;
+use std::{fs::File, io};
+
+/// Serializes directly into a memory-mapped file.
+///
+/// The file is grown geometrically as the archive is written, and is truncated to the length of the
+/// archive when the serializer is [finished](MmapSerializer::finish). Bytes are written straight
+/// into the memory map, so the archive is never buffered or held in memory as a whole.
+///
+/// Use [`MmapArchive`](crate::MmapArchive) to load the archive back from the file.
+///
+/// ## Examples
+/// ```
+/// use rkyv::{
+///     ser::{serializers::MmapSerializer, Serializer},
+///     Archive,
+///     MmapArchive,
+///     Serialize,
+/// };
+/// use std::fs::OpenOptions;
+///
+/// #[derive(Archive, Serialize)]
+/// struct Example {
+///     name: String,
+///     value: i32,
+/// }
+///
+/// let path = std::env::temp_dir().join("rkyv_mmap_serializer_example");
+/// let file = OpenOptions::new()
+///     .read(true)
+///     .write(true)
+///     .create(true)
+///     .truncate(true)
+///     .open(&path)
+///     .unwrap();
+///
+/// let mut serializer = MmapSerializer::new(file).unwrap();
+/// serializer.serialize_value(&Example {
+///     name: "pi".to_string(),
+///     value: 31415926,
+/// }).unwrap();
+/// let file = serializer.finish().unwrap();
+///
+/// let archive = unsafe { MmapArchive::map(&file).unwrap() };
+/// let archived = unsafe { archive.archived_root::<Example>() };
+/// assert_eq!(archived.name, "pi");
+/// assert_eq!(archived.value, 31415926);
+/// # drop(archive);
+/// # std::fs::remove_file(path).unwrap();
+/// ```
+pub struct MmapSerializer {
+    file: File,
+    mmap: MmapMut,
+    pos: usize,
+    len: usize,
+}
+
+impl MmapSerializer {
+    /// The number of bytes that are mapped when a serializer is created with [`new`](Self::new).
+    pub const DEFAULT_CAPACITY: usize = 4096;
+
+    /// Creates a new serializer that writes to the given file.
+    ///
+    /// The file must be opened for reading and writing. Any existing contents of the file are
+    /// discarded.
+    #[inline]
+    pub fn new(file: File) -> io::Result<Self> {
+        Self::with_capacity(file, Self::DEFAULT_CAPACITY)
+    }
+
+    /// Creates a new serializer that writes to the given file and initially maps `capacity` bytes
+    /// of it.
+    ///
+    /// The file must be opened for reading and writing. Any existing contents of the file are
+    /// discarded.
+    pub fn with_capacity(file: File, capacity: usize) -> io::Result<Self> {
+        // Truncating first zeroes any existing contents
+        file.set_len(0)?;
+        file.set_len(capacity as u64)?;
+        let mmap = unsafe { MmapMut::map_mut(&file)? };
+        Ok(Self {
+            file,
+            mmap,
+            pos: 0,
+            len: 0,
+        })
+    }
+
+    /// Returns the number of bytes that are currently mapped.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.mmap.len()
+    }
+
+    /// Flushes the archive to the file, truncates the file to the length of the archive, and
+    /// returns the file.
+    pub fn finish(self) -> io::Result<File> {
+        let Self {
+            file, mmap, len, ..
+        } = self;
+        mmap.flush()?;
+        // The file has to be unmapped before it can be truncated
+        drop(mmap);
+        file.set_len(len as u64)?;
+        Ok(file)
+    }
+
+    /// Grows the file and remaps it if necessary so that at least `end` bytes are mapped.
+    fn reserve(&mut self, end: usize) -> io::Result<()> {
+        if end > self.mmap.len() {
+            let capacity = end.max(self.mmap.len().saturating_mul(2));
+            self.file.set_len(capacity as u64)?;
+            self.mmap = unsafe { MmapMut::map_mut(&self.file)? };
+        }
+        Ok(())
+    }
+}
+
+impl Fallible for MmapSerializer {
+    type Error = io::Error;
+}
+
+impl Serializer for MmapSerializer {
+    #[inline]
+    fn pos(&self) -> usize {
+        self.pos
+    }
+
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) -> Result<(), Self::Error> {
+        let end = self.pos + bytes.len();
+        self.reserve(end)?;
+        self.mmap[self.pos..end].copy_from_slice(bytes);
+        self.pos = end;
+        self.len = self.len.max(end);
+        Ok(())
+    }
+
+    #[inline]
+    fn pad(&mut self, padding: usize) -> Result<(), Self::Error> {
+        let end = self.pos + padding;
+        self.reserve(end)?;
+        for byte in self.mmap[self.pos..end].iter_mut() {
+            *byte = 0;
+        }
+        self.pos = end;
+        self.len = self.len.max(end);
+        Ok(())
+    }
+}
+
+impl SeekSerializer for MmapSerializer {
+    #[inline]
+    fn seek(&mut self, pos: usize) -> Result<(), Self::Error> {
+        self.reserve(pos)?;
+        self.pos = pos;
+        self.len = self.len.max(pos);
+        Ok(())
+    }
+}
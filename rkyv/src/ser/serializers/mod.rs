@@ -1,14 +1,24 @@
 //! Serializers that can be used standalone and provide basic capabilities.
 
+#[cfg(any(feature = "futures_io", feature = "tokio_io"))]
+mod async_write;
+#[cfg(feature = "mmap")]
+mod mmap;
 #[cfg(feature = "std")]
 mod std;
 
 use crate::{
-    ser::{SeekSerializer, Serializer},
-    Fallible,
+    ser::{ScratchSpace, SeekSerializer, Serializer, SharedSerializeRegistry, SharedSerializer},
+    Archive, ArchiveUnsized, Fallible, Infallible, SerializeUnsized,
 };
-use core::ptr;
+use core::{alloc::Layout, fmt, mem, ptr, ptr::NonNull};
 
+#[doc(inline)]
+#[cfg(any(feature = "futures_io", feature = "tokio_io"))]
+pub use self::async_write::*;
+#[doc(inline)]
+#[cfg(feature = "mmap")]
+pub use self::mmap::*;
 #[doc(inline)]
 #[cfg(feature = "std")]
 pub use self::std::*;
@@ -153,3 +163,488 @@ impl<T: AsRef<[u8]> + AsMut<[u8]>> SeekSerializer for BufferSerializer<T> {
         }
     }
 }
+
+/// The error type returned by [`BufferScratch`] and other fixed-size scratch spaces.
+#[derive(Debug)]
+pub enum FixedSizeScratchError {
+    /// There was not enough space left in the buffer for the requested allocation.
+    OutOfSpace {
+        /// The size of the requested allocation
+        size: usize,
+        /// The alignment of the requested allocation
+        align: usize,
+        /// The number of bytes left in the buffer
+        remaining: usize,
+    },
+    /// The popped memory was not the last memory pushed.
+    NotPoppedInReverseOrder {
+        /// The offset of the popped memory in the buffer
+        offset: usize,
+        /// The size of the popped memory
+        size: usize,
+        /// The offset of the end of the allocated space in the buffer
+        pos: usize,
+    },
+    /// The popped memory was not allocated from this scratch space.
+    UnownedAllocation,
+}
+
+impl fmt::Display for FixedSizeScratchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FixedSizeScratchError::OutOfSpace {
+                size,
+                align,
+                remaining,
+            } => write!(
+                f,
+                "out of scratch space: requested {} bytes with alignment {} but only {} bytes remain",
+                size, align, remaining
+            ),
+            FixedSizeScratchError::NotPoppedInReverseOrder { offset, size, pos } => write!(
+                f,
+                "scratch space was not popped in reverse order: popped {} bytes at offset {} but the allocated space ends at offset {}",
+                size, offset, pos
+            ),
+            FixedSizeScratchError::UnownedAllocation => {
+                write!(f, "popped scratch space that was not allocated from this buffer")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl ::std::error::Error for FixedSizeScratchError {}
+
+/// Scratch space that allocates from a fixed-size byte buffer.
+///
+/// Allocations are made from the front of the buffer and must be popped in reverse order. The
+/// buffer does not need to be aligned; allocations are aligned by padding them inside the buffer.
+/// Each allocation is preceded by a small header that records the allocation before it, so popping
+/// anything other than the last allocation is caught.
+///
+/// ## Examples
+/// ```
+/// use core::alloc::Layout;
+/// use rkyv::ser::{serializers::BufferScratch, ScratchSpace};
+///
+/// let mut scratch = BufferScratch::new([0u8; 64]);
+/// unsafe {
+///     let layout = Layout::new::<[u32; 4]>();
+///     let memory = scratch.push_scratch(layout).unwrap();
+///     assert_eq!(memory.as_ref().len(), 16);
+///     scratch.pop_scratch(memory.cast(), layout).unwrap();
+///
+///     assert!(scratch.push_scratch(Layout::new::<[u8; 128]>()).is_err());
+/// }
+/// ```
+pub struct BufferScratch<T> {
+    buffer: T,
+    pos: usize,
+    top: usize,
+}
+
+/// The header written before each allocation: the position and top of the scratch space before the
+/// allocation was made.
+type BufferScratchHeader = [usize; 2];
+
+impl<T> BufferScratch<T> {
+    /// Creates a new scratch space that allocates from the given buffer.
+    #[inline]
+    pub fn new(buffer: T) -> Self {
+        Self {
+            buffer,
+            pos: 0,
+            top: 0,
+        }
+    }
+
+    /// Releases all of the scratch space allocated from the buffer.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.pos = 0;
+        self.top = 0;
+    }
+
+    /// Consumes the scratch space and returns the underlying buffer.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.buffer
+    }
+}
+
+impl<T> Fallible for BufferScratch<T> {
+    type Error = FixedSizeScratchError;
+}
+
+impl<T: AsMut<[u8]>> ScratchSpace for BufferScratch<T> {
+    unsafe fn push_scratch(&mut self, layout: Layout) -> Result<NonNull<[u8]>, Self::Error> {
+        let bytes = self.buffer.as_mut();
+        let start = bytes.as_mut_ptr() as usize;
+        let unaligned = start + self.pos + mem::size_of::<BufferScratchHeader>();
+        let mask = layout.align() - 1;
+        let offset = ((unaligned + mask) & !mask) - start;
+        match offset.checked_add(layout.size()) {
+            Some(end) if end <= bytes.len() => {
+                let result = bytes.as_mut_ptr().add(offset);
+                result
+                    .sub(mem::size_of::<BufferScratchHeader>())
+                    .cast::<BufferScratchHeader>()
+                    .write_unaligned([self.pos, self.top]);
+                self.pos = end;
+                self.top = offset;
+                Ok(NonNull::new_unchecked(ptr::slice_from_raw_parts_mut(
+                    result,
+                    layout.size(),
+                )))
+            }
+            _ => Err(FixedSizeScratchError::OutOfSpace {
+                size: layout.size(),
+                align: layout.align(),
+                remaining: bytes.len() - self.pos,
+            }),
+        }
+    }
+
+    unsafe fn pop_scratch(&mut self, ptr: NonNull<u8>, layout: Layout) -> Result<(), Self::Error> {
+        let bytes = self.buffer.as_mut();
+        let start = bytes.as_mut_ptr() as usize;
+        let addr = ptr.as_ptr() as usize;
+        if addr < start || addr >= start + bytes.len() {
+            Err(FixedSizeScratchError::UnownedAllocation)
+        } else {
+            let offset = addr - start;
+            if self.pos == 0 || offset != self.top || offset + layout.size() != self.pos {
+                Err(FixedSizeScratchError::NotPoppedInReverseOrder {
+                    offset,
+                    size: layout.size(),
+                    pos: self.pos,
+                })
+            } else {
+                let [pos, top] = ptr
+                    .as_ptr()
+                    .sub(mem::size_of::<BufferScratchHeader>())
+                    .cast::<BufferScratchHeader>()
+                    .read_unaligned();
+                self.pos = pos;
+                self.top = top;
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Scratch space that tries to allocate from a main scratch space and falls back to another scratch
+/// space when that fails.
+///
+/// This is most commonly used to pair a fixed-size scratch space with a heap allocator, so that
+/// small serializations don't allocate and large ones still succeed.
+pub struct FallbackScratch<M, F> {
+    main: M,
+    fallback: F,
+}
+
+impl<M, F> FallbackScratch<M, F> {
+    /// Creates a new fallback scratch space from a main and fallback scratch space.
+    #[inline]
+    pub fn new(main: M, fallback: F) -> Self {
+        Self { main, fallback }
+    }
+
+    /// Consumes the scratch space and returns the main and fallback scratch spaces.
+    #[inline]
+    pub fn into_inner(self) -> (M, F) {
+        (self.main, self.fallback)
+    }
+}
+
+impl<M: Default, F: Default> Default for FallbackScratch<M, F> {
+    #[inline]
+    fn default() -> Self {
+        Self::new(M::default(), F::default())
+    }
+}
+
+impl<M, F: Fallible> Fallible for FallbackScratch<M, F> {
+    type Error = F::Error;
+}
+
+impl<M: ScratchSpace, F: ScratchSpace> ScratchSpace for FallbackScratch<M, F> {
+    #[inline]
+    unsafe fn push_scratch(&mut self, layout: Layout) -> Result<NonNull<[u8]>, Self::Error> {
+        match self.main.push_scratch(layout) {
+            Ok(result) => Ok(result),
+            Err(_) => self.fallback.push_scratch(layout),
+        }
+    }
+
+    #[inline]
+    unsafe fn pop_scratch(&mut self, ptr: NonNull<u8>, layout: Layout) -> Result<(), Self::Error> {
+        match self.main.pop_scratch(ptr, layout) {
+            Ok(()) => Ok(()),
+            Err(_) => self.fallback.pop_scratch(ptr, layout),
+        }
+    }
+}
+
+/// A serializer built from composeable pieces.
+///
+/// The serializer writes with `S`, allocates scratch space with `C`, and keeps track of shared
+/// values with `H`. It implements every serializer trait that its pieces support:
+///
+/// - [`Serializer`] if `S` is a `Serializer` and `C` is a [`ScratchSpace`]
+/// - [`SeekSerializer`] if `S` is a `SeekSerializer`
+/// - [`SharedSerializer`] if `S` is a `Serializer` and `H` is a [`SharedSerializeRegistry`]
+///
+/// Pieces that aren't needed can be left as [`Infallible`]. With the `std` feature, `Infallible`
+/// allocates scratch space with the global allocator.
+///
+/// ## Examples
+/// ```
+/// use rkyv::{
+///     archived_value,
+///     ser::{
+///         serializers::{BufferScratch, BufferSerializer, CompositeSerializer},
+///         Serializer,
+///     },
+///     Aligned,
+///     Infallible,
+/// };
+///
+/// let mut serializer = CompositeSerializer::new(
+///     BufferSerializer::new(Aligned([0u8; 256])),
+///     BufferScratch::new([0u8; 64]),
+///     Infallible,
+/// );
+/// let pos = serializer.serialize_value(&vec![1, 2, 3, 4])
+///     .expect("failed to archive value");
+/// let buf = serializer.into_serializer().into_inner();
+/// let archived = unsafe { archived_value::<Vec<i32>>(buf.as_ref(), pos) };
+/// assert_eq!(archived.as_slice(), &[1, 2, 3, 4]);
+/// ```
+#[derive(Debug)]
+pub struct CompositeSerializer<S = Infallible, C = Infallible, H = Infallible> {
+    serializer: S,
+    scratch: C,
+    shared: H,
+}
+
+impl<S, C, H> CompositeSerializer<S, C, H> {
+    /// Creates a new composite serializer from a serializer, scratch space, and shared registry.
+    #[inline]
+    pub fn new(serializer: S, scratch: C, shared: H) -> Self {
+        Self {
+            serializer,
+            scratch,
+            shared,
+        }
+    }
+
+    /// Consumes the composite serializer and returns the serializer.
+    #[inline]
+    pub fn into_serializer(self) -> S {
+        self.serializer
+    }
+
+    /// Consumes the composite serializer and returns the serializer, scratch space, and shared
+    /// registry.
+    #[inline]
+    pub fn into_components(self) -> (S, C, H) {
+        (self.serializer, self.scratch, self.shared)
+    }
+}
+
+impl<S: Default, C: Default, H: Default> Default for CompositeSerializer<S, C, H> {
+    #[inline]
+    fn default() -> Self {
+        Self::new(S::default(), C::default(), H::default())
+    }
+}
+
+/// The error type returned by a [`CompositeSerializer`].
+#[derive(Debug)]
+pub enum CompositeSerializerError<S, C, H> {
+    /// An error occurred while serializing
+    SerializerError(S),
+    /// An error occurred while allocating scratch space
+    ScratchSpaceError(C),
+    /// An error occurred while serializing shared memory
+    SharedError(H),
+}
+
+impl<S, C, H> fmt::Display for CompositeSerializerError<S, C, H>
+where
+    S: fmt::Display,
+    C: fmt::Display,
+    H: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompositeSerializerError::SerializerError(e) => write!(f, "{}", e),
+            CompositeSerializerError::ScratchSpaceError(e) => {
+                write!(f, "scratch space error: {}", e)
+            }
+            CompositeSerializerError::SharedError(e) => write!(f, "shared memory error: {}", e),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<S, C, H> ::std::error::Error for CompositeSerializerError<S, C, H>
+where
+    S: ::std::error::Error + 'static,
+    C: ::std::error::Error + 'static,
+    H: ::std::error::Error + 'static,
+{
+    fn source(&self) -> Option<&(dyn ::std::error::Error + 'static)> {
+        match self {
+            CompositeSerializerError::SerializerError(e) => Some(e as &dyn ::std::error::Error),
+            CompositeSerializerError::ScratchSpaceError(e) => Some(e as &dyn ::std::error::Error),
+            CompositeSerializerError::SharedError(e) => Some(e as &dyn ::std::error::Error),
+        }
+    }
+}
+
+impl<S: Fallible, C: Fallible, H: Fallible> Fallible for CompositeSerializer<S, C, H> {
+    type Error = CompositeSerializerError<S::Error, C::Error, H::Error>;
+}
+
+impl<S: Serializer, C: ScratchSpace, H: Fallible> Serializer for CompositeSerializer<S, C, H> {
+    #[inline]
+    fn pos(&self) -> usize {
+        self.serializer.pos()
+    }
+
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.serializer
+            .write(bytes)
+            .map_err(CompositeSerializerError::SerializerError)
+    }
+
+    #[inline]
+    fn pad(&mut self, padding: usize) -> Result<(), Self::Error> {
+        self.serializer
+            .pad(padding)
+            .map_err(CompositeSerializerError::SerializerError)
+    }
+
+    #[inline]
+    fn align(&mut self, align: usize) -> Result<usize, Self::Error> {
+        self.serializer
+            .align(align)
+            .map_err(CompositeSerializerError::SerializerError)
+    }
+
+    #[inline]
+    fn align_for<T>(&mut self) -> Result<usize, Self::Error> {
+        self.serializer
+            .align_for::<T>()
+            .map_err(CompositeSerializerError::SerializerError)
+    }
+
+    #[inline]
+    fn write_plain_bytes(&mut self, bytes: &[u8], align: usize) -> Result<usize, Self::Error> {
+        self.serializer
+            .write_plain_bytes(bytes, align)
+            .map_err(CompositeSerializerError::SerializerError)
+    }
+
+    #[inline]
+    fn enter_nested(&mut self, len: usize) -> Result<(), Self::Error> {
+        self.serializer
+            .enter_nested(len)
+            .map_err(CompositeSerializerError::SerializerError)
+    }
+
+    #[inline]
+    fn exit_nested(&mut self) {
+        self.serializer.exit_nested()
+    }
+
+    #[cfg(feature = "std")]
+    #[inline]
+    unsafe fn push_scratch(&mut self, layout: Layout) -> Result<NonNull<[u8]>, Self::Error> {
+        self.scratch
+            .push_scratch(layout)
+            .map_err(CompositeSerializerError::ScratchSpaceError)
+    }
+
+    #[cfg(feature = "std")]
+    #[inline]
+    unsafe fn pop_scratch(&mut self, ptr: NonNull<u8>, layout: Layout) -> Result<(), Self::Error> {
+        self.scratch
+            .pop_scratch(ptr, layout)
+            .map_err(CompositeSerializerError::ScratchSpaceError)
+    }
+
+    #[inline]
+    unsafe fn resolve_aligned<T: Archive + ?Sized>(
+        &mut self,
+        value: &T,
+        resolver: T::Resolver,
+    ) -> Result<usize, Self::Error> {
+        self.serializer
+            .resolve_aligned(value, resolver)
+            .map_err(CompositeSerializerError::SerializerError)
+    }
+
+    #[inline]
+    unsafe fn resolve_unsized_aligned<T: ArchiveUnsized + ?Sized>(
+        &mut self,
+        value: &T,
+        to: usize,
+        metadata_resolver: T::MetadataResolver,
+    ) -> Result<usize, Self::Error> {
+        self.serializer
+            .resolve_unsized_aligned(value, to, metadata_resolver)
+            .map_err(CompositeSerializerError::SerializerError)
+    }
+}
+
+impl<S: SeekSerializer, C: ScratchSpace, H: Fallible> SeekSerializer
+    for CompositeSerializer<S, C, H>
+{
+    #[inline]
+    fn seek(&mut self, pos: usize) -> Result<(), Self::Error> {
+        self.serializer
+            .seek(pos)
+            .map_err(CompositeSerializerError::SerializerError)
+    }
+}
+
+impl<S: Fallible, C: Fallible, H: SharedSerializeRegistry> SharedSerializeRegistry
+    for CompositeSerializer<S, C, H>
+{
+    #[inline]
+    fn get_shared_ptr(&self, value: *const u8) -> Option<usize> {
+        self.shared.get_shared_ptr(value)
+    }
+
+    #[inline]
+    fn add_shared_ptr(&mut self, value: *const u8, pos: usize) -> Result<(), Self::Error> {
+        self.shared
+            .add_shared_ptr(value, pos)
+            .map_err(CompositeSerializerError::SharedError)
+    }
+}
+
+impl<S: Serializer, C: ScratchSpace, H: SharedSerializeRegistry> SharedSerializer
+    for CompositeSerializer<S, C, H>
+{
+    fn serialize_shared<T: SerializeUnsized<Self> + ?Sized>(
+        &mut self,
+        value: &T,
+    ) -> Result<usize, Self::Error> {
+        let key = (value as *const T).cast::<u8>();
+        if let Some(pos) = self.shared.get_shared_ptr(key) {
+            Ok(pos)
+        } else {
+            let pos = value.serialize_unsized(self)?;
+            self.shared
+                .add_shared_ptr(key, pos)
+                .map_err(CompositeSerializerError::SharedError)?;
+            Ok(pos)
+        }
+    }
+}
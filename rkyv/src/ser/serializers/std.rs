@@ -1,13 +1,25 @@
+#[cfg(feature = "const_generics")]
+use crate::ser::serializers::{BufferScratch, FallbackScratch, FixedSizeScratchError};
 use crate::{
-    ser::{SeekSerializer, Serializer},
+    ser::{
+        serializers::CompositeSerializer, ScratchSpace, SeekSerializer, Serializer,
+        SharedSerializeRegistry, SharedSerializer,
+    },
     util::AlignedVec,
-    Archive, ArchiveUnsized, Fallible, RelPtr, Unreachable,
+    Archive, ArchiveUnsized, Fallible, RelPtr, SerializeUnsized, Unreachable,
 };
 use core::{
+    alloc::Layout,
     borrow::{Borrow, BorrowMut},
-    mem,
+    fmt, mem,
+    ptr::{self, NonNull},
+};
+use std::{
+    alloc,
+    collections::{hash_map::Entry, HashMap},
+    error::Error,
+    io,
 };
-use std::io;
 
 /// Wraps a type that implements [`io::Write`](std::io::Write) and equips it with [`Serializer`].
 ///
@@ -61,7 +73,8 @@ impl<W: io::Write> Serializer for WriteSerializer<W> {
 
     #[inline]
     fn write(&mut self, bytes: &[u8]) -> Result<(), Self::Error> {
-        self.pos += self.inner.write(bytes)?;
+        self.inner.write_all(bytes)?;
+        self.pos += bytes.len();
         Ok(())
     }
 }
@@ -97,6 +110,15 @@ impl<A: Borrow<AlignedVec>> AlignedSerializer<A> {
     }
 }
 
+impl<A: Default> Default for AlignedSerializer<A> {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            inner: A::default(),
+        }
+    }
+}
+
 impl<A> Fallible for AlignedSerializer<A> {
     type Error = Unreachable;
 }
@@ -160,3 +182,646 @@ impl<A: Borrow<AlignedVec> + BorrowMut<AlignedVec>> Serializer for AlignedSerial
         Ok(from)
     }
 }
+
+/// Fixed-size scratch space allocated on the heap.
+///
+/// The `N` bytes of scratch space are allocated once when the scratch space is created and reused
+/// for every allocation after that.
+#[cfg(feature = "const_generics")]
+pub struct HeapScratch<const N: usize> {
+    inner: BufferScratch<Box<[u8]>>,
+}
+
+#[cfg(feature = "const_generics")]
+impl<const N: usize> HeapScratch<N> {
+    /// Creates a new heap scratch space.
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            inner: BufferScratch::new(vec![0u8; N].into_boxed_slice()),
+        }
+    }
+
+    /// Releases all of the scratch space allocated from the buffer.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.inner.clear();
+    }
+}
+
+#[cfg(feature = "const_generics")]
+impl<const N: usize> Default for HeapScratch<N> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "const_generics")]
+impl<const N: usize> Fallible for HeapScratch<N> {
+    type Error = FixedSizeScratchError;
+}
+
+#[cfg(feature = "const_generics")]
+impl<const N: usize> ScratchSpace for HeapScratch<N> {
+    #[inline]
+    unsafe fn push_scratch(&mut self, layout: Layout) -> Result<NonNull<[u8]>, Self::Error> {
+        self.inner.push_scratch(layout)
+    }
+
+    #[inline]
+    unsafe fn pop_scratch(&mut self, ptr: NonNull<u8>, layout: Layout) -> Result<(), Self::Error> {
+        self.inner.pop_scratch(ptr, layout)
+    }
+}
+
+/// The error type returned by [`AllocScratch`].
+#[derive(Debug)]
+pub enum AllocScratchError {
+    /// The allocation would have exceeded the limit of the scratch space.
+    ExceededLimit {
+        /// The size of the requested allocation
+        requested: usize,
+        /// The number of bytes that could still be allocated
+        remaining: usize,
+    },
+    /// The popped memory was not the last memory pushed.
+    NotPoppedInReverseOrder {
+        /// The address of the last allocation
+        expected: *mut u8,
+        /// The layout of the last allocation
+        expected_layout: Layout,
+        /// The address of the popped memory
+        actual: *mut u8,
+        /// The layout of the popped memory
+        actual_layout: Layout,
+    },
+    /// There was no allocation left to pop.
+    NoAllocationsToPop,
+}
+
+impl fmt::Display for AllocScratchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AllocScratchError::ExceededLimit {
+                requested,
+                remaining,
+            } => write!(
+                f,
+                "exceeded the scratch space limit: requested {} bytes but only {} bytes remain",
+                requested, remaining
+            ),
+            AllocScratchError::NotPoppedInReverseOrder {
+                expected,
+                expected_layout,
+                actual,
+                actual_layout,
+            } => write!(
+                f,
+                "scratch space was not popped in reverse order: expected {:p} with size {} and alignment {} but got {:p} with size {} and alignment {}",
+                expected,
+                expected_layout.size(),
+                expected_layout.align(),
+                actual,
+                actual_layout.size(),
+                actual_layout.align()
+            ),
+            AllocScratchError::NoAllocationsToPop => {
+                write!(f, "popped scratch space but there were no allocations to pop")
+            }
+        }
+    }
+}
+
+impl Error for AllocScratchError {}
+
+/// Scratch space that allocates every block of memory with the global allocator.
+///
+/// An optional limit can be placed on the number of bytes that are allocated at once.
+pub struct AllocScratch {
+    remaining: Option<usize>,
+    allocations: Vec<(*mut u8, Layout)>,
+}
+
+// SAFETY: `AllocScratch` exclusively owns all of the memory it has allocated.
+unsafe impl Send for AllocScratch {}
+
+// SAFETY: `AllocScratch` has no interior mutability.
+unsafe impl Sync for AllocScratch {}
+
+impl AllocScratch {
+    /// Creates a new scratch allocator with no allocation limit.
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            remaining: None,
+            allocations: Vec::new(),
+        }
+    }
+
+    /// Creates a new scratch allocator that can allocate at most `limit` bytes at once.
+    #[inline]
+    pub fn with_limit(limit: usize) -> Self {
+        Self {
+            remaining: Some(limit),
+            allocations: Vec::new(),
+        }
+    }
+}
+
+impl Default for AllocScratch {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for AllocScratch {
+    fn drop(&mut self) {
+        for (ptr, layout) in self.allocations.drain(..).rev() {
+            unsafe {
+                alloc::dealloc(ptr, layout);
+            }
+        }
+    }
+}
+
+impl Fallible for AllocScratch {
+    type Error = AllocScratchError;
+}
+
+impl ScratchSpace for AllocScratch {
+    #[inline]
+    unsafe fn push_scratch(&mut self, layout: Layout) -> Result<NonNull<[u8]>, Self::Error> {
+        if let Some(remaining) = self.remaining {
+            if remaining < layout.size() {
+                return Err(AllocScratchError::ExceededLimit {
+                    requested: layout.size(),
+                    remaining,
+                });
+            }
+        }
+        let result = alloc::alloc(layout);
+        if result.is_null() {
+            alloc::handle_alloc_error(layout);
+        }
+        self.allocations.push((result, layout));
+        if let Some(remaining) = self.remaining.as_mut() {
+            *remaining -= layout.size();
+        }
+        Ok(NonNull::new_unchecked(ptr::slice_from_raw_parts_mut(
+            result,
+            layout.size(),
+        )))
+    }
+
+    #[inline]
+    unsafe fn pop_scratch(&mut self, ptr: NonNull<u8>, layout: Layout) -> Result<(), Self::Error> {
+        if let Some(&(last_ptr, last_layout)) = self.allocations.last() {
+            if ptr.as_ptr() == last_ptr && layout == last_layout {
+                alloc::dealloc(last_ptr, last_layout);
+                self.allocations.pop();
+                if let Some(remaining) = self.remaining.as_mut() {
+                    *remaining += layout.size();
+                }
+                Ok(())
+            } else {
+                Err(AllocScratchError::NotPoppedInReverseOrder {
+                    expected: last_ptr,
+                    expected_layout: last_layout,
+                    actual: ptr.as_ptr(),
+                    actual_layout: layout,
+                })
+            }
+        } else {
+            Err(AllocScratchError::NoAllocationsToPop)
+        }
+    }
+}
+
+/// The error type returned by [`SharedSerializeMap`].
+#[derive(Debug)]
+pub enum SharedSerializeMapError {
+    /// A shared value was added to the registry more than once.
+    DuplicateSharedPointer(*const u8),
+}
+
+impl fmt::Display for SharedSerializeMapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SharedSerializeMapError::DuplicateSharedPointer(ptr) => {
+                write!(
+                    f,
+                    "shared pointer {:p} was added to the registry more than once",
+                    ptr
+                )
+            }
+        }
+    }
+}
+
+impl Error for SharedSerializeMapError {}
+
+/// A shared value registry that keeps the positions of serialized shared values in a hash map.
+#[derive(Debug, Default)]
+pub struct SharedSerializeMap {
+    shared_resolvers: HashMap<*const u8, usize>,
+}
+
+// SAFETY: The pointers in the map are only used as keys and never dereferenced.
+unsafe impl Send for SharedSerializeMap {}
+
+// SAFETY: The pointers in the map are only used as keys and never dereferenced.
+unsafe impl Sync for SharedSerializeMap {}
+
+impl SharedSerializeMap {
+    /// Creates a new, empty shared value registry.
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            shared_resolvers: HashMap::new(),
+        }
+    }
+}
+
+impl Fallible for SharedSerializeMap {
+    type Error = SharedSerializeMapError;
+}
+
+impl SharedSerializeRegistry for SharedSerializeMap {
+    #[inline]
+    fn get_shared_ptr(&self, value: *const u8) -> Option<usize> {
+        self.shared_resolvers.get(&value).copied()
+    }
+
+    #[inline]
+    fn add_shared_ptr(&mut self, value: *const u8, pos: usize) -> Result<(), Self::Error> {
+        match self.shared_resolvers.entry(value) {
+            Entry::Occupied(_) => Err(SharedSerializeMapError::DuplicateSharedPointer(value)),
+            Entry::Vacant(e) => {
+                e.insert(pos);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// A serializer that computes the size of an archive without writing any bytes.
+///
+/// Only the position, alignment padding, and positions of shared values are tracked, so it can be
+/// used to find the exact number of bytes that serializing a value will take before allocating a
+/// buffer for it. It does not provide scratch space, so values that need it should be serialized
+/// with an [`AllocSizeSerializer`] instead.
+///
+/// ## Examples
+/// ```
+/// use rkyv::{
+///     ser::{
+///         serializers::{AlignedSerializer, SizeSerializer},
+///         Serializer,
+///     },
+///     AlignedVec,
+///     Archive,
+///     Serialize,
+/// };
+///
+/// #[derive(Archive, Serialize)]
+/// struct Example {
+///     name: String,
+///     value: i32,
+/// }
+///
+/// let value = Example {
+///     name: "pi".to_string(),
+///     value: 31415926,
+/// };
+///
+/// let mut size_serializer = SizeSerializer::new();
+/// size_serializer.serialize_value(&value).unwrap();
+///
+/// let mut serializer = AlignedSerializer::new(AlignedVec::new());
+/// serializer.serialize_value(&value).unwrap();
+/// assert_eq!(size_serializer.pos(), serializer.into_inner().len());
+/// ```
+#[derive(Debug, Default)]
+pub struct SizeSerializer {
+    pos: usize,
+    shared_resolvers: HashMap<*const u8, usize>,
+}
+
+// SAFETY: The pointers in the map are only used as keys and never dereferenced.
+unsafe impl Send for SizeSerializer {}
+
+// SAFETY: The pointers in the map are only used as keys and never dereferenced.
+unsafe impl Sync for SizeSerializer {}
+
+impl SizeSerializer {
+    /// Creates a new size serializer.
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            pos: 0,
+            shared_resolvers: HashMap::new(),
+        }
+    }
+}
+
+impl Fallible for SizeSerializer {
+    type Error = Unreachable;
+}
+
+impl Serializer for SizeSerializer {
+    #[inline]
+    fn pos(&self) -> usize {
+        self.pos
+    }
+
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.pos += bytes.len();
+        Ok(())
+    }
+
+    #[inline]
+    fn pad(&mut self, padding: usize) -> Result<(), Self::Error> {
+        self.pos += padding;
+        Ok(())
+    }
+
+    #[inline]
+    unsafe fn resolve_aligned<T: Archive + ?Sized>(
+        &mut self,
+        _: &T,
+        _: T::Resolver,
+    ) -> Result<usize, Self::Error> {
+        // Resolving only writes to the output, so it can be skipped entirely
+        let pos = self.pos;
+        debug_assert!(pos & (mem::align_of::<T::Archived>() - 1) == 0);
+        self.pos += mem::size_of::<T::Archived>();
+        Ok(pos)
+    }
+
+    #[inline]
+    unsafe fn resolve_unsized_aligned<T: ArchiveUnsized + ?Sized>(
+        &mut self,
+        _: &T,
+        _: usize,
+        _: T::MetadataResolver,
+    ) -> Result<usize, Self::Error> {
+        let from = self.pos;
+        debug_assert!(from & (mem::align_of::<RelPtr<T::Archived>>() - 1) == 0);
+        self.pos += mem::size_of::<RelPtr<T::Archived>>();
+        Ok(from)
+    }
+}
+
+impl SeekSerializer for SizeSerializer {
+    #[inline]
+    fn seek(&mut self, pos: usize) -> Result<(), Self::Error> {
+        self.pos = pos;
+        Ok(())
+    }
+}
+
+impl SharedSerializer for SizeSerializer {
+    fn serialize_shared<T: SerializeUnsized<Self> + ?Sized>(
+        &mut self,
+        value: &T,
+    ) -> Result<usize, Self::Error> {
+        let key = (value as *const T).cast();
+        if let Some(existing) = self.shared_resolvers.get(&key) {
+            Ok(*existing)
+        } else {
+            let resolver = value.serialize_unsized(self)?;
+            self.shared_resolvers.insert(key, resolver);
+            Ok(resolver)
+        }
+    }
+}
+
+/// A [`SizeSerializer`] with scratch space and shared pointer support.
+///
+/// This computes the size of the archive that an [`AllocSerializer`] would produce for the same
+/// value. See [`archived_size_of`](crate::archived_size_of) for a convenient way to use it.
+pub type AllocSizeSerializer =
+    CompositeSerializer<SizeSerializer, AllocScratch, SharedSerializeMap>;
+
+/// A general-purpose serializer that writes to an [`AlignedVec`].
+///
+/// It uses `N` bytes of heap scratch space that is allocated once when the serializer is created,
+/// and falls back to allocating more scratch space when that runs out. It supports shared pointers
+/// but not seeking.
+///
+/// ## Examples
+/// ```
+/// use rkyv::{
+///     archived_root,
+///     ser::{serializers::AllocSerializer, Serializer},
+///     Archive,
+///     Serialize,
+/// };
+/// use std::rc::Rc;
+///
+/// #[derive(Archive, Serialize)]
+/// struct Example {
+///     names: Vec<String>,
+///     shared: (Rc<u32>, Rc<u32>),
+/// }
+///
+/// let shared = Rc::new(42);
+/// let value = Example {
+///     names: vec!["hello".to_string(), "world".to_string()],
+///     shared: (shared.clone(), shared),
+/// };
+///
+/// let mut serializer = AllocSerializer::<256>::default();
+/// serializer.serialize_value(&value).expect("failed to archive value");
+/// let buf = serializer.into_serializer().into_inner();
+///
+/// let archived = unsafe { archived_root::<Example>(buf.as_ref()) };
+/// assert_eq!(archived.names.len(), 2);
+/// assert_eq!(*archived.shared.0, 42);
+/// assert!(core::ptr::eq(&*archived.shared.0, &*archived.shared.1));
+/// ```
+#[cfg(feature = "const_generics")]
+pub type AllocSerializer<const N: usize> = CompositeSerializer<
+    AlignedSerializer<AlignedVec>,
+    FallbackScratch<HeapScratch<N>, AllocScratch>,
+    SharedSerializeMap,
+>;
+
+#[derive(Debug)]
+enum Segment<'a> {
+    Owned { start: usize, end: usize },
+    Borrowed(&'a [u8]),
+}
+
+/// A serializer that borrows large byte blocks instead of copying them, and writes the archive with
+/// vectored writes.
+///
+/// Byte blocks are only borrowed if they lie within a region that was [lent](Self::lend) to the
+/// serializer. All other bytes, including headers and padding, are copied into an internal buffer.
+/// The finished archive is a list of segments that can be written with
+/// [`write_to`](Self::write_to) or passed to a vectored write as [`io_slices`](Self::io_slices),
+/// so large payloads are never copied before they're written to a file or socket.
+///
+/// Blocks are written as plain bytes by strings and by slices of types that enable
+/// [`COPY_OPTIMIZATION`](crate::Archive::COPY_OPTIMIZATION), like `Vec<u8>`. With the
+/// `specialization` feature, slices of all [`ArchiveCopy`](crate::ArchiveCopy) types are too.
+///
+/// ## Examples
+/// ```
+/// use rkyv::{
+///     archived_root,
+///     ser::{serializers::VectoredSerializer, Serializer},
+///     AlignedVec,
+///     Archive,
+///     Serialize,
+/// };
+///
+/// #[derive(Archive, Serialize)]
+/// struct Message {
+///     id: u32,
+///     body: String,
+/// }
+///
+/// let value = Message {
+///     id: 42,
+///     body: "a very long message body".repeat(1000),
+/// };
+///
+/// let mut serializer = VectoredSerializer::new();
+/// serializer.lend(value.body.as_bytes());
+/// serializer.serialize_value(&value).unwrap();
+///
+/// // The message body is borrowed rather than copied
+/// assert!(serializer
+///     .io_slices()
+///     .iter()
+///     .any(|slice| slice.as_ptr() == value.body.as_ptr()));
+///
+/// let mut buf = AlignedVec::new();
+/// serializer.write_to(&mut buf).unwrap();
+/// let archived = unsafe { archived_root::<Message>(buf.as_ref()) };
+/// assert_eq!(archived.id, 42);
+/// assert_eq!(archived.body, value.body);
+/// ```
+#[derive(Debug, Default)]
+pub struct VectoredSerializer<'a> {
+    buffer: Vec<u8>,
+    segments: Vec<Segment<'a>>,
+    lent: Vec<&'a [u8]>,
+    pos: usize,
+}
+
+impl<'a> VectoredSerializer<'a> {
+    /// Creates a new vectored serializer.
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            buffer: Vec::new(),
+            segments: Vec::new(),
+            lent: Vec::new(),
+            pos: 0,
+        }
+    }
+
+    /// Lends a region of memory to the serializer.
+    ///
+    /// Any byte blocks that lie entirely within the region are borrowed instead of copied.
+    #[inline]
+    pub fn lend(&mut self, region: &'a [u8]) {
+        if !region.is_empty() {
+            self.lent.push(region);
+        }
+    }
+
+    /// Returns the segments of the archive in order.
+    #[inline]
+    pub fn io_slices(&self) -> Vec<io::IoSlice<'_>> {
+        self.segments
+            .iter()
+            .map(|segment| io::IoSlice::new(self.segment_bytes(segment)))
+            .collect()
+    }
+
+    /// Writes the archive to the given writer with vectored writes.
+    pub fn write_to<W: io::Write + ?Sized>(&self, writer: &mut W) -> io::Result<()> {
+        let mut slices = self.io_slices();
+        let mut remaining = &mut slices[..];
+        while !remaining.is_empty() {
+            match writer.write_vectored(remaining) {
+                Ok(0) => return Err(io::Error::from(io::ErrorKind::WriteZero)),
+                Ok(written) => io::IoSlice::advance_slices(&mut remaining, written),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => (),
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn segment_bytes<'s>(&'s self, segment: &Segment<'a>) -> &'s [u8] {
+        match *segment {
+            Segment::Owned { start, end } => &self.buffer[start..end],
+            Segment::Borrowed(bytes) => bytes,
+        }
+    }
+
+    /// Returns the part of a lent region that holds the given bytes, if there is one.
+    #[inline]
+    fn find_lent(&self, bytes: &[u8]) -> Option<&'a [u8]> {
+        if bytes.is_empty() {
+            return None;
+        }
+
+        let start = bytes.as_ptr() as usize;
+        self.lent.iter().find_map(|region| {
+            let offset = start.wrapping_sub(region.as_ptr() as usize);
+            if offset < region.len() && bytes.len() <= region.len() - offset {
+                Some(&region[offset..offset + bytes.len()])
+            } else {
+                None
+            }
+        })
+    }
+}
+
+impl Fallible for VectoredSerializer<'_> {
+    type Error = Unreachable;
+}
+
+impl Serializer for VectoredSerializer<'_> {
+    #[inline]
+    fn pos(&self) -> usize {
+        self.pos
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<(), Self::Error> {
+        if bytes.is_empty() {
+            return Ok(());
+        }
+
+        let start = self.buffer.len();
+        self.buffer.extend_from_slice(bytes);
+        let end = self.buffer.len();
+        match self.segments.last_mut() {
+            Some(Segment::Owned { end: last_end, .. }) if *last_end == start => *last_end = end,
+            _ => self.segments.push(Segment::Owned { start, end }),
+        }
+        self.pos += bytes.len();
+        Ok(())
+    }
+
+    #[inline]
+    fn write_plain_bytes(&mut self, bytes: &[u8], align: usize) -> Result<usize, Self::Error> {
+        let pos = self.align(align)?;
+        if let Some(borrowed) = self.find_lent(bytes) {
+            self.segments.push(Segment::Borrowed(borrowed));
+            self.pos += borrowed.len();
+        } else {
+            self.write(bytes)?;
+        }
+        Ok(pos)
+    }
+}
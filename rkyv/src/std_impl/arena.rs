@@ -0,0 +1,138 @@
+//! [`Archive`] implementations for arena-allocated types from `bumpalo`.
+
+use super::{ArchivedBox, ArchivedString, ArchivedVec, BoxResolver, StringResolver, VecResolver};
+use crate::{
+    de::{ArenaDeserializer, PathSegment},
+    offset_of, Archive, ArchiveUnsized, Deserialize, DeserializeUnsized, Fallible,
+    MetadataResolver, Serialize, SerializeUnsized,
+};
+use ::bumpalo::{boxed::Box, collections::String, collections::Vec};
+use core::{mem::MaybeUninit, ops::Deref};
+
+impl Archive for String<'_> {
+    type Archived = ArchivedString;
+    type Resolver = StringResolver;
+
+    #[inline]
+    fn resolve(&self, pos: usize, resolver: StringResolver, out: &mut MaybeUninit<Self::Archived>) {
+        #[allow(clippy::unit_arg)]
+        self.as_str().resolve_unsized(
+            pos + offset_of!(Self::Archived, 0),
+            resolver.pos,
+            resolver.metadata_resolver,
+            project_struct!(out: Self::Archived => 0),
+        );
+    }
+}
+
+impl<S: Fallible + ?Sized> Serialize<S> for String<'_>
+where
+    str: SerializeUnsized<S>,
+{
+    #[inline]
+    fn serialize(&self, serializer: &mut S) -> Result<Self::Resolver, S::Error> {
+        Ok(StringResolver {
+            pos: self.as_str().serialize_unsized(serializer)?,
+            metadata_resolver: self.as_str().serialize_metadata(serializer)?,
+        })
+    }
+}
+
+impl<'a, D: ArenaDeserializer<'a> + ?Sized> Deserialize<String<'a>, D> for ArchivedString {
+    #[inline]
+    fn deserialize(&self, deserializer: &mut D) -> Result<String<'a>, D::Error> {
+        Ok(String::from_str_in(self.as_str(), deserializer.arena()))
+    }
+}
+
+impl<T: ArchiveUnsized + ?Sized> Archive for Box<'_, T> {
+    type Archived = ArchivedBox<T::Archived>;
+    type Resolver = BoxResolver<T::MetadataResolver>;
+
+    #[inline]
+    fn resolve(&self, pos: usize, resolver: Self::Resolver, out: &mut MaybeUninit<Self::Archived>) {
+        self.deref().resolve_unsized(
+            pos + offset_of!(Self::Archived, 0),
+            resolver.pos,
+            resolver.metadata_resolver,
+            project_struct!(out: Self::Archived => 0),
+        );
+    }
+}
+
+impl<T: SerializeUnsized<S> + ?Sized, S: Fallible + ?Sized> Serialize<S> for Box<'_, T> {
+    #[inline]
+    fn serialize(&self, serializer: &mut S) -> Result<Self::Resolver, S::Error> {
+        Ok(BoxResolver {
+            pos: self.deref().serialize_unsized(serializer)?,
+            metadata_resolver: self.deref().serialize_metadata(serializer)?,
+        })
+    }
+}
+
+impl<'a, T: ArchiveUnsized + ?Sized, D: ArenaDeserializer<'a> + ?Sized> Deserialize<Box<'a, T>, D>
+    for ArchivedBox<T::Archived>
+where
+    T::Archived: DeserializeUnsized<T, D>,
+{
+    #[inline]
+    fn deserialize(&self, deserializer: &mut D) -> Result<Box<'a, T>, D::Error> {
+        unsafe {
+            // The arena deserializer allocates from the arena, so the box can take ownership of
+            // the deserialized value directly
+            let data_address = self.deref().deserialize_unsized(deserializer)?;
+            let metadata = self.deref().deserialize_metadata(deserializer)?;
+            let ptr = ptr_meta::from_raw_parts_mut(data_address, metadata);
+            Ok(Box::from_raw(ptr))
+        }
+    }
+}
+
+impl<T: Archive> Archive for Vec<'_, T> {
+    type Archived = ArchivedVec<T::Archived>;
+    type Resolver = VecResolver<MetadataResolver<[T]>>;
+
+    #[inline]
+    fn resolve(&self, pos: usize, resolver: Self::Resolver, out: &mut MaybeUninit<Self::Archived>) {
+        #[allow(clippy::unit_arg)]
+        self.as_slice().resolve_unsized(
+            pos + offset_of!(Self::Archived, 0),
+            resolver.pos,
+            resolver.metadata_resolver,
+            project_struct!(out: Self::Archived => 0),
+        );
+    }
+}
+
+impl<T: Serialize<S>, S: Fallible + ?Sized> Serialize<S> for Vec<'_, T>
+where
+    [T]: SerializeUnsized<S>,
+{
+    #[inline]
+    fn serialize(&self, serializer: &mut S) -> Result<Self::Resolver, S::Error> {
+        Ok(VecResolver {
+            pos: self.as_slice().serialize_unsized(serializer)?,
+            metadata_resolver: self.as_slice().serialize_metadata(serializer)?,
+        })
+    }
+}
+
+impl<'a, T: Archive, D: ArenaDeserializer<'a> + ?Sized> Deserialize<Vec<'a, T>, D>
+    for ArchivedVec<T::Archived>
+where
+    T::Archived: Deserialize<T, D>,
+{
+    #[inline]
+    fn deserialize(&self, deserializer: &mut D) -> Result<Vec<'a, T>, D::Error> {
+        deserializer.nested(self.len(), |deserializer| {
+            let mut result = Vec::with_capacity_in(self.len(), deserializer.arena());
+            for (i, item) in self.iter().enumerate() {
+                deserializer.enter_path(PathSegment::Index(i));
+                let value = item.deserialize(deserializer);
+                deserializer.exit_path();
+                result.push(value?);
+            }
+            Ok(result)
+        })
+    }
+}
@@ -7,12 +7,17 @@
 pub mod validation;
 
 use crate::{
-    offset_of, project_struct, ser::Serializer, Archive, Archived, ArchivedUsize, Deserialize,
-    Fallible, RawRelPtr, Serialize,
+    de::{Deserializer, PathSegment},
+    offset_of, project_struct,
+    ser::Serializer,
+    Archive, Archived, ArchivedUsize, Deserialize, DeserializeInto, RawRelPtr, ScratchVec,
+    Serialize,
 };
 use core::{
+    alloc::Layout,
     borrow::Borrow,
     cmp::Reverse,
+    fmt,
     hash::{Hash, Hasher},
     iter::FusedIterator,
     marker::PhantomData,
@@ -261,25 +266,95 @@ impl<K: Hash + Eq, V> ArchivedHashMap<K, V> {
         len: usize,
         serializer: &mut S,
     ) -> Result<ArchivedHashMapResolver, S::Error> {
-        let mut bucket_size = vec![0u32; len];
-        let mut displaces = Vec::with_capacity(len);
-
-        for (key, value) in iter {
-            let mut hasher = Self::make_hasher();
-            key.hash(&mut hasher);
-            let displace = (hasher.finish() % len as u64) as u32;
-            displaces.push((displace, (key, value)));
-            bucket_size[displace as usize] += 1;
-        }
-
-        displaces.sort_by_key(|&(displace, _)| (Reverse(bucket_size[displace as usize]), displace));
+        // Each scratch vec is freed when the closure it's passed to returns, so they're freed in
+        // reverse order even if serialization fails partway through
+        serializer.nested(len, |serializer| unsafe {
+            ScratchVec::with(serializer, len, |serializer, bucket_size| {
+                bucket_size.fill_with(|| 0u32);
+                ScratchVec::with(serializer, len, |serializer, displaces| {
+                    for (key, value) in iter {
+                        let mut hasher = Self::make_hasher();
+                        key.hash(&mut hasher);
+                        let displace = (hasher.finish() % len as u64) as u32;
+                        displaces.push((displace, (key, value)));
+                        bucket_size[displace as usize] += 1;
+                    }
 
-        let mut entries = Vec::with_capacity(len);
-        entries.resize_with(len, || None);
-        let mut displacements = vec![u32::MAX; len];
+                    displaces.sort_unstable_by_key(|&(displace, _)| {
+                        (Reverse(bucket_size[displace as usize]), displace)
+                    });
+
+                    ScratchVec::with(serializer, len, |serializer, entries| {
+                        entries.fill_with(|| None);
+                        ScratchVec::with(serializer, len, |serializer, displacements| {
+                            displacements.fill_with(|| u32::MAX);
+
+                            // Buckets are sorted by size, so the first bucket is the largest
+                            let max_bucket_size = displaces.first().map_or(0, |&(displace, _)| {
+                                bucket_size[displace as usize] as usize
+                            });
+                            ScratchVec::with(serializer, max_bucket_size, |_, assignments| {
+                                Self::place_buckets(
+                                    bucket_size,
+                                    displaces,
+                                    entries,
+                                    displacements,
+                                    assignments,
+                                );
+                                Ok(())
+                            })?;
+
+                            ScratchVec::with(serializer, len, |serializer, resolvers| {
+                                // Archive entries
+                                for e in entries.iter() {
+                                    let (key, value) = e.unwrap();
+                                    resolvers.push((
+                                        key.serialize(serializer)?,
+                                        value.serialize(serializer)?,
+                                    ));
+                                }
+
+                                // Write blocks
+                                let displace_pos = serializer.align_for::<u32>()?;
+                                let displacements_slice = slice::from_raw_parts(
+                                    displacements.as_ptr().cast::<u8>(),
+                                    displacements.len() * size_of::<u32>(),
+                                );
+                                serializer.write(displacements_slice)?;
+
+                                let entries_pos = serializer.align_for::<Entry<K, V>>()?;
+                                for ((key, value), (key_resolver, value_resolver)) in
+                                    entries.iter().map(|r| r.unwrap()).zip(resolvers.drain())
+                                {
+                                    serializer.resolve_aligned(
+                                        &Entry { key, value },
+                                        (key_resolver, value_resolver),
+                                    )?;
+                                }
+
+                                Ok(ArchivedHashMapResolver {
+                                    displace_pos,
+                                    entries_pos,
+                                })
+                            })
+                        })
+                    })
+                })
+            })
+        })
+    }
 
+    /// Assigns every bucket of displaced entries to slots in `entries`, recording either the seed
+    /// or the slot of each bucket in `displacements`.
+    fn place_buckets<'a, KU: Hash, VU>(
+        bucket_size: &[u32],
+        displaces: &[(u32, (&'a KU, &'a VU))],
+        entries: &mut [Option<(&'a KU, &'a VU)>],
+        displacements: &mut [u32],
+        assignments: &mut ScratchVec<u32>,
+    ) {
+        let len = entries.len();
         let mut first_empty = 0;
-        let mut assignments = Vec::with_capacity(8);
 
         let mut start = 0;
         while start < displaces.len() {
@@ -324,40 +399,6 @@ impl<K: Hash + Eq, V> ArchivedHashMap<K, V> {
                 first_empty += 1;
             }
         }
-
-        // Archive entries
-        let mut resolvers = entries
-            .iter()
-            .map(|e| {
-                let (key, value) = e.unwrap();
-                Ok((key.serialize(serializer)?, value.serialize(serializer)?))
-            })
-            .collect::<Result<Vec<_>, _>>()?;
-
-        // Write blocks
-        let displace_pos = serializer.align_for::<u32>()?;
-        let displacements_slice = unsafe {
-            slice::from_raw_parts(
-                displacements.as_ptr().cast::<u8>(),
-                displacements.len() * size_of::<u32>(),
-            )
-        };
-        serializer.write(displacements_slice)?;
-
-        let entries_pos = serializer.align_for::<Entry<K, V>>()?;
-        for ((key, value), (key_resolver, value_resolver)) in
-            entries.iter().map(|r| r.unwrap()).zip(resolvers.drain(..))
-        {
-            unsafe {
-                serializer
-                    .resolve_aligned(&Entry { key, value }, (key_resolver, value_resolver))?;
-            }
-        }
-
-        Ok(ArchivedHashMapResolver {
-            displace_pos,
-            entries_pos,
-        })
     }
 }
 
@@ -633,7 +674,43 @@ where
     }
 }
 
-impl<K: Archive + Hash + Eq, V: Archive, D: Fallible + ?Sized, S: Default + BuildHasher>
+/// Deserializes a value with `f`, reporting `segment` as part of its path.
+#[inline]
+fn with_path<T, D: Deserializer + ?Sized>(
+    deserializer: &mut D,
+    segment: PathSegment,
+    f: impl FnOnce(&mut D) -> Result<T, D::Error>,
+) -> Result<T, D::Error> {
+    deserializer.enter_path(segment);
+    let result = f(deserializer);
+    deserializer.exit_path();
+    result
+}
+
+/// Deserializes the key and value of the entry at the given index.
+#[inline]
+fn deserialize_entry<K: Archive, V: Archive, D: Deserializer + ?Sized>(
+    index: usize,
+    key: &K::Archived,
+    value: &V::Archived,
+    deserializer: &mut D,
+) -> Result<(K, V), D::Error>
+where
+    K::Archived: Deserialize<K, D>,
+    V::Archived: Deserialize<V, D>,
+{
+    with_path(deserializer, PathSegment::Index(index), |deserializer| {
+        let key = with_path(deserializer, PathSegment::Field("key"), |deserializer| {
+            key.deserialize(deserializer)
+        })?;
+        let value = with_path(deserializer, PathSegment::Field("value"), |deserializer| {
+            value.deserialize(deserializer)
+        })?;
+        Ok((key, value))
+    })
+}
+
+impl<K: Archive + Hash + Eq, V: Archive, D: Deserializer + ?Sized, S: Default + BuildHasher>
     Deserialize<HashMap<K, V, S>, D> for Archived<HashMap<K, V>>
 where
     K::Archived: Deserialize<K, D> + Hash + Eq,
@@ -641,11 +718,72 @@ where
 {
     #[inline]
     fn deserialize(&self, deserializer: &mut D) -> Result<HashMap<K, V, S>, D::Error> {
-        let mut result = HashMap::with_capacity_and_hasher(self.len(), S::default());
-        for (k, v) in self.iter() {
-            result.insert(k.deserialize(deserializer)?, v.deserialize(deserializer)?);
-        }
-        Ok(result)
+        deserializer.nested(self.len(), |deserializer| {
+            deserializer.reserve(Layout::array::<(K, V)>(self.len()).unwrap())?;
+            let mut result = HashMap::with_capacity_and_hasher(self.len(), S::default());
+            for (i, (k, v)) in self.iter().enumerate() {
+                let (key, value) = deserialize_entry(i, k, v, deserializer)?;
+                result.insert(key, value);
+            }
+            Ok(result)
+        })
+    }
+}
+
+impl<K: Archive + Hash + Eq, V: Archive, D: Deserializer + ?Sized, S: Default + BuildHasher>
+    DeserializeInto<HashMap<K, V, S>, D> for Archived<HashMap<K, V>>
+where
+    K::Archived: Deserialize<K, D> + Hash + Eq,
+    V::Archived: DeserializeInto<V, D>,
+{
+    #[inline]
+    fn deserialize_into(
+        &self,
+        out: &mut HashMap<K, V, S>,
+        deserializer: &mut D,
+    ) -> Result<(), D::Error> {
+        deserializer.nested(self.len(), |deserializer| {
+            let additional = self.len().saturating_sub(out.capacity());
+            deserializer.reserve(Layout::array::<(K, V)>(additional).unwrap())?;
+            for (i, (k, v)) in self.iter().enumerate() {
+                with_path(deserializer, PathSegment::Index(i), |deserializer| {
+                    let key = with_path(deserializer, PathSegment::Field("key"), |deserializer| {
+                        k.deserialize(deserializer)
+                    })?;
+                    with_path(
+                        deserializer,
+                        PathSegment::Field("value"),
+                        |deserializer| match out.get_mut(&key) {
+                            Some(value) => v.deserialize_into(value, deserializer),
+                            None => {
+                                out.insert(key, v.deserialize(deserializer)?);
+                                Ok(())
+                            }
+                        },
+                    )
+                })?;
+            }
+
+            if out.len() != self.len() {
+                // The map still has entries for keys that aren't in the archive. Finding them
+                // would mean deserializing every key again, so the map is rebuilt and only keeps
+                // its capacity.
+                out.clear();
+                for (i, (k, v)) in self.iter().enumerate() {
+                    let (key, value) = deserialize_entry(i, k, v, deserializer)?;
+                    out.insert(key, value);
+                }
+            }
+
+            Ok(())
+        })
+    }
+}
+
+impl<K: Hash + Eq + fmt::Debug, V: fmt::Debug> fmt::Debug for ArchivedHashMap<K, V> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
     }
 }
 
@@ -748,6 +886,13 @@ impl<K: Hash + Eq> ArchivedHashSet<K> {
     }
 }
 
+impl<K: Hash + Eq + fmt::Debug> fmt::Debug for ArchivedHashSet<K> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
 /// The resolver for archived hash sets.
 pub struct ArchivedHashSetResolver(ArchivedHashMapResolver);
 
@@ -784,17 +929,46 @@ where
     }
 }
 
-impl<K: Archive + Hash + Eq, D: Fallible + ?Sized> Deserialize<HashSet<K>, D>
+impl<K: Archive + Hash + Eq, D: Deserializer + ?Sized> Deserialize<HashSet<K>, D>
     for Archived<HashSet<K>>
 where
     K::Archived: Deserialize<K, D> + Hash + Eq,
 {
     #[inline]
     fn deserialize(&self, deserializer: &mut D) -> Result<HashSet<K>, D::Error> {
-        let mut result = HashSet::new();
-        for k in self.iter() {
-            result.insert(k.deserialize(deserializer)?);
-        }
-        Ok(result)
+        deserializer.nested(self.len(), |deserializer| {
+            deserializer.reserve(Layout::array::<K>(self.len()).unwrap())?;
+            let mut result = HashSet::with_capacity(self.len());
+            for (i, k) in self.iter().enumerate() {
+                let key = with_path(deserializer, PathSegment::Index(i), |deserializer| {
+                    k.deserialize(deserializer)
+                })?;
+                result.insert(key);
+            }
+            Ok(result)
+        })
+    }
+}
+
+impl<K: Archive + Hash + Eq, D: Deserializer + ?Sized> DeserializeInto<HashSet<K>, D>
+    for Archived<HashSet<K>>
+where
+    K::Archived: Deserialize<K, D> + Hash + Eq,
+{
+    #[inline]
+    fn deserialize_into(&self, out: &mut HashSet<K>, deserializer: &mut D) -> Result<(), D::Error> {
+        deserializer.nested(self.len(), |deserializer| {
+            let additional = self.len().saturating_sub(out.capacity());
+            deserializer.reserve(Layout::array::<K>(additional).unwrap())?;
+            out.clear();
+            out.reserve(self.len());
+            for (i, k) in self.iter().enumerate() {
+                let key = with_path(deserializer, PathSegment::Index(i), |deserializer| {
+                    k.deserialize(deserializer)
+                })?;
+                out.insert(key);
+            }
+            Ok(())
+        })
     }
 }
@@ -1,9 +1,10 @@
 //! Validation implementations for HashMap and HashSet.
 
 use crate::{
+    de::PathSegment,
     offset_of,
     std_impl::chd::{ArchivedHashMap, Entry},
-    validation::{ArchiveBoundsContext, ArchiveMemoryContext},
+    validation::{check_elements, ArchiveBoundsContext, ArchiveMemoryContext},
     Fallible, RawRelPtr,
 };
 use bytecheck::{CheckBytes, SliceCheckError, Unreachable};
@@ -37,8 +38,11 @@ impl<K: fmt::Debug + fmt::Display, V: fmt::Debug + fmt::Display> Error
 {
 }
 
-impl<K: CheckBytes<C>, V: CheckBytes<C>, C: ArchiveMemoryContext + ?Sized> CheckBytes<C>
-    for Entry<K, V>
+impl<
+        K: CheckBytes<C>,
+        V: CheckBytes<C>,
+        C: ArchiveBoundsContext + ArchiveMemoryContext + ?Sized,
+    > CheckBytes<C> for Entry<K, V>
 {
     type Error = ArchivedHashMapEntryError<K::Error, V::Error>;
 
@@ -47,10 +51,14 @@ impl<K: CheckBytes<C>, V: CheckBytes<C>, C: ArchiveMemoryContext + ?Sized> Check
         context: &mut C,
     ) -> Result<&'a Self, Self::Error> {
         let bytes = value.cast::<u8>();
+        context.enter_path(PathSegment::Field("key"));
         K::check_bytes(bytes.add(offset_of!(Entry<K, V>, key)).cast(), context)
             .map_err(ArchivedHashMapEntryError::KeyCheckError)?;
+        context.exit_path();
+        context.enter_path(PathSegment::Field("value"));
         V::check_bytes(bytes.add(offset_of!(Entry<K, V>, value)).cast(), context)
             .map_err(ArchivedHashMapEntryError::ValueCheckError)?;
+        context.exit_path();
         Ok(&*value)
     }
 }
@@ -189,7 +197,7 @@ where
         context
             .claim_owned_ptr(entries_ptr)
             .map_err(HashMapError::ContextError)?;
-        let entries = <[Entry<K, V>]>::check_bytes(entries_ptr, context)?;
+        let entries = check_elements::<Entry<K, V>, C>(entries_ptr, context)?;
 
         for (i, entry) in entries.iter().enumerate() {
             let mut hasher = ArchivedHashMap::<K, V>::make_hasher();
@@ -1,14 +1,19 @@
 //! [`Archive`] implementations for std types.
 
+#[cfg(feature = "arena")]
+mod arena;
 pub mod chd;
 pub mod net;
+#[cfg(feature = "serde")]
+mod serde;
 pub mod shared;
 #[cfg(feature = "validation")]
 pub mod validation;
 
 use crate::{
-    de::Deserializer, offset_of, project_struct, Archive, ArchivePointee, ArchiveUnsized, Archived,
-    Deserialize, DeserializeUnsized, Fallible, MetadataResolver, RelPtr, Serialize,
+    de::{move_to_global, Deserializer, PathSegment},
+    offset_of, project_struct, Archive, ArchivePointee, ArchiveUnsized, Archived, Deserialize,
+    DeserializeInto, DeserializeUnsized, Fallible, MetadataResolver, RelPtr, Serialize,
     SerializeUnsized,
 };
 use core::{
@@ -22,7 +27,6 @@ use core::{
 /// An archived [`String`].
 ///
 /// Uses a [`RelPtr`] to a `str` under the hood.
-#[derive(Debug)]
 #[repr(transparent)]
 pub struct ArchivedString(RelPtr<str>);
 
@@ -139,6 +143,13 @@ impl PartialEq<ArchivedString> for String {
     }
 }
 
+impl fmt::Debug for ArchivedString {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
 impl fmt::Display for ArchivedString {
     #[inline]
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -181,7 +192,7 @@ where
     }
 }
 
-impl<D: Fallible + ?Sized> Deserialize<String, D> for Archived<String>
+impl<D: Deserializer + ?Sized> Deserialize<String, D> for Archived<String>
 where
     str: DeserializeUnsized<str, D>,
 {
@@ -190,25 +201,46 @@ where
         unsafe {
             let data_address = self.as_str().deserialize_unsized(deserializer)?;
             let metadata = self.0.metadata().deserialize(deserializer)?;
-            let ptr = ptr_meta::from_raw_parts_mut(data_address, metadata);
+            let ptr = move_to_global(
+                deserializer,
+                ptr_meta::from_raw_parts_mut(data_address, metadata),
+            );
             Ok(Box::<str>::from_raw(ptr).into())
         }
     }
 }
 
+impl<D: Deserializer + ?Sized> DeserializeInto<String, D> for Archived<String>
+where
+    str: DeserializeUnsized<str, D>,
+{
+    #[inline]
+    fn deserialize_into(&self, out: &mut String, deserializer: &mut D) -> Result<(), D::Error> {
+        deserializer.nested(self.len(), |_| {
+            out.clear();
+            out.push_str(self.as_str());
+            Ok(())
+        })
+    }
+}
+
 /// An archived [`Box`].
 ///
 /// This is a thin wrapper around a [`RelPtr`] to the archived type.
 #[repr(transparent)]
 pub struct ArchivedBox<T: ArchivePointee + ?Sized>(RelPtr<T>);
 
-impl<T: ArchivePointee + ?Sized> fmt::Debug for ArchivedBox<T>
-where
-    T::ArchivedMetadata: fmt::Debug,
-{
+impl<T: ArchivePointee + fmt::Debug + ?Sized> fmt::Debug for ArchivedBox<T> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.deref(), f)
+    }
+}
+
+impl<T: ArchivePointee + fmt::Display + ?Sized> fmt::Display for ArchivedBox<T> {
     #[inline]
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_tuple("ArchivedBox").field(&self.0).finish()
+        fmt::Display::fmt(self.deref(), f)
     }
 }
 
@@ -236,6 +268,36 @@ impl<T: ArchivePointee + ?Sized> DerefMut for ArchivedBox<T> {
     }
 }
 
+impl<T: ArchivePointee + PartialEq + ?Sized> PartialEq for ArchivedBox<T> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.deref().eq(other.deref())
+    }
+}
+
+impl<T: ArchivePointee + Eq + ?Sized> Eq for ArchivedBox<T> {}
+
+impl<T: ArchivePointee + PartialOrd + ?Sized> PartialOrd for ArchivedBox<T> {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        self.deref().partial_cmp(other.deref())
+    }
+}
+
+impl<T: ArchivePointee + Ord + ?Sized> Ord for ArchivedBox<T> {
+    #[inline]
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        self.deref().cmp(other.deref())
+    }
+}
+
+impl<T: ArchivePointee + hash::Hash + ?Sized> hash::Hash for ArchivedBox<T> {
+    #[inline]
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.deref().hash(state)
+    }
+}
+
 impl<T: ArchivePointee + PartialEq<U> + ?Sized, U: ?Sized> PartialEq<Box<U>> for ArchivedBox<T> {
     #[inline]
     fn eq(&self, other: &Box<U>) -> bool {
@@ -284,16 +346,30 @@ where
         unsafe {
             let data_address = self.deref().deserialize_unsized(deserializer)?;
             let metadata = self.deref().deserialize_metadata(deserializer)?;
-            let ptr = ptr_meta::from_raw_parts_mut(data_address, metadata);
+            let ptr = move_to_global(
+                deserializer,
+                ptr_meta::from_raw_parts_mut(data_address, metadata),
+            );
             Ok(Box::from_raw(ptr))
         }
     }
 }
 
+impl<T: Archive, D: Deserializer + ?Sized> DeserializeInto<Box<T>, D> for Archived<Box<T>>
+where
+    T::Archived: DeserializeInto<T, D>,
+{
+    #[inline]
+    fn deserialize_into(&self, out: &mut Box<T>, deserializer: &mut D) -> Result<(), D::Error> {
+        deserializer.nested(1, |deserializer| {
+            self.deref().deserialize_into(&mut **out, deserializer)
+        })
+    }
+}
+
 /// An archived [`Vec`].
 ///
 /// Uses a [`RelPtr`] to a `T` slice under the hood.
-#[derive(Debug)]
 #[repr(transparent)]
 pub struct ArchivedVec<T>(RelPtr<[T]>);
 
@@ -320,6 +396,13 @@ impl<T> ArchivedVec<T> {
     }
 }
 
+impl<T: fmt::Debug> fmt::Debug for ArchivedVec<T> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
 impl<T> Deref for ArchivedVec<T> {
     type Target = [T];
 
@@ -371,7 +454,7 @@ where
     }
 }
 
-impl<T: Archive, D: Fallible + ?Sized> Deserialize<Vec<T>, D> for Archived<Vec<T>>
+impl<T: Archive, D: Deserializer + ?Sized> Deserialize<Vec<T>, D> for Archived<Vec<T>>
 where
     [T::Archived]: DeserializeUnsized<[T], D>,
 {
@@ -380,12 +463,73 @@ where
         unsafe {
             let data_address = self.deref().deserialize_unsized(deserializer)?;
             let metadata = self.deref().deserialize_metadata(deserializer)?;
-            let ptr = ptr_meta::from_raw_parts_mut(data_address, metadata);
+            let ptr = move_to_global(
+                deserializer,
+                ptr_meta::from_raw_parts_mut(data_address, metadata),
+            );
             Ok(Box::<[T]>::from_raw(ptr).into())
         }
     }
 }
 
+impl<T: Archive, D: Deserializer + ?Sized> DeserializeInto<Vec<T>, D> for Archived<Vec<T>>
+where
+    T::Archived: DeserializeInto<T, D>,
+    [T::Archived]: DeserializeUnsized<[T], D>,
+{
+    #[inline]
+    fn deserialize_into(&self, out: &mut Vec<T>, deserializer: &mut D) -> Result<(), D::Error> {
+        deserializer.nested(self.len(), |deserializer| {
+            out.truncate(self.len());
+            let (existing, new) = self.as_slice().split_at(out.len());
+            for (i, (value, out)) in existing.iter().zip(out.iter_mut()).enumerate() {
+                deserializer.enter_path(PathSegment::Index(i));
+                let result = value.deserialize_into(out, deserializer);
+                deserializer.exit_path();
+                result?;
+            }
+            out.reserve(new.len());
+            for (i, value) in new.iter().enumerate() {
+                deserializer.enter_path(PathSegment::Index(existing.len() + i));
+                let result = value.deserialize(deserializer);
+                deserializer.exit_path();
+                out.push(result?);
+            }
+            Ok(())
+        })
+    }
+}
+
+impl<T: PartialEq> PartialEq for ArchivedVec<T> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.as_slice().eq(other.as_slice())
+    }
+}
+
+impl<T: Eq> Eq for ArchivedVec<T> {}
+
+impl<T: PartialOrd> PartialOrd for ArchivedVec<T> {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        self.as_slice().partial_cmp(other.as_slice())
+    }
+}
+
+impl<T: Ord> Ord for ArchivedVec<T> {
+    #[inline]
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        self.as_slice().cmp(other.as_slice())
+    }
+}
+
+impl<T: hash::Hash> hash::Hash for ArchivedVec<T> {
+    #[inline]
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.as_slice().hash(state)
+    }
+}
+
 impl<T: PartialEq<U>, U> PartialEq<Vec<U>> for ArchivedVec<T> {
     #[inline]
     fn eq(&self, other: &Vec<U>) -> bool {
@@ -1,7 +1,7 @@
 //! [`Archive`] implementations for network types.
 
 use crate::{offset_of, project_struct, Archive, Archived, Deserialize, Fallible, Serialize};
-use core::{cmp, mem::MaybeUninit};
+use core::{cmp, fmt, mem::MaybeUninit};
 use std::{
     io,
     net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6, ToSocketAddrs},
@@ -109,6 +109,26 @@ impl ArchivedIpv4Addr {
     }
 }
 
+macro_rules! impl_fmt {
+    ($archived:ty, $as:ident) => {
+        impl fmt::Debug for $archived {
+            #[inline]
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::Debug::fmt(&self.$as(), f)
+            }
+        }
+
+        impl fmt::Display for $archived {
+            #[inline]
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::Display::fmt(&self.$as(), f)
+            }
+        }
+    };
+}
+
+impl_fmt!(ArchivedIpv4Addr, as_ipv4);
+
 impl Archive for Ipv4Addr {
     type Archived = ArchivedIpv4Addr;
     type Resolver = ();
@@ -233,6 +253,8 @@ impl ArchivedIpv6Addr {
     }
 }
 
+impl_fmt!(ArchivedIpv6Addr, as_ipv6);
+
 impl Archive for Ipv6Addr {
     type Archived = ArchivedIpv6Addr;
     type Resolver = ();
@@ -369,6 +391,8 @@ struct ArchivedIpAddrVariantV4(ArchivedIpAddrTag, ArchivedIpv4Addr);
 #[repr(C)]
 struct ArchivedIpAddrVariantV6(ArchivedIpAddrTag, ArchivedIpv6Addr);
 
+impl_fmt!(ArchivedIpAddr, as_ipaddr);
+
 impl Archive for IpAddr {
     type Archived = ArchivedIpAddr;
     type Resolver = ();
@@ -508,6 +532,8 @@ impl ToSocketAddrs for ArchivedSocketAddrV4 {
     }
 }
 
+impl_fmt!(ArchivedSocketAddrV4, as_socket_addr_v4);
+
 impl Archive for SocketAddrV4 {
     type Archived = ArchivedSocketAddrV4;
     type Resolver = ();
@@ -627,6 +653,8 @@ impl ToSocketAddrs for ArchivedSocketAddrV6 {
     }
 }
 
+impl_fmt!(ArchivedSocketAddrV6, as_socket_addr_v6);
+
 impl Archive for SocketAddrV6 {
     type Archived = ArchivedSocketAddrV6;
     type Resolver = ();
@@ -781,6 +809,8 @@ struct ArchivedSocketAddrVariantV4(ArchivedSocketAddrTag, ArchivedSocketAddrV4);
 #[repr(C)]
 struct ArchivedSocketAddrVariantV6(ArchivedSocketAddrTag, ArchivedSocketAddrV6);
 
+impl_fmt!(ArchivedSocketAddr, as_socket_addr);
+
 impl Archive for SocketAddr {
     type Archived = ArchivedSocketAddr;
     type Resolver = ();
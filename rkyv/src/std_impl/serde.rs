@@ -0,0 +1,97 @@
+//! [`Serialize`](serde::Serialize) implementations for archived std types.
+//!
+//! Each archived type serializes the same way that serde serializes its unarchived counterpart.
+
+use super::{
+    chd::{ArchivedHashMap, ArchivedHashSet},
+    net::{
+        ArchivedIpAddr, ArchivedIpv4Addr, ArchivedIpv6Addr, ArchivedSocketAddr,
+        ArchivedSocketAddrV4, ArchivedSocketAddrV6,
+    },
+    shared::{ArchivedArc, ArchivedArcWeak, ArchivedRc, ArchivedRcWeak},
+    ArchivedBox, ArchivedString, ArchivedVec,
+};
+use crate::ArchivePointee;
+use core::hash::Hash;
+use serde::{Serialize, Serializer};
+
+impl Serialize for ArchivedString {
+    #[inline]
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<T: ArchivePointee + Serialize + ?Sized> Serialize for ArchivedBox<T> {
+    #[inline]
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (**self).serialize(serializer)
+    }
+}
+
+impl<T: Serialize> Serialize for ArchivedVec<T> {
+    #[inline]
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<K: Hash + Eq + Serialize, V: Serialize> Serialize for ArchivedHashMap<K, V> {
+    #[inline]
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.iter())
+    }
+}
+
+impl<K: Hash + Eq + Serialize> Serialize for ArchivedHashSet<K> {
+    #[inline]
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<T: ArchivePointee + Serialize + ?Sized> Serialize for ArchivedRc<T> {
+    #[inline]
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (**self).serialize(serializer)
+    }
+}
+
+impl<T: ArchivePointee + Serialize + ?Sized> Serialize for ArchivedRcWeak<T> {
+    #[inline]
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.upgrade().serialize(serializer)
+    }
+}
+
+impl<T: ArchivePointee + Serialize + ?Sized> Serialize for ArchivedArc<T> {
+    #[inline]
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (**self).serialize(serializer)
+    }
+}
+
+impl<T: ArchivePointee + Serialize + ?Sized> Serialize for ArchivedArcWeak<T> {
+    #[inline]
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.upgrade().serialize(serializer)
+    }
+}
+
+macro_rules! impl_serialize_as {
+    ($archived:ty, $as:ident) => {
+        impl Serialize for $archived {
+            #[inline]
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                self.$as().serialize(serializer)
+            }
+        }
+    };
+}
+
+impl_serialize_as!(ArchivedIpv4Addr, as_ipv4);
+impl_serialize_as!(ArchivedIpv6Addr, as_ipv6);
+impl_serialize_as!(ArchivedIpAddr, as_ipaddr);
+impl_serialize_as!(ArchivedSocketAddrV4, as_socket_addr_v4);
+impl_serialize_as!(ArchivedSocketAddrV6, as_socket_addr_v6);
+impl_serialize_as!(ArchivedSocketAddr, as_socket_addr);
@@ -4,7 +4,8 @@
 pub mod validation;
 
 use core::{
-    cmp::PartialEq,
+    cmp::{self, PartialEq},
+    fmt, hash,
     mem::{forget, MaybeUninit},
     ops::Deref,
     pin::Pin,
@@ -50,6 +51,20 @@ impl<T: ArchivePointee + ?Sized> ArchivedRc<T> {
     }
 }
 
+impl<T: ArchivePointee + fmt::Debug + ?Sized> fmt::Debug for ArchivedRc<T> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.deref(), f)
+    }
+}
+
+impl<T: ArchivePointee + fmt::Display + ?Sized> fmt::Display for ArchivedRc<T> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self.deref(), f)
+    }
+}
+
 impl<T: ArchivePointee + ?Sized> Deref for ArchivedRc<T> {
     type Target = T;
 
@@ -59,6 +74,36 @@ impl<T: ArchivePointee + ?Sized> Deref for ArchivedRc<T> {
     }
 }
 
+impl<T: ArchivePointee + PartialEq + ?Sized> PartialEq for ArchivedRc<T> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.deref().eq(other.deref())
+    }
+}
+
+impl<T: ArchivePointee + Eq + ?Sized> Eq for ArchivedRc<T> {}
+
+impl<T: ArchivePointee + PartialOrd + ?Sized> PartialOrd for ArchivedRc<T> {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        self.deref().partial_cmp(other.deref())
+    }
+}
+
+impl<T: ArchivePointee + Ord + ?Sized> Ord for ArchivedRc<T> {
+    #[inline]
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        self.deref().cmp(other.deref())
+    }
+}
+
+impl<T: ArchivePointee + hash::Hash + ?Sized> hash::Hash for ArchivedRc<T> {
+    #[inline]
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.deref().hash(state)
+    }
+}
+
 impl<T: ArchivePointee + PartialEq<U> + ?Sized, U: ?Sized> PartialEq<rc::Rc<U>> for ArchivedRc<T> {
     #[inline]
     fn eq(&self, other: &rc::Rc<U>) -> bool {
@@ -140,6 +185,13 @@ struct ArchivedRcWeakVariantNone(ArchivedRcWeakTag);
 #[repr(C)]
 struct ArchivedRcWeakVariantSome<T: ArchivePointee + ?Sized>(ArchivedRcWeakTag, ArchivedRc<T>);
 
+impl<T: ArchivePointee + ?Sized> fmt::Debug for ArchivedRcWeak<T> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "(Weak)")
+    }
+}
+
 impl<T: ArchivePointee + ?Sized> ArchivedRcWeak<T> {
     /// Attempts to upgrade the weak pointer to an `ArchivedArc`.
     ///
@@ -256,6 +308,20 @@ impl<T: ArchivePointee + ?Sized> ArchivedArc<T> {
     }
 }
 
+impl<T: ArchivePointee + fmt::Debug + ?Sized> fmt::Debug for ArchivedArc<T> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.deref(), f)
+    }
+}
+
+impl<T: ArchivePointee + fmt::Display + ?Sized> fmt::Display for ArchivedArc<T> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self.deref(), f)
+    }
+}
+
 impl<T: ArchivePointee + ?Sized> Deref for ArchivedArc<T> {
     type Target = T;
 
@@ -265,6 +331,36 @@ impl<T: ArchivePointee + ?Sized> Deref for ArchivedArc<T> {
     }
 }
 
+impl<T: ArchivePointee + PartialEq + ?Sized> PartialEq for ArchivedArc<T> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.deref().eq(other.deref())
+    }
+}
+
+impl<T: ArchivePointee + Eq + ?Sized> Eq for ArchivedArc<T> {}
+
+impl<T: ArchivePointee + PartialOrd + ?Sized> PartialOrd for ArchivedArc<T> {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        self.deref().partial_cmp(other.deref())
+    }
+}
+
+impl<T: ArchivePointee + Ord + ?Sized> Ord for ArchivedArc<T> {
+    #[inline]
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        self.deref().cmp(other.deref())
+    }
+}
+
+impl<T: ArchivePointee + hash::Hash + ?Sized> hash::Hash for ArchivedArc<T> {
+    #[inline]
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.deref().hash(state)
+    }
+}
+
 impl<T: ArchivePointee + PartialEq<U> + ?Sized, U: ?Sized> PartialEq<sync::Arc<U>>
     for ArchivedArc<T>
 {
@@ -347,6 +443,13 @@ struct ArchivedArcWeakVariantNone(ArchivedRcWeakTag);
 #[repr(C)]
 struct ArchivedArcWeakVariantSome<T: ArchivePointee + ?Sized>(ArchivedArcWeakTag, ArchivedArc<T>);
 
+impl<T: ArchivePointee + ?Sized> fmt::Debug for ArchivedArcWeak<T> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "(Weak)")
+    }
+}
+
 impl<T: ArchivePointee + ?Sized> ArchivedArcWeak<T> {
     /// Attempts to upgrade the weak pointer to an `ArchivedArc`.
     ///
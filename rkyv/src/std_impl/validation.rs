@@ -2,10 +2,10 @@
 
 use super::{ArchivedBox, ArchivedString, ArchivedVec};
 use crate::{
-    validation::{ArchiveBoundsContext, ArchiveMemoryContext, LayoutMetadata},
+    validation::{check_elements, ArchiveBoundsContext, ArchiveMemoryContext, LayoutMetadata},
     ArchivePointee, Fallible, RelPtr,
 };
-use bytecheck::CheckBytes;
+use bytecheck::{CheckBytes, SliceCheckError};
 use core::fmt;
 use ptr_meta::Pointee;
 use std::error::Error;
@@ -106,7 +106,11 @@ where
     C::Error: Error,
     <[T] as Pointee>::Metadata: LayoutMetadata<[T]>,
 {
-    type Error = CheckOwnedPointerError<[T], C>;
+    type Error = OwnedPointerError<
+        <<[T] as ArchivePointee>::ArchivedMetadata as CheckBytes<C>>::Error,
+        SliceCheckError<T::Error>,
+        C::Error,
+    >;
 
     unsafe fn check_bytes<'a>(
         value: *const Self,
@@ -117,7 +121,7 @@ where
         let ptr = context
             .claim_owned_rel_ptr(rel_ptr)
             .map_err(OwnedPointerError::ContextError)?;
-        <[T]>::check_bytes(ptr, context).map_err(OwnedPointerError::ValueCheckBytesError)?;
+        check_elements(ptr, context).map_err(OwnedPointerError::ValueCheckBytesError)?;
         Ok(&*value)
     }
 }
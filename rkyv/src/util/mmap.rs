@@ -0,0 +1,79 @@
+#[cfg(feature = "validation")]
+use crate::validation::{check_archived_root, CheckTypeError, DefaultArchiveValidator};
+use crate::{archived_root, Archive};
+#[cfg(feature = "validation")]
+use bytecheck::CheckBytes;
+use core::ops::Deref;
+use memmap2::Mmap;
+use std::{fs::File, io};
+
+/// An archive that is loaded from a memory-mapped file.
+///
+/// Memory maps are page-aligned, so the archive can be accessed directly from the mapped file
+/// without copying it into an aligned buffer. Archives written with an
+/// [`MmapSerializer`](crate::ser::serializers::MmapSerializer) can be loaded with this.
+pub struct MmapArchive {
+    mmap: Mmap,
+}
+
+impl MmapArchive {
+    /// Maps the given file into memory as a read-only archive.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified, including by other processes, while it is mapped.
+    #[inline]
+    pub unsafe fn map(file: &File) -> io::Result<Self> {
+        Ok(Self {
+            mmap: Mmap::map(file)?,
+        })
+    }
+
+    /// Returns the bytes of the archive.
+    #[inline]
+    pub fn as_slice(&self) -> &[u8] {
+        &self.mmap
+    }
+
+    /// Gets the root object of the archive without validating it.
+    ///
+    /// # Safety
+    ///
+    /// The caller must guarantee that the file contains a valid archive of `T` with its root object
+    /// at the end. See [`archived_root`](crate::archived_root) for more details.
+    #[inline]
+    pub unsafe fn archived_root<T: Archive + ?Sized>(&self) -> &T::Archived {
+        archived_root::<T>(self.as_slice())
+    }
+
+    /// Validates the archive and gets its root object.
+    ///
+    /// This is a safe alternative to [`archived_root`](MmapArchive::archived_root). See
+    /// [`check_archived_root`](crate::validation::check_archived_root) for more details.
+    #[cfg(feature = "validation")]
+    #[inline]
+    pub fn check_archived_root<T: Archive>(
+        &self,
+    ) -> Result<&T::Archived, CheckTypeError<T::Archived, DefaultArchiveValidator>>
+    where
+        T::Archived: CheckBytes<DefaultArchiveValidator>,
+    {
+        check_archived_root::<T>(self.as_slice())
+    }
+}
+
+impl AsRef<[u8]> for MmapArchive {
+    #[inline]
+    fn as_ref(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+impl Deref for MmapArchive {
+    type Target = [u8];
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        self.as_slice()
+    }
+}
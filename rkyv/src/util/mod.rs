@@ -9,6 +9,10 @@
 //! Alignment helpers ensure that byte buffers are properly aligned when accessing and deserializing
 //! data.
 
+#[cfg(feature = "mmap")]
+mod mmap;
+#[cfg(feature = "std")]
+mod scratch_vec;
 #[cfg(feature = "std")]
 mod std;
 
@@ -19,6 +23,10 @@ use core::{
     pin::Pin,
 };
 
+#[cfg(feature = "mmap")]
+pub use self::mmap::MmapArchive;
+#[cfg(feature = "std")]
+pub use self::scratch_vec::ScratchVec;
 #[doc(inline)]
 #[cfg(feature = "std")]
 pub use self::std::*;
@@ -0,0 +1,235 @@
+use crate::ser::Serializer;
+use core::{
+    alloc::Layout,
+    mem,
+    ops::{Deref, DerefMut},
+    ptr::{self, NonNull},
+    slice,
+};
+
+/// A vector with a fixed capacity that stores its elements in the scratch space of a serializer.
+///
+/// The memory for the vector is allocated with [`push_scratch`](Serializer::push_scratch) when it
+/// is created and must be returned with [`free`](ScratchVec::free) to the same serializer. Scratch
+/// vectors must be freed in the reverse order that they were created in. [`with`](ScratchVec::with)
+/// takes care of this, including when serialization fails partway through.
+///
+/// Dropping a scratch vector without freeing it drops its elements but does not return its memory
+/// to the serializer.
+pub struct ScratchVec<T> {
+    ptr: NonNull<T>,
+    cap: usize,
+    len: usize,
+}
+
+impl<T> Drop for ScratchVec<T> {
+    #[inline]
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl<T> ScratchVec<T> {
+    /// Constructs a new, empty `ScratchVec` with the specified capacity.
+    ///
+    /// Zero-sized vectors and vectors of zero-sized types don't allocate any scratch space.
+    ///
+    /// # Safety
+    ///
+    /// The vector must be freed with the same serializer before any scratch space allocated before
+    /// it is freed.
+    #[inline]
+    pub unsafe fn new<S: Serializer + ?Sized>(
+        serializer: &mut S,
+        capacity: usize,
+    ) -> Result<Self, S::Error> {
+        let layout = Self::layout(capacity);
+        let ptr = if layout.size() == 0 {
+            NonNull::dangling()
+        } else {
+            serializer.push_scratch(layout)?.cast()
+        };
+        Ok(Self {
+            ptr,
+            cap: capacity,
+            len: 0,
+        })
+    }
+
+    /// Drops the elements of the vector and returns its memory to the serializer.
+    ///
+    /// # Safety
+    ///
+    /// The serializer must be the same one that the vector was created with, and no scratch space
+    /// allocated after the vector may still be in use.
+    #[inline]
+    pub unsafe fn free<S: Serializer + ?Sized>(
+        mut self,
+        serializer: &mut S,
+    ) -> Result<(), S::Error> {
+        self.clear();
+        let layout = Self::layout(self.cap);
+        let ptr = self.ptr.cast();
+        mem::forget(self);
+        if layout.size() != 0 {
+            serializer.pop_scratch(ptr, layout)?;
+        }
+        Ok(())
+    }
+
+    /// Creates a scratch vector with the specified capacity, calls `f` with it, and then frees it.
+    ///
+    /// The vector is freed whether or not `f` succeeds. If `f` returns an error, that error is
+    /// returned instead of any error from freeing the vector.
+    ///
+    /// # Safety
+    ///
+    /// `f` must free all of the scratch space it allocates before returning, and must not replace
+    /// the vector it's given with another scratch vector.
+    #[inline]
+    pub unsafe fn with<S, R, F>(serializer: &mut S, capacity: usize, f: F) -> Result<R, S::Error>
+    where
+        S: Serializer + ?Sized,
+        F: FnOnce(&mut S, &mut Self) -> Result<R, S::Error>,
+    {
+        let mut vec = Self::new(serializer, capacity)?;
+        let result = f(serializer, &mut vec);
+        let freed = vec.free(serializer);
+        let value = result?;
+        freed?;
+        Ok(value)
+    }
+
+    #[inline]
+    fn layout(capacity: usize) -> Layout {
+        Layout::array::<T>(capacity).unwrap()
+    }
+
+    /// Returns the number of elements the vector can hold.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.cap
+    }
+
+    /// Returns the number of elements in the vector.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the vector contains no elements.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Appends an element to the back of the vector.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the vector is already at capacity.
+    #[inline]
+    pub fn push(&mut self, value: T) {
+        assert!(self.len < self.cap, "scratch vec capacity exceeded");
+        unsafe {
+            self.ptr.as_ptr().add(self.len).write(value);
+        }
+        self.len += 1;
+    }
+
+    /// Appends elements produced by `f` to the vector until it is at capacity.
+    #[inline]
+    pub fn fill_with<F: FnMut() -> T>(&mut self, mut f: F) {
+        while self.len < self.cap {
+            self.push(f());
+        }
+    }
+
+    /// Drops all of the elements in the vector.
+    #[inline]
+    pub fn clear(&mut self) {
+        let elements = self.as_mut_slice() as *mut [T];
+        self.len = 0;
+        unsafe {
+            ptr::drop_in_place(elements);
+        }
+    }
+
+    /// Removes all of the elements from the vector and returns them in an iterator.
+    ///
+    /// Any elements that the iterator doesn't yield are dropped when it is dropped.
+    #[inline]
+    pub fn drain(&mut self) -> impl ExactSizeIterator<Item = T> + '_ {
+        let len = self.len;
+        self.len = 0;
+        Drain {
+            vec: self,
+            index: 0,
+            len,
+        }
+    }
+
+    /// Extracts a slice containing the entire vector.
+    #[inline]
+    pub fn as_slice(&self) -> &[T] {
+        unsafe { slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+
+    /// Extracts a mutable slice containing the entire vector.
+    #[inline]
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        unsafe { slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl<T> Deref for ScratchVec<T> {
+    type Target = [T];
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        self.as_slice()
+    }
+}
+
+impl<T> DerefMut for ScratchVec<T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.as_mut_slice()
+    }
+}
+
+struct Drain<'a, T> {
+    vec: &'a mut ScratchVec<T>,
+    index: usize,
+    len: usize,
+}
+
+impl<T> Iterator for Drain<'_, T> {
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<T> {
+        if self.index < self.len {
+            let result = unsafe { self.vec.ptr.as_ptr().add(self.index).read() };
+            self.index += 1;
+            Some(result)
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.len - self.index;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<T> ExactSizeIterator for Drain<'_, T> {}
+
+impl<T> Drop for Drain<'_, T> {
+    #[inline]
+    fn drop(&mut self) {
+        self.for_each(drop);
+    }
+}
@@ -1,3 +1,7 @@
+use crate::{
+    ser::{serializers::AllocSizeSerializer, Serializer},
+    Fallible, Serialize,
+};
 use core::{
     fmt,
     ops::{Deref, DerefMut, Index, IndexMut},
@@ -46,7 +50,9 @@ impl AlignedVec {
     #[inline]
     pub fn new() -> Self {
         AlignedVec {
-            ptr: NonNull::dangling(),
+            // Zero-sized values may still be written to an empty vector, so the dangling pointer
+            // must have the same alignment as an allocated one
+            ptr: unsafe { NonNull::new_unchecked(Self::ALIGNMENT as *mut u8) },
             cap: 0,
             len: 0,
         }
@@ -98,6 +104,37 @@ impl AlignedVec {
         }
     }
 
+    /// Constructs a new, empty `AlignedVec` with exactly enough capacity to hold the archive of
+    /// `value`.
+    ///
+    /// This serializes `value` once with an [`AllocSizeSerializer`] to compute the size of its
+    /// archive. See [`archived_size_of`] for more details.
+    ///
+    /// # Examples
+    /// ```
+    /// use rkyv::{
+    ///     ser::{serializers::AlignedSerializer, Serializer},
+    ///     AlignedVec,
+    /// };
+    ///
+    /// let value = "hello world".to_string();
+    /// let vec = AlignedVec::with_capacity_for(&value).unwrap();
+    /// let capacity = vec.capacity();
+    ///
+    /// // Serializing the value fills the vector without reallocating
+    /// let mut serializer = AlignedSerializer::new(vec);
+    /// serializer.serialize_value(&value).unwrap();
+    /// let vec = serializer.into_inner();
+    /// assert_eq!(vec.len(), capacity);
+    /// assert_eq!(vec.capacity(), capacity);
+    /// ```
+    #[inline]
+    pub fn with_capacity_for<T: Serialize<AllocSizeSerializer>>(
+        value: &T,
+    ) -> Result<Self, <AllocSizeSerializer as Fallible>::Error> {
+        Ok(Self::with_capacity(archived_size_of(value)?))
+    }
+
     #[inline]
     fn layout(&self) -> alloc::Layout {
         unsafe { alloc::Layout::from_size_align_unchecked(self.cap, Self::ALIGNMENT) }
@@ -419,6 +456,39 @@ impl AlignedVec {
         }
     }
 
+    /// Resizes the vector in-place so that `len` is equal to `new_len`.
+    ///
+    /// If `new_len` is greater than `len`, the vector is extended by the difference, with each
+    /// additional slot filled with `value`. If `new_len` is less than `len`, the vector is simply
+    /// truncated.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new capacity exceeds `usize::MAX` bytes.
+    ///
+    /// # Examples
+    /// ```
+    /// use rkyv::AlignedVec;
+    ///
+    /// let mut vec = AlignedVec::new();
+    /// vec.extend_from_slice(&[1, 2, 3]);
+    /// vec.resize(5, 0);
+    /// assert_eq!(vec.as_slice(), &[1, 2, 3, 0, 0]);
+    /// vec.resize(2, 0);
+    /// assert_eq!(vec.as_slice(), &[1, 2]);
+    /// ```
+    #[inline]
+    pub fn resize(&mut self, new_len: usize, value: u8) {
+        if new_len > self.len {
+            let additional = new_len - self.len;
+            self.reserve(additional);
+            unsafe {
+                core::ptr::write_bytes(self.as_mut_ptr().add(self.len), value, additional);
+            }
+        }
+        self.len = new_len;
+    }
+
     /// Reserves the minimum capacity for exactly `additional` more elements to be inserted in the
     /// given `AlignedVec`. After calling `reserve_exact`, capacity will be greater than or equal
     /// to `self.len() + additional`. Does nothing if the capacity is already sufficient.
@@ -557,6 +627,36 @@ impl AlignedVec {
     }
 }
 
+/// Computes the number of bytes that serializing `value` will take.
+///
+/// The returned size is exactly the length of the buffer that an
+/// [`AllocSerializer`](crate::ser::serializers::AllocSerializer) or any other serializer that starts
+/// at position 0 produces for the same value, including alignment padding and deduplicated shared
+/// values. No bytes are written while computing it.
+///
+/// # Examples
+/// ```
+/// use rkyv::{
+///     archived_size_of,
+///     ser::{serializers::AllocSerializer, Serializer},
+/// };
+///
+/// let value = vec!["hello".to_string(), "world".to_string()];
+/// let size = archived_size_of(&value).unwrap();
+///
+/// let mut serializer = AllocSerializer::<256>::default();
+/// serializer.serialize_value(&value).unwrap();
+/// assert_eq!(serializer.into_serializer().into_inner().len(), size);
+/// ```
+#[inline]
+pub fn archived_size_of<T: Serialize<AllocSizeSerializer>>(
+    value: &T,
+) -> Result<usize, <AllocSizeSerializer as Fallible>::Error> {
+    let mut serializer = AllocSizeSerializer::default();
+    serializer.serialize_value(value)?;
+    Ok(serializer.pos())
+}
+
 impl From<AlignedVec> for Vec<u8> {
     #[inline]
     fn from(aligned: AlignedVec) -> Self {
@@ -1,17 +1,30 @@
 //! Validation implementations and helper types.
 
 use crate::{
-    offset_of, Archive, ArchivePointee, Archived, ArchivedIsize, Fallible, RawRelPtr, RelPtr,
+    checksum::{verify_checksum, ChecksumError, StreamingHasher},
+    de::{
+        adapters::{write_path, SharedDeserializerAdapter},
+        deserializers::AllocDeserializer,
+        PathSegment,
+    },
+    offset_of, Archive, ArchivePointee, Archived, ArchivedIsize, Deserialize, Fallible, RawRelPtr,
+    RelPtr,
+};
+use bytecheck::{
+    CheckBytes, SliceCheckError, StructCheckError, TupleStructCheckError, Unreachable,
 };
-use bytecheck::{CheckBytes, Unreachable};
 use core::{
-    alloc::Layout,
+    alloc::{Layout, LayoutError},
     any::TypeId,
     fmt,
     marker::{PhantomData, PhantomPinned},
+    ops::Range,
 };
 use ptr_meta::{DynMetadata, Pointee};
-use std::{collections::HashMap, error::Error};
+use std::{
+    collections::{BTreeMap, HashMap},
+    error::Error,
+};
 
 impl RawRelPtr {
     /// Checks the bytes of the given raw relative pointer.
@@ -69,34 +82,133 @@ impl<T: ArchivePointee + ?Sized> RelPtr<T> {
 /// Gets the layout of a type from its metadata.
 pub trait LayoutMetadata<T: ?Sized> {
     /// Gets the layout of the type.
-    fn layout(self) -> Layout;
+    ///
+    /// Metadata comes from untrusted archives, so this fails instead of panicking if the metadata
+    /// describes a value that's too large to fit in memory.
+    fn layout(self) -> Result<Layout, LayoutError>;
 }
 
 impl<T> LayoutMetadata<T> for () {
     #[inline]
-    fn layout(self) -> Layout {
-        Layout::new::<T>()
+    fn layout(self) -> Result<Layout, LayoutError> {
+        Ok(Layout::new::<T>())
     }
 }
 
 impl<T> LayoutMetadata<[T]> for usize {
     #[inline]
-    fn layout(self) -> Layout {
-        Layout::array::<T>(self).unwrap()
+    fn layout(self) -> Result<Layout, LayoutError> {
+        Layout::array::<T>(self)
     }
 }
 
 impl LayoutMetadata<str> for usize {
     #[inline]
-    fn layout(self) -> Layout {
-        Layout::array::<u8>(self).unwrap()
+    fn layout(self) -> Result<Layout, LayoutError> {
+        Layout::array::<u8>(self)
     }
 }
 
 impl<T: ?Sized> LayoutMetadata<T> for DynMetadata<T> {
     #[inline]
-    fn layout(self) -> Layout {
-        self.layout()
+    fn layout(self) -> Result<Layout, LayoutError> {
+        Ok(self.layout())
+    }
+}
+
+/// Checks the elements of a slice like `<[T]>::check_bytes`, reporting the index of each element to
+/// the context so errors can be located.
+///
+/// # Safety
+///
+/// The caller must guarantee that the given pointer is aligned and points to enough bytes to
+/// represent the slice.
+pub(crate) unsafe fn check_elements<'a, T, C>(
+    value: *const [T],
+    context: &mut C,
+) -> Result<&'a [T], SliceCheckError<T::Error>>
+where
+    T: CheckBytes<C>,
+    C: ArchiveBoundsContext + ?Sized,
+{
+    let data = value.cast::<T>();
+    for index in 0..ptr_meta::metadata(value) {
+        context.enter_path(PathSegment::Index(index));
+        T::check_bytes(data.add(index), context)
+            .map_err(|error| SliceCheckError::CheckBytes { index, error })?;
+        context.exit_path();
+    }
+    Ok(&*value)
+}
+
+/// An error resulting from an invalid archived enum.
+///
+/// This is the error type of the `CheckBytes` implementations generated for archived enums by the
+/// `Archive` derive.
+#[derive(Debug)]
+pub enum EnumCheckError<T> {
+    /// A struct variant was invalid
+    InvalidStruct {
+        /// The name of the enum
+        type_name: &'static str,
+        /// The name of the variant that was invalid
+        variant_name: &'static str,
+        /// The error that occurred while checking the variant
+        inner: StructCheckError,
+    },
+    /// A tuple variant was invalid
+    InvalidTupleStruct {
+        /// The name of the enum
+        type_name: &'static str,
+        /// The name of the variant that was invalid
+        variant_name: &'static str,
+        /// The error that occurred while checking the variant
+        inner: TupleStructCheckError,
+    },
+    /// The enum tag did not match any variant
+    InvalidTag {
+        /// The name of the enum
+        type_name: &'static str,
+        /// The invalid tag
+        tag: T,
+    },
+}
+
+impl<T: fmt::Display> fmt::Display for EnumCheckError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EnumCheckError::InvalidStruct {
+                type_name,
+                variant_name,
+                inner,
+            } => write!(
+                f,
+                "check failed for enum `{}` struct variant `{}`: {}",
+                type_name, variant_name, inner
+            ),
+            EnumCheckError::InvalidTupleStruct {
+                type_name,
+                variant_name,
+                inner,
+            } => write!(
+                f,
+                "check failed for enum `{}` tuple variant `{}`: {}",
+                type_name, variant_name, inner
+            ),
+            EnumCheckError::InvalidTag { type_name, tag } => {
+                write!(f, "invalid tag {} for enum `{}`", tag, type_name)
+            }
+        }
+    }
+}
+
+impl<T: fmt::Debug + fmt::Display> Error for EnumCheckError<T> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            EnumCheckError::InvalidStruct { inner, .. } => Some(inner as &dyn Error),
+            EnumCheckError::InvalidTupleStruct { inner, .. } => Some(inner as &dyn Error),
+            EnumCheckError::InvalidTag { .. } => None,
+        }
     }
 }
 
@@ -135,6 +247,13 @@ pub enum ArchiveBoundsError {
         /// The required alignment of the type
         align: usize,
     },
+    /// The metadata of the type described a value too large to fit in memory
+    InvalidLayout {
+        /// The position of the type
+        pos: usize,
+        /// The error from computing the layout of the type
+        error: LayoutError,
+    },
 }
 
 impl fmt::Display for ArchiveBoundsError {
@@ -154,7 +273,7 @@ impl fmt::Display for ArchiveBoundsError {
                 archive_len,
             } => write!(
                 f,
-                "out of bounds pointer: base {} offset {} in archive len {}",
+                "relative pointer at offset {:#x} out of bounds: points {} bytes away in archive of length {:#x}",
                 base, offset, archive_len
             ),
             ArchiveBoundsError::Overrun {
@@ -163,14 +282,17 @@ impl fmt::Display for ArchiveBoundsError {
                 archive_len,
             } => write!(
                 f,
-                "archive overrun: pos {} size {} in archive len {}",
-                pos, size, archive_len
+                "archive overrun: {} bytes at offset {:#x} extend past archive of length {:#x}",
+                size, pos, archive_len
             ),
             ArchiveBoundsError::Unaligned { pos, align } => write!(
                 f,
-                "unaligned pointer: pos {} unaligned for alignment {}",
+                "unaligned pointer: offset {:#x} is not aligned to {}",
                 pos, align
             ),
+            ArchiveBoundsError::InvalidLayout { pos, error } => {
+                write!(f, "invalid layout for value at offset {:#x}: {}", pos, error)
+            }
         }
     }
 }
@@ -200,9 +322,66 @@ pub trait ArchiveBoundsContext: Fallible {
         ptr: *const u8,
         layout: &Layout,
     ) -> Result<(), Self::Error>;
+
+    /// Checks the memory block for a value whose layout was computed from its metadata.
+    ///
+    /// Computing the layout fails if the metadata describes a value that's too large to fit in
+    /// memory. Since such a value can't fit in the archive either, that's reported as a bounds
+    /// issue. Otherwise, this checks the memory block like
+    /// [`bounds_check_ptr`](ArchiveBoundsContext::bounds_check_ptr) and returns the layout.
+    ///
+    /// # Safety
+    ///
+    /// The caller must guarantee that the pointer is inside the archive for this context.
+    unsafe fn bounds_check_layout(
+        &mut self,
+        ptr: *const u8,
+        layout: Result<Layout, LayoutError>,
+    ) -> Result<Layout, Self::Error>;
+
+    /// Gets the position of the given pointer relative to the start of the archive.
+    ///
+    /// Errors report positions instead of addresses so they can be matched up with the bytes of the
+    /// archive. Contexts that don't know where the archive starts return the address of the pointer.
+    #[inline]
+    fn position_of(&self, ptr: *const u8) -> usize {
+        ptr as usize
+    }
+
+    /// Called before checking a part of a value that has a location, like a field or an element of
+    /// a slice.
+    ///
+    /// Slices report the index of each element, and types that derive `Archive` with
+    /// `#[archive(track_path)]` report their type name, the variant for enums, and the name of each
+    /// field. Unlike deserializers, [`exit_path`](ArchiveBoundsContext::exit_path) is only called
+    /// once the part checks successfully. When checking fails, the segments leading to the invalid
+    /// value are left in place and can be retrieved with [`path`](ArchiveBoundsContext::path).
+    ///
+    /// Keeping track of paths slows down checking large archives, so only contexts wrapped in an
+    /// [`ArchivePathValidator`] do so.
+    #[inline]
+    fn enter_path(&mut self, segment: PathSegment) {
+        let _ = segment;
+    }
+
+    /// Called after successfully checking a part of a value that has a location.
+    #[inline]
+    fn exit_path(&mut self) {}
+
+    /// Gets the path to the value that is being checked, or to the invalid value if checking
+    /// failed.
+    ///
+    /// Contexts that don't keep track of paths always return an empty path.
+    #[inline]
+    fn path(&self) -> &[PathSegment] {
+        &[]
+    }
 }
 
 /// A validator that can bounds check pointers in an archive.
+///
+/// This validator doesn't keep track of paths; wrap it in an [`ArchivePathValidator`] to report
+/// where errors occur.
 pub struct ArchiveBoundsValidator {
     begin: *const u8,
     len: usize,
@@ -286,6 +465,103 @@ impl ArchiveBoundsContext for ArchiveBoundsValidator {
             }
         }
     }
+
+    unsafe fn bounds_check_layout(
+        &mut self,
+        ptr: *const u8,
+        layout: Result<Layout, LayoutError>,
+    ) -> Result<Layout, Self::Error> {
+        let layout = layout.map_err(|error| ArchiveBoundsError::InvalidLayout {
+            pos: self.position_of(ptr),
+            error,
+        })?;
+        self.bounds_check_ptr(ptr, &layout)?;
+        Ok(layout)
+    }
+
+    #[inline]
+    fn position_of(&self, ptr: *const u8) -> usize {
+        (ptr as usize).wrapping_sub(self.begin as usize)
+    }
+}
+
+/// An adapter that keeps track of the path to the value being checked.
+///
+/// When checking fails, the path to the invalid value is left in place and can be retrieved with
+/// [`path`](ArchiveBoundsContext::path).
+pub struct ArchivePathValidator<C> {
+    inner: C,
+    path: Vec<PathSegment>,
+}
+
+impl<C> ArchivePathValidator<C> {
+    /// Wraps the given context and adds path tracking.
+    #[inline]
+    pub fn new(inner: C) -> Self {
+        Self {
+            inner,
+            path: Vec::new(),
+        }
+    }
+
+    /// Consumes the adapter and returns the underlying context.
+    #[inline]
+    pub fn into_inner(self) -> C {
+        self.inner
+    }
+}
+
+impl<C: Fallible> Fallible for ArchivePathValidator<C> {
+    type Error = C::Error;
+}
+
+impl<C: ArchiveBoundsContext> ArchiveBoundsContext for ArchivePathValidator<C> {
+    #[inline]
+    unsafe fn check_rel_ptr(
+        &mut self,
+        base: *const u8,
+        offset: isize,
+    ) -> Result<*const u8, Self::Error> {
+        self.inner.check_rel_ptr(base, offset)
+    }
+
+    #[inline]
+    unsafe fn bounds_check_ptr(
+        &mut self,
+        ptr: *const u8,
+        layout: &Layout,
+    ) -> Result<(), Self::Error> {
+        self.inner.bounds_check_ptr(ptr, layout)
+    }
+
+    #[inline]
+    unsafe fn bounds_check_layout(
+        &mut self,
+        ptr: *const u8,
+        layout: Result<Layout, LayoutError>,
+    ) -> Result<Layout, Self::Error> {
+        self.inner.bounds_check_layout(ptr, layout)
+    }
+
+    #[inline]
+    fn position_of(&self, ptr: *const u8) -> usize {
+        self.inner.position_of(ptr)
+    }
+
+    #[inline]
+    fn enter_path(&mut self, segment: PathSegment) {
+        self.path.push(segment);
+    }
+
+    #[inline]
+    fn exit_path(&mut self) {
+        self.path.pop();
+    }
+
+    #[inline]
+    fn path(&self) -> &[PathSegment] {
+        &self.path
+    }
 }
 
 /// A range of bytes in an archive.
@@ -305,6 +581,178 @@ impl Interval {
     }
 }
 
+/// The disjoint intervals of bytes that have been claimed in an archive.
+///
+/// Intervals are kept sorted in chunks of at most [`MAX_CHUNK_LEN`] intervals, so claiming an
+/// interval only ever shifts the intervals in one chunk. This keeps claiming fast for archives with
+/// millions of objects no matter what order they're claimed in. Since archives are mostly checked
+/// in the order they were written, claims past the end of the last interval are appended and
+/// claims right next to the last claimed interval are placed without searching.
+struct ClaimedIntervals {
+    chunks: Vec<Vec<Interval>>,
+    /// The chunk and index of the interval that the last claim was merged into
+    last: (usize, usize),
+}
+
+/// The maximum number of intervals in a chunk of [`ClaimedIntervals`].
+const MAX_CHUNK_LEN: usize = 512;
+
+impl ClaimedIntervals {
+    #[inline]
+    fn new() -> Self {
+        Self {
+            chunks: Vec::new(),
+            last: (0, 0),
+        }
+    }
+
+    /// Claims a nonempty interval, returning the claimed interval that it overlaps if there is one.
+    #[inline]
+    fn claim(&mut self, interval: Interval) -> Result<(), Interval> {
+        // Chunks are never empty
+        if let Some(chunk) = self.chunks.last_mut() {
+            let index = chunk.len() - 1;
+            let last = &mut chunk[index];
+            if interval.start == last.end {
+                last.end = interval.end;
+                self.last = (self.chunks.len() - 1, index);
+                return Ok(());
+            } else if interval.start > last.end {
+                if chunk.len() < MAX_CHUNK_LEN {
+                    chunk.push(interval);
+                    self.last = (self.chunks.len() - 1, index + 1);
+                } else {
+                    self.chunks.push(vec![interval]);
+                    self.last = (self.chunks.len() - 1, 0);
+                }
+                return Ok(());
+            }
+        }
+
+        match self.position_near_last(interval) {
+            Some((chunk_index, index)) => {
+                self.insert(chunk_index, index, interval);
+                Ok(())
+            }
+            None => self.claim_slow(interval),
+        }
+    }
+
+    /// Returns where the interval goes if it fits right before or after the last claimed interval.
+    #[inline]
+    fn position_near_last(&self, interval: Interval) -> Option<(usize, usize)> {
+        let (chunk_index, index) = self.last;
+        let chunk = self.chunks.get(chunk_index)?;
+        let last = chunk.get(index)?;
+        if interval.start >= last.end {
+            let next = match chunk.get(index + 1) {
+                Some(next) => Some(next),
+                None => self.chunks.get(chunk_index + 1).map(|next| &next[0]),
+            };
+            match next {
+                Some(next) if next.start < interval.end => (),
+                _ => return Some((chunk_index, index + 1)),
+            }
+        } else if interval.end <= last.start && index > 0 && chunk[index - 1].end <= interval.start
+        {
+            return Some((chunk_index, index));
+        }
+        None
+    }
+
+    #[inline(never)]
+    fn claim_slow(&mut self, interval: Interval) -> Result<(), Interval> {
+        // Only the interval that starts closest before the end of the claim can overlap it
+        let chunk_index = self
+            .chunks
+            .partition_point(|chunk| chunk[0].start < interval.end);
+        if chunk_index == 0 {
+            match self.chunks.first_mut() {
+                Some(chunk) if chunk[0].start == interval.end => chunk[0].start = interval.start,
+                Some(chunk) => {
+                    chunk.insert(0, interval);
+                    self.split(0, 0);
+                }
+                None => self.chunks.push(vec![interval]),
+            }
+            self.last = (0, 0);
+            return Ok(());
+        }
+
+        let chunk_index = chunk_index - 1;
+        let chunk = &self.chunks[chunk_index];
+        let index = chunk.partition_point(|claimed| claimed.start < interval.end);
+        let prev = chunk[index - 1];
+        if prev.end > interval.start {
+            return Err(prev);
+        }
+
+        self.insert(chunk_index, index, interval);
+        Ok(())
+    }
+
+    /// Inserts an interval that doesn't overlap the intervals around it before the interval at the
+    /// given position, which must come after the first interval.
+    #[inline]
+    fn insert(&mut self, chunk_index: usize, index: usize, interval: Interval) {
+        // Adjacent intervals are merged into the new claim to keep the chunks small
+        let chunk = &self.chunks[chunk_index];
+        let prev_end = chunk[index - 1].end;
+        let next_start = match chunk.get(index) {
+            Some(next) => Some(next.start),
+            None => self.chunks.get(chunk_index + 1).map(|next| next[0].start),
+        };
+        let chunk = &mut self.chunks[chunk_index];
+        self.last = match (prev_end == interval.start, next_start == Some(interval.end)) {
+            (true, true) => {
+                if index < chunk.len() {
+                    chunk[index - 1].end = chunk.remove(index).end;
+                } else {
+                    let next = &mut self.chunks[chunk_index + 1];
+                    let end = next.remove(0).end;
+                    if next.is_empty() {
+                        self.chunks.remove(chunk_index + 1);
+                    }
+                    self.chunks[chunk_index][index - 1].end = end;
+                }
+                (chunk_index, index - 1)
+            }
+            (true, false) => {
+                chunk[index - 1].end = interval.end;
+                (chunk_index, index - 1)
+            }
+            (false, true) if index < chunk.len() => {
+                chunk[index].start = interval.start;
+                (chunk_index, index)
+            }
+            (false, true) => {
+                self.chunks[chunk_index + 1][0].start = interval.start;
+                (chunk_index + 1, 0)
+            }
+            (false, false) => {
+                chunk.insert(index, interval);
+                self.split(chunk_index, index)
+            }
+        };
+    }
+
+    /// Splits the chunk at the given index in half if it has grown too long, returning the new
+    /// position of the interval at the given index in the chunk.
+    #[inline]
+    fn split(&mut self, chunk_index: usize, index: usize) -> (usize, usize) {
+        let chunk = &mut self.chunks[chunk_index];
+        if chunk.len() > MAX_CHUNK_LEN {
+            let mid = chunk.len() / 2;
+            let tail = chunk.split_off(mid);
+            self.chunks.insert(chunk_index + 1, tail);
+            if index >= mid {
+                return (chunk_index + 1, index - mid);
+            }
+        }
+        (chunk_index, index)
+    }
+}
+
 /// Errors that can occur related to archive memory.
 #[derive(Debug)]
 pub enum ArchiveMemoryError<E> {
@@ -312,10 +760,10 @@ pub enum ArchiveMemoryError<E> {
     Inner(E),
     /// Multiple objects claim to own the same memory region
     ClaimOverlap {
-        /// A previous interval of bytes claimed by some object
-        previous: Interval,
-        /// The current interval of bytes being claimed by some object
-        current: Interval,
+        /// The positions of a previous range of bytes claimed by some object
+        previous: Range<usize>,
+        /// The positions of the current range of bytes being claimed by some object
+        current: Range<usize>,
     },
 }
 
@@ -325,7 +773,7 @@ impl<E: fmt::Display> fmt::Display for ArchiveMemoryError<E> {
             ArchiveMemoryError::Inner(e) => e.fmt(f),
             ArchiveMemoryError::ClaimOverlap { previous, current } => write!(
                 f,
-                "memory claim overlap: current [{:#?}..{:#?}] overlaps previous [{:#?}..{:#?}]",
+                "memory claim overlap: current [{:#x}..{:#x}] overlaps previous [{:#x}..{:#x}]",
                 current.start, current.end, previous.start, previous.end
             ),
         }
@@ -368,9 +816,10 @@ pub trait ArchiveMemoryContext: Fallible {
         Self: ArchiveBoundsContext,
         <T as Pointee>::Metadata: LayoutMetadata<T>,
     {
-        let metadata = ptr_meta::metadata(ptr);
-        let layout = LayoutMetadata::<T>::layout(metadata);
-        self.bounds_check_ptr(ptr.cast(), &layout)?;
+        let layout = self.bounds_check_layout(
+            ptr.cast(),
+            LayoutMetadata::<T>::layout(ptr_meta::metadata(ptr)),
+        )?;
         self.claim_bytes(ptr.cast(), layout.size())?;
         Ok(())
     }
@@ -397,7 +846,7 @@ pub trait ArchiveMemoryContext: Fallible {
 /// An adapter that adds memory validation to a context.
 pub struct ArchiveValidator<C> {
     inner: C,
-    intervals: Vec<Interval>,
+    intervals: ClaimedIntervals,
 }
 
 impl<C> ArchiveValidator<C> {
@@ -406,7 +855,7 @@ impl<C> ArchiveValidator<C> {
     pub fn new(inner: C) -> Self {
         Self {
             inner,
-            intervals: Vec::new(),
+            intervals: ClaimedIntervals::new(),
         }
     }
 
@@ -417,6 +866,16 @@ impl<C> ArchiveValidator<C> {
     }
 }
 
+impl<C: ArchiveBoundsContext> ArchiveValidator<C> {
+    #[inline]
+    fn claim_overlap(&self, previous: Interval, current: Interval) -> ArchiveMemoryError<C::Error> {
+        ArchiveMemoryError::ClaimOverlap {
+            previous: self.inner.position_of(previous.start)..self.inner.position_of(previous.end),
+            current: self.inner.position_of(current.start)..self.inner.position_of(current.end),
+        }
+    }
+}
+
 impl<C: Fallible> Fallible for ArchiveValidator<C> {
     type Error = ArchiveMemoryError<C::Error>;
 }
@@ -443,48 +902,176 @@ impl<C: ArchiveBoundsContext> ArchiveBoundsContext for ArchiveValidator<C> {
             .bounds_check_ptr(ptr, layout)
             .map_err(ArchiveMemoryError::Inner)
     }
+
+    #[inline]
+    unsafe fn bounds_check_layout(
+        &mut self,
+        ptr: *const u8,
+        layout: Result<Layout, LayoutError>,
+    ) -> Result<Layout, Self::Error> {
+        self.inner
+            .bounds_check_layout(ptr, layout)
+            .map_err(ArchiveMemoryError::Inner)
+    }
+
+    #[inline]
+    fn position_of(&self, ptr: *const u8) -> usize {
+        self.inner.position_of(ptr)
+    }
+
+    #[inline]
+    fn enter_path(&mut self, segment: PathSegment) {
+        self.inner.enter_path(segment);
+    }
+
+    #[inline]
+    fn exit_path(&mut self) {
+        self.inner.exit_path();
+    }
+
+    #[inline]
+    fn path(&self) -> &[PathSegment] {
+        self.inner.path()
+    }
 }
 
 impl<C: ArchiveBoundsContext> ArchiveMemoryContext for ArchiveValidator<C> {
     unsafe fn claim_bytes(&mut self, start: *const u8, len: usize) -> Result<(), Self::Error> {
+        // Claiming no bytes can't overlap with anything
+        if len == 0 {
+            return Ok(());
+        }
+
         let interval = Interval {
             start,
             end: start.add(len),
         };
-        match self.intervals.binary_search(&interval) {
-            Ok(index) => Err(ArchiveMemoryError::ClaimOverlap {
-                previous: self.intervals[index],
-                current: interval,
-            }),
-            Err(index) => {
-                if index < self.intervals.len() {
-                    if self.intervals[index].overlaps(&interval) {
-                        return Err(ArchiveMemoryError::ClaimOverlap {
-                            previous: self.intervals[index],
-                            current: interval,
-                        });
-                    } else if self.intervals[index].start == interval.end {
-                        self.intervals[index].start = interval.start;
-                        return Ok(());
-                    }
-                }
+        self.intervals
+            .claim(interval)
+            .map_err(|previous| self.claim_overlap(previous, interval))
+    }
+}
 
-                if index > 0 {
-                    if self.intervals[index - 1].overlaps(&interval) {
-                        return Err(ArchiveMemoryError::ClaimOverlap {
-                            previous: self.intervals[index - 1],
-                            current: interval,
-                        });
-                    } else if self.intervals[index - 1].end == interval.start {
-                        self.intervals[index - 1].end = interval.end;
-                        return Ok(());
-                    }
-                }
+/// A block of bytes claimed by an [`ArchiveDedupValidator`].
+struct ClaimedBlock {
+    end: *const u8,
+    has_pointers: bool,
+}
 
-                self.intervals.insert(index, interval);
-                Ok(())
+/// An adapter that allows blocks of bytes without any pointers to be claimed more than once.
+///
+/// Serializers like [`DedupSerializer`](crate::ser::adapters::DedupSerializer) point identical
+/// strings and slices of copy values at the same bytes, which [`ArchiveValidator`] rejects as
+/// overlapping claims. This adapter sits on top of an `ArchiveValidator` and accepts a claim of
+/// exactly the same bytes as an earlier claim, as long as no relative pointers have been checked
+/// inside of those bytes. Blocks with pointers still have a single owner, so claiming can't recurse
+/// into the same block forever.
+///
+/// Values in a deduplicated archive may share their bytes with other values, so the archive must
+/// never be accessed mutably. Only use this validator for archives that are read through shared
+/// references, and use the default strict validator for everything else.
+pub struct ArchiveDedupValidator<C> {
+    inner: C,
+    blocks: BTreeMap<*const u8, ClaimedBlock>,
+}
+
+impl<C> ArchiveDedupValidator<C> {
+    /// Wraps the given context and allows plain blocks of bytes to be claimed more than once.
+    #[inline]
+    pub fn new(inner: C) -> Self {
+        Self {
+            inner,
+            blocks: BTreeMap::new(),
+        }
+    }
+
+    /// Consumes the adapter and returns the underlying context.
+    #[inline]
+    pub fn into_inner(self) -> C {
+        self.inner
+    }
+}
+
+impl<C: Fallible> Fallible for ArchiveDedupValidator<C> {
+    type Error = C::Error;
+}
+
+impl<C: ArchiveBoundsContext> ArchiveBoundsContext for ArchiveDedupValidator<C> {
+    #[inline]
+    unsafe fn check_rel_ptr(
+        &mut self,
+        base: *const u8,
+        offset: isize,
+    ) -> Result<*const u8, Self::Error> {
+        // Any block containing a relative pointer can't be shared anymore
+        if let Some((_, block)) = self.blocks.range_mut(..=base).next_back() {
+            if base < block.end {
+                block.has_pointers = true;
+            }
+        }
+        self.inner.check_rel_ptr(base, offset)
+    }
+
+    #[inline]
+    unsafe fn bounds_check_ptr(
+        &mut self,
+        ptr: *const u8,
+        layout: &Layout,
+    ) -> Result<(), Self::Error> {
+        self.inner.bounds_check_ptr(ptr, layout)
+    }
+
+    #[inline]
+    unsafe fn bounds_check_layout(
+        &mut self,
+        ptr: *const u8,
+        layout: Result<Layout, LayoutError>,
+    ) -> Result<Layout, Self::Error> {
+        self.inner.bounds_check_layout(ptr, layout)
+    }
+
+    #[inline]
+    fn position_of(&self, ptr: *const u8) -> usize {
+        self.inner.position_of(ptr)
+    }
+
+    #[inline]
+    fn enter_path(&mut self, segment: PathSegment) {
+        self.inner.enter_path(segment);
+    }
+
+    #[inline]
+    fn exit_path(&mut self) {
+        self.inner.exit_path();
+    }
+
+    #[inline]
+    fn path(&self) -> &[PathSegment] {
+        self.inner.path()
+    }
+}
+
+impl<C: ArchiveMemoryContext> ArchiveMemoryContext for ArchiveDedupValidator<C> {
+    unsafe fn claim_bytes(&mut self, start: *const u8, len: usize) -> Result<(), Self::Error> {
+        let end = start.add(len);
+        if let Some(block) = self.blocks.get(&start) {
+            if block.end == end && !block.has_pointers {
+                return Ok(());
             }
         }
+
+        // Any other claim that overlaps with a claimed block is rejected by the inner context
+        self.inner.claim_bytes(start, len)?;
+        if len != 0 {
+            self.blocks.insert(
+                start,
+                ClaimedBlock {
+                    end,
+                    has_pointers: false,
+                },
+            );
+        }
+        Ok(())
     }
 }
 
@@ -558,8 +1145,8 @@ pub trait SharedArchiveContext: Fallible {
             let data = self.check_rel_ptr(rel_ptr.base(), rel_ptr.offset())?;
             let metadata = T::pointer_metadata(rel_ptr.metadata());
             let ptr = ptr_meta::from_raw_parts::<T>(data.cast(), metadata);
-            let layout = LayoutMetadata::<T>::layout(metadata);
-            self.bounds_check_ptr(ptr.cast(), &layout)?;
+            let layout =
+                self.bounds_check_layout(ptr.cast(), LayoutMetadata::<T>::layout(metadata))?;
             if self.claim_shared_bytes(ptr.cast(), layout.size(), type_id)? {
                 Ok(Some(ptr))
             } else {
@@ -618,6 +1205,37 @@ impl<C: ArchiveBoundsContext> ArchiveBoundsContext for SharedArchiveValidator<C>
             .bounds_check_ptr(ptr, layout)
             .map_err(SharedArchiveError::Inner)
     }
+
+    #[inline]
+    unsafe fn bounds_check_layout(
+        &mut self,
+        ptr: *const u8,
+        layout: Result<Layout, LayoutError>,
+    ) -> Result<Layout, Self::Error> {
+        self.inner
+            .bounds_check_layout(ptr, layout)
+            .map_err(SharedArchiveError::Inner)
+    }
+
+    #[inline]
+    fn position_of(&self, ptr: *const u8) -> usize {
+        self.inner.position_of(ptr)
+    }
+
+    #[inline]
+    fn enter_path(&mut self, segment: PathSegment) {
+        self.inner.enter_path(segment);
+    }
+
+    #[inline]
+    fn exit_path(&mut self) {
+        self.inner.exit_path();
+    }
+
+    #[inline]
+    fn path(&self) -> &[PathSegment] {
+        self.inner.path()
+    }
 }
 
 impl<C: ArchiveMemoryContext> ArchiveMemoryContext for SharedArchiveValidator<C> {
@@ -685,6 +1303,18 @@ impl<T: Error + 'static, C: Error + 'static> Error for CheckArchiveError<T, C> {
 /// A validator that supports all builtin types.
 pub type DefaultArchiveValidator = SharedArchiveValidator<ArchiveValidator<ArchiveBoundsValidator>>;
 
+/// A validator that supports all builtin types and allows the plain blocks of bytes written by
+/// [`DedupSerializer`](crate::ser::adapters::DedupSerializer) to be shared.
+///
+/// See [`ArchiveDedupValidator`] for more information.
+pub type DefaultArchiveDedupValidator =
+    SharedArchiveValidator<ArchiveDedupValidator<ArchiveValidator<ArchiveBoundsValidator>>>;
+
+/// A validator that supports all builtin types and keeps track of the path to the value being
+/// checked.
+pub type DefaultArchivePathValidator =
+    SharedArchiveValidator<ArchiveValidator<ArchivePathValidator<ArchiveBoundsValidator>>>;
+
 /// The error type that can be produced by checking the given type with the given validator.
 pub type CheckTypeError<T, C> =
     CheckArchiveError<<T as CheckBytes<C>>::Error, <C as Fallible>::Error>;
@@ -749,7 +1379,11 @@ pub fn check_archived_root<T: Archive>(
 where
     T::Archived: CheckBytes<DefaultArchiveValidator>,
 {
-    check_archived_value::<T>(buf, buf.len() - core::mem::size_of::<T::Archived>())
+    // Buffers too small to hold the root object fail the bounds check instead of underflowing
+    let pos = buf
+        .len()
+        .saturating_sub(core::mem::size_of::<T::Archived>());
+    check_archived_value::<T>(buf, pos)
 }
 
 /// Checks the given archive with an additional context.
@@ -773,9 +1407,8 @@ where
             .check_rel_ptr(buf.as_ptr(), pos as isize)
             .map_err(CheckArchiveError::ContextError)?;
         let ptr = ptr_meta::from_raw_parts::<<T as Archive>::Archived>(data.cast(), ());
-        let layout = LayoutMetadata::<T::Archived>::layout(());
-        context
-            .bounds_check_ptr(ptr.cast(), &layout)
+        let layout = context
+            .bounds_check_layout(ptr.cast(), LayoutMetadata::<T::Archived>::layout(()))
             .map_err(CheckArchiveError::ContextError)?;
         context
             .claim_bytes(ptr.cast(), layout.size())
@@ -799,9 +1432,350 @@ pub fn check_archived_root_with_context<
 where
     T::Archived: CheckBytes<C> + Pointee<Metadata = ()>,
 {
-    check_archived_value_with_context::<T, C>(
-        buf,
-        buf.len() - core::mem::size_of::<T::Archived>(),
-        context,
-    )
+    let pos = buf
+        .len()
+        .saturating_sub(core::mem::size_of::<T::Archived>());
+    check_archived_value_with_context::<T, C>(buf, pos, context)
+}
+
+/// The path to a value in an archive.
+///
+/// Paths are displayed like Rust expressions that access the value from the root, for example
+/// `Player.inventory[12].name`. Only the type of the root value is displayed, but the types of all
+/// of the values along the path are available from [`segments`](ArchivePath::segments).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ArchivePath {
+    segments: Vec<PathSegment>,
+}
+
+impl ArchivePath {
+    /// Returns the segments of the path, starting from the root.
+    #[inline]
+    pub fn segments(&self) -> &[PathSegment] {
+        &self.segments
+    }
+
+    /// Returns `true` if the path has no segments.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+}
+
+impl fmt::Display for ArchivePath {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_path(&self.segments, f)
+    }
+}
+
+/// An error that occurred while checking an archive, along with the path to the invalid value.
+///
+/// The path is only as detailed as the types being checked report it. Builtin collections report
+/// the index of each element, and types that derive `Archive` with `#[archive(track_path)]` report
+/// their fields and variants.
+#[derive(Debug)]
+pub struct ArchivePathError<E> {
+    path: ArchivePath,
+    inner: E,
+}
+
+impl<E> ArchivePathError<E> {
+    /// Creates a new error from an error that occurred while checking with the given context.
+    ///
+    /// The path is taken from the context, which still holds the path to the invalid value.
+    #[inline]
+    pub fn new<C: ArchiveBoundsContext + ?Sized>(inner: E, context: &C) -> Self {
+        Self {
+            path: ArchivePath {
+                segments: context.path().to_vec(),
+            },
+            inner,
+        }
+    }
+
+    /// Returns the path to the invalid value.
+    #[inline]
+    pub fn path(&self) -> &ArchivePath {
+        &self.path
+    }
+
+    /// Returns a reference to the error that occurred.
+    #[inline]
+    pub fn inner(&self) -> &E {
+        &self.inner
+    }
+
+    /// Consumes the error and returns the error that occurred.
+    #[inline]
+    pub fn into_inner(self) -> E {
+        self.inner
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for ArchivePathError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.inner)
+        } else {
+            write!(f, "`{}`: {}", self.path, self.inner)
+        }
+    }
+}
+
+impl<E: Error + 'static> Error for ArchivePathError<E> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.inner as &dyn Error)
+    }
+}
+
+/// Checks the given archive at the given position for an archived version of the given type, and
+/// reports the path to the invalid value if it fails.
+///
+/// Keeping track of the path makes checking slower, so prefer [`check_archived_value`] for archives
+/// that are expected to be valid. See [`check_archived_value`] for more details.
+///
+/// # Examples
+/// ```
+/// use rkyv::{
+///     archived_value,
+///     check_archived_value_with_path,
+///     ser::{serializers::AllocSerializer, Serializer},
+/// };
+///
+/// let mut serializer = AllocSerializer::<256>::default();
+/// let pos = serializer.serialize_value(&vec!["alice".to_string(), "bob".to_string()])
+///     .expect("failed to archive names");
+/// let mut buf = serializer.into_serializer().into_inner();
+///
+/// // Point the second name out of the bounds of the archive
+/// let archived = unsafe { archived_value::<Vec<String>>(buf.as_ref(), pos) };
+/// let name_pos = &archived[1] as *const _ as usize - buf.as_ptr() as usize;
+/// buf[name_pos..name_pos + 4].copy_from_slice(&[0x7f; 4]);
+///
+/// let error = check_archived_value_with_path::<Vec<String>>(buf.as_ref(), pos).err().unwrap();
+/// assert_eq!(error.path().to_string(), "[1]");
+/// assert!(error.to_string().starts_with("`[1]`: "));
+/// ```
+pub fn check_archived_value_with_path<T: Archive>(
+    buf: &[u8],
+    pos: usize,
+) -> Result<&T::Archived, ArchivePathError<CheckTypeError<T::Archived, DefaultArchivePathValidator>>>
+where
+    T::Archived: CheckBytes<DefaultArchivePathValidator>,
+{
+    let mut validator = SharedArchiveValidator::new(ArchiveValidator::new(
+        ArchivePathValidator::new(ArchiveBoundsValidator::new(buf)),
+    ));
+    check_archived_value_with_context::<T, DefaultArchivePathValidator>(buf, pos, &mut validator)
+        .map_err(|e| ArchivePathError::new(e, &validator))
+}
+
+/// Checks the given archive for an archived version of the given type, and reports the path to the
+/// invalid value if it fails.
+///
+/// See [`check_archived_value_with_path`] for more details.
+pub fn check_archived_root_with_path<T: Archive>(
+    buf: &[u8],
+) -> Result<&T::Archived, ArchivePathError<CheckTypeError<T::Archived, DefaultArchivePathValidator>>>
+where
+    T::Archived: CheckBytes<DefaultArchivePathValidator>,
+{
+    let pos = buf
+        .len()
+        .saturating_sub(core::mem::size_of::<T::Archived>());
+    check_archived_value_with_path::<T>(buf, pos)
+}
+
+/// Errors that can occur when checking an archive with a checksum.
+#[derive(Debug)]
+pub enum CheckChecksumError<E> {
+    /// The checksum of the archive could not be verified
+    ChecksumError(ChecksumError),
+    /// The archive failed validation
+    CheckArchiveError(E),
+}
+
+impl<E: fmt::Display> fmt::Display for CheckChecksumError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CheckChecksumError::ChecksumError(e) => write!(f, "checksum error: {}", e),
+            CheckChecksumError::CheckArchiveError(e) => write!(f, "check archive error: {}", e),
+        }
+    }
+}
+
+impl<E: Error + 'static> Error for CheckChecksumError<E> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CheckChecksumError::ChecksumError(e) => Some(e as &dyn Error),
+            CheckChecksumError::CheckArchiveError(e) => Some(e as &dyn Error),
+        }
+    }
+}
+
+/// Verifies the checksum trailer of the given archive with a hasher of type `H`, then checks the
+/// archive for a root object of the given type.
+///
+/// This is the counterpart to [`ChecksumSerializer`](crate::ser::adapters::ChecksumSerializer).
+/// The checksum is verified before the archive is validated, so corruption that leaves the archive
+/// structurally valid is still detected.
+///
+/// # Example
+/// ```
+/// use rkyv::{
+///     checksum::{Crc32c, CHECKSUM_SIZE},
+///     ser::{adapters::ChecksumSerializer, serializers::AlignedSerializer, Serializer},
+///     validation::{check_archived_root_with_checksum, CheckChecksumError},
+///     AlignedVec,
+///     Archive,
+///     Serialize,
+/// };
+/// use bytecheck::CheckBytes;
+///
+/// #[derive(Archive, Serialize)]
+/// #[archive(derive(CheckBytes))]
+/// struct Example {
+///     name: String,
+///     value: f64,
+/// }
+///
+/// let mut serializer =
+///     ChecksumSerializer::<_, Crc32c>::new(AlignedSerializer::new(AlignedVec::new()));
+/// serializer.serialize_value(&Example {
+///     name: "pi".to_string(),
+///     value: 3.1415926,
+/// }).unwrap();
+/// let mut buf = serializer.finish().unwrap().into_inner();
+///
+/// let archived = check_archived_root_with_checksum::<Example, Crc32c>(&buf).unwrap();
+/// assert_eq!(archived.name, "pi");
+/// assert_eq!(archived.value, 3.1415926);
+///
+/// // Flipping a bit of the float still leaves a valid archive, but the checksum catches it
+/// let len = buf.len();
+/// buf[len - CHECKSUM_SIZE - 1] ^= 1;
+/// match check_archived_root_with_checksum::<Example, Crc32c>(&buf) {
+///     Err(CheckChecksumError::ChecksumError(_)) => (),
+///     _ => panic!("expected checksum error"),
+/// }
+/// ```
+#[inline]
+pub fn check_archived_root_with_checksum<T: Archive, H: StreamingHasher + Default>(
+    buf: &[u8],
+) -> Result<&T::Archived, CheckChecksumError<CheckTypeError<T::Archived, DefaultArchiveValidator>>>
+where
+    T::Archived: CheckBytes<DefaultArchiveValidator>,
+{
+    let archive = verify_checksum::<H>(buf).map_err(CheckChecksumError::ChecksumError)?;
+    check_archived_root::<T>(archive).map_err(CheckChecksumError::CheckArchiveError)
+}
+
+/// The deserializer used by [`from_bytes`].
+pub type FromBytesDeserializer = SharedDeserializerAdapter<AllocDeserializer>;
+
+/// The error type returned by [`from_bytes`] and [`from_bytes_with`] for a root object of type
+/// `T` deserialized with a `D`.
+pub type FromBytesTypeError<T, D = FromBytesDeserializer> =
+    FromBytesError<CheckTypeError<Archived<T>, DefaultArchiveValidator>, <D as Fallible>::Error>;
+
+/// Errors that can occur when validating and deserializing an archive.
+#[derive(Debug)]
+pub enum FromBytesError<C, D> {
+    /// The archive failed validation
+    CheckArchiveError(C),
+    /// The archived value failed to deserialize
+    DeserializeError(D),
+}
+
+impl<C: fmt::Display, D: fmt::Display> fmt::Display for FromBytesError<C, D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FromBytesError::CheckArchiveError(e) => write!(f, "check archive error: {}", e),
+            FromBytesError::DeserializeError(e) => write!(f, "deserialize error: {}", e),
+        }
+    }
+}
+
+impl<C: Error + 'static, D: Error + 'static> Error for FromBytesError<C, D> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            FromBytesError::CheckArchiveError(e) => Some(e as &dyn Error),
+            FromBytesError::DeserializeError(e) => Some(e as &dyn Error),
+        }
+    }
+}
+
+/// Checks the given archive for a root object of the given type and deserializes it.
+///
+/// This is the safe counterpart to calling [`archived_root`](crate::archived_root) and then
+/// deserializing, and is suitable for untrusted input. Shared pointers are deserialized with a
+/// [`FromBytesDeserializer`]; use [`from_bytes_with`] to deserialize with a different
+/// deserializer.
+///
+/// This is equivalent to calling [`check_archived_root`] and then deserializing, and walks the
+/// archive twice. [`Deserialize`] implementations read archived values directly, so the whole
+/// archive is validated before anything is deserialized. In exchange, no memory is allocated for
+/// archives that turn out to be invalid.
+///
+/// # Example
+/// ```
+/// use rkyv::{
+///     from_bytes,
+///     ser::{serializers::AllocSerializer, Serializer},
+///     validation::FromBytesError,
+///     Archive,
+///     Deserialize,
+///     Serialize,
+/// };
+/// use bytecheck::CheckBytes;
+///
+/// #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+/// #[archive(derive(CheckBytes))]
+/// struct Example {
+///     name: String,
+///     values: Vec<i32>,
+/// }
+///
+/// let value = Example {
+///     name: "primes".to_string(),
+///     values: vec![2, 3, 5, 7],
+/// };
+///
+/// let mut serializer = AllocSerializer::<256>::default();
+/// serializer.serialize_value(&value).unwrap();
+/// let buf = serializer.into_serializer().into_inner();
+///
+/// assert_eq!(from_bytes::<Example>(&buf).unwrap(), value);
+///
+/// // Truncated archives fail validation instead of being read
+/// match from_bytes::<Example>(&buf[..buf.len() - 4]) {
+///     Err(FromBytesError::CheckArchiveError(_)) => (),
+///     result => panic!("expected check archive error, got {:?}", result),
+/// }
+/// ```
+#[inline]
+pub fn from_bytes<T: Archive>(buf: &[u8]) -> Result<T, FromBytesTypeError<T>>
+where
+    T::Archived: CheckBytes<DefaultArchiveValidator> + Deserialize<T, FromBytesDeserializer>,
+{
+    from_bytes_with::<T, _>(buf, &mut SharedDeserializerAdapter::new(AllocDeserializer))
+}
+
+/// Checks the given archive for a root object of the given type and deserializes it with the given
+/// deserializer.
+///
+/// See [`from_bytes`] for more details.
+#[inline]
+pub fn from_bytes_with<T: Archive, D: Fallible + ?Sized>(
+    buf: &[u8],
+    deserializer: &mut D,
+) -> Result<T, FromBytesTypeError<T, D>>
+where
+    T::Archived: CheckBytes<DefaultArchiveValidator> + Deserialize<T, D>,
+{
+    check_archived_root::<T>(buf)
+        .map_err(FromBytesError::CheckArchiveError)?
+        .deserialize(deserializer)
+        .map_err(FromBytesError::DeserializeError)
 }
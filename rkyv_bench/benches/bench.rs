@@ -1,4 +1,5 @@
 use bytecheck::CheckBytes;
+use core::mem::MaybeUninit;
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 use rand::Rng;
 use rand_pcg::Lcg64Xsh32;
@@ -9,7 +10,8 @@ use rkyv::{
         serializers::{AlignedSerializer, WriteSerializer},
         Serializer,
     },
-    AlignedVec, Archive, Deserialize, Serialize,
+    std_impl::ArchivedBox,
+    AlignedVec, Archive, Deserialize, RawRelPtr, Serialize,
 };
 use std::collections::HashMap;
 
@@ -450,5 +452,103 @@ pub fn criterion_benchmark(c: &mut Criterion) {
     group.finish();
 }
 
-criterion_group!(benches, criterion_benchmark);
+pub fn large_archive_benchmark(c: &mut Criterion) {
+    const PLAYERS: usize = 20_000;
+    const ITEMS: usize = 1_000_000;
+    const BYTES: usize = 64 * 1024 * 1024;
+    const STATE: u64 = 3141592653;
+    const STREAM: u64 = 5897932384;
+
+    let mut rng = Lcg64Xsh32::new(STATE, STREAM);
+    let players = (0..PLAYERS)
+        .map(|_| Player::generate(&mut rng))
+        .collect::<Vec<_>>();
+    // Each boxed item is written right after its id, so the objects that validation claims are
+    // scattered across the whole archive instead of forming a few long runs
+    let items = (0..ITEMS)
+        .map(|_| Box::new(Item::generate(&mut rng)))
+        .collect::<Vec<_>>();
+
+    let mut players_buffer = AlignedVec::new();
+    let mut serializer = AlignedSerializer::new(&mut players_buffer);
+    serializer.serialize_value(&players).unwrap();
+
+    let mut items_buffer = AlignedVec::new();
+    let mut serializer = AlignedSerializer::new(&mut items_buffer);
+    serializer.serialize_value(&items).unwrap();
+
+    // Pointing the boxes at the items in random order makes validation claim them out of order
+    let mut shuffled_buffer = items_buffer.clone();
+    let base = shuffled_buffer.as_ptr() as usize;
+    let archived = unsafe { archived_root::<Vec<Box<Item>>>(shuffled_buffer.as_ref()) };
+    let mut boxes = archived
+        .iter()
+        .map(|item| {
+            let from = item as *const ArchivedBox<ArchivedItem> as usize - base;
+            let to = &**item as *const ArchivedItem as usize - base;
+            (from, to)
+        })
+        .collect::<Vec<_>>();
+    for i in (1..boxes.len()).rev() {
+        let j = rng.gen_range(0..=i);
+        let to = boxes[i].1;
+        boxes[i].1 = boxes[j].1;
+        boxes[j].1 = to;
+    }
+    for (from, to) in boxes {
+        let out = unsafe {
+            &mut *shuffled_buffer
+                .as_mut_ptr()
+                .add(from)
+                .cast::<MaybeUninit<RawRelPtr>>()
+        };
+        RawRelPtr::emplace(from, to, out);
+    }
+
+    let mut map = HashMap::with_capacity(ITEMS);
+    while map.len() < ITEMS {
+        map.insert(rng.gen::<u64>(), Item::generate(&mut rng));
+    }
+
+    let mut map_buffer = AlignedVec::new();
+    let mut serializer = AlignedSerializer::new(&mut map_buffer);
+    serializer.serialize_value(&map).unwrap();
+
+    let bytes = (0..BYTES).map(|_| rng.gen::<u8>()).collect::<Vec<_>>();
+
+    let mut bytes_buffer = AlignedVec::new();
+    let mut serializer = AlignedSerializer::new(&mut bytes_buffer);
+    serializer.serialize_value(&bytes).unwrap();
+
+    let mut group = c.benchmark_group("rkyv (large)");
+    group.sample_size(10);
+    group.bench_function("validate players", |b| {
+        b.iter(|| {
+            check_archived_root::<Vec<Player>>(black_box(players_buffer.as_ref())).unwrap();
+        })
+    });
+    group.bench_function("validate boxed items", |b| {
+        b.iter(|| {
+            check_archived_root::<Vec<Box<Item>>>(black_box(items_buffer.as_ref())).unwrap();
+        })
+    });
+    group.bench_function("validate shuffled boxed items", |b| {
+        b.iter(|| {
+            check_archived_root::<Vec<Box<Item>>>(black_box(shuffled_buffer.as_ref())).unwrap();
+        })
+    });
+    group.bench_function("validate map", |b| {
+        b.iter(|| {
+            check_archived_root::<HashMap<u64, Item>>(black_box(map_buffer.as_ref())).unwrap();
+        })
+    });
+    group.bench_function("validate bytes", |b| {
+        b.iter(|| {
+            check_archived_root::<Vec<u8>>(black_box(bytes_buffer.as_ref())).unwrap();
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark, large_archive_benchmark);
 criterion_main!(benches);
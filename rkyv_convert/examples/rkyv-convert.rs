@@ -0,0 +1,27 @@
+//! An `rkyv-convert` tool for a few example types.
+//!
+//! Run it with `cargo run -p rkyv_convert --example rkyv-convert -- Monster monster.json out.rkyv`,
+//! where `monster.json` contains something like:
+//!
+//! ```json
+//! { "name": "Slime", "health": 10, "drops": ["Gel"] }
+//! ```
+
+use rkyv::{Archive, Serialize};
+use std::collections::HashMap;
+
+#[derive(Archive, Serialize, serde::Deserialize)]
+pub struct Monster {
+    pub name: String,
+    pub health: u32,
+    pub drops: Vec<String>,
+}
+
+#[derive(Archive, Serialize, serde::Deserialize)]
+pub struct Level {
+    pub name: String,
+    pub monsters: Vec<Monster>,
+    pub spawn_rates: HashMap<String, f32>,
+}
+
+rkyv_convert::main!(Monster, Level);
@@ -0,0 +1,255 @@
+//! Converts JSON data into rkyv archives.
+//!
+//! A [`Converter`] holds a set of named types that implement both `serde::Deserialize` and rkyv's
+//! `Serialize`, and converts JSON input into an archive of any of them. The [`main!`] macro builds
+//! an `rkyv-convert` command line tool for a list of types, which is usually placed in a binary of
+//! the crate that defines them:
+//!
+//! ```no_run
+//! # mod my_crate { pub type Monster = Vec<String>; pub type Level = Vec<u32>; }
+//! // src/bin/rkyv-convert.rs
+//! rkyv_convert::main!(my_crate::Monster, my_crate::Level);
+//! ```
+//!
+//! The tool is then run with the name of the type, the input file and the output file:
+//!
+//! ```sh
+//! rkyv-convert Monster monster.json monster.rkyv
+//! ```
+//!
+//! Either file can be `-` to read from stdin or write to stdout, and the output file defaults to
+//! stdout. `rkyv-convert --list` prints the names of all of the registered types.
+
+use rkyv::{
+    convert::{serialize_from, ConvertError},
+    ser::serializers::AllocSerializer,
+    AlignedVec, Fallible, Serialize,
+};
+use serde::de::DeserializeOwned;
+use std::{
+    error::Error,
+    fmt, fs,
+    io::{self, Read, Write},
+};
+
+/// The serializer used to build archives.
+pub type ConvertSerializer = AllocSerializer<4096>;
+
+/// The error type returned when serializing converted values.
+pub type ConvertSerializerError = <ConvertSerializer as Fallible>::Error;
+
+/// An error that can occur while converting data.
+#[derive(Debug)]
+pub enum ConvertDataError {
+    /// No type was registered with the requested name
+    UnknownType(String),
+    /// The command line arguments were invalid
+    Usage(String),
+    /// An error occurred while reading the input or writing the output
+    Io(io::Error),
+    /// An error occurred while converting the input into an archive
+    Convert(ConvertError<serde_json::Error, ConvertSerializerError>),
+}
+
+impl From<io::Error> for ConvertDataError {
+    #[inline]
+    fn from(e: io::Error) -> Self {
+        ConvertDataError::Io(e)
+    }
+}
+
+impl fmt::Display for ConvertDataError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConvertDataError::UnknownType(name) => write!(f, "unknown type `{}`", name),
+            ConvertDataError::Usage(usage) => write!(f, "{}", usage),
+            ConvertDataError::Io(e) => write!(f, "{}", e),
+            ConvertDataError::Convert(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for ConvertDataError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ConvertDataError::UnknownType(_) | ConvertDataError::Usage(_) => None,
+            ConvertDataError::Io(e) => Some(e as &dyn Error),
+            ConvertDataError::Convert(e) => Some(e as &dyn Error),
+        }
+    }
+}
+
+type ConvertFn = fn(&mut dyn Read) -> Result<AlignedVec, ConvertDataError>;
+
+fn convert_json<T>(input: &mut dyn Read) -> Result<AlignedVec, ConvertDataError>
+where
+    T: DeserializeOwned + Serialize<ConvertSerializer>,
+{
+    let mut deserializer = serde_json::Deserializer::from_reader(input);
+    let mut serializer = ConvertSerializer::default();
+    serialize_from::<T, _, _>(&mut deserializer, &mut serializer)
+        .map_err(ConvertDataError::Convert)?;
+    // Trailing data after the value means the input wasn't a single JSON value
+    deserializer
+        .end()
+        .map_err(|e| ConvertDataError::Convert(ConvertError::Deserialize(e)))?;
+    Ok(serializer.into_serializer().into_inner())
+}
+
+/// A set of named types that JSON data can be converted into.
+///
+/// # Examples
+/// ```
+/// use rkyv::archived_root;
+/// use rkyv_convert::Converter;
+/// use std::collections::HashMap;
+///
+/// let converter = Converter::new()
+///     .register::<Vec<String>>("Names")
+///     .register::<HashMap<String, u32>>("Scores");
+///
+/// let archive = converter
+///     .convert("Names", &mut r#"["alice", "bob"]"#.as_bytes())
+///     .unwrap();
+/// let archived = unsafe { archived_root::<Vec<String>>(archive.as_ref()) };
+/// assert_eq!(archived[1].as_str(), "bob");
+///
+/// assert!(converter.convert("Ages", &mut "{}".as_bytes()).is_err());
+/// ```
+#[derive(Default)]
+pub struct Converter {
+    types: Vec<(&'static str, ConvertFn)>,
+}
+
+impl Converter {
+    /// Creates a new converter with no registered types.
+    #[inline]
+    pub fn new() -> Self {
+        Self { types: Vec::new() }
+    }
+
+    /// Registers a type under the given name.
+    ///
+    /// Registering a type with the same name as an already registered type replaces it.
+    pub fn register<T>(mut self, name: &'static str) -> Self
+    where
+        T: DeserializeOwned + Serialize<ConvertSerializer>,
+    {
+        let convert = convert_json::<T> as ConvertFn;
+        match self.types.iter_mut().find(|(n, _)| *n == name) {
+            Some(entry) => entry.1 = convert,
+            None => self.types.push((name, convert)),
+        }
+        self
+    }
+
+    /// Returns an iterator over the names of the registered types.
+    #[inline]
+    pub fn type_names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.types.iter().map(|(name, _)| *name)
+    }
+
+    /// Converts JSON from the given input into an archive of the type registered with the given
+    /// name.
+    pub fn convert(
+        &self,
+        type_name: &str,
+        input: &mut dyn Read,
+    ) -> Result<AlignedVec, ConvertDataError> {
+        let convert = self
+            .types
+            .iter()
+            .find(|(name, _)| *name == type_name)
+            .map(|(_, convert)| convert)
+            .ok_or_else(|| ConvertDataError::UnknownType(type_name.to_string()))?;
+        convert(input)
+    }
+
+    /// Runs the converter with the given command line arguments, not including the program name.
+    ///
+    /// The arguments are either `--list`, or the type name, the input file and an optional output
+    /// file. Listed type names are written to `stdout`, as is the archive if no output file is
+    /// given.
+    pub fn run<I: IntoIterator<Item = String>>(
+        &self,
+        args: I,
+        stdout: &mut dyn Write,
+    ) -> Result<(), ConvertDataError> {
+        let args = args.into_iter().collect::<Vec<_>>();
+        match args.as_slice() {
+            [flag] if flag == "--list" => {
+                for name in self.type_names() {
+                    writeln!(stdout, "{}", name)?;
+                }
+                Ok(())
+            }
+            [type_name, input] => self.run_file(type_name, input, "-", stdout),
+            [type_name, input, output] => self.run_file(type_name, input, output, stdout),
+            _ => Err(ConvertDataError::Usage(
+                "usage: rkyv-convert <TYPE> <INPUT> [OUTPUT]\n       rkyv-convert --list"
+                    .to_string(),
+            )),
+        }
+    }
+
+    fn run_file(
+        &self,
+        type_name: &str,
+        input: &str,
+        output: &str,
+        stdout: &mut dyn Write,
+    ) -> Result<(), ConvertDataError> {
+        let archive = if input == "-" {
+            self.convert(type_name, &mut io::stdin().lock())?
+        } else {
+            self.convert(type_name, &mut io::BufReader::new(fs::File::open(input)?))?
+        };
+        if output == "-" {
+            stdout.write_all(&archive)?;
+            stdout.flush()?;
+        } else {
+            fs::write(output, &archive)?;
+        }
+        Ok(())
+    }
+
+    /// Runs the converter with the arguments of the process and exits.
+    ///
+    /// Errors are printed to `stderr` and exit the process with a nonzero status code.
+    pub fn main(&self) -> ! {
+        let result = self.run(std::env::args().skip(1), &mut io::stdout().lock());
+        match result {
+            Ok(()) => std::process::exit(0),
+            Err(e) => {
+                eprintln!("rkyv-convert: {}", e);
+                std::process::exit(1)
+            }
+        }
+    }
+}
+
+/// Returns the name of a type as written in source without any leading module path.
+#[doc(hidden)]
+pub fn short_type_name(name: &'static str) -> &'static str {
+    // Only the path before any generic arguments is stripped
+    let path_len = name.find('<').unwrap_or(name.len());
+    match name[..path_len].rfind("::") {
+        Some(index) => name[index + 2..].trim(),
+        None => name.trim(),
+    }
+}
+
+/// Defines a `main` function that runs an `rkyv-convert` command line tool for the given types.
+///
+/// Each type is registered under its name as written, without any leading module path. Use
+/// [`Converter`] directly to choose the names of the types.
+#[macro_export]
+macro_rules! main {
+    ($($ty:ty),* $(,)?) => {
+        fn main() {
+            $crate::Converter::new()
+                $(.register::<$ty>($crate::short_type_name(stringify!($ty))))*
+                .main()
+        }
+    };
+}
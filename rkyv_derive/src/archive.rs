@@ -1,24 +1,48 @@
-use crate::attributes::{parse_attributes, Attributes};
+use crate::{
+    attributes::{parse_attributes, Attributes},
+    check_bytes::{derive_check_bytes_impl, derive_check_bytes_unsized_impl},
+    compare::{derive_archived_compare_impls, ARCHIVED_COMPARES},
+    debug::derive_debug_impl,
+    deserialize_fields::derive_deserialize_fields_impl,
+    serde::derive_serde_impl,
+    tail::{unsized_tail, UnsizedTail},
+};
 use proc_macro2::TokenStream;
 use quote::{quote, quote_spanned};
 use syn::{
-    parse_quote, spanned::Spanned, Attribute, Data, DeriveInput, Error, Fields, Ident, Index,
+    parse_quote, spanned::Spanned, Attribute, Data, DataEnum, DeriveInput, Error, Fields, Ident,
+    Index,
 };
 
 pub fn derive(input: DeriveInput) -> Result<TokenStream, Error> {
     let attributes = parse_attributes(&input)?;
 
-    if attributes.copy.is_some() {
+    if unsized_tail(&input).is_some() {
+        derive_archive_unsized_impl(input, &attributes)
+    } else if attributes.copy.is_some() {
         derive_archive_copy_impl(input, &attributes)
     } else {
         derive_archive_impl(input, &attributes)
     }
 }
 
+/// Returns the integer type used for the tag of the archived enum.
+pub fn archived_repr(data: &DataEnum) -> TokenStream {
+    match data.variants.len() {
+        0..=255 => quote! { u8 },
+        256..=65_535 => quote! { u16 },
+        65_536..=4_294_967_295 => quote! { u32 },
+        4_294_967_296..=18_446_744_073_709_551_615 => quote! { u64 },
+        _ => quote! { u128 },
+    }
+}
+
 fn derive_archive_impl(
     mut input: DeriveInput,
     attributes: &Attributes,
 ) -> Result<TokenStream, Error> {
+    let rkyv_path = attributes.rkyv_path();
+
     input.generics.make_where_clause();
 
     let name = &input.ident;
@@ -61,13 +85,13 @@ fn derive_archive_impl(
                         let ty = &field.ty;
                         archive_where
                             .predicates
-                            .push(parse_quote! { #ty: rkyv::Archive });
+                            .push(parse_quote! { #ty: #rkyv_path::Archive });
                     }
 
                     let resolver_fields = fields.named.iter().map(|f| {
                         let name = &f.ident;
                         let ty = &f.ty;
-                        quote_spanned! { f.span() => #name: rkyv::Resolver<#ty> }
+                        quote_spanned! { f.span() => #name: #rkyv_path::Resolver<#ty> }
                     });
 
                     let archived_fields = fields.named.iter().map(|f| {
@@ -81,7 +105,7 @@ fn derive_archive_impl(
                         );
                         quote_spanned! { f.span() =>
                             #[doc = #field_doc]
-                            #vis #field_name: rkyv::Archived<#ty>
+                            #vis #field_name: #rkyv_path::Archived<#ty>
                         }
                     });
 
@@ -89,9 +113,9 @@ fn derive_archive_impl(
                         let name = &f.ident;
                         quote_spanned! { f.span() =>
                             self.#name.resolve(
-                                pos + rkyv::offset_of!(#archived #ty_generics, #name),
+                                pos + #rkyv_path::offset_of!(#archived #ty_generics, #name),
                                 resolver.#name,
-                                rkyv::project_struct!(out: Self::Archived => #name)
+                                #rkyv_path::project_struct!(out: Self::Archived => #name)
                             )
                         }
                     });
@@ -106,9 +130,9 @@ fn derive_archive_impl(
                                     !f.attrs.iter().any(|a| a.path.is_ident("omit_bounds"))
                                 }) {
                                     let ty = &field.ty;
-                                    partial_eq_where
-                                        .predicates
-                                        .push(parse_quote! { rkyv::Archived<#ty>: PartialEq<#ty> });
+                                    partial_eq_where.predicates.push(
+                                        parse_quote! { #rkyv_path::Archived<#ty>: PartialEq<#ty> },
+                                    );
                                 }
 
                                 let field_names = fields.named.iter().map(|f| &f.ident);
@@ -135,7 +159,7 @@ fn derive_archive_impl(
                                 }) {
                                     let ty = &field.ty;
                                     partial_ord_where.predicates.push(
-                                        parse_quote! { rkyv::Archived<#ty>: PartialOrd<#ty> },
+                                        parse_quote! { #rkyv_path::Archived<#ty>: PartialOrd<#ty> },
                                     );
                                 }
 
@@ -162,8 +186,8 @@ fn derive_archive_impl(
                                         }
                                     }
                                 });
-                            } else {
-                                return Err(Error::new_spanned(compare, "unrecognized compare argument, supported compares are PartialEq and PartialOrd"));
+                            } else if !ARCHIVED_COMPARES.iter().any(|c| compare.is_ident(c)) {
+                                return Err(Error::new_spanned(compare, "unrecognized compare argument, supported compares are PartialEq, PartialOrd, Eq, Ord and Hash"));
                             }
                         }
                     }
@@ -183,7 +207,7 @@ fn derive_archive_impl(
                             }
                         },
                         quote! {
-                            impl #impl_generics rkyv::Archive for #name #ty_generics #archive_where {
+                            impl #impl_generics #rkyv_path::Archive for #name #ty_generics #archive_where {
                                 type Archived = #archived #ty_generics;
                                 type Resolver = #resolver #ty_generics;
 
@@ -209,12 +233,12 @@ fn derive_archive_impl(
                         let ty = &field.ty;
                         archive_where
                             .predicates
-                            .push(parse_quote! { #ty: rkyv::Archive });
+                            .push(parse_quote! { #ty: #rkyv_path::Archive });
                     }
 
                     let resolver_fields = fields.unnamed.iter().map(|f| {
                         let ty = &f.ty;
-                        quote_spanned! { f.span() => rkyv::Resolver<#ty> }
+                        quote_spanned! { f.span() => #rkyv_path::Resolver<#ty> }
                     });
 
                     let archived_fields = fields.unnamed.iter().enumerate().map(|(i, f)| {
@@ -223,7 +247,7 @@ fn derive_archive_impl(
                         let field_doc = format!("The archived counterpart of `{}::{}`", name, i);
                         quote_spanned! { f.span() =>
                             #[doc = #field_doc]
-                            #vis rkyv::Archived<#ty>
+                            #vis #rkyv_path::Archived<#ty>
                         }
                     });
 
@@ -231,9 +255,9 @@ fn derive_archive_impl(
                         let index = Index::from(i);
                         quote_spanned! { f.span() =>
                             self.#index.resolve(
-                                pos + rkyv::offset_of!(#archived #ty_generics, #index),
+                                pos + #rkyv_path::offset_of!(#archived #ty_generics, #index),
                                 resolver.#index,
-                                rkyv::project_struct!(out: Self::Archived => #index)
+                                #rkyv_path::project_struct!(out: Self::Archived => #index)
                             )
                         }
                     });
@@ -248,9 +272,9 @@ fn derive_archive_impl(
                                     !f.attrs.iter().any(|a| a.path.is_ident("omit_bounds"))
                                 }) {
                                     let ty = &field.ty;
-                                    partial_eq_where
-                                        .predicates
-                                        .push(parse_quote! { rkyv::Archived<#ty>: PartialEq<#ty> });
+                                    partial_eq_where.predicates.push(
+                                        parse_quote! { #rkyv_path::Archived<#ty>: PartialEq<#ty> },
+                                    );
                                 }
 
                                 let field_names = fields
@@ -281,7 +305,7 @@ fn derive_archive_impl(
                                 }) {
                                     let ty = &field.ty;
                                     partial_ord_where.predicates.push(
-                                        parse_quote! { rkyv::Archived<#ty>: PartialOrd<#ty> },
+                                        parse_quote! { #rkyv_path::Archived<#ty>: PartialOrd<#ty> },
                                     );
                                 }
 
@@ -312,8 +336,8 @@ fn derive_archive_impl(
                                         }
                                     }
                                 });
-                            } else {
-                                return Err(Error::new_spanned(compare, "unrecognized compare argument, supported compares are PartialEq and PartialOrd"));
+                            } else if !ARCHIVED_COMPARES.iter().any(|c| compare.is_ident(c)) {
+                                return Err(Error::new_spanned(compare, "unrecognized compare argument, supported compares are PartialEq, PartialOrd, Eq, Ord and Hash"));
                             }
                         }
                     }
@@ -329,7 +353,7 @@ fn derive_archive_impl(
                             #vis struct #resolver #generics (#(#resolver_fields,)*) #archive_where;
                         },
                         quote! {
-                            impl #impl_generics rkyv::Archive for #name #ty_generics #archive_where {
+                            impl #impl_generics #rkyv_path::Archive for #name #ty_generics #archive_where {
                                 type Archived = #archived #ty_generics;
                                 type Resolver = #resolver #ty_generics;
 
@@ -382,8 +406,8 @@ fn derive_archive_impl(
                                         }
                                     }
                                 });
-                            } else {
-                                return Err(Error::new_spanned(compare, "unrecognized compare argument, supported compares are PartialEq and PartialOrd"));
+                            } else if !ARCHIVED_COMPARES.iter().any(|c| compare.is_ident(c)) {
+                                return Err(Error::new_spanned(compare, "unrecognized compare argument, supported compares are PartialEq, PartialOrd, Eq, Ord and Hash"));
                             }
                         }
                     }
@@ -401,7 +425,7 @@ fn derive_archive_impl(
                             #where_clause;
                         },
                         quote! {
-                            impl #impl_generics rkyv::Archive for #name #ty_generics #where_clause {
+                            impl #impl_generics #rkyv_path::Archive for #name #ty_generics #where_clause {
                                 type Archived = #archived #ty_generics;
                                 type Resolver = #resolver #ty_generics;
 
@@ -429,7 +453,7 @@ fn derive_archive_impl(
                             let ty = &field.ty;
                             archive_where
                                 .predicates
-                                .push(parse_quote! { #ty: rkyv::Archive });
+                                .push(parse_quote! { #ty: #rkyv_path::Archive });
                         }
                     }
                     Fields::Unnamed(ref fields) => {
@@ -441,7 +465,7 @@ fn derive_archive_impl(
                             let ty = &field.ty;
                             archive_where
                                 .predicates
-                                .push(parse_quote! { #ty: rkyv::Archive });
+                                .push(parse_quote! { #ty: #rkyv_path::Archive });
                         }
                     }
                     Fields::Unit => (),
@@ -455,7 +479,7 @@ fn derive_archive_impl(
                         let fields = fields.named.iter().map(|f| {
                             let name = &f.ident;
                             let ty = &f.ty;
-                            quote_spanned! { f.span() => #name: rkyv::Resolver<#ty> }
+                            quote_spanned! { f.span() => #name: #rkyv_path::Resolver<#ty> }
                         });
                         quote_spanned! { variant.span() =>
                             #[allow(dead_code)]
@@ -467,7 +491,7 @@ fn derive_archive_impl(
                     Fields::Unnamed(ref fields) => {
                         let fields = fields.unnamed.iter().map(|f| {
                             let ty = &f.ty;
-                            quote_spanned! { f.span() => rkyv::Resolver<#ty> }
+                            quote_spanned! { f.span() => #rkyv_path::Resolver<#ty> }
                         });
                         quote_spanned! { variant.span() =>
                             #[allow(dead_code)]
@@ -502,9 +526,9 @@ fn derive_archive_impl(
                             let resolver_binding = Ident::new(&format!("resolver_{}", name.as_ref().unwrap().to_string()), name.span());
                             quote! {
                                 #self_binding.resolve(
-                                    pos + rkyv::offset_of!(#archived_variant_name #ty_generics, #name),
+                                    pos + #rkyv_path::offset_of!(#archived_variant_name #ty_generics, #name),
                                     #resolver_binding,
-                                    rkyv::project_struct!(out: #archived_variant_name #ty_generics => #name),
+                                    #rkyv_path::project_struct!(out: #archived_variant_name #ty_generics => #name),
                                 )
                             }
                         });
@@ -514,7 +538,7 @@ fn derive_archive_impl(
                                     #name::#variant { #(#self_bindings,)* } => {
                                         unsafe {
                                             let out = &mut *out.as_mut_ptr().cast::<MaybeUninit<#archived_variant_name #ty_generics>>();
-                                            rkyv::project_struct!(out: #archived_variant_name #ty_generics => __tag: ArchivedTag)
+                                            #rkyv_path::project_struct!(out: #archived_variant_name #ty_generics => __tag: ArchivedTag)
                                                 .as_mut_ptr()
                                                 .write(ArchivedTag::#variant);
                                             #(#resolves;)*
@@ -541,9 +565,9 @@ fn derive_archive_impl(
                             let resolver_binding = Ident::new(&format!("resolver_{}", i), f.span());
                             quote! {
                                 #self_binding.resolve(
-                                    pos + rkyv::offset_of!(#archived_variant_name #ty_generics, #index),
+                                    pos + #rkyv_path::offset_of!(#archived_variant_name #ty_generics, #index),
                                     #resolver_binding,
-                                    rkyv::project_struct!(out: #archived_variant_name #ty_generics => #index),
+                                    #rkyv_path::project_struct!(out: #archived_variant_name #ty_generics => #index),
                                 )
                             }
                         });
@@ -553,7 +577,7 @@ fn derive_archive_impl(
                                     #name::#variant(#(#self_bindings,)*) => {
                                         unsafe {
                                             let out = &mut *out.as_mut_ptr().cast::<MaybeUninit<#archived_variant_name #ty_generics>>();
-                                            rkyv::project_struct!(out: #archived_variant_name #ty_generics => 0: ArchivedTag)
+                                            #rkyv_path::project_struct!(out: #archived_variant_name #ty_generics => 0: ArchivedTag)
                                                 .as_mut_ptr()
                                                 .write(ArchivedTag::#variant);
                                             #(#resolves;)*
@@ -575,13 +599,7 @@ fn derive_archive_impl(
                 }
             });
 
-            let archived_repr = match data.variants.len() {
-                0..=255 => quote! { u8 },
-                256..=65_535 => quote! { u16 },
-                65_536..=4_294_967_295 => quote! { u32 },
-                4_294_967_296..=18_446_744_073_709_551_615 => quote! { u64 },
-                _ => quote! { u128 },
-            };
+            let archived_repr = archived_repr(data);
 
             let archived_variants = data.variants.iter().map(|v| {
                 let variant = &v.ident;
@@ -591,7 +609,7 @@ fn derive_archive_impl(
                             let name = &f.ident;
                             let ty = &f.ty;
                             let vis = &f.vis;
-                            quote_spanned! { f.span() => #vis #name: rkyv::Archived<#ty> }
+                            quote_spanned! { f.span() => #vis #name: #rkyv_path::Archived<#ty> }
                         });
                         quote_spanned! { variant.span() =>
                             #[allow(dead_code)]
@@ -604,7 +622,7 @@ fn derive_archive_impl(
                         let fields = fields.unnamed.iter().map(|f| {
                             let ty = &f.ty;
                             let vis = &f.vis;
-                            quote_spanned! { f.span() => #vis rkyv::Archived<#ty> }
+                            quote_spanned! { f.span() => #vis #rkyv_path::Archived<#ty> }
                         });
                         quote_spanned! { variant.span() =>
                             #[allow(dead_code)]
@@ -631,7 +649,7 @@ fn derive_archive_impl(
                         let fields = fields.named.iter().map(|f| {
                             let name = &f.ident;
                             let ty = &f.ty;
-                            quote_spanned! { f.span() => #name: rkyv::Archived<#ty> }
+                            quote_spanned! { f.span() => #name: #rkyv_path::Archived<#ty> }
                         });
                         quote_spanned! { name.span() =>
                             #[repr(C)]
@@ -645,7 +663,7 @@ fn derive_archive_impl(
                     Fields::Unnamed(ref fields) => {
                         let fields = fields.unnamed.iter().map(|f| {
                             let ty = &f.ty;
-                            quote_spanned! { f.span() => rkyv::Archived<#ty> }
+                            quote_spanned! { f.span() => #rkyv_path::Archived<#ty> }
                         });
                         quote_spanned! { name.span() =>
                             #[repr(C)]
@@ -670,7 +688,7 @@ fn derive_archive_impl(
                                     }) {
                                         let ty = &field.ty;
                                         partial_eq_where.predicates.push(
-                                            parse_quote! { rkyv::Archived<#ty>: PartialEq<#ty> },
+                                            parse_quote! { #rkyv_path::Archived<#ty>: PartialEq<#ty> },
                                         );
                                     }
                                 }
@@ -680,7 +698,7 @@ fn derive_archive_impl(
                                     }) {
                                         let ty = &field.ty;
                                         partial_eq_where.predicates.push(
-                                            parse_quote! { rkyv::Archived<#ty>: PartialEq<#ty> },
+                                            parse_quote! { #rkyv_path::Archived<#ty>: PartialEq<#ty> },
                                         );
                                     }
                                 }
@@ -765,7 +783,7 @@ fn derive_archive_impl(
                                     }) {
                                         let ty = &field.ty;
                                         partial_ord_where.predicates.push(
-                                            parse_quote! { rkyv::Archived<#ty>: PartialOrd<#ty> },
+                                            parse_quote! { #rkyv_path::Archived<#ty>: PartialOrd<#ty> },
                                         );
                                     }
                                 }
@@ -775,7 +793,7 @@ fn derive_archive_impl(
                                     }) {
                                         let ty = &field.ty;
                                         partial_ord_where.predicates.push(
-                                            parse_quote! { rkyv::Archived<#ty>: PartialOrd<#ty> },
+                                            parse_quote! { #rkyv_path::Archived<#ty>: PartialOrd<#ty> },
                                         );
                                     }
                                 }
@@ -905,8 +923,8 @@ fn derive_archive_impl(
                                 }
                             }
                         });
-                    } else {
-                        return Err(Error::new_spanned(compare, "unrecognized compare argument, supported compares are PartialEq (PartialOrd is not supported for enums)"));
+                    } else if !ARCHIVED_COMPARES.iter().any(|c| compare.is_ident(c)) {
+                        return Err(Error::new_spanned(compare, "unrecognized compare argument, supported compares are PartialEq, PartialOrd, Eq, Ord and Hash"));
                     }
                 }
             }
@@ -933,7 +951,7 @@ fn derive_archive_impl(
 
                     #(#archived_variant_structs)*
 
-                    impl #impl_generics rkyv::Archive for #name #ty_generics #archive_where {
+                    impl #impl_generics #rkyv_path::Archive for #name #ty_generics #archive_where {
                         type Archived = #archived #ty_generics;
                         type Resolver = #resolver #ty_generics;
 
@@ -959,6 +977,22 @@ fn derive_archive_impl(
         }
     };
 
+    let compare_impls = derive_archived_compare_impls(&input, attributes, &archived, &rkyv_path)?;
+    let check_bytes_impl = derive_check_bytes_impl(&input, attributes, &archived, &rkyv_path)?;
+    let debug_impl = attributes
+        .debug
+        .as_ref()
+        .map(|_| derive_debug_impl(&input, &archived, &rkyv_path));
+    let deserialize_fields_impl = attributes
+        .deserialize_fields
+        .as_ref()
+        .map(|_| derive_deserialize_fields_impl(&input, &archived, &rkyv_path))
+        .transpose()?;
+    let serde_impl = attributes
+        .serde
+        .as_ref()
+        .map(|_| derive_serde_impl(&input, &archived, &rkyv_path));
+
     Ok(quote! {
         #archive_types
 
@@ -966,6 +1000,259 @@ fn derive_archive_impl(
             use core::{marker::PhantomData, mem::MaybeUninit};
 
             #archive_impls
+            #compare_impls
+            #debug_impl
+            #deserialize_fields_impl
+            #serde_impl
+            #check_bytes_impl
+        };
+    })
+}
+
+fn derive_archive_unsized_impl(
+    mut input: DeriveInput,
+    attributes: &Attributes,
+) -> Result<TokenStream, Error> {
+    let rkyv_path = attributes.rkyv_path();
+
+    if let Some(ref copy) = attributes.copy {
+        return Err(Error::new_spanned(
+            copy,
+            "archive copy types cannot be unsized",
+        ));
+    }
+
+    if let Some((ref compares, _)) = attributes.compares {
+        return Err(Error::new_spanned(
+            compares,
+            "compares are not supported for unsized structs",
+        ));
+    }
+
+    if let Some(ref resolver) = attributes.resolver {
+        return Err(Error::new_spanned(
+            resolver,
+            "unsized structs do not have resolvers",
+        ));
+    }
+
+    if let Some(ref deserialize_fields) = attributes.deserialize_fields {
+        return Err(Error::new_spanned(
+            deserialize_fields,
+            "deserialize_fields is not supported for unsized structs",
+        ));
+    }
+
+    input.generics.make_where_clause();
+
+    let name = &input.ident;
+    let vis = &input.vis;
+    let generics = &input.generics;
+
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let where_clause = where_clause.unwrap();
+
+    let archive_derives = attributes
+        .derives
+        .as_ref()
+        .map::<Attribute, _>(|d| parse_quote! { #[#d] });
+
+    let archived = attributes.archived.as_ref().map_or_else(
+        || Ident::new(&format!("Archived{}", name), name.span()),
+        |value| value.clone(),
+    );
+    let archived_doc = format!("An archived `{}`", name);
+
+    let fields = match input.data {
+        Data::Struct(ref data) => &data.fields,
+        _ => unreachable!(),
+    };
+    let (tail_field, tail) = unsized_tail(&input).unwrap();
+
+    let mut archive_where = where_clause.clone();
+    for field in fields
+        .iter()
+        .filter(|f| !f.attrs.iter().any(|a| a.path.is_ident("omit_bounds")))
+    {
+        if core::ptr::eq(field, tail_field) {
+            if let UnsizedTail::Slice(ty) = tail {
+                archive_where
+                    .predicates
+                    .push(parse_quote! { #ty: #rkyv_path::Archive });
+            }
+        } else {
+            let ty = &field.ty;
+            archive_where
+                .predicates
+                .push(parse_quote! { #ty: #rkyv_path::Archive });
+        }
+    }
+
+    let archived_fields = fields.iter().enumerate().map(|(i, f)| {
+        let vis = &f.vis;
+        let ty = if core::ptr::eq(f, tail_field) {
+            match tail {
+                UnsizedTail::Slice(ty) => quote! { [#rkyv_path::Archived<#ty>] },
+                UnsizedTail::Str => quote! { str },
+            }
+        } else {
+            let ty = &f.ty;
+            quote! { #rkyv_path::Archived<#ty> }
+        };
+        if let Some(ref field_name) = f.ident {
+            let field_doc = format!("The archived counterpart of `{}::{}`", name, field_name);
+            quote_spanned! { f.span() =>
+                #[doc = #field_doc]
+                #vis #field_name: #ty
+            }
+        } else {
+            let field_doc = format!("The archived counterpart of `{}::{}`", name, i);
+            quote_spanned! { f.span() =>
+                #[doc = #field_doc]
+                #vis #ty
+            }
+        }
+    });
+
+    let archived_struct = match fields {
+        Fields::Named(_) => quote! {
+            #[doc = #archived_doc]
+            #archive_derives
+            #[repr(C)]
+            #vis struct #archived #generics #archive_where {
+                #(#archived_fields,)*
+            }
+        },
+        Fields::Unnamed(_) => quote! {
+            #[doc = #archived_doc]
+            #archive_derives
+            #[repr(C)]
+            #vis struct #archived #generics (#(#archived_fields,)*) #archive_where;
+        },
+        Fields::Unit => unreachable!(),
+    };
+
+    let debug_impl = attributes
+        .debug
+        .as_ref()
+        .map(|_| derive_debug_impl(&input, &archived, &rkyv_path));
+    let serde_impl = attributes
+        .serde
+        .as_ref()
+        .map(|_| derive_serde_impl(&input, &archived, &rkyv_path));
+
+    // Validation locates the fields with a sized header that ends where the unsized field begins
+    let validation_impl = if cfg!(feature = "check_bytes") {
+        let header_fields = fields.iter().map(|f| {
+            let ty = if core::ptr::eq(f, tail_field) {
+                match tail {
+                    UnsizedTail::Slice(ty) => quote! { [#rkyv_path::Archived<#ty>; 0] },
+                    UnsizedTail::Str => quote! { [u8; 0] },
+                }
+            } else {
+                let ty = &f.ty;
+                quote! { #rkyv_path::Archived<#ty> }
+            };
+            match f.ident {
+                Some(ref field_name) => quote_spanned! { f.span() => #field_name: #ty },
+                None => quote_spanned! { f.span() => #ty },
+            }
+        });
+        let header_struct = match fields {
+            Fields::Named(_) => quote! {
+                #[repr(C)]
+                struct ArchivedHeader #generics #archive_where {
+                    #(#header_fields,)*
+                }
+            },
+            Fields::Unnamed(_) => quote! {
+                #[repr(C)]
+                struct ArchivedHeader #generics (#(#header_fields,)*) #archive_where;
+            },
+            Fields::Unit => unreachable!(),
+        };
+        let tail_member = tail_field.ident.as_ref().map_or_else(
+            || {
+                let index = Index::from(fields.len() - 1);
+                quote! { #index }
+            },
+            |n| quote! { #n },
+        );
+        let tail_elem = match tail {
+            UnsizedTail::Slice(ty) => quote! { #rkyv_path::Archived<#ty> },
+            UnsizedTail::Str => quote! { u8 },
+        };
+        let header = quote! { ArchivedHeader #ty_generics };
+        let check_bytes_impl =
+            derive_check_bytes_unsized_impl(&input, attributes, &archived, &header, &rkyv_path)?;
+
+        Some(quote! {
+            #header_struct
+
+            impl #impl_generics #rkyv_path::validation::LayoutMetadata<#archived #ty_generics> for usize #archive_where {
+                #[inline]
+                fn layout(self) -> core::result::Result<core::alloc::Layout, core::alloc::LayoutError> {
+                    let tail = core::alloc::Layout::array::<#tail_elem>(self)?;
+                    // A size that overflows is too large for from_size_align, so it fails too
+                    let size = #rkyv_path::offset_of!(#header, #tail_member)
+                        .checked_add(tail.size())
+                        .unwrap_or(usize::MAX);
+                    Ok(core::alloc::Layout::from_size_align(size, core::mem::align_of::<#header>())?
+                        .pad_to_align())
+                }
+            }
+
+            #check_bytes_impl
+        })
+    } else {
+        None
+    };
+
+    Ok(quote! {
+        #archived_struct
+
+        const _: () = {
+            use core::mem::MaybeUninit;
+            use #rkyv_path::ptr_meta::Pointee;
+
+            impl #impl_generics Pointee for #name #ty_generics #where_clause {
+                type Metadata = usize;
+            }
+
+            impl #impl_generics Pointee for #archived #ty_generics #archive_where {
+                type Metadata = usize;
+            }
+
+            impl #impl_generics #rkyv_path::ArchivePointee for #archived #ty_generics #archive_where {
+                type ArchivedMetadata = #rkyv_path::ArchivedUsize;
+
+                #[inline]
+                fn pointer_metadata(archived: &Self::ArchivedMetadata) -> usize {
+                    *archived as usize
+                }
+            }
+
+            impl #impl_generics #rkyv_path::ArchiveUnsized for #name #ty_generics #archive_where {
+                type Archived = #archived #ty_generics;
+                type MetadataResolver = ();
+
+                #[inline]
+                fn resolve_metadata(
+                    &self,
+                    _: usize,
+                    _: Self::MetadataResolver,
+                    out: &mut MaybeUninit<#rkyv_path::ArchivedMetadata<Self>>,
+                ) {
+                    unsafe {
+                        out.as_mut_ptr()
+                            .write(#rkyv_path::ptr_meta::metadata(self) as #rkyv_path::ArchivedUsize);
+                    }
+                }
+            }
+
+            #debug_impl
+            #serde_impl
+            #validation_impl
         };
     })
 }
@@ -974,6 +1261,8 @@ fn derive_archive_copy_impl(
     mut input: DeriveInput,
     attributes: &Attributes,
 ) -> Result<TokenStream, Error> {
+    let rkyv_path = attributes.rkyv_path();
+
     if let Some(ref derives) = attributes.derives {
         return Err(Error::new_spanned(
             derives,
@@ -981,6 +1270,34 @@ fn derive_archive_copy_impl(
         ));
     }
 
+    if let Some(ref debug) = attributes.debug {
+        return Err(Error::new_spanned(
+            debug,
+            "archive copy types are archived as themselves, derive Debug on the type instead",
+        ));
+    }
+
+    if let Some(ref serde) = attributes.serde {
+        return Err(Error::new_spanned(
+            serde,
+            "archive copy types are archived as themselves, derive serde::Serialize on the type instead",
+        ));
+    }
+
+    if let Some(ref no_check) = attributes.no_check {
+        return Err(Error::new_spanned(
+            no_check,
+            "archive copy types are archived as themselves and are not checked automatically",
+        ));
+    }
+
+    if let Some(ref deserialize_fields) = attributes.deserialize_fields {
+        return Err(Error::new_spanned(
+            deserialize_fields,
+            "archive copy types are archived as themselves, deserialize them directly instead",
+        ));
+    }
+
     if let Some((ref compares, _)) = attributes.compares {
         return Err(Error::new_spanned(
             compares,
@@ -1113,7 +1430,7 @@ fn derive_archive_copy_impl(
     Ok(quote! {
         const _: () = {
             use core::mem::MaybeUninit;
-            use rkyv::{
+            use #rkyv_path::{
                 Archive,
                 ArchiveCopy,
                 Serialize,
@@ -1,5 +1,8 @@
 use quote::ToTokens;
-use syn::{AttrStyle, DeriveInput, Error, Ident, Lit, LitStr, Meta, MetaList, NestedMeta, Path};
+use syn::{
+    parse_quote, AttrStyle, DeriveInput, Error, Ident, Lit, LitStr, Meta, MetaList, NestedMeta,
+    Path,
+};
 
 pub struct Repr {
     pub rust: Option<Path>,
@@ -23,32 +26,55 @@ impl Default for Repr {
 
 pub struct Attributes {
     pub copy: Option<Path>,
+    pub debug: Option<Path>,
+    pub deserialize_fields: Option<Path>,
+    pub serde: Option<Path>,
     pub repr: Repr,
     pub derives: Option<MetaList>,
     pub compares: Option<(Path, Vec<Path>)>,
     pub serialize_bound: Option<LitStr>,
     pub deserialize_bound: Option<LitStr>,
+    pub check_bytes_bound: Option<LitStr>,
     pub archived: Option<Ident>,
     pub resolver: Option<Ident>,
     pub strict: Option<Path>,
+    pub no_check: Option<Path>,
+    pub track_path: Option<Path>,
+    pub crate_path: Option<Path>,
 }
 
 impl Default for Attributes {
     fn default() -> Self {
         Self {
             copy: None,
+            debug: None,
+            deserialize_fields: None,
+            serde: None,
             repr: Default::default(),
             derives: None,
             compares: None,
             serialize_bound: None,
             deserialize_bound: None,
+            check_bytes_bound: None,
             archived: None,
             resolver: None,
             strict: None,
+            no_check: None,
+            track_path: None,
+            crate_path: None,
         }
     }
 }
 
+impl Attributes {
+    /// Returns the path to use for the `rkyv` crate in generated code.
+    pub fn rkyv_path(&self) -> Path {
+        self.crate_path
+            .clone()
+            .unwrap_or_else(|| parse_quote! { rkyv })
+    }
+}
+
 fn try_set_attribute<T: ToTokens>(
     attribute: &mut Option<T>,
     value: T,
@@ -70,8 +96,22 @@ fn parse_archive_attributes(attributes: &mut Attributes, meta: &Meta) -> Result<
         Meta::Path(path) => {
             if path.is_ident("copy") {
                 try_set_attribute(&mut attributes.copy, path.clone(), "copy")
+            } else if path.is_ident("debug") {
+                try_set_attribute(&mut attributes.debug, path.clone(), "debug")
+            } else if path.is_ident("deserialize_fields") {
+                try_set_attribute(
+                    &mut attributes.deserialize_fields,
+                    path.clone(),
+                    "deserialize_fields",
+                )
+            } else if path.is_ident("serde") {
+                try_set_attribute(&mut attributes.serde, path.clone(), "serde")
             } else if path.is_ident("strict") {
                 try_set_attribute(&mut attributes.strict, path.clone(), "strict")
+            } else if path.is_ident("no_check") {
+                try_set_attribute(&mut attributes.no_check, path.clone(), "no_check")
+            } else if path.is_ident("track_path") {
+                try_set_attribute(&mut attributes.track_path, path.clone(), "track_path")
             } else {
                 Err(Error::new_spanned(path, "unrecognized archive parameter"))
             }
@@ -119,10 +159,19 @@ fn parse_archive_attributes(attributes: &mut Attributes, meta: &Meta) -> Result<
                                         "serialize bound already specified",
                                     ));
                                 }
+                            } else if name_value.path.is_ident("check_bytes") {
+                                if attributes.check_bytes_bound.is_none() {
+                                    attributes.check_bytes_bound = Some(lit_str.clone());
+                                } else {
+                                    return Err(Error::new_spanned(
+                                        bound,
+                                        "check_bytes bound already specified",
+                                    ));
+                                }
                             } else {
                                 return Err(Error::new_spanned(
                                     bound,
-                                    "bounds must be either serialize or deserialize",
+                                    "bounds must be either serialize, deserialize or check_bytes",
                                 ));
                             }
                         } else {
@@ -134,7 +183,7 @@ fn parse_archive_attributes(attributes: &mut Attributes, meta: &Meta) -> Result<
                     } else {
                         return Err(Error::new_spanned(
                             bound,
-                            "bounds arguments must be serialize or deserialize bounds to apply",
+                            "bounds arguments must be serialize, deserialize or check_bytes bounds to apply",
                         ));
                     }
                 }
@@ -167,6 +216,12 @@ fn parse_archive_attributes(attributes: &mut Attributes, meta: &Meta) -> Result<
                 } else {
                     Err(Error::new_spanned(meta, "resolver must be a string"))
                 }
+            } else if meta.path.is_ident("crate") {
+                if let Lit::Str(ref lit_str) = meta.lit {
+                    try_set_attribute(&mut attributes.crate_path, lit_str.parse()?, "crate")
+                } else {
+                    Err(Error::new_spanned(meta, "crate must be a string"))
+                }
             } else {
                 Err(Error::new_spanned(meta, "unrecognized archive parameter"))
             }
@@ -0,0 +1,502 @@
+use crate::{
+    archive::archived_repr,
+    attributes::Attributes,
+    deserialize::name_lit,
+    tail::{unsized_tail, UnsizedTail},
+};
+use proc_macro2::TokenStream;
+use quote::{quote, quote_spanned};
+use syn::{
+    parse_quote, punctuated::Punctuated, spanned::Spanned, Data, DeriveInput, Error, Field, Fields,
+    GenericParam, Ident, Index, LitStr, Meta, NestedMeta, Path, Token, WhereClause, WherePredicate,
+};
+
+/// Returns whether `CheckBytes` is already derived for the archived type with `derive(...)`.
+///
+/// Only the paths that name bytecheck's derive are recognized: `CheckBytes`,
+/// `bytecheck::CheckBytes` and the re-export at `rkyv::bytecheck::CheckBytes`.
+fn derives_check_bytes(attributes: &Attributes, rkyv_path: &Path) -> bool {
+    fn path_string(path: &Path) -> String {
+        path.segments
+            .iter()
+            .map(|s| s.ident.to_string())
+            .collect::<Vec<_>>()
+            .join("::")
+    }
+
+    let reexported = format!("{}::bytecheck::CheckBytes", path_string(rkyv_path));
+    attributes.derives.as_ref().is_some_and(|derives| {
+        derives.nested.iter().any(|derive| {
+            matches!(derive, NestedMeta::Meta(Meta::Path(path)) if {
+                let path = path_string(path);
+                path == "CheckBytes" || path == "bytecheck::CheckBytes" || path == reexported
+            })
+        })
+    })
+}
+
+/// Returns the expression used to check the bytes of `field` located at the offset of `member` in
+/// `parent`, mapping any error with `map_err`.
+fn check_field(
+    field: &Field,
+    parent: &TokenStream,
+    member: TokenStream,
+    rkyv_path: &Path,
+    map_err: TokenStream,
+) -> TokenStream {
+    let ty = &field.ty;
+    quote_spanned! { field.span() =>
+        <#rkyv_path::Archived<#ty> as #rkyv_path::bytecheck::CheckBytes<__C>>::check_bytes(
+            value.cast::<u8>().add(#rkyv_path::offset_of!(#parent, #member)).cast(),
+            context,
+        )
+        .map_err(#map_err)?;
+    }
+}
+
+/// Wraps `checks` in calls that report the path segment to the context if the path is tracked.
+///
+/// The segment is only exited once the checks succeed, so the context keeps the path to the value
+/// that failed.
+fn track_path(
+    tracked: bool,
+    rkyv_path: &Path,
+    segment: TokenStream,
+    checks: TokenStream,
+) -> TokenStream {
+    if tracked {
+        quote! {
+            <__C as #rkyv_path::validation::ArchiveBoundsContext>::enter_path(
+                context,
+                #rkyv_path::de::PathSegment::#segment,
+            );
+            #checks
+            <__C as #rkyv_path::validation::ArchiveBoundsContext>::exit_path(context);
+        }
+    } else {
+        checks
+    }
+}
+
+/// Returns the generics and where clause of the `CheckBytes` implementation for the archived type.
+///
+/// Each field requires that its archived type can be checked with the context. The trailing field
+/// of an unsized struct instead requires this of its archived elements.
+fn check_generics(
+    input: &DeriveInput,
+    attributes: &Attributes,
+    rkyv_path: &Path,
+) -> Result<(TokenStream, WhereClause), Error> {
+    let (_, _, where_clause) = input.generics.split_for_impl();
+
+    let mut impl_params = Punctuated::<_, Token![,]>::new();
+    // Lifetime parameters have to come before type parameters
+    for param in input.generics.lifetimes() {
+        impl_params.push(GenericParam::Lifetime(param.clone()));
+    }
+    impl_params.push(parse_quote! { __C: ?Sized });
+    for param in input.generics.params.iter() {
+        // Defaults for generic parameters aren't allowed on impls
+        let mut param = param.clone();
+        match param {
+            GenericParam::Type(ref mut param) => {
+                param.eq_token = None;
+                param.default = None;
+            }
+            GenericParam::Const(ref mut param) => {
+                param.eq_token = None;
+                param.default = None;
+            }
+            GenericParam::Lifetime(_) => continue,
+        }
+        impl_params.push(param);
+    }
+    let impl_generics = quote! { <#impl_params> };
+
+    let mut check_where = where_clause
+        .cloned()
+        .unwrap_or_else(|| parse_quote! { where });
+    let fields: Box<dyn Iterator<Item = _>> = match input.data {
+        Data::Struct(ref data) => Box::new(data.fields.iter()),
+        Data::Enum(ref data) => Box::new(data.variants.iter().flat_map(|v| v.fields.iter())),
+        Data::Union(_) => Box::new(core::iter::empty()),
+    };
+    let mut omits_bounds = false;
+    for field in fields {
+        if field.attrs.iter().any(|a| a.path.is_ident("omit_bounds")) {
+            omits_bounds = true;
+            continue;
+        }
+        let ty = match unsized_tail(input) {
+            Some((tail_field, tail)) if core::ptr::eq(field, tail_field) => match tail {
+                UnsizedTail::Slice(ty) => ty,
+                // Strings can be checked with any context
+                UnsizedTail::Str => continue,
+            },
+            _ => &field.ty,
+        };
+        check_where
+            .predicates
+            .push(parse_quote! { #ty: #rkyv_path::Archive });
+        check_where.predicates.push(
+            parse_quote! { #rkyv_path::Archived<#ty>: #rkyv_path::bytecheck::CheckBytes<__C> },
+        );
+    }
+    // Fields with omitted bounds are usually recursive through a box, so the context has to support
+    // checking boxes without naming the field types
+    if omits_bounds {
+        check_where.predicates.push(parse_quote! {
+            __C: #rkyv_path::validation::ArchiveBoundsContext
+                + #rkyv_path::validation::ArchiveMemoryContext
+        });
+        check_where.predicates.push(parse_quote! {
+            <__C as #rkyv_path::Fallible>::Error: ::std::error::Error
+        });
+    }
+    if attributes.track_path.is_some() {
+        check_where
+            .predicates
+            .push(parse_quote! { __C: #rkyv_path::validation::ArchiveBoundsContext });
+    }
+    if let Some(ref bounds) = attributes.check_bytes_bound {
+        let clauses =
+            bounds.parse_with(Punctuated::<WherePredicate, Token![,]>::parse_terminated)?;
+        for clause in clauses {
+            check_where.predicates.push(clause);
+        }
+    }
+
+    Ok((impl_generics, check_where))
+}
+
+/// Returns the calls that report the archived type to the context if the path is tracked.
+///
+/// The type is only exited once all of its fields check successfully.
+fn track_type(tracked: bool, rkyv_path: &Path, name: &Ident) -> (TokenStream, TokenStream) {
+    if tracked {
+        let type_name = name_lit(name);
+        (
+            quote! {
+                <__C as #rkyv_path::validation::ArchiveBoundsContext>::enter_path(
+                    context,
+                    #rkyv_path::de::PathSegment::Type(#type_name),
+                );
+            },
+            quote! {
+                <__C as #rkyv_path::validation::ArchiveBoundsContext>::exit_path(context);
+            },
+        )
+    } else {
+        (quote! {}, quote! {})
+    }
+}
+
+/// Generates a `CheckBytes` implementation for the archived type when the `check_bytes` feature is
+/// enabled.
+///
+/// Nothing is generated if the type opted out with `no_check` or already derives `CheckBytes` for
+/// the archived type with `derive(...)`.
+pub fn derive_check_bytes_impl(
+    input: &DeriveInput,
+    attributes: &Attributes,
+    archived: &Ident,
+    rkyv_path: &Path,
+) -> Result<Option<TokenStream>, Error> {
+    if !cfg!(feature = "check_bytes")
+        || attributes.no_check.is_some()
+        || derives_check_bytes(attributes, rkyv_path)
+    {
+        return Ok(None);
+    }
+
+    let name = &input.ident;
+    let name_str = name.to_string();
+    let tracked = attributes.track_path.is_some();
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+    let (impl_generics, check_where) = check_generics(input, attributes, rkyv_path)?;
+    let archived_ty = quote! { #archived #ty_generics };
+    let (enter_type, exit_type) = track_type(tracked, rkyv_path, name);
+
+    let (error, body) = match input.data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => {
+                let checks = fields.named.iter().map(|f| {
+                    let field_name = f.ident.as_ref().unwrap();
+                    let field_name_str = field_name.to_string();
+                    let field_name_lit = name_lit(field_name);
+                    let check = check_field(
+                        f,
+                        &archived_ty,
+                        quote! { #field_name },
+                        rkyv_path,
+                        quote! {
+                            |e| #rkyv_path::bytecheck::StructCheckError {
+                                field_name: #field_name_str,
+                                inner: e.into(),
+                            }
+                        },
+                    );
+                    track_path(tracked, rkyv_path, quote! { Field(#field_name_lit) }, check)
+                });
+                (
+                    quote! { #rkyv_path::bytecheck::StructCheckError },
+                    quote! {
+                        #enter_type
+                        #(#checks)*
+                        #exit_type
+                        Ok(&*value)
+                    },
+                )
+            }
+            Fields::Unnamed(ref fields) => {
+                let checks = fields.unnamed.iter().enumerate().map(|(i, f)| {
+                    let index = Index::from(i);
+                    let field_name_lit = LitStr::new(&i.to_string(), f.span());
+                    let check = check_field(
+                        f,
+                        &archived_ty,
+                        quote! { #index },
+                        rkyv_path,
+                        quote! {
+                            |e| #rkyv_path::bytecheck::TupleStructCheckError {
+                                field_index: #i,
+                                inner: e.into(),
+                            }
+                        },
+                    );
+                    track_path(tracked, rkyv_path, quote! { Field(#field_name_lit) }, check)
+                });
+                (
+                    quote! { #rkyv_path::bytecheck::TupleStructCheckError },
+                    quote! {
+                        #enter_type
+                        #(#checks)*
+                        #exit_type
+                        Ok(&*value)
+                    },
+                )
+            }
+            Fields::Unit => (
+                quote! { #rkyv_path::bytecheck::Unreachable },
+                quote! { Ok(&*value) },
+            ),
+        },
+        Data::Enum(ref data) => {
+            let repr = archived_repr(data);
+
+            let variant_checks = data.variants.iter().map(|v| {
+                let variant = &v.ident;
+                let variant_str = variant.to_string();
+                let variant_struct = Ident::new(&format!("ArchivedVariant{}", variant), v.span());
+                let variant_ty = quote! { #variant_struct #ty_generics };
+                let checks = match v.fields {
+                    Fields::Named(ref fields) => fields
+                        .named
+                        .iter()
+                        .map(|f| {
+                            let field_name = f.ident.as_ref().unwrap();
+                            let field_name_str = field_name.to_string();
+                            let field_name_lit = name_lit(field_name);
+                            let check = check_field(
+                                f,
+                                &variant_ty,
+                                quote! { #field_name },
+                                rkyv_path,
+                                quote! {
+                                    |e| #rkyv_path::validation::EnumCheckError::InvalidStruct {
+                                        type_name: #name_str,
+                                        variant_name: #variant_str,
+                                        inner: #rkyv_path::bytecheck::StructCheckError {
+                                            field_name: #field_name_str,
+                                            inner: e.into(),
+                                        },
+                                    }
+                                },
+                            );
+                            track_path(tracked, rkyv_path, quote! { Field(#field_name_lit) }, check)
+                        })
+                        .collect::<Vec<_>>(),
+                    Fields::Unnamed(ref fields) => fields
+                        .unnamed
+                        .iter()
+                        .enumerate()
+                        .map(|(i, f)| {
+                            // The first field of the variant struct is the tag
+                            let index = Index::from(i + 1);
+                            let field_name_lit = LitStr::new(&i.to_string(), f.span());
+                            let check = check_field(
+                                f,
+                                &variant_ty,
+                                quote! { #index },
+                                rkyv_path,
+                                quote! {
+                                    |e| #rkyv_path::validation::EnumCheckError::InvalidTupleStruct {
+                                        type_name: #name_str,
+                                        variant_name: #variant_str,
+                                        inner: #rkyv_path::bytecheck::TupleStructCheckError {
+                                            field_index: #i,
+                                            inner: e.into(),
+                                        },
+                                    }
+                                },
+                            );
+                            track_path(tracked, rkyv_path, quote! { Field(#field_name_lit) }, check)
+                        })
+                        .collect::<Vec<_>>(),
+                    Fields::Unit => Vec::new(),
+                };
+                // Unit variants have nothing to check, so they don't report a path
+                let checks = if checks.is_empty() {
+                    quote! {}
+                } else {
+                    let variant_name = name_lit(variant);
+                    track_path(
+                        tracked,
+                        rkyv_path,
+                        quote! { Variant(#variant_name) },
+                        quote! { #(#checks)* },
+                    )
+                };
+                quote! {
+                    if tag == ArchivedTag::#variant as #repr {
+                        #checks
+                        #exit_type
+                        return Ok(&*value);
+                    }
+                }
+            });
+
+            (
+                quote! { #rkyv_path::validation::EnumCheckError<#repr> },
+                quote! {
+                    #enter_type
+                    let tag = *value.cast::<#repr>();
+                    #(#variant_checks)*
+                    Err(#rkyv_path::validation::EnumCheckError::InvalidTag {
+                        type_name: #name_str,
+                        tag,
+                    })
+                },
+            )
+        }
+        Data::Union(_) => unreachable!(),
+    };
+
+    Ok(Some(quote! {
+        impl #impl_generics #rkyv_path::bytecheck::CheckBytes<__C> for #archived_ty #check_where {
+            type Error = #error;
+
+            #[allow(unused_variables)]
+            unsafe fn check_bytes<'__a>(
+                value: *const Self,
+                context: &mut __C,
+            ) -> ::core::result::Result<&'__a Self, Self::Error> {
+                #body
+            }
+        }
+    }))
+}
+
+/// Generates a `CheckBytes` implementation for the archived type of an unsized struct when the
+/// `check_bytes` feature is enabled.
+///
+/// The fields are located with `header`, a sized struct that has the same fields as the archived
+/// type except that the unsized field is replaced with an empty array of its elements.
+pub fn derive_check_bytes_unsized_impl(
+    input: &DeriveInput,
+    attributes: &Attributes,
+    archived: &Ident,
+    header: &TokenStream,
+    rkyv_path: &Path,
+) -> Result<Option<TokenStream>, Error> {
+    if !cfg!(feature = "check_bytes")
+        || attributes.no_check.is_some()
+        || derives_check_bytes(attributes, rkyv_path)
+    {
+        return Ok(None);
+    }
+
+    let name = &input.ident;
+    let tracked = attributes.track_path.is_some();
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+    let (impl_generics, check_where) = check_generics(input, attributes, rkyv_path)?;
+    let (enter_type, exit_type) = track_type(tracked, rkyv_path, name);
+
+    let fields = match input.data {
+        Data::Struct(ref data) => &data.fields,
+        _ => unreachable!(),
+    };
+    let (tail_field, tail) = unsized_tail(input).unwrap();
+    let tail_ty = match tail {
+        UnsizedTail::Slice(ty) => quote! { [#rkyv_path::Archived<#ty>] },
+        UnsizedTail::Str => quote! { str },
+    };
+
+    let error = match fields {
+        Fields::Named(_) => quote! { #rkyv_path::bytecheck::StructCheckError },
+        Fields::Unnamed(_) => quote! { #rkyv_path::bytecheck::TupleStructCheckError },
+        Fields::Unit => unreachable!(),
+    };
+
+    let checks = fields.iter().enumerate().map(|(i, f)| {
+        let (member, field_name_lit, map_err) = match f.ident {
+            Some(ref field_name) => {
+                let field_name_str = field_name.to_string();
+                (
+                    quote! { #field_name },
+                    name_lit(field_name),
+                    quote! {
+                        |e| #rkyv_path::bytecheck::StructCheckError {
+                            field_name: #field_name_str,
+                            inner: e.into(),
+                        }
+                    },
+                )
+            }
+            None => {
+                let index = Index::from(i);
+                (
+                    quote! { #index },
+                    LitStr::new(&i.to_string(), f.span()),
+                    quote! {
+                        |e| #rkyv_path::bytecheck::TupleStructCheckError {
+                            field_index: #i,
+                            inner: e.into(),
+                        }
+                    },
+                )
+            }
+        };
+        let check = if core::ptr::eq(f, tail_field) {
+            quote_spanned! { f.span() =>
+                <#tail_ty as #rkyv_path::bytecheck::CheckBytes<__C>>::check_bytes(
+                    #rkyv_path::ptr_meta::from_raw_parts(
+                        value.cast::<u8>().add(#rkyv_path::offset_of!(#header, #member)).cast(),
+                        #rkyv_path::ptr_meta::metadata(value),
+                    ),
+                    context,
+                )
+                .map_err(#map_err)?;
+            }
+        } else {
+            check_field(f, header, member, rkyv_path, map_err)
+        };
+        track_path(tracked, rkyv_path, quote! { Field(#field_name_lit) }, check)
+    });
+
+    Ok(Some(quote! {
+        impl #impl_generics #rkyv_path::bytecheck::CheckBytes<__C> for #archived #ty_generics #check_where {
+            type Error = #error;
+
+            unsafe fn check_bytes<'__a>(
+                value: *const Self,
+                context: &mut __C,
+            ) -> ::core::result::Result<&'__a Self, Self::Error> {
+                #enter_type
+                #(#checks)*
+                #exit_type
+                Ok(&*value)
+            }
+        }
+    }))
+}
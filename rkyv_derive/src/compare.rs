@@ -0,0 +1,459 @@
+use crate::attributes::Attributes;
+use proc_macro2::{Literal, TokenStream};
+use quote::{quote, quote_spanned};
+use syn::{
+    parse_quote, spanned::Spanned, Data, DeriveInput, Error, Expr, Fields, Ident, Index, Member,
+    Path, WhereClause,
+};
+
+/// The compares that are implemented between archived values instead of between an archived value
+/// and its unarchived counterpart.
+pub const ARCHIVED_COMPARES: [&str; 3] = ["Eq", "Ord", "Hash"];
+
+fn has_compare(attributes: &Attributes, name: &str) -> bool {
+    attributes
+        .compares
+        .as_ref()
+        .is_some_and(|(_, compares)| compares.iter().any(|c| c.is_ident(name)))
+}
+
+/// Adds the bound `Archived<T>: trait` for every field that does not omit its bounds.
+fn compare_where(input: &DeriveInput, rkyv_path: &Path, bound: TokenStream) -> WhereClause {
+    let mut result = input
+        .generics
+        .where_clause
+        .clone()
+        .unwrap_or_else(|| parse_quote! { where });
+    let fields: Box<dyn Iterator<Item = _>> = match input.data {
+        Data::Struct(ref data) => Box::new(data.fields.iter()),
+        Data::Enum(ref data) => Box::new(data.variants.iter().flat_map(|v| v.fields.iter())),
+        Data::Union(_) => Box::new(core::iter::empty()),
+    };
+    for field in fields.filter(|f| !f.attrs.iter().any(|a| a.path.is_ident("omit_bounds"))) {
+        let ty = &field.ty;
+        result
+            .predicates
+            .push(parse_quote! { #ty: #rkyv_path::Archive });
+        result
+            .predicates
+            .push(parse_quote! { #rkyv_path::Archived<#ty>: #bound });
+    }
+    result
+}
+
+/// Returns the members used to access each of the fields.
+fn members(fields: &Fields) -> Vec<Member> {
+    fields
+        .iter()
+        .enumerate()
+        .map(|(i, f)| {
+            f.ident
+                .clone()
+                .map_or_else(|| Member::Unnamed(Index::from(i)), Member::Named)
+        })
+        .collect()
+}
+
+/// Returns the bindings for the fields of `fields` with the given prefix, and a pattern that
+/// destructures a value of type `path` into them.
+fn destructure(path: TokenStream, fields: &Fields, prefix: &str) -> (TokenStream, Vec<Ident>) {
+    let bindings = fields
+        .iter()
+        .enumerate()
+        .map(|(i, f)| Ident::new(&format!("{}_{}", prefix, i), f.span()))
+        .collect::<Vec<_>>();
+    let pattern = match fields {
+        Fields::Named(ref fields) => {
+            let names = fields.named.iter().map(|f| &f.ident);
+            quote! { #path { #(#names: #bindings,)* } }
+        }
+        Fields::Unnamed(_) => quote! { #path(#(#bindings,)*) },
+        Fields::Unit => quote! { #path },
+    };
+    (pattern, bindings)
+}
+
+/// Generates an expression that evaluates to the discriminant of the archived enum `self`, matching
+/// the discriminant of the corresponding variant of the unarchived enum.
+fn discriminant(
+    input: &DeriveInput,
+    attributes: &Attributes,
+    archived: &Ident,
+    value: TokenStream,
+) -> TokenStream {
+    let data = match input.data {
+        Data::Enum(ref data) => data,
+        _ => unreachable!(),
+    };
+
+    let discriminant_ty = attributes
+        .repr
+        .int
+        .as_ref()
+        .map_or_else(|| quote! { isize }, |int| quote! { #int });
+
+    let mut last: Option<&Expr> = None;
+    let mut offset = 0usize;
+    let arms = data.variants.iter().map(|v| {
+        if let Some((_, ref expr)) = v.discriminant {
+            last = Some(expr);
+            offset = 0;
+        }
+        let variant = &v.ident;
+        let pattern = match v.fields {
+            Fields::Named(_) => quote! { #archived::#variant { .. } },
+            Fields::Unnamed(_) => quote! { #archived::#variant(..) },
+            Fields::Unit => quote! { #archived::#variant },
+        };
+        let index = Literal::usize_unsuffixed(offset);
+        let discriminant = match last {
+            Some(expr) if offset == 0 => quote! { #expr },
+            Some(expr) => quote! { (#expr) + #index },
+            None => quote! { #index },
+        };
+        offset += 1;
+        quote_spanned! { variant.span() => #pattern => #discriminant }
+    });
+    let arms = arms.collect::<Vec<_>>();
+
+    quote! {
+        {
+            let discriminant: #discriminant_ty = match #value {
+                #(#arms,)*
+            };
+            discriminant
+        }
+    }
+}
+
+fn derive_eq_impl(input: &DeriveInput, archived: &Ident, rkyv_path: &Path) -> TokenStream {
+    let (impl_generics, ty_generics, _) = input.generics.split_for_impl();
+    let partial_eq_where = compare_where(input, rkyv_path, quote! { PartialEq });
+    let eq_where = compare_where(input, rkyv_path, quote! { Eq });
+
+    let body = match input.data {
+        Data::Struct(ref data) => {
+            let members = members(&data.fields);
+            quote! { true #(&& self.#members == other.#members)* }
+        }
+        Data::Enum(ref data) => {
+            let arms = data.variants.iter().map(|v| {
+                let variant = &v.ident;
+                let (self_pattern, self_bindings) =
+                    destructure(quote! { #archived::#variant }, &v.fields, "self");
+                let (other_pattern, other_bindings) =
+                    destructure(quote! { #archived::#variant }, &v.fields, "other");
+                quote! {
+                    (#self_pattern, #other_pattern) => true #(&& #self_bindings == #other_bindings)*
+                }
+            });
+            quote! {
+                match (self, other) {
+                    #(#arms,)*
+                    #[allow(unreachable_patterns)]
+                    _ => false,
+                }
+            }
+        }
+        Data::Union(_) => unreachable!(),
+    };
+
+    quote! {
+        impl #impl_generics PartialEq for #archived #ty_generics #partial_eq_where {
+            #[inline]
+            fn eq(&self, other: &Self) -> bool {
+                #body
+            }
+        }
+
+        impl #impl_generics Eq for #archived #ty_generics #eq_where {}
+    }
+}
+
+fn derive_ord_impl(
+    input: &DeriveInput,
+    attributes: &Attributes,
+    archived: &Ident,
+    rkyv_path: &Path,
+) -> TokenStream {
+    let (impl_generics, ty_generics, _) = input.generics.split_for_impl();
+    let partial_ord_where = compare_where(input, rkyv_path, quote! { PartialOrd });
+    let ord_where = compare_where(input, rkyv_path, quote! { Ord });
+
+    let (partial_cmp_body, cmp_body) = match input.data {
+        Data::Struct(ref data) => {
+            let members = members(&data.fields);
+            (
+                quote! {
+                    #(
+                        match self.#members.partial_cmp(&other.#members) {
+                            Some(::core::cmp::Ordering::Equal) => (),
+                            cmp => return cmp,
+                        }
+                    )*
+                    Some(::core::cmp::Ordering::Equal)
+                },
+                quote! {
+                    #(
+                        match self.#members.cmp(&other.#members) {
+                            ::core::cmp::Ordering::Equal => (),
+                            cmp => return cmp,
+                        }
+                    )*
+                    ::core::cmp::Ordering::Equal
+                },
+            )
+        }
+        Data::Enum(ref data) => {
+            let self_discriminant = discriminant(input, attributes, archived, quote! { self });
+            let other_discriminant = discriminant(input, attributes, archived, quote! { other });
+
+            let variant_arms = |method: TokenStream, equal: TokenStream, wrap: TokenStream| {
+                data.variants
+                    .iter()
+                    .map(|v| {
+                        let variant = &v.ident;
+                        let (self_pattern, self_bindings) =
+                            destructure(quote! { #archived::#variant }, &v.fields, "self");
+                        let (other_pattern, other_bindings) =
+                            destructure(quote! { #archived::#variant }, &v.fields, "other");
+                        quote! {
+                            (#self_pattern, #other_pattern) => {
+                                #(
+                                    match #self_bindings.#method(#other_bindings) {
+                                        #equal => (),
+                                        cmp => return cmp,
+                                    }
+                                )*
+                                #wrap
+                            }
+                        }
+                    })
+                    .collect::<Vec<_>>()
+            };
+            let partial_cmp_arms = variant_arms(
+                quote! { partial_cmp },
+                quote! { Some(::core::cmp::Ordering::Equal) },
+                quote! { Some(::core::cmp::Ordering::Equal) },
+            );
+            let cmp_arms = variant_arms(
+                quote! { cmp },
+                quote! { ::core::cmp::Ordering::Equal },
+                quote! { ::core::cmp::Ordering::Equal },
+            );
+
+            (
+                quote! {
+                    let self_discriminant = #self_discriminant;
+                    let other_discriminant = #other_discriminant;
+                    if self_discriminant != other_discriminant {
+                        return self_discriminant.partial_cmp(&other_discriminant);
+                    }
+                    match (self, other) {
+                        #(#partial_cmp_arms,)*
+                        #[allow(unreachable_patterns)]
+                        _ => unsafe { ::core::hint::unreachable_unchecked() },
+                    }
+                },
+                quote! {
+                    let self_discriminant = #self_discriminant;
+                    let other_discriminant = #other_discriminant;
+                    if self_discriminant != other_discriminant {
+                        return self_discriminant.cmp(&other_discriminant);
+                    }
+                    match (self, other) {
+                        #(#cmp_arms,)*
+                        #[allow(unreachable_patterns)]
+                        _ => unsafe { ::core::hint::unreachable_unchecked() },
+                    }
+                },
+            )
+        }
+        Data::Union(_) => unreachable!(),
+    };
+
+    quote! {
+        impl #impl_generics PartialOrd for #archived #ty_generics #partial_ord_where {
+            #[inline]
+            fn partial_cmp(&self, other: &Self) -> Option<::core::cmp::Ordering> {
+                #partial_cmp_body
+            }
+        }
+
+        impl #impl_generics Ord for #archived #ty_generics #ord_where {
+            #[inline]
+            fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+                #cmp_body
+            }
+        }
+    }
+}
+
+fn derive_hash_impl(
+    input: &DeriveInput,
+    attributes: &Attributes,
+    archived: &Ident,
+    rkyv_path: &Path,
+) -> TokenStream {
+    let (impl_generics, ty_generics, _) = input.generics.split_for_impl();
+    let hash_where = compare_where(input, rkyv_path, quote! { ::core::hash::Hash });
+
+    // This must hash the same values in the same order as `#[derive(Hash)]` on the unarchived type
+    let body = match input.data {
+        Data::Struct(ref data) => {
+            let members = members(&data.fields);
+            quote! { #(::core::hash::Hash::hash(&self.#members, state);)* }
+        }
+        Data::Enum(ref data) => {
+            let hash_discriminant = if data.variants.len() > 1 {
+                let discriminant = discriminant(input, attributes, archived, quote! { self });
+                Some(quote! { ::core::hash::Hash::hash(&#discriminant, state); })
+            } else {
+                None
+            };
+            let arms = data.variants.iter().map(|v| {
+                let variant = &v.ident;
+                let (pattern, bindings) =
+                    destructure(quote! { #archived::#variant }, &v.fields, "self");
+                quote! {
+                    #pattern => {
+                        #(::core::hash::Hash::hash(#bindings, state);)*
+                    }
+                }
+            });
+            quote! {
+                #hash_discriminant
+                match self {
+                    #(#arms,)*
+                }
+            }
+        }
+        Data::Union(_) => unreachable!(),
+    };
+
+    quote! {
+        impl #impl_generics ::core::hash::Hash for #archived #ty_generics #hash_where {
+            #[inline]
+            fn hash<__H: ::core::hash::Hasher>(&self, state: &mut __H) {
+                #body
+            }
+        }
+    }
+}
+
+/// Implements `ArchiveKey` for the type and its archived counterpart, and lets both of them borrow
+/// as a `dyn ArchiveKey` so either can be used to look up entries in hash maps keyed by the other.
+fn derive_key_impls(input: &DeriveInput, archived: &Ident, rkyv_path: &Path) -> TokenStream {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, _) = input.generics.split_for_impl();
+
+    let mut key_where = input
+        .generics
+        .where_clause
+        .clone()
+        .unwrap_or_else(|| parse_quote! { where });
+    let fields: Box<dyn Iterator<Item = _>> = match input.data {
+        Data::Struct(ref data) => Box::new(data.fields.iter()),
+        Data::Enum(ref data) => Box::new(data.variants.iter().flat_map(|v| v.fields.iter())),
+        Data::Union(_) => Box::new(core::iter::empty()),
+    };
+    for field in fields.filter(|f| !f.attrs.iter().any(|a| a.path.is_ident("omit_bounds"))) {
+        let ty = &field.ty;
+        key_where
+            .predicates
+            .push(parse_quote! { #ty: #rkyv_path::Archive });
+    }
+
+    let mut borrow_generics = input.generics.clone();
+    borrow_generics.params.insert(0, parse_quote! { '__a });
+    let (borrow_impl_generics, _, _) = borrow_generics.split_for_impl();
+    let mut borrow_ref_generics = borrow_generics.clone();
+    borrow_ref_generics.params.insert(1, parse_quote! { '__b });
+    let (borrow_ref_impl_generics, _, _) = borrow_ref_generics.split_for_impl();
+    let mut unarchived_where = key_where.clone();
+    unarchived_where
+        .predicates
+        .push(parse_quote! { #name #ty_generics: '__a });
+    let mut archived_where = key_where.clone();
+    archived_where
+        .predicates
+        .push(parse_quote! { #archived #ty_generics: '__a });
+
+    let key = quote! { #rkyv_path::compare::ArchiveKey<#name #ty_generics> };
+
+    quote! {
+        impl #impl_generics #key for #name #ty_generics #key_where {
+            #[inline]
+            fn key(&self) -> #rkyv_path::compare::KeyRef<'_, #name #ty_generics> {
+                #rkyv_path::compare::KeyRef::Unarchived(self)
+            }
+        }
+
+        impl #impl_generics #key for #archived #ty_generics #key_where {
+            #[inline]
+            fn key(&self) -> #rkyv_path::compare::KeyRef<'_, #name #ty_generics> {
+                #rkyv_path::compare::KeyRef::Archived(self)
+            }
+        }
+
+        impl #borrow_impl_generics ::core::borrow::Borrow<dyn #key + '__a> for #name #ty_generics #unarchived_where {
+            #[inline]
+            fn borrow(&self) -> &(dyn #key + '__a) {
+                self
+            }
+        }
+
+        impl #borrow_impl_generics ::core::borrow::Borrow<dyn #key + '__a> for #archived #ty_generics #archived_where {
+            #[inline]
+            fn borrow(&self) -> &(dyn #key + '__a) {
+                self
+            }
+        }
+
+        impl #borrow_ref_impl_generics ::core::borrow::Borrow<dyn #key + '__a> for &'__b #name #ty_generics #unarchived_where {
+            #[inline]
+            fn borrow(&self) -> &(dyn #key + '__a) {
+                *self
+            }
+        }
+
+        impl #borrow_ref_impl_generics ::core::borrow::Borrow<dyn #key + '__a> for &'__b #archived #ty_generics #archived_where {
+            #[inline]
+            fn borrow(&self) -> &(dyn #key + '__a) {
+                *self
+            }
+        }
+    }
+}
+
+/// Generates the compares between archived values requested by the `compare(...)` attribute.
+pub fn derive_archived_compare_impls(
+    input: &DeriveInput,
+    attributes: &Attributes,
+    archived: &Ident,
+    rkyv_path: &Path,
+) -> Result<TokenStream, Error> {
+    let eq = has_compare(attributes, "Eq");
+    let ord = has_compare(attributes, "Ord");
+    let hash = has_compare(attributes, "Hash");
+
+    if ord && !eq {
+        let (ref path, _) = attributes.compares.as_ref().unwrap();
+        return Err(Error::new_spanned(
+            path,
+            "comparing archived values with Ord requires comparing them with Eq as well",
+        ));
+    }
+
+    let eq_impl = eq.then(|| derive_eq_impl(input, archived, rkyv_path));
+    let ord_impl = ord.then(|| derive_ord_impl(input, attributes, archived, rkyv_path));
+    let hash_impl = hash.then(|| derive_hash_impl(input, attributes, archived, rkyv_path));
+    let key_impls = hash.then(|| derive_key_impls(input, archived, rkyv_path));
+
+    Ok(quote! {
+        #eq_impl
+        #ord_impl
+        #hash_impl
+        #key_impls
+    })
+}
@@ -0,0 +1,138 @@
+use crate::tail::{unsized_tail, UnsizedTail};
+use proc_macro2::TokenStream;
+use quote::{quote, quote_spanned};
+use syn::{
+    parse_quote, spanned::Spanned, Data, DeriveInput, Field, Fields, Ident, Index, Path,
+    WhereClause,
+};
+
+fn debug_fields(
+    fields: &Fields,
+    name: &str,
+    bindings: impl Fn(usize, &Field) -> TokenStream,
+) -> TokenStream {
+    match fields {
+        Fields::Named(ref fields) => {
+            let field_names = fields
+                .named
+                .iter()
+                .map(|f| f.ident.as_ref().unwrap().to_string());
+            let field_values = fields.named.iter().enumerate().map(|(i, f)| bindings(i, f));
+            quote! {
+                f.debug_struct(#name)
+                    #(.field(#field_names, #field_values))*
+                    .finish()
+            }
+        }
+        Fields::Unnamed(ref fields) => {
+            let field_values = fields
+                .unnamed
+                .iter()
+                .enumerate()
+                .map(|(i, f)| bindings(i, f));
+            quote! {
+                f.debug_tuple(#name)
+                    #(.field(#field_values))*
+                    .finish()
+            }
+        }
+        Fields::Unit => quote! { f.write_str(#name) },
+    }
+}
+
+/// Generates a `Debug` implementation for the archived type that formats it the same way that the
+/// derived `Debug` implementation for the original type would.
+pub fn derive_debug_impl(input: &DeriveInput, archived: &Ident, rkyv_path: &Path) -> TokenStream {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let mut debug_where = where_clause.cloned().unwrap_or_else(|| WhereClause {
+        where_token: Default::default(),
+        predicates: Default::default(),
+    });
+    let tail = unsized_tail(input);
+    let mut add_bounds = |field: &Field| {
+        if field.attrs.iter().any(|a| a.path.is_ident("omit_bounds")) {
+            return;
+        }
+        match tail {
+            Some((tail_field, ref tail)) if core::ptr::eq(field, tail_field) => {
+                if let UnsizedTail::Slice(ty) = tail {
+                    debug_where
+                        .predicates
+                        .push(parse_quote! { #ty: #rkyv_path::Archive });
+                    debug_where
+                        .predicates
+                        .push(parse_quote! { #rkyv_path::Archived<#ty>: ::core::fmt::Debug });
+                }
+            }
+            _ => {
+                let ty = &field.ty;
+                debug_where
+                    .predicates
+                    .push(parse_quote! { #ty: #rkyv_path::Archive });
+                debug_where
+                    .predicates
+                    .push(parse_quote! { #rkyv_path::Archived<#ty>: ::core::fmt::Debug });
+            }
+        }
+    };
+
+    let body = match input.data {
+        Data::Struct(ref data) => {
+            data.fields.iter().for_each(&mut add_bounds);
+
+            debug_fields(&data.fields, &name.to_string(), |i, f| {
+                if let Some(ref ident) = f.ident {
+                    quote_spanned! { f.span() => &&self.#ident }
+                } else {
+                    let index = Index::from(i);
+                    quote_spanned! { f.span() => &&self.#index }
+                }
+            })
+        }
+        Data::Enum(ref data) => {
+            let variant_impls = data.variants.iter().map(|v| {
+                v.fields.iter().for_each(&mut add_bounds);
+
+                let variant = &v.ident;
+                let bindings = v
+                    .fields
+                    .iter()
+                    .enumerate()
+                    .map(|(i, f)| Ident::new(&format!("field_{}", i), f.span()))
+                    .collect::<Vec<_>>();
+                let pattern = match v.fields {
+                    Fields::Named(ref fields) => {
+                        let field_names = fields.named.iter().map(|f| &f.ident);
+                        quote! { #archived::#variant { #(#field_names: #bindings,)* } }
+                    }
+                    Fields::Unnamed(_) => quote! { #archived::#variant(#(#bindings,)*) },
+                    Fields::Unit => quote! { #archived::#variant },
+                };
+                let body = debug_fields(&v.fields, &variant.to_string(), |i, _| {
+                    let binding = &bindings[i];
+                    quote! { &#binding }
+                });
+                quote! { #pattern => #body }
+            });
+            // Collect eagerly so that all of the bounds are added before the where clause is used
+            let variant_impls = variant_impls.collect::<Vec<_>>();
+
+            quote! {
+                match self {
+                    #(#variant_impls,)*
+                }
+            }
+        }
+        Data::Union(_) => unreachable!(),
+    };
+
+    quote! {
+        impl #impl_generics ::core::fmt::Debug for #archived #ty_generics #debug_where {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                #body
+            }
+        }
+    }
+}
@@ -1,25 +1,50 @@
-use crate::attributes::{parse_attributes, Attributes};
+use crate::{
+    attributes::{parse_attributes, Attributes},
+    tail::{unsized_tail, UnsizedTail},
+};
 use proc_macro2::TokenStream;
 use quote::{quote, quote_spanned};
 use syn::{
-    parse_quote, punctuated::Punctuated, spanned::Spanned, Data, DeriveInput, Error, Fields,
-    Generics, Ident, Index, Token, WherePredicate,
+    ext::IdentExt, parse_quote, punctuated::Punctuated, spanned::Spanned, Data, DeriveInput, Error,
+    Fields, GenericParam, Generics, Ident, Index, LitStr, Member, Path, Token, WherePredicate,
 };
 
 pub fn derive(input: DeriveInput) -> Result<TokenStream, Error> {
     let attributes = parse_attributes(&input)?;
 
-    if attributes.copy.is_some() {
+    if unsized_tail(&input).is_some() {
+        derive_deserialize_unsized_impl(input, &attributes)
+    } else if attributes.copy.is_some() {
         derive_deserialize_copy_impl(input, &attributes)
     } else {
         derive_deserialize_impl(input, &attributes)
     }
 }
 
+/// Wraps an expression that deserializes part of a value so that it reports the given path segment
+/// to the deserializer.
+fn track_path(rkyv_path: &Path, segment: TokenStream, expr: TokenStream) -> TokenStream {
+    quote! {
+        {
+            deserializer.enter_path(#rkyv_path::de::PathSegment::#segment);
+            let result = #expr;
+            deserializer.exit_path();
+            result
+        }
+    }
+}
+
+pub fn name_lit(ident: &Ident) -> LitStr {
+    LitStr::new(&ident.unraw().to_string(), ident.span())
+}
+
 fn derive_deserialize_impl(
     mut input: DeriveInput,
     attributes: &Attributes,
 ) -> Result<TokenStream, Error> {
+    let rkyv_path = attributes.rkyv_path();
+    let tracked = attributes.track_path.is_some();
+
     let where_clause = input.generics.make_where_clause();
     if let Some(ref bounds) = attributes.deserialize_bound {
         let clauses =
@@ -30,9 +55,19 @@ fn derive_deserialize_impl(
     }
 
     let mut impl_input_params = Punctuated::default();
-    impl_input_params.push(parse_quote! { __D: Fallible + ?Sized });
+    // Lifetime parameters have to come before type parameters
+    for param in input.generics.lifetimes() {
+        impl_input_params.push(GenericParam::Lifetime(param.clone()));
+    }
+    if tracked {
+        impl_input_params.push(parse_quote! { __D: Deserializer + ?Sized });
+    } else {
+        impl_input_params.push(parse_quote! { __D: Fallible + ?Sized });
+    }
     for param in input.generics.params.iter() {
-        impl_input_params.push(param.clone());
+        if !matches!(param, GenericParam::Lifetime(_)) {
+            impl_input_params.push(param.clone());
+        }
     }
     let impl_input_generics = Generics {
         lt_token: Some(Default::default()),
@@ -46,6 +81,25 @@ fn derive_deserialize_impl(
     let (_, ty_generics, where_clause) = input.generics.split_for_impl();
     let where_clause = where_clause.unwrap();
 
+    // Deserializes a field and reports its name if paths are tracked
+    let deserialize_field = |field_name: LitStr, value: TokenStream| {
+        let value = if tracked {
+            track_path(&rkyv_path, quote! { Field(#field_name) }, value)
+        } else {
+            value
+        };
+        quote! { #value? }
+    };
+    // Evaluates an expression that returns a result and reports the given segment while it runs
+    let track_result = |segment: TokenStream, result: TokenStream| {
+        track_path(
+            &rkyv_path,
+            segment,
+            quote! { (|| -> core::result::Result<#name #ty_generics, __D::Error> { #result })() },
+        )
+    };
+    let type_name = name_lit(name);
+
     let deserialize_impl = match input.data {
         Data::Struct(ref data) => match data.fields {
             Fields::Named(ref fields) => {
@@ -65,17 +119,28 @@ fn derive_deserialize_impl(
                 }
 
                 let deserialize_fields = fields.named.iter().map(|f| {
-                    let name = &f.ident;
-                    quote! { #name: self.#name.deserialize(deserializer)? }
+                    let name = f.ident.as_ref().unwrap();
+                    let value = deserialize_field(
+                        name_lit(name),
+                        quote! { self.#name.deserialize(deserializer) },
+                    );
+                    quote! { #name: #value }
                 });
 
+                let mut result = quote! {
+                    Ok(#name {
+                        #(#deserialize_fields,)*
+                    })
+                };
+                if tracked {
+                    result = track_result(quote! { Type(#type_name) }, result);
+                }
+
                 quote! {
                     impl #impl_generics Deserialize<#name #ty_generics, __D> for Archived<#name #ty_generics> #deserialize_where {
                         #[inline]
                         fn deserialize(&self, deserializer: &mut __D) -> core::result::Result<#name #ty_generics, __D::Error> {
-                            Ok(#name {
-                                #(#deserialize_fields,)*
-                            })
+                            #result
                         }
                     }
                 }
@@ -96,18 +161,28 @@ fn derive_deserialize_impl(
                         .push(parse_quote! { Archived<#ty>: Deserialize<#ty, __D> });
                 }
 
-                let deserialize_fields = fields.unnamed.iter().enumerate().map(|(i, _)| {
+                let deserialize_fields = fields.unnamed.iter().enumerate().map(|(i, f)| {
                     let index = Index::from(i);
-                    quote! { self.#index.deserialize(deserializer)? }
+                    deserialize_field(
+                        LitStr::new(&i.to_string(), f.span()),
+                        quote! { self.#index.deserialize(deserializer) },
+                    )
                 });
 
+                let mut result = quote! {
+                    Ok(#name(
+                        #(#deserialize_fields,)*
+                    ))
+                };
+                if tracked {
+                    result = track_result(quote! { Type(#type_name) }, result);
+                }
+
                 quote! {
                     impl #impl_generics Deserialize<#name #ty_generics, __D> for Archived<#name #ty_generics> #deserialize_where {
                         #[inline]
                         fn deserialize(&self, deserializer: &mut __D) -> core::result::Result<#name #ty_generics, __D::Error> {
-                            Ok(#name(
-                                #(#deserialize_fields,)*
-                            ))
+                            #result
                         }
                     }
                 }
@@ -161,6 +236,7 @@ fn derive_deserialize_impl(
 
             let deserialize_variants = data.variants.iter().map(|v| {
                 let variant = &v.ident;
+                let variant_name = name_lit(variant);
                 match v.fields {
                     Fields::Named(ref fields) => {
                         let bindings = fields.named.iter().map(|f| {
@@ -168,13 +244,23 @@ fn derive_deserialize_impl(
                             quote_spanned! { name.span() => #name }
                         });
                         let fields = fields.named.iter().map(|f| {
-                            let name = &f.ident;
-                            quote! {
-                                #name: #name.deserialize(deserializer)?
-                            }
+                            let name = f.ident.as_ref().unwrap();
+                            let value = deserialize_field(
+                                name_lit(name),
+                                quote! { #name.deserialize(deserializer) },
+                            );
+                            quote! { #name: #value }
                         });
+                        let mut value = quote! { #name::#variant { #(#fields,)* } };
+                        if tracked {
+                            let result = track_result(
+                                quote! { Variant(#variant_name) },
+                                quote! { Ok(#value) },
+                            );
+                            value = quote! { #result? };
+                        }
                         quote_spanned! { variant.span() =>
-                            Self::#variant { #(#bindings,)* } => #name::#variant { #(#fields,)* }
+                            Self::#variant { #(#bindings,)* } => #value
                         }
                     }
                     Fields::Unnamed(ref fields) => {
@@ -184,12 +270,21 @@ fn derive_deserialize_impl(
                         });
                         let fields = fields.unnamed.iter().enumerate().map(|(i, f)| {
                             let binding = Ident::new(&format!("_{}", i), f.span());
-                            quote! {
-                                #binding.deserialize(deserializer)?
-                            }
+                            deserialize_field(
+                                LitStr::new(&i.to_string(), f.span()),
+                                quote! { #binding.deserialize(deserializer) },
+                            )
                         });
+                        let mut value = quote! { #name::#variant(#(#fields,)*) };
+                        if tracked {
+                            let result = track_result(
+                                quote! { Variant(#variant_name) },
+                                quote! { Ok(#value) },
+                            );
+                            value = quote! { #result? };
+                        }
                         quote_spanned! { variant.span() =>
-                            Self::#variant( #(#bindings,)* ) => #name::#variant(#(#fields,)*)
+                            Self::#variant( #(#bindings,)* ) => #value
                         }
                     }
                     Fields::Unit => {
@@ -198,13 +293,20 @@ fn derive_deserialize_impl(
                 }
             });
 
+            let mut result = quote! {
+                Ok(match self {
+                    #(#deserialize_variants,)*
+                })
+            };
+            if tracked {
+                result = track_result(quote! { Type(#type_name) }, result);
+            }
+
             quote! {
                 impl #impl_generics Deserialize<#name #ty_generics, __D> for Archived<#name #ty_generics> #deserialize_where {
                     #[inline]
                     fn deserialize(&self, deserializer: &mut __D) -> core::result::Result<#name #ty_generics, __D::Error> {
-                        Ok(match self {
-                            #(#deserialize_variants,)*
-                        })
+                        #result
                     }
                 }
             }
@@ -217,18 +319,272 @@ fn derive_deserialize_impl(
         }
     };
 
+    let deserializer_import = if tracked {
+        quote! { use #rkyv_path::de::Deserializer; }
+    } else {
+        quote! {}
+    };
+
     Ok(quote! {
         const _: () = {
-            use rkyv::{Archive, Archived, Deserialize, Fallible};
+            use #rkyv_path::{Archive, Archived, Deserialize, Fallible};
+            #deserializer_import
             #deserialize_impl
         };
     })
 }
 
+fn derive_deserialize_unsized_impl(
+    mut input: DeriveInput,
+    attributes: &Attributes,
+) -> Result<TokenStream, Error> {
+    let rkyv_path = attributes.rkyv_path();
+
+    if attributes.repr.c.is_none() {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "unsized structs must be repr(C) to derive Deserialize",
+        ));
+    }
+
+    let where_clause = input.generics.make_where_clause();
+    if let Some(ref bounds) = attributes.deserialize_bound {
+        let clauses =
+            bounds.parse_with(Punctuated::<WherePredicate, Token![,]>::parse_terminated)?;
+        for clause in clauses {
+            where_clause.predicates.push(clause);
+        }
+    }
+
+    let mut impl_input_params = Punctuated::default();
+    // Lifetime parameters have to come before type parameters
+    for param in input.generics.lifetimes() {
+        impl_input_params.push(GenericParam::Lifetime(param.clone()));
+    }
+    impl_input_params.push(parse_quote! { __D: Deserializer + ?Sized });
+    for param in input.generics.params.iter() {
+        if !matches!(param, GenericParam::Lifetime(_)) {
+            impl_input_params.push(param.clone());
+        }
+    }
+    let impl_input_generics = Generics {
+        lt_token: Some(Default::default()),
+        params: impl_input_params,
+        gt_token: Some(Default::default()),
+        where_clause: input.generics.where_clause.clone(),
+    };
+
+    let name = &input.ident;
+    let generics = &input.generics;
+    let (impl_generics, _, _) = impl_input_generics.split_for_impl();
+    let (_, ty_generics, where_clause) = input.generics.split_for_impl();
+    let where_clause = where_clause.unwrap();
+
+    let fields = match input.data {
+        Data::Struct(ref data) => &data.fields,
+        _ => unreachable!(),
+    };
+    let (tail_field, tail) = unsized_tail(&input).unwrap();
+    let head_fields = fields
+        .iter()
+        .take(fields.len() - 1)
+        .enumerate()
+        .map(|(i, f)| {
+            let member = f.ident.as_ref().map_or_else(
+                || Member::Unnamed(Index::from(i)),
+                |n| Member::Named(n.clone()),
+            );
+            let header_field = Ident::new(&format!("field_{}", i), f.span());
+            (f, member, header_field)
+        })
+        .collect::<Vec<_>>();
+    let tail_member = tail_field.ident.as_ref().map_or_else(
+        || Member::Unnamed(Index::from(fields.len() - 1)),
+        |n| Member::Named(n.clone()),
+    );
+
+    let mut deserialize_where = where_clause.clone();
+    for (field, _, _) in head_fields
+        .iter()
+        .filter(|(f, _, _)| !f.attrs.iter().any(|a| a.path.is_ident("omit_bounds")))
+    {
+        let ty = &field.ty;
+        deserialize_where
+            .predicates
+            .push(parse_quote! { #ty: Archive });
+        deserialize_where
+            .predicates
+            .push(parse_quote! { Archived<#ty>: Deserialize<#ty, __D> });
+    }
+    let omit_tail_bounds = tail_field
+        .attrs
+        .iter()
+        .any(|a| a.path.is_ident("omit_bounds"));
+    if let (UnsizedTail::Slice(ty), false) = (&tail, omit_tail_bounds) {
+        deserialize_where
+            .predicates
+            .push(parse_quote! { #ty: Archive });
+        deserialize_where
+            .predicates
+            .push(parse_quote! { Archived<#ty>: Deserialize<#ty, __D> });
+    }
+
+    let header_fields = head_fields.iter().map(|(f, _, header_field)| {
+        let ty = &f.ty;
+        quote_spanned! { f.span() => #header_field: #ty }
+    });
+    let tail_ty = match tail {
+        UnsizedTail::Slice(ty) => quote! { #ty },
+        UnsizedTail::Str => quote! { u8 },
+    };
+
+    let tracked = attributes.track_path.is_some();
+    let member_name = |member: &Member| match member {
+        Member::Named(name) => name_lit(name),
+        Member::Unnamed(index) => LitStr::new(&index.index.to_string(), index.span),
+    };
+
+    let deserialize_fields = head_fields.iter().map(|(f, member, header_field)| {
+        let ty = &f.ty;
+        let mut value = quote! { self.#member.deserialize(deserializer) };
+        if tracked {
+            let field_name = member_name(member);
+            value = track_path(&rkyv_path, quote! { Field(#field_name) }, value);
+        }
+        quote_spanned! { f.span() =>
+            result
+                .add(#rkyv_path::offset_of!(Header #ty_generics, #header_field))
+                .cast::<#ty>()
+                .write(#value?);
+            head_len += 1;
+        }
+    });
+    let drop_fields = head_fields
+        .iter()
+        .enumerate()
+        .map(|(i, (f, _, header_field))| {
+            let ty = &f.ty;
+            quote_spanned! { f.span() =>
+                if head_len > #i {
+                    core::ptr::drop_in_place(
+                        result
+                            .add(#rkyv_path::offset_of!(Header #ty_generics, #header_field))
+                            .cast::<#ty>(),
+                    );
+                }
+            }
+        });
+
+    let deserialize_tail = match tail {
+        UnsizedTail::Slice(_) if tracked => {
+            let value = track_path(
+                &rkyv_path,
+                quote! { Index(i) },
+                quote! { value.deserialize(deserializer) },
+            );
+            let tail_name = member_name(&tail_member);
+            let deserialize_tail = track_path(
+                &rkyv_path,
+                quote! { Field(#tail_name) },
+                quote! {
+                    (|| -> core::result::Result<(), __D::Error> {
+                        for (i, value) in self.#tail_member.iter().enumerate() {
+                            tail.add(i).write(#value?);
+                            tail_len += 1;
+                        }
+                        Ok(())
+                    })()
+                },
+            );
+            quote! { #deserialize_tail?; }
+        }
+        UnsizedTail::Slice(_) => quote! {
+            for value in self.#tail_member.iter() {
+                tail.add(tail_len).write(value.deserialize(deserializer)?);
+                tail_len += 1;
+            }
+        },
+        UnsizedTail::Str => quote! {
+            core::ptr::copy_nonoverlapping(self.#tail_member.as_ptr(), tail, len);
+            tail_len = len;
+        },
+    };
+
+    let mut deserialize_unsized = quote! {
+        let len = #rkyv_path::ptr_meta::metadata(self);
+        let tail_offset = #rkyv_path::offset_of!(Header #ty_generics, tail);
+        let layout = Layout::from_size_align_unchecked(
+            tail_offset + len * core::mem::size_of::<#tail_ty>(),
+            core::mem::align_of::<Header #ty_generics>(),
+        )
+        .pad_to_align();
+        let result = if layout.size() == 0 {
+            layout.align() as *mut u8
+        } else {
+            deserializer.alloc(layout)?
+        };
+        let tail = result.add(tail_offset).cast::<#tail_ty>();
+        // If a field fails to deserialize, the fields that were already written have to be
+        // dropped and the memory freed before returning the error.
+        #[allow(unused_mut)]
+        let mut head_len = 0usize;
+        #[allow(unused_mut)]
+        let mut tail_len = 0usize;
+        let written = (|| -> core::result::Result<(), __D::Error> {
+            #(#deserialize_fields)*
+            #deserialize_tail
+            Ok(())
+        })();
+        if let Err(e) = written {
+            #(#drop_fields)*
+            core::ptr::drop_in_place(core::ptr::slice_from_raw_parts_mut(tail, tail_len));
+            if layout.size() != 0 {
+                deserializer.dealloc(result, layout);
+            }
+            return Err(e);
+        }
+        Ok(result.cast())
+    };
+    if tracked {
+        let type_name = name_lit(name);
+        deserialize_unsized = track_path(
+            &rkyv_path,
+            quote! { Type(#type_name) },
+            quote! { (|| -> core::result::Result<*mut (), __D::Error> { #deserialize_unsized })() },
+        );
+    }
+
+    Ok(quote! {
+        const _: () = {
+            use core::alloc::Layout;
+            use #rkyv_path::{de::Deserializer, Archive, Archived, ArchiveUnsized, Deserialize, DeserializeUnsized};
+
+            #[repr(C)]
+            struct Header #generics #where_clause {
+                #(#header_fields,)*
+                tail: [#tail_ty; 0],
+            }
+
+            impl #impl_generics DeserializeUnsized<#name #ty_generics, __D> for <#name #ty_generics as ArchiveUnsized>::Archived #deserialize_where {
+                unsafe fn deserialize_unsized(&self, deserializer: &mut __D) -> core::result::Result<*mut (), __D::Error> {
+                    #deserialize_unsized
+                }
+
+                #[inline]
+                fn deserialize_metadata(&self, _: &mut __D) -> core::result::Result<usize, __D::Error> {
+                    Ok(#rkyv_path::ptr_meta::metadata(self))
+                }
+            }
+        };
+    })
+}
+
 fn derive_deserialize_copy_impl(
     mut input: DeriveInput,
     attributes: &Attributes,
 ) -> Result<TokenStream, Error> {
+    let rkyv_path = attributes.rkyv_path();
+
     if let Some(ref archived) = attributes.archived {
         return Err(Error::new_spanned(
             archived,
@@ -241,12 +597,25 @@ fn derive_deserialize_copy_impl(
         ));
     };
 
+    if let Some(ref track_path) = attributes.track_path {
+        return Err(Error::new_spanned(
+            track_path,
+            "archive copy types cannot fail to deserialize and do not track paths",
+        ));
+    }
+
     input.generics.make_where_clause();
 
     let mut impl_input_params = Punctuated::default();
+    // Lifetime parameters have to come before type parameters
+    for param in input.generics.lifetimes() {
+        impl_input_params.push(GenericParam::Lifetime(param.clone()));
+    }
     impl_input_params.push(parse_quote! { __D: Fallible + ?Sized });
     for param in input.generics.params.iter() {
-        impl_input_params.push(param.clone());
+        if !matches!(param, GenericParam::Lifetime(_)) {
+            impl_input_params.push(param.clone());
+        }
     }
     let impl_input_generics = Generics {
         lt_token: Some(Default::default()),
@@ -351,7 +720,7 @@ fn derive_deserialize_copy_impl(
 
     Ok(quote! {
         const _: () = {
-            use rkyv::{ArchiveCopy, Archived, Deserialize, Fallible};
+            use #rkyv_path::{ArchiveCopy, Archived, Deserialize, Fallible};
             #deserialize_impl
         };
     })
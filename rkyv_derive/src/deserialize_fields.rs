@@ -0,0 +1,79 @@
+use proc_macro2::TokenStream;
+use quote::{quote, quote_spanned};
+use syn::{
+    parse_quote, spanned::Spanned, Data, DeriveInput, Error, Ident, Index, Path, WhereClause,
+};
+
+/// Generates a `deserialize_<field>` method on the archived type for each field of the struct,
+/// which deserializes just that field.
+pub fn derive_deserialize_fields_impl(
+    input: &DeriveInput,
+    archived: &Ident,
+    rkyv_path: &Path,
+) -> Result<TokenStream, Error> {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let fields = match input.data {
+        Data::Struct(ref data) => &data.fields,
+        _ => {
+            return Err(Error::new_spanned(
+                &input.ident,
+                "deserialize_fields is only supported for structs",
+            ))
+        }
+    };
+
+    // The archived struct requires its field types to be archivable, so the impl has to as well
+    let mut impl_where = where_clause.cloned().unwrap_or_else(|| WhereClause {
+        where_token: Default::default(),
+        predicates: Default::default(),
+    });
+    for field in fields
+        .iter()
+        .filter(|f| !f.attrs.iter().any(|a| a.path.is_ident("omit_bounds")))
+    {
+        let ty = &field.ty;
+        impl_where
+            .predicates
+            .push(parse_quote! { #ty: #rkyv_path::Archive });
+    }
+
+    let methods = fields.iter().enumerate().map(|(i, f)| {
+        let ty = &f.ty;
+        let vis = &f.vis;
+        let (member, field_name) = match f.ident {
+            Some(ref ident) => (quote! { #ident }, ident.to_string()),
+            None => {
+                let index = Index::from(i);
+                (quote! { #index }, i.to_string())
+            }
+        };
+        let method = Ident::new(&format!("deserialize_{}", field_name), f.span());
+        let doc = format!(
+            "Deserializes only the `{}` field of the archived `{}`.",
+            field_name, name
+        );
+        quote_spanned! { f.span() =>
+            #[doc = #doc]
+            #[allow(dead_code)]
+            #[inline]
+            #vis fn #method<__D: #rkyv_path::Fallible + ?Sized>(
+                &self,
+                deserializer: &mut __D,
+            ) -> ::core::result::Result<#ty, __D::Error>
+            where
+                #ty: #rkyv_path::Archive,
+                #rkyv_path::Archived<#ty>: #rkyv_path::Deserialize<#ty, __D>,
+            {
+                #rkyv_path::Deserialize::<#ty, __D>::deserialize(&self.#member, deserializer)
+            }
+        }
+    });
+
+    Ok(quote! {
+        impl #impl_generics #archived #ty_generics #impl_where {
+            #(#methods)*
+        }
+    })
+}
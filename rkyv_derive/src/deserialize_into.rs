@@ -0,0 +1,205 @@
+use crate::{
+    attributes::{parse_attributes, Attributes},
+    tail::unsized_tail,
+};
+use proc_macro2::TokenStream;
+use quote::{quote, quote_spanned};
+use syn::{
+    parse_quote, punctuated::Punctuated, spanned::Spanned, Data, DeriveInput, Error, Field, Fields,
+    GenericParam, Generics, Ident, Index, Token, WhereClause, WherePredicate,
+};
+
+pub fn derive(input: DeriveInput) -> Result<TokenStream, Error> {
+    let attributes = parse_attributes(&input)?;
+
+    if unsized_tail(&input).is_some() {
+        Err(Error::new_spanned(
+            &input.ident,
+            "DeserializeInto cannot be derived for unsized structs",
+        ))
+    } else {
+        derive_deserialize_into_impl(input, &attributes)
+    }
+}
+
+fn push_field_bounds<'a>(where_clause: &mut WhereClause, fields: impl Iterator<Item = &'a Field>) {
+    for field in fields.filter(|f| !f.attrs.iter().any(|a| a.path.is_ident("omit_bounds"))) {
+        let ty = &field.ty;
+        where_clause.predicates.push(parse_quote! { #ty: Archive });
+        where_clause
+            .predicates
+            .push(parse_quote! { Archived<#ty>: DeserializeInto<#ty, __D> });
+    }
+}
+
+fn derive_deserialize_into_impl(
+    mut input: DeriveInput,
+    attributes: &Attributes,
+) -> Result<TokenStream, Error> {
+    let rkyv_path = attributes.rkyv_path();
+
+    let where_clause = input.generics.make_where_clause();
+    if let Some(ref bounds) = attributes.deserialize_bound {
+        let clauses =
+            bounds.parse_with(Punctuated::<WherePredicate, Token![,]>::parse_terminated)?;
+        for clause in clauses {
+            where_clause.predicates.push(clause);
+        }
+    }
+
+    let mut impl_input_params = Punctuated::default();
+    // Lifetime parameters have to come before type parameters
+    for param in input.generics.lifetimes() {
+        impl_input_params.push(GenericParam::Lifetime(param.clone()));
+    }
+    impl_input_params.push(parse_quote! { __D: Fallible + ?Sized });
+    for param in input.generics.params.iter() {
+        if !matches!(param, GenericParam::Lifetime(_)) {
+            impl_input_params.push(param.clone());
+        }
+    }
+    let impl_input_generics = Generics {
+        lt_token: Some(Default::default()),
+        params: impl_input_params,
+        gt_token: Some(Default::default()),
+        where_clause: input.generics.where_clause.clone(),
+    };
+
+    let name = &input.ident;
+    let (impl_generics, _, _) = impl_input_generics.split_for_impl();
+    let (_, ty_generics, where_clause) = input.generics.split_for_impl();
+    let mut deserialize_where = where_clause.unwrap().clone();
+
+    let deserialize_into_body = if attributes.copy.is_some() {
+        // Copy types are archived as themselves, so there's nothing to reuse
+        deserialize_where.predicates.push(
+            parse_quote! { Archived<#name #ty_generics>: Deserialize<#name #ty_generics, __D> },
+        );
+        quote! {
+            *out = Deserialize::deserialize(self, deserializer)?;
+        }
+    } else {
+        match input.data {
+            Data::Struct(ref data) => match data.fields {
+                Fields::Named(ref fields) => {
+                    push_field_bounds(&mut deserialize_where, fields.named.iter());
+
+                    let deserialize_fields = fields.named.iter().map(|f| {
+                        let name = &f.ident;
+                        quote! { self.#name.deserialize_into(&mut out.#name, deserializer)?; }
+                    });
+
+                    quote! { #(#deserialize_fields)* }
+                }
+                Fields::Unnamed(ref fields) => {
+                    push_field_bounds(&mut deserialize_where, fields.unnamed.iter());
+
+                    let deserialize_fields = fields.unnamed.iter().enumerate().map(|(i, _)| {
+                        let index = Index::from(i);
+                        quote! { self.#index.deserialize_into(&mut out.#index, deserializer)?; }
+                    });
+
+                    quote! { #(#deserialize_fields)* }
+                }
+                Fields::Unit => quote! { let _ = (out, deserializer); },
+            },
+            Data::Enum(ref data) => {
+                for variant in data.variants.iter() {
+                    match variant.fields {
+                        Fields::Named(ref fields) => {
+                            push_field_bounds(&mut deserialize_where, fields.named.iter())
+                        }
+                        Fields::Unnamed(ref fields) => {
+                            push_field_bounds(&mut deserialize_where, fields.unnamed.iter())
+                        }
+                        Fields::Unit => (),
+                    }
+                }
+                // Values that change variants have to be deserialized from scratch
+                deserialize_where.predicates.push(
+                    parse_quote! { Archived<#name #ty_generics>: Deserialize<#name #ty_generics, __D> },
+                );
+
+                let deserialize_variants = data.variants.iter().map(|v| {
+                    let variant = &v.ident;
+                    match v.fields {
+                        Fields::Named(ref fields) => {
+                            let bindings = fields.named.iter().map(|f| {
+                                let name = &f.ident;
+                                quote_spanned! { name.span() => #name }
+                            });
+                            let out_bindings = fields.named.iter().map(|f| {
+                                let name = &f.ident;
+                                let out_name =
+                                    Ident::new(&format!("__out_{}", name.as_ref().unwrap()), name.span());
+                                quote_spanned! { name.span() => #name: #out_name }
+                            });
+                            let fields = fields.named.iter().map(|f| {
+                                let name = &f.ident;
+                                let out_name =
+                                    Ident::new(&format!("__out_{}", name.as_ref().unwrap()), name.span());
+                                quote! { #name.deserialize_into(#out_name, deserializer)?; }
+                            });
+                            quote_spanned! { variant.span() =>
+                                (Self::#variant { #(#bindings,)* }, #name::#variant { #(#out_bindings,)* }) => {
+                                    #(#fields)*
+                                }
+                            }
+                        }
+                        Fields::Unnamed(ref fields) => {
+                            let bindings = fields.unnamed.iter().enumerate().map(|(i, f)| {
+                                let name = Ident::new(&format!("_{}", i), f.span());
+                                quote_spanned! { name.span() => #name }
+                            });
+                            let out_bindings = fields.unnamed.iter().enumerate().map(|(i, f)| {
+                                let name = Ident::new(&format!("__out_{}", i), f.span());
+                                quote_spanned! { name.span() => #name }
+                            });
+                            let fields = fields.unnamed.iter().enumerate().map(|(i, f)| {
+                                let binding = Ident::new(&format!("_{}", i), f.span());
+                                let out_binding = Ident::new(&format!("__out_{}", i), f.span());
+                                quote! { #binding.deserialize_into(#out_binding, deserializer)?; }
+                            });
+                            quote_spanned! { variant.span() =>
+                                (Self::#variant( #(#bindings,)* ), #name::#variant( #(#out_bindings,)* )) => {
+                                    #(#fields)*
+                                }
+                            }
+                        }
+                        Fields::Unit => {
+                            quote_spanned! { name.span() => (Self::#variant, #name::#variant) => () }
+                        }
+                    }
+                });
+
+                quote! {
+                    #[allow(unreachable_patterns)]
+                    match (self, out) {
+                        #(#deserialize_variants,)*
+                        (archived, out) => *out = Deserialize::deserialize(archived, deserializer)?,
+                    }
+                }
+            }
+            Data::Union(_) => {
+                return Err(Error::new_spanned(
+                    input,
+                    "DeserializeInto cannot be derived for unions",
+                ))
+            }
+        }
+    };
+
+    Ok(quote! {
+        const _: () = {
+            use #rkyv_path::{Archive, Archived, Deserialize, DeserializeInto, Fallible};
+
+            impl #impl_generics DeserializeInto<#name #ty_generics, __D> for Archived<#name #ty_generics> #deserialize_where {
+                #[inline]
+                fn deserialize_into(&self, out: &mut #name #ty_generics, deserializer: &mut __D) -> core::result::Result<(), __D::Error> {
+                    #deserialize_into_body
+                    Ok(())
+                }
+            }
+        };
+    })
+}
@@ -2,8 +2,15 @@
 
 mod archive;
 mod attributes;
+mod check_bytes;
+mod compare;
+mod debug;
 mod deserialize;
+mod deserialize_fields;
+mod deserialize_into;
+mod serde;
 mod serialize;
+mod tail;
 
 extern crate proc_macro;
 
@@ -17,15 +24,60 @@ use syn::{parse_macro_input, DeriveInput};
 ///   directly archived (i.e. plain data).
 /// - `compare(...)`: Implements common comparison operators between the original and archived
 ///   types. Supported comparisons are `PartialEq` and `PartialOrd` (i.e.
-///   `#[archive(compare(PartialEq, PartialOrd))]`).
+///   `#[archive(compare(PartialEq, PartialOrd))]`). `Eq`, `Ord` and `Hash` can also be listed to
+///   implement those traits for the archived type, which allows comparing archived values from
+///   different archives. The archived `Hash` implementation hashes the same values as
+///   `#[derive(Hash)]` on the original type, so an archived value and its unarchived counterpart
+///   hash the same way. Both types then also borrow as `dyn rkyv::compare::ArchiveKey`, which
+///   looks up entries in hash maps keyed by either type.
 /// - `derive(...)`: Adds a `#[derive(...)]` attribute to the archived type.
+/// - `debug`: Implements `Debug` for the archived type. The archived type is formatted the same way
+///   that `#[derive(Debug)]` would format the original type, and only requires that the archived
+///   field types implement `Debug`.
+/// - `deserialize_fields`: Generates a `deserialize_<field>` method on the archived struct for each
+///   field (`deserialize_0`, `deserialize_1`, ... for tuple structs). Each method deserializes only
+///   that field, which avoids deserializing the whole struct when only part of it is needed. The
+///   methods have the same visibility as their fields.
 /// - `name`, `name = "..."`: Exposes the archived type with the given name. If used without a name
 ///   assignment, uses the name `"Archived" + name`.
+/// - `serde`: Implements `serde::Serialize` for the archived type. The archived type is serialized
+///   the same way that `#[derive(serde::Serialize)]` would serialize the original type, and only
+///   requires that the archived field types implement `serde::Serialize`. This requires the `serde`
+///   feature of `rkyv`. Serde attributes like `#[serde(rename = "...")]` are not supported.
 /// - `strict`: Marks structs at `#[repr(C)]` for strictly guaranteed stability and compatibility.
 ///   This is equivalent to enabling the `strict` feature for only this struct.
-/// - `bound(...)`: Adds additional bounds to the `Serialize` and `Deserialize` implementations.
+/// - `bound(...)`: Adds additional bounds to the `Serialize`, `Deserialize` and `CheckBytes`
+///   implementations (i.e. `bound(serialize = "...", deserialize = "...", check_bytes = "...")`).
 ///   This can be especially useful when dealing with recursive structures, where bounds may need to
-///   be omitted to prevent recursive type definitions.
+///   be omitted to prevent recursive type definitions. The validation context is named `__C` in
+///   `check_bytes` bounds.
+/// - `no_check`: Opts out of the `CheckBytes` implementation generated for the archived type when
+///   rkyv's `validation` feature is enabled. Use this when implementing `CheckBytes` manually.
+/// - `track_path`: Reports the path to each field to the deserializer while deserializing and to
+///   the validation context while checking, so adapters like `PathDeserializer` and functions like
+///   `check_archived_root_with_path` can describe where errors occur (e.g.
+///   `Player.inventory[12].name`). The `Deserialize` implementation then requires a `Deserializer`
+///   instead of any `Fallible` type, and the generated `CheckBytes` implementation requires an
+///   `ArchiveBoundsContext`.
+/// - `crate = "..."`: Chooses the path to the `rkyv` crate used by the generated code. This is
+///   useful when `rkyv` is only available through a re-export (e.g.
+///   `#[archive(crate = "my_framework::rkyv")]`).
+///
+/// Structs whose last field is a slice (`[T]`) or string slice (`str`) are archived as unsized
+/// types. Instead of `Archive`, the derive implements `Pointee` for the struct and `ArchiveUnsized`
+/// for it, and generates an archived struct that ends in the archived slice. Such structs can be
+/// archived inline behind a `Box` or any other pointer that supports unsized types. Deriving
+/// `Deserialize` for them additionally requires that they are `#[repr(C)]`.
+///
+/// When rkyv's `validation` feature is enabled, the archived type also implements `CheckBytes` for
+/// any validation context that all of its archived fields can be checked with. Archived enums
+/// report errors with `rkyv::validation::EnumCheckError`, which names the enum and the variant or
+/// tag that failed to validate. No implementation is generated if the archived type already lists
+/// `CheckBytes` in `derive(...)` or for `copy` types (which are archived as themselves). Archived
+/// unsized structs can also be checked behind a `Box` or other pointer. If a field has
+/// `#[omit_bounds]`, the implementation instead requires that the validation context implements
+/// `ArchiveBoundsContext` and `ArchiveMemoryContext` so that recursive types through boxes can be
+/// checked without extra bounds.
 ///
 /// This derive macro automatically adds a type bound `field: Archive` for each field type. This can
 /// cause an overflow while evaluating trait bounds if the structure eventually references its own
@@ -64,3 +116,20 @@ pub fn derive_deserialize(input: proc_macro::TokenStream) -> proc_macro::TokenSt
         Err(e) => e.to_compile_error().into(),
     }
 }
+
+/// Derives `DeserializeInto` for the labeled type.
+///
+/// Each field of a struct is deserialized into the corresponding field of the existing value, so
+/// containers like `Vec`, `String` and `HashMap` keep their allocations. Enums deserialize into
+/// their fields when the archived and existing values are the same variant, and are deserialized
+/// from scratch otherwise. Because of this, the type must also implement `Deserialize`.
+///
+/// This macro also supports the `#[archive]` and `#[omit_bounds]` attributes. The `deserialize`
+/// bounds from `bound(...)` are added to the implementation. See [`Archive`] for more information.
+#[proc_macro_derive(DeserializeInto, attributes(archive, omit_bounds))]
+pub fn derive_deserialize_into(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    match deserialize_into::derive(parse_macro_input!(input as DeriveInput)) {
+        Ok(result) => result.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
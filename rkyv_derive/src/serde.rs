@@ -0,0 +1,177 @@
+use crate::tail::{unsized_tail, UnsizedTail};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{
+    parse_quote, spanned::Spanned, Data, DeriveInput, Field, Fields, Ident, Index, Path,
+    WhereClause,
+};
+
+/// Serializes the given fields with the serde serializer method matching their shape, the same way
+/// that `#[derive(serde::Serialize)]` would.
+fn serialize_fields(
+    fields: &Fields,
+    serde: &TokenStream,
+    bindings: &[TokenStream],
+    name: &str,
+    variant: Option<(u32, &str)>,
+) -> TokenStream {
+    let len = fields.len();
+    match (fields, variant) {
+        (Fields::Named(ref fields), _) => {
+            let field_names = fields
+                .named
+                .iter()
+                .map(|f| f.ident.as_ref().unwrap().to_string());
+            let (trait_, begin) = match variant {
+                Some((index, variant)) => (
+                    quote! { SerializeStructVariant },
+                    quote! { serializer.serialize_struct_variant(#name, #index, #variant, #len) },
+                ),
+                None => (
+                    quote! { SerializeStruct },
+                    quote! { serializer.serialize_struct(#name, #len) },
+                ),
+            };
+            quote! {
+                use #serde::ser::#trait_;
+                let mut state = #begin?;
+                #(state.serialize_field(#field_names, #bindings)?;)*
+                state.end()
+            }
+        }
+        (Fields::Unnamed(_), Some((index, variant))) if len == 1 => {
+            let binding = &bindings[0];
+            quote! { serializer.serialize_newtype_variant(#name, #index, #variant, #binding) }
+        }
+        (Fields::Unnamed(_), None) if len == 1 => {
+            let binding = &bindings[0];
+            quote! { serializer.serialize_newtype_struct(#name, #binding) }
+        }
+        (Fields::Unnamed(_), _) => {
+            let (trait_, begin) = match variant {
+                Some((index, variant)) => (
+                    quote! { SerializeTupleVariant },
+                    quote! { serializer.serialize_tuple_variant(#name, #index, #variant, #len) },
+                ),
+                None => (
+                    quote! { SerializeTupleStruct },
+                    quote! { serializer.serialize_tuple_struct(#name, #len) },
+                ),
+            };
+            quote! {
+                use #serde::ser::#trait_;
+                let mut state = #begin?;
+                #(state.serialize_field(#bindings)?;)*
+                state.end()
+            }
+        }
+        (Fields::Unit, Some((index, variant))) => {
+            quote! { serializer.serialize_unit_variant(#name, #index, #variant) }
+        }
+        (Fields::Unit, None) => quote! { serializer.serialize_unit_struct(#name) },
+    }
+}
+
+/// Generates a `serde::Serialize` implementation for the archived type that serializes it the same
+/// way that the derived `serde::Serialize` implementation for the original type would.
+pub fn derive_serde_impl(input: &DeriveInput, archived: &Ident, rkyv_path: &Path) -> TokenStream {
+    let name = &input.ident;
+    let name_str = name.to_string();
+    let serde = quote! { #rkyv_path::serde };
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let mut serde_where = where_clause.cloned().unwrap_or_else(|| WhereClause {
+        where_token: Default::default(),
+        predicates: Default::default(),
+    });
+    let tail = unsized_tail(input);
+    let mut add_bounds = |field: &Field| {
+        if field.attrs.iter().any(|a| a.path.is_ident("omit_bounds")) {
+            return;
+        }
+        let ty = match tail {
+            Some((tail_field, ref tail)) if core::ptr::eq(field, tail_field) => match tail {
+                UnsizedTail::Slice(ty) => *ty,
+                UnsizedTail::Str => return,
+            },
+            _ => &field.ty,
+        };
+        serde_where
+            .predicates
+            .push(parse_quote! { #ty: #rkyv_path::Archive });
+        serde_where
+            .predicates
+            .push(parse_quote! { #rkyv_path::Archived<#ty>: #serde::Serialize });
+    };
+
+    let body = match input.data {
+        Data::Struct(ref data) => {
+            data.fields.iter().for_each(&mut add_bounds);
+
+            let bindings = data
+                .fields
+                .iter()
+                .enumerate()
+                .map(|(i, f)| {
+                    if let Some(ref ident) = f.ident {
+                        quote! { &self.#ident }
+                    } else {
+                        let index = Index::from(i);
+                        quote! { &self.#index }
+                    }
+                })
+                .collect::<Vec<_>>();
+            serialize_fields(&data.fields, &serde, &bindings, &name_str, None)
+        }
+        Data::Enum(ref data) => {
+            let variant_impls = data.variants.iter().enumerate().map(|(index, v)| {
+                v.fields.iter().for_each(&mut add_bounds);
+
+                let variant = &v.ident;
+                let bindings = v
+                    .fields
+                    .iter()
+                    .enumerate()
+                    .map(|(i, f)| Ident::new(&format!("field_{}", i), f.span()))
+                    .collect::<Vec<_>>();
+                let pattern = match v.fields {
+                    Fields::Named(ref fields) => {
+                        let field_names = fields.named.iter().map(|f| &f.ident);
+                        quote! { #archived::#variant { #(#field_names: #bindings,)* } }
+                    }
+                    Fields::Unnamed(_) => quote! { #archived::#variant(#(#bindings,)*) },
+                    Fields::Unit => quote! { #archived::#variant },
+                };
+                let bindings = bindings.iter().map(|b| quote! { #b }).collect::<Vec<_>>();
+                let body = serialize_fields(
+                    &v.fields,
+                    &serde,
+                    &bindings,
+                    &name_str,
+                    Some((index as u32, &variant.to_string())),
+                );
+                quote! { #pattern => { #body } }
+            });
+            // Collect eagerly so that all of the bounds are added before the where clause is used
+            let variant_impls = variant_impls.collect::<Vec<_>>();
+
+            quote! {
+                match self {
+                    #(#variant_impls,)*
+                }
+            }
+        }
+        Data::Union(_) => unreachable!(),
+    };
+
+    quote! {
+        impl #impl_generics #serde::Serialize for #archived #ty_generics #serde_where {
+            fn serialize<__S: #serde::Serializer>(
+                &self,
+                serializer: __S,
+            ) -> ::core::result::Result<__S::Ok, __S::Error> {
+                #body
+            }
+        }
+    }
+}
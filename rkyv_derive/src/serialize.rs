@@ -1,15 +1,20 @@
-use crate::attributes::{parse_attributes, Attributes};
+use crate::{
+    attributes::{parse_attributes, Attributes},
+    tail::{unsized_tail, UnsizedTail},
+};
 use proc_macro2::TokenStream;
 use quote::{quote, quote_spanned};
 use syn::{
     parse_quote, punctuated::Punctuated, spanned::Spanned, Data, DeriveInput, Error, Fields,
-    Generics, Ident, Index, Token, WherePredicate,
+    GenericParam, Generics, Ident, Index, Member, Token, WherePredicate,
 };
 
 pub fn derive(input: DeriveInput) -> Result<TokenStream, Error> {
     let attributes = parse_attributes(&input)?;
 
-    if attributes.copy.is_some() {
+    if unsized_tail(&input).is_some() {
+        derive_serialize_unsized_impl(input, &attributes)
+    } else if attributes.copy.is_some() {
         derive_serialize_copy_impl(input, &attributes)
     } else {
         derive_serialize_impl(input, &attributes)
@@ -20,6 +25,8 @@ fn derive_serialize_impl(
     mut input: DeriveInput,
     attributes: &Attributes,
 ) -> Result<TokenStream, Error> {
+    let rkyv_path = attributes.rkyv_path();
+
     let where_clause = input.generics.make_where_clause();
     if let Some(ref bounds) = attributes.serialize_bound {
         let clauses =
@@ -30,9 +37,15 @@ fn derive_serialize_impl(
     }
 
     let mut impl_input_params = Punctuated::default();
+    // Lifetime parameters have to come before type parameters
+    for param in input.generics.lifetimes() {
+        impl_input_params.push(GenericParam::Lifetime(param.clone()));
+    }
     impl_input_params.push(parse_quote! { __S: Fallible + ?Sized });
     for param in input.generics.params.iter() {
-        impl_input_params.push(param.clone());
+        if !matches!(param, GenericParam::Lifetime(_)) {
+            impl_input_params.push(param.clone());
+        }
     }
     let impl_input_generics = Generics {
         lt_token: Some(Default::default()),
@@ -216,16 +229,198 @@ fn derive_serialize_impl(
 
     Ok(quote! {
         const _: () = {
-            use rkyv::{Archive, Fallible, Serialize};
+            use #rkyv_path::{Archive, Fallible, Serialize};
             #serialize_impl
         };
     })
 }
 
+fn derive_serialize_unsized_impl(
+    mut input: DeriveInput,
+    attributes: &Attributes,
+) -> Result<TokenStream, Error> {
+    let rkyv_path = attributes.rkyv_path();
+
+    let where_clause = input.generics.make_where_clause();
+    if let Some(ref bounds) = attributes.serialize_bound {
+        let clauses =
+            bounds.parse_with(Punctuated::<WherePredicate, Token![,]>::parse_terminated)?;
+        for clause in clauses {
+            where_clause.predicates.push(clause);
+        }
+    }
+
+    let mut impl_input_params = Punctuated::default();
+    // Lifetime parameters have to come before type parameters
+    for param in input.generics.lifetimes() {
+        impl_input_params.push(GenericParam::Lifetime(param.clone()));
+    }
+    impl_input_params.push(parse_quote! { __S: Serializer + ?Sized });
+    for param in input.generics.params.iter() {
+        if !matches!(param, GenericParam::Lifetime(_)) {
+            impl_input_params.push(param.clone());
+        }
+    }
+    let impl_input_generics = Generics {
+        lt_token: Some(Default::default()),
+        params: impl_input_params,
+        gt_token: Some(Default::default()),
+        where_clause: input.generics.where_clause.clone(),
+    };
+
+    let name = &input.ident;
+    let generics = &input.generics;
+    let (impl_generics, _, _) = impl_input_generics.split_for_impl();
+    let (_, ty_generics, where_clause) = input.generics.split_for_impl();
+    let where_clause = where_clause.unwrap();
+
+    let fields = match input.data {
+        Data::Struct(ref data) => &data.fields,
+        _ => unreachable!(),
+    };
+    let (tail_field, tail) = unsized_tail(&input).unwrap();
+    let head_fields = fields
+        .iter()
+        .take(fields.len() - 1)
+        .enumerate()
+        .map(|(i, f)| {
+            let member = f.ident.as_ref().map_or_else(
+                || Member::Unnamed(Index::from(i)),
+                |n| Member::Named(n.clone()),
+            );
+            let header_field = Ident::new(&format!("field_{}", i), f.span());
+            let resolver = Ident::new(&format!("resolver_{}", i), f.span());
+            (f, member, header_field, resolver)
+        })
+        .collect::<Vec<_>>();
+    let tail_member = tail_field.ident.as_ref().map_or_else(
+        || Member::Unnamed(Index::from(fields.len() - 1)),
+        |n| Member::Named(n.clone()),
+    );
+
+    let mut header_where = where_clause.clone();
+    let mut serialize_where = where_clause.clone();
+    for (field, _, _, _) in head_fields
+        .iter()
+        .filter(|(f, _, _, _)| !f.attrs.iter().any(|a| a.path.is_ident("omit_bounds")))
+    {
+        let ty = &field.ty;
+        header_where.predicates.push(parse_quote! { #ty: Archive });
+        serialize_where
+            .predicates
+            .push(parse_quote! { #ty: Serialize<__S> });
+    }
+    let omit_tail_bounds = tail_field
+        .attrs
+        .iter()
+        .any(|a| a.path.is_ident("omit_bounds"));
+    if let (UnsizedTail::Slice(ty), false) = (&tail, omit_tail_bounds) {
+        header_where.predicates.push(parse_quote! { #ty: Archive });
+        serialize_where
+            .predicates
+            .push(parse_quote! { #ty: Serialize<__S> });
+    }
+
+    let header_fields = head_fields.iter().map(|(f, _, header_field, _)| {
+        let ty = &f.ty;
+        quote_spanned! { f.span() => #header_field: Archived<#ty> }
+    });
+    let header_tail = match tail {
+        UnsizedTail::Slice(ty) => quote! { [Archived<#ty>; 0] },
+        UnsizedTail::Str => quote! { [u8; 0] },
+    };
+
+    let serialize_fields = head_fields.iter().map(|(f, member, _, resolver)| {
+        quote_spanned! { f.span() =>
+            let #resolver = Serialize::<__S>::serialize(&self.#member, serializer)?;
+        }
+    });
+    let resolve_fields = head_fields
+        .iter()
+        .map(|(f, member, header_field, resolver)| {
+            quote_spanned! { f.span() =>
+                self.#member.resolve(
+                    pos + #rkyv_path::offset_of!(ArchivedHeader #ty_generics, #header_field),
+                    #resolver,
+                    #rkyv_path::project_struct!(out: ArchivedHeader #ty_generics => #header_field),
+                );
+            }
+        });
+
+    let write_value = |resolve_tail: TokenStream| {
+        quote! {
+            let pos = serializer.align_for::<ArchivedHeader #ty_generics>()?;
+            let mut header = MaybeUninit::<ArchivedHeader #ty_generics>::uninit();
+            unsafe {
+                header.as_mut_ptr().write_bytes(0, 1);
+                let out = &mut header;
+                #(#resolve_fields)*
+                let header_len = #rkyv_path::offset_of!(ArchivedHeader #ty_generics, tail);
+                serializer.write(core::slice::from_raw_parts(header.as_ptr().cast::<u8>(), header_len))?;
+                #resolve_tail
+            }
+            serializer.align_for::<ArchivedHeader #ty_generics>()?;
+
+            Ok(pos)
+        }
+    };
+    let serialize_value = match tail {
+        UnsizedTail::Slice(_) => {
+            let write_value = write_value(quote! {
+                for (value, resolver) in self.#tail_member.iter().zip(tail_resolvers.drain()) {
+                    serializer.resolve_aligned(value, resolver)?;
+                }
+            });
+            quote! {
+                let tail = &self.#tail_member;
+                unsafe {
+                    #rkyv_path::ScratchVec::with(serializer, tail.len(), |serializer, tail_resolvers| {
+                        for value in tail.iter() {
+                            tail_resolvers.push(Serialize::<__S>::serialize(value, serializer)?);
+                        }
+                        #write_value
+                    })
+                }
+            }
+        }
+        UnsizedTail::Str => write_value(quote! {
+            serializer.write(self.#tail_member.as_bytes())?;
+        }),
+    };
+
+    Ok(quote! {
+        const _: () = {
+            use core::mem::MaybeUninit;
+            use #rkyv_path::{ser::Serializer, Archive, Archived, Serialize, SerializeUnsized};
+
+            #[repr(C)]
+            struct ArchivedHeader #generics #header_where {
+                #(#header_fields,)*
+                tail: #header_tail,
+            }
+
+            impl #impl_generics SerializeUnsized<__S> for #name #ty_generics #serialize_where {
+                #[allow(clippy::let_unit_value, clippy::unit_arg)]
+                fn serialize_unsized(&self, serializer: &mut __S) -> core::result::Result<usize, __S::Error> {
+                    #(#serialize_fields)*
+                    #serialize_value
+                }
+
+                #[inline]
+                fn serialize_metadata(&self, _: &mut __S) -> core::result::Result<Self::MetadataResolver, __S::Error> {
+                    Ok(())
+                }
+            }
+        };
+    })
+}
+
 fn derive_serialize_copy_impl(
     mut input: DeriveInput,
     attributes: &Attributes,
 ) -> Result<TokenStream, Error> {
+    let rkyv_path = attributes.rkyv_path();
+
     if let Some(ref archived) = attributes.archived {
         return Err(Error::new_spanned(
             archived,
@@ -241,9 +436,15 @@ fn derive_serialize_copy_impl(
     input.generics.make_where_clause();
 
     let mut impl_input_params = Punctuated::default();
+    // Lifetime parameters have to come before type parameters
+    for param in input.generics.lifetimes() {
+        impl_input_params.push(GenericParam::Lifetime(param.clone()));
+    }
     impl_input_params.push(parse_quote! { __S: Fallible + ?Sized });
     for param in input.generics.params.iter() {
-        impl_input_params.push(param.clone());
+        if !matches!(param, GenericParam::Lifetime(_)) {
+            impl_input_params.push(param.clone());
+        }
     }
     let impl_input_generics = Generics {
         lt_token: Some(Default::default()),
@@ -349,7 +550,7 @@ fn derive_serialize_copy_impl(
 
     Ok(quote! {
         const _: () = {
-            use rkyv::{Archive, ArchiveCopy, Fallible, Serialize};
+            use #rkyv_path::{Archive, ArchiveCopy, Fallible, Serialize};
 
             #serialize_copy_impl
         };
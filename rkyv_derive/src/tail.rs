@@ -0,0 +1,27 @@
+use syn::{Data, DeriveInput, Field, Type};
+
+/// The kind of unsized field at the end of a dynamically-sized struct.
+pub enum UnsizedTail<'a> {
+    /// A trailing slice `[T]` with the given element type
+    Slice(&'a Type),
+    /// A trailing string slice `str`
+    Str,
+}
+
+/// Checks whether the input is a struct with a trailing unsized field.
+///
+/// Returns the trailing field and its kind if it is, and `None` otherwise.
+pub fn unsized_tail(input: &DeriveInput) -> Option<(&Field, UnsizedTail<'_>)> {
+    if let Data::Struct(ref data) = input.data {
+        let field = data.fields.iter().last()?;
+        match field.ty {
+            Type::Slice(ref slice) => Some((field, UnsizedTail::Slice(&slice.elem))),
+            Type::Path(ref path) if path.qself.is_none() && path.path.is_ident("str") => {
+                Some((field, UnsizedTail::Str))
+            }
+            _ => None,
+        }
+    } else {
+        None
+    }
+}
@@ -27,17 +27,32 @@ use core::{
     hash::{Hash, Hasher},
     marker::PhantomData,
     mem::MaybeUninit,
+    ptr::NonNull,
 };
 use ptr_meta::{DynMetadata, Pointee};
-use rkyv::{de::Deserializer, project_struct, ser::Serializer, Fallible, Serialize};
+use rkyv::{
+    de::{Deserializer, PathSegment},
+    project_struct,
+    ser::Serializer,
+    Fallible, Serialize,
+};
 pub use rkyv_dyn_derive::archive_dyn;
 use rkyv_typename::TypeName;
 use std::collections::{hash_map::DefaultHasher, HashMap};
 #[cfg(feature = "validation")]
 pub use validation::{CheckDynError, DynContext};
 
+#[cfg(feature = "validation")]
+#[doc(hidden)]
+pub use bytecheck;
 #[doc(hidden)]
 pub use inventory;
+#[doc(hidden)]
+pub use ptr_meta;
+#[doc(hidden)]
+pub use rkyv;
+#[doc(hidden)]
+pub use rkyv_typename;
 
 #[cfg(all(feature = "vtable_cache", feature = "nightly"))]
 use core::intrinsics::likely;
@@ -61,6 +76,40 @@ pub trait DynSerializer {
 
     /// Attempts to write the given bytes to the serializer.
     fn write_dyn(&mut self, bytes: &[u8]) -> Result<(), DynError>;
+
+    /// Aligns the serializer and writes a block of bytes that does not contain any pointers.
+    ///
+    /// See [`Serializer::write_plain_bytes`].
+    fn write_plain_bytes_dyn(&mut self, bytes: &[u8], align: usize) -> Result<usize, DynError>;
+
+    /// Called by builtin types before they serialize the values behind a pointer.
+    ///
+    /// See [`Serializer::enter_nested`].
+    fn enter_nested_dyn(&mut self, len: usize) -> Result<(), DynError>;
+
+    /// Called by builtin types after they serialize the values behind a pointer.
+    ///
+    /// See [`Serializer::exit_nested`].
+    fn exit_nested_dyn(&mut self);
+
+    /// Allocates scratch space of the requested size.
+    ///
+    /// # Safety
+    ///
+    /// See [`Serializer::push_scratch`].
+    unsafe fn push_scratch_dyn(&mut self, layout: alloc::Layout)
+        -> Result<NonNull<[u8]>, DynError>;
+
+    /// Deallocates previously allocated scratch space.
+    ///
+    /// # Safety
+    ///
+    /// See [`Serializer::pop_scratch`].
+    unsafe fn pop_scratch_dyn(
+        &mut self,
+        ptr: NonNull<u8>,
+        layout: alloc::Layout,
+    ) -> Result<(), DynError>;
 }
 
 impl<'a> Fallible for dyn DynSerializer + 'a {
@@ -75,6 +124,30 @@ impl<'a> Serializer for dyn DynSerializer + 'a {
     fn write(&mut self, bytes: &[u8]) -> Result<(), Self::Error> {
         self.write_dyn(bytes)
     }
+
+    fn write_plain_bytes(&mut self, bytes: &[u8], align: usize) -> Result<usize, Self::Error> {
+        self.write_plain_bytes_dyn(bytes, align)
+    }
+
+    fn enter_nested(&mut self, len: usize) -> Result<(), Self::Error> {
+        self.enter_nested_dyn(len)
+    }
+
+    fn exit_nested(&mut self) {
+        self.exit_nested_dyn()
+    }
+
+    unsafe fn push_scratch(&mut self, layout: alloc::Layout) -> Result<NonNull<[u8]>, Self::Error> {
+        self.push_scratch_dyn(layout)
+    }
+
+    unsafe fn pop_scratch(
+        &mut self,
+        ptr: NonNull<u8>,
+        layout: alloc::Layout,
+    ) -> Result<(), Self::Error> {
+        self.pop_scratch_dyn(ptr, layout)
+    }
 }
 
 impl<S: Serializer + ?Sized> DynSerializer for &mut S {
@@ -88,6 +161,45 @@ impl<S: Serializer + ?Sized> DynSerializer for &mut S {
             Err(e) => Err(Box::new(e)),
         }
     }
+
+    fn write_plain_bytes_dyn(&mut self, bytes: &[u8], align: usize) -> Result<usize, DynError> {
+        match self.write_plain_bytes(bytes, align) {
+            Ok(pos) => Ok(pos),
+            Err(e) => Err(Box::new(e)),
+        }
+    }
+
+    fn enter_nested_dyn(&mut self, len: usize) -> Result<(), DynError> {
+        match self.enter_nested(len) {
+            Ok(()) => Ok(()),
+            Err(e) => Err(Box::new(e)),
+        }
+    }
+
+    fn exit_nested_dyn(&mut self) {
+        self.exit_nested()
+    }
+
+    unsafe fn push_scratch_dyn(
+        &mut self,
+        layout: alloc::Layout,
+    ) -> Result<NonNull<[u8]>, DynError> {
+        match self.push_scratch(layout) {
+            Ok(result) => Ok(result),
+            Err(e) => Err(Box::new(e)),
+        }
+    }
+
+    unsafe fn pop_scratch_dyn(
+        &mut self,
+        ptr: NonNull<u8>,
+        layout: alloc::Layout,
+    ) -> Result<(), DynError> {
+        match self.pop_scratch(ptr, layout) {
+            Ok(()) => Ok(()),
+            Err(e) => Err(Box::new(e)),
+        }
+    }
 }
 
 fn hash_type<T: TypeName + ?Sized>() -> u64 {
@@ -230,9 +342,38 @@ pub trait DynDeserializer {
     ///
     /// # Safety
     ///
-    /// The caller must guarantee that the memory returned by this function is deallocated by the
-    /// global allocator.
+    /// The caller must uphold the safety requirements of [`Deserializer::alloc`] for the memory
+    /// returned by this function.
     unsafe fn alloc_dyn(&mut self, layout: alloc::Layout) -> Result<*mut u8, DynError>;
+
+    /// Frees memory returned by `alloc_dyn` that was never handed out.
+    ///
+    /// # Safety
+    ///
+    /// The caller must uphold the safety requirements of [`Deserializer::dealloc`].
+    unsafe fn dealloc_dyn(&mut self, ptr: *mut u8, layout: alloc::Layout);
+
+    /// Called before allocating memory for values without `alloc_dyn`.
+    ///
+    /// See [`Deserializer::reserve`].
+    fn reserve_dyn(&mut self, layout: alloc::Layout) -> Result<(), DynError>;
+
+    /// Returns whether the memory returned by `alloc_dyn` is owned by the global allocator.
+    fn allocates_globally_dyn(&self) -> bool;
+
+    /// Called before deserializing the values behind a pointer.
+    fn enter_nested_dyn(&mut self, len: usize) -> Result<(), DynError>;
+
+    /// Called after deserializing the values behind a pointer.
+    fn exit_nested_dyn(&mut self);
+
+    /// Called before deserializing a part of a value that has a location.
+    ///
+    /// See [`Deserializer::enter_path`].
+    fn enter_path_dyn(&mut self, segment: PathSegment);
+
+    /// Called after deserializing a part of a value that has a location.
+    fn exit_path_dyn(&mut self);
 }
 
 impl<'a> Fallible for dyn DynDeserializer + 'a {
@@ -243,12 +384,68 @@ impl<'a> Deserializer for (dyn DynDeserializer + 'a) {
     unsafe fn alloc(&mut self, layout: alloc::Layout) -> Result<*mut u8, Self::Error> {
         self.alloc_dyn(layout)
     }
+
+    unsafe fn dealloc(&mut self, ptr: *mut u8, layout: alloc::Layout) {
+        self.dealloc_dyn(ptr, layout)
+    }
+
+    fn reserve(&mut self, layout: alloc::Layout) -> Result<(), Self::Error> {
+        self.reserve_dyn(layout)
+    }
+
+    fn allocates_globally(&self) -> bool {
+        self.allocates_globally_dyn()
+    }
+
+    fn enter_nested(&mut self, len: usize) -> Result<(), Self::Error> {
+        self.enter_nested_dyn(len)
+    }
+
+    fn exit_nested(&mut self) {
+        self.exit_nested_dyn()
+    }
+
+    fn enter_path(&mut self, segment: PathSegment) {
+        self.enter_path_dyn(segment)
+    }
+
+    fn exit_path(&mut self) {
+        self.exit_path_dyn()
+    }
 }
 
 impl<D: Deserializer + ?Sized> DynDeserializer for &mut D {
     unsafe fn alloc_dyn(&mut self, layout: alloc::Layout) -> Result<*mut u8, DynError> {
         self.alloc(layout).map_err(|e| Box::new(e) as DynError)
     }
+
+    unsafe fn dealloc_dyn(&mut self, ptr: *mut u8, layout: alloc::Layout) {
+        self.dealloc(ptr, layout)
+    }
+
+    fn reserve_dyn(&mut self, layout: alloc::Layout) -> Result<(), DynError> {
+        self.reserve(layout).map_err(|e| Box::new(e) as DynError)
+    }
+
+    fn allocates_globally_dyn(&self) -> bool {
+        (**self).allocates_globally()
+    }
+
+    fn enter_nested_dyn(&mut self, len: usize) -> Result<(), DynError> {
+        self.enter_nested(len).map_err(|e| Box::new(e) as DynError)
+    }
+
+    fn exit_nested_dyn(&mut self) {
+        self.exit_nested()
+    }
+
+    fn enter_path_dyn(&mut self, segment: PathSegment) {
+        self.enter_path(segment)
+    }
+
+    fn exit_path_dyn(&mut self) {
+        self.exit_path()
+    }
 }
 
 /// A trait object that can be deserialized.
@@ -348,7 +545,7 @@ pub struct ImplDebugInfo {
 #[macro_export]
 macro_rules! debug_info {
     () => {
-        rkyv_dyn::ImplDebugInfo {
+        $crate::ImplDebugInfo {
             file: core::file!(),
             line: core::line!(),
             column: core::column!(),
@@ -366,7 +563,7 @@ pub struct ImplDebugInfo;
 #[macro_export]
 macro_rules! debug_info {
     () => {
-        rkyv_dyn::ImplDebugInfo
+        $crate::ImplDebugInfo
     };
 }
 
@@ -492,7 +689,7 @@ macro_rules! register_impl {
     ($type:ty as $trait:ty) => {
         const _: () = {
             use core::mem::MaybeUninit;
-            use rkyv_dyn::{
+            use $crate::{
                 debug_info, inventory, register_validation, ImplData, ImplDebugInfo, ImplEntry,
                 RegisteredImpl,
             };
@@ -500,7 +697,7 @@ macro_rules! register_impl {
             unsafe impl RegisteredImpl<$trait> for $type {
                 fn vtable() -> usize {
                     unsafe {
-                        core::mem::transmute(ptr_meta::metadata(
+                        core::mem::transmute($crate::ptr_meta::metadata(
                             core::ptr::null::<$type>() as *const $trait
                         ))
                     }
@@ -4,8 +4,14 @@ use crate::{ArchivedDynMetadata, RegisteredImpl, IMPL_REGISTRY};
 use bytecheck::{CheckBytes, Unreachable};
 #[cfg(feature = "vtable_cache")]
 use core::sync::atomic::{AtomicU64, Ordering};
-use core::{alloc::Layout, any::TypeId, fmt, marker::PhantomData};
+use core::{
+    alloc::{Layout, LayoutError},
+    any::TypeId,
+    fmt,
+    marker::PhantomData,
+};
 use rkyv::{
+    de::PathSegment,
     offset_of,
     validation::{ArchiveBoundsContext, ArchiveMemoryContext, SharedArchiveContext},
     Fallible,
@@ -37,6 +43,39 @@ pub trait DynContext {
         layout: &Layout,
     ) -> Result<(), Box<dyn Error>>;
 
+    /// Checks the memory block for a value whose layout was computed from its metadata.
+    ///
+    /// # Safety
+    ///
+    /// The caller must guarantee that the pointer is inside the archive for this context.
+    unsafe fn bounds_check_layout_dyn(
+        &mut self,
+        ptr: *const u8,
+        layout: Result<Layout, LayoutError>,
+    ) -> Result<Layout, Box<dyn Error>>;
+
+    /// Gets the position of the given pointer relative to the start of the archive.
+    ///
+    /// Returns the address of the pointer by default.
+    fn position_of_dyn(&self, ptr: *const u8) -> usize {
+        ptr as usize
+    }
+
+    /// Called before checking a part of a value that has a location.
+    fn enter_path_dyn(&mut self, segment: PathSegment) {
+        let _ = segment;
+    }
+
+    /// Called after successfully checking a part of a value that has a location.
+    fn exit_path_dyn(&mut self) {}
+
+    /// Gets the path to the value that is being checked.
+    ///
+    /// Returns an empty path by default.
+    fn path_dyn(&self) -> &[PathSegment] {
+        &[]
+    }
+
     /// Claims `count` bytes located `offset` bytes away from `base`.
     ///
     /// # Safety
@@ -86,6 +125,31 @@ where
             .map_err(|e| Box::new(e) as Box<dyn Error>)
     }
 
+    unsafe fn bounds_check_layout_dyn(
+        &mut self,
+        ptr: *const u8,
+        layout: Result<Layout, LayoutError>,
+    ) -> Result<Layout, Box<dyn Error>> {
+        self.bounds_check_layout(ptr, layout)
+            .map_err(|e| Box::new(e) as Box<dyn Error>)
+    }
+
+    fn position_of_dyn(&self, ptr: *const u8) -> usize {
+        self.position_of(ptr)
+    }
+
+    fn enter_path_dyn(&mut self, segment: PathSegment) {
+        self.enter_path(segment);
+    }
+
+    fn exit_path_dyn(&mut self) {
+        self.exit_path();
+    }
+
+    fn path_dyn(&self) -> &[PathSegment] {
+        self.path()
+    }
+
     unsafe fn claim_bytes_dyn(
         &mut self,
         start: *const u8,
@@ -126,6 +190,30 @@ impl ArchiveBoundsContext for (dyn DynContext + '_) {
     ) -> Result<(), Self::Error> {
         self.bounds_check_ptr_dyn(ptr, layout)
     }
+
+    unsafe fn bounds_check_layout(
+        &mut self,
+        ptr: *const u8,
+        layout: Result<Layout, LayoutError>,
+    ) -> Result<Layout, Self::Error> {
+        self.bounds_check_layout_dyn(ptr, layout)
+    }
+
+    fn position_of(&self, ptr: *const u8) -> usize {
+        self.position_of_dyn(ptr)
+    }
+
+    fn enter_path(&mut self, segment: PathSegment) {
+        self.enter_path_dyn(segment);
+    }
+
+    fn exit_path(&mut self) {
+        self.exit_path_dyn();
+    }
+
+    fn path(&self) -> &[PathSegment] {
+        self.path_dyn()
+    }
 }
 
 impl ArchiveMemoryContext for (dyn DynContext + '_) {
@@ -200,7 +288,7 @@ pub struct ImplValidation {
 #[macro_export]
 macro_rules! validation {
     ($type:ty as $trait:ty) => {
-        use rkyv_dyn::validation::{ImplValidation, IsCheckBytesDyn, NotCheckBytesDyn};
+        use $crate::validation::{ImplValidation, IsCheckBytesDyn, NotCheckBytesDyn};
     };
 }
 
@@ -375,8 +463,8 @@ lazy_static::lazy_static! {
 #[macro_export]
 macro_rules! register_validation {
     ($type:ty as $trait:ty) => {
-        use rkyv_dyn::validation::{CheckBytesEntry, IsCheckBytesDyn, NotCheckBytesDyn};
+        use $crate::validation::{CheckBytesEntry, IsCheckBytesDyn, NotCheckBytesDyn};
 
-        inventory::submit! { CheckBytesEntry::new::<$type, $trait>(IsCheckBytesDyn::<$type>::CHECK_BYTES_DYN) }
+        $crate::inventory::submit! { CheckBytesEntry::new::<$type, $trait>(IsCheckBytesDyn::<$type>::CHECK_BYTES_DYN) }
     }
 }
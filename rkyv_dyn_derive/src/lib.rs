@@ -5,9 +5,9 @@ extern crate proc_macro;
 use quote::{quote, quote_spanned};
 use syn::{
     parse::{Parse, ParseStream, Result},
-    parse_macro_input,
+    parse_macro_input, parse_quote,
     spanned::Spanned,
-    Attribute, Error, Ident, ItemImpl, ItemTrait, LitStr, Token, Visibility,
+    Attribute, Error, Ident, ItemImpl, ItemTrait, LitStr, Path, Token, Visibility,
 };
 
 enum Input {
@@ -49,6 +49,7 @@ impl Parse for Input {
 struct Args {
     serialize: Option<LitStr>,
     deserialize: Option<Option<LitStr>>,
+    crate_path: Option<Path>,
 }
 
 impl Parse for Args {
@@ -60,6 +61,7 @@ impl Parse for Args {
 
         let mut serialize = None;
         let mut deserialize = None;
+        let mut crate_path = None;
 
         let mut needs_punct = false;
         while !input.is_empty() {
@@ -87,10 +89,18 @@ impl Parse for Args {
                 } else {
                     deserialize = Some(None);
                 }
+            } else if input.peek(Token![crate]) {
+                if crate_path.is_some() {
+                    return Err(input.error("duplicate crate argument"));
+                }
+
+                input.parse::<Token![crate]>()?;
+                input.parse::<Token![=]>()?;
+                crate_path = Some(input.parse::<LitStr>()?.parse::<Path>()?);
             } else {
-                return Err(
-                    input.error("expected serialize = \"...\" or deserialize = \"...\" parameters")
-                );
+                return Err(input.error(
+                    "expected serialize = \"...\", deserialize = \"...\" or crate = \"...\" parameters",
+                ));
             }
 
             needs_punct = true;
@@ -99,6 +109,7 @@ impl Parse for Args {
         Ok(Args {
             serialize,
             deserialize,
+            crate_path,
         })
     }
 }
@@ -117,6 +128,9 @@ impl Parse for Args {
 /// - `deserialize`, `deserialize = "..."`: Adds deserialization support to the archived trait.
 ///   Similarly to the `name` parameter, you can choose the name of the deserialize trait and by
 ///   default it will be named "Deserialize" + your trait name.
+/// - `crate = "..."`: Chooses the path to the `rkyv_dyn` crate used by the generated code. This is
+///   useful when `rkyv_dyn` is only available through a re-export (e.g.
+///   `#[archive_dyn(crate = "my_framework::rkyv_dyn")]`).
 #[proc_macro_attribute]
 pub fn archive_dyn(
     attr: proc_macro::TokenStream,
@@ -126,6 +140,11 @@ pub fn archive_dyn(
 
     let args = parse_macro_input!(attr as Args);
 
+    let rkyv_dyn: Path = args
+        .crate_path
+        .clone()
+        .unwrap_or_else(|| parse_quote! { rkyv_dyn });
+
     let input_impl = match input {
         Input::Impl(ref input) => {
             if !input.generics.params.is_empty() {
@@ -169,15 +188,17 @@ pub fn archive_dyn(
                                 Archived<#ty>: for<'a> Deserialize<#ty, (dyn DynDeserializer + 'a)>,
                             {
                                 unsafe fn deserialize_dyn(&self, deserializer: &mut dyn DynDeserializer) -> Result<*mut (), DynError> {
+                                    deserializer.enter_nested_dyn(1)?;
                                     let result = deserializer.alloc_dyn(core::alloc::Layout::new::<#ty>())? as *mut #ty;
                                     result.write(self.deserialize(deserializer)?);
+                                    deserializer.exit_nested_dyn();
                                     Ok(result as *mut ())
                                 }
 
-                                fn deserialize_dyn_metadata(&self, deserializer: &mut dyn DynDeserializer) -> Result<<dyn #serialize_trait as ptr_meta::Pointee>::Metadata, DynError> {
+                                fn deserialize_dyn_metadata(&self, deserializer: &mut dyn DynDeserializer) -> Result<<dyn #serialize_trait as #rkyv_dyn::ptr_meta::Pointee>::Metadata, DynError> {
                                     unsafe {
                                         Ok(core::mem::transmute(
-                                            ptr_meta::metadata(core::ptr::null::<#ty>() as *const dyn #serialize_trait)
+                                            #rkyv_dyn::ptr_meta::metadata(core::ptr::null::<#ty>() as *const dyn #serialize_trait)
                                         ))
                                     }
                                 }
@@ -192,17 +213,17 @@ pub fn archive_dyn(
                     #input
 
                     const _: () = {
-                        use rkyv::{
-                            Archived,
-                            Deserialize,
-                        };
-                        use rkyv_dyn::{
+                        use #rkyv_dyn::{
+                            rkyv::{
+                                Archived,
+                                Deserialize,
+                            },
                             DeserializeDyn,
                             DynDeserializer,
                             DynError,
                         };
 
-                        rkyv_dyn::register_impl!(Archived<#ty> as dyn #deserialize_trait);
+                        #rkyv_dyn::register_impl!(Archived<#ty> as dyn #deserialize_trait);
 
                         #deserialize_impl
                     };
@@ -254,10 +275,13 @@ pub fn archive_dyn(
                     (
                         deserialize_trait.clone(),
                         quote! {
-                            #[ptr_meta::pointee]
-                            #vis trait #deserialize_trait<#generic_params>: #name<#generic_args> + rkyv_dyn::DeserializeDyn<dyn #serialize_trait<#generic_args>> {}
+                            #vis trait #deserialize_trait<#generic_params>: #name<#generic_args> + #rkyv_dyn::DeserializeDyn<dyn #serialize_trait<#generic_args>> {}
                         },
                         quote! {
+                            impl<#generic_params> Pointee for (dyn #deserialize_trait<#generic_args> + '_) {
+                                type Metadata = DynMetadata<Self>;
+                            }
+
                             impl<__T: #name<#generic_args> + DeserializeDyn<dyn #serialize_trait<#generic_args>>, #generic_params> #deserialize_trait<#generic_args> for __T {}
 
                             impl<__D: Deserializer + ?Sized, #generic_params> DeserializeUnsized<dyn #serialize_trait<#generic_args>, __D> for dyn #deserialize_trait<#generic_args> {
@@ -265,7 +289,7 @@ pub fn archive_dyn(
                                     self.deserialize_dyn(&mut deserializer).map_err(|e| *e.downcast().unwrap())
                                 }
 
-                                fn deserialize_metadata(&self, mut deserializer: &mut __D) -> Result<<dyn #serialize_trait<#generic_args> as ptr_meta::Pointee>::Metadata, __D::Error> {
+                                fn deserialize_metadata(&self, mut deserializer: &mut __D) -> Result<<dyn #serialize_trait<#generic_args> as Pointee>::Metadata, __D::Error> {
                                     self.deserialize_dyn_metadata(&mut deserializer).map_err(|e| *e.downcast().unwrap())
                                 }
                             }
@@ -277,7 +301,11 @@ pub fn archive_dyn(
                         name.clone(),
                         quote! {},
                         quote! {},
-                        quote! { #[ptr_meta::pointee] },
+                        quote! {
+                            impl<#generic_params> Pointee for (dyn #name<#generic_args> + '_) {
+                                type Metadata = DynMetadata<Self>;
+                            }
+                        },
                     )
                 };
 
@@ -300,14 +328,16 @@ pub fn archive_dyn(
 
             #[cfg(feature = "validation")]
             let validation_impl = quote! {
-                use bytecheck::CheckBytes;
-                use rkyv_dyn::validation::{CHECK_BYTES_REGISTRY, CheckDynError, DynContext};
+                use #rkyv_dyn::{
+                    bytecheck::CheckBytes,
+                    validation::{CHECK_BYTES_REGISTRY, CheckDynError, DynContext},
+                };
 
                 impl<#generic_params> CheckBytes<dyn DynContext + '_> for (dyn #deserialize_trait<#generic_args> + '_) {
                     type Error = CheckDynError;
 
                     unsafe fn check_bytes<'a>(value: *const Self, context: &mut (dyn DynContext + '_)) -> Result<&'a Self, Self::Error> {
-                        let vtable = core::mem::transmute(ptr_meta::metadata(value));
+                        let vtable = core::mem::transmute(#rkyv_dyn::ptr_meta::metadata(value));
                         if let Some(validation) = CHECK_BYTES_REGISTRY.get(vtable) {
                             (validation.check_bytes_dyn)(value.cast(), context)?;
                             Ok(&*value)
@@ -330,27 +360,28 @@ pub fn archive_dyn(
             let validation_impl = quote! {};
 
             quote! {
-                #pointee_input
                 #input
 
-                #[ptr_meta::pointee]
-                #vis trait #serialize_trait<#generic_params>: #name<#generic_args> + rkyv_dyn::SerializeDyn {}
+                #vis trait #serialize_trait<#generic_params>: #name<#generic_args> + #rkyv_dyn::SerializeDyn {}
 
                 #deserialize_trait_def
 
                 const _: ()  = {
                     use core::mem::MaybeUninit;
-                    use rkyv::{
-                        de::Deserializer,
-                        ser::Serializer,
-                        Archived,
-                        ArchivedMetadata,
-                        ArchivePointee,
-                        ArchiveUnsized,
-                        DeserializeUnsized,
-                        SerializeUnsized,
-                    };
-                    use rkyv_dyn::{
+                    use #rkyv_dyn::{
+                        ptr_meta::{DynMetadata, Pointee},
+                        rkyv::{
+                            de::Deserializer,
+                            ser::Serializer,
+                            Archive,
+                            Archived,
+                            ArchivedMetadata,
+                            ArchivePointee,
+                            ArchiveUnsized,
+                            DeserializeUnsized,
+                            SerializeUnsized,
+                        },
+                        rkyv_typename::TypeName,
                         ArchivedDynMetadata,
                         DynDeserializer,
                         RegisteredImpl,
@@ -358,7 +389,12 @@ pub fn archive_dyn(
                         DeserializeDyn,
                         DynSerializer,
                     };
-                    use rkyv_typename::TypeName;
+
+                    #pointee_input
+
+                    impl<#generic_params> Pointee for (dyn #serialize_trait<#generic_args> + '_) {
+                        type Metadata = DynMetadata<Self>;
+                    }
 
                     impl<__T: Archive + SerializeDyn + #name<#generic_args>, #generic_params> #serialize_trait<#generic_args> for __T
                     where
@@ -388,14 +424,17 @@ pub fn archive_dyn(
                     impl<#generic_params> ArchivePointee for dyn #deserialize_trait<#generic_args> {
                         type ArchivedMetadata = ArchivedDynMetadata<Self>;
 
-                        fn pointer_metadata(archived: &Self::ArchivedMetadata) -> <Self as ptr_meta::Pointee>::Metadata {
+                        fn pointer_metadata(archived: &Self::ArchivedMetadata) -> <Self as Pointee>::Metadata {
                             archived.pointer_metadata()
                         }
                     }
 
                     impl<__S: Serializer + ?Sized, #generic_params> SerializeUnsized<__S> for dyn #serialize_trait<#generic_args> {
                         fn serialize_unsized(&self, mut serializer: &mut __S) -> Result<usize, __S::Error> {
-                            self.serialize_dyn(&mut serializer).map_err(|e| *e.downcast::<__S::Error>().unwrap())
+                            serializer.enter_nested(1)?;
+                            let result = self.serialize_dyn(&mut serializer).map_err(|e| *e.downcast::<__S::Error>().unwrap())?;
+                            serializer.exit_nested();
+                            Ok(result)
                         }
 
                         fn serialize_metadata(&self, _: &mut __S) -> Result<Self::MetadataResolver, __S::Error> {
@@ -8,31 +8,43 @@ mod util {
     #[cfg(feature = "wasm")]
     wasm_bindgen_test::wasm_bindgen_test_configure!();
 
+    #[cfg(all(feature = "std", feature = "const_generics"))]
+    use rkyv::ser::serializers::AllocSerializer;
+    #[cfg(all(feature = "std", not(feature = "const_generics")))]
+    use rkyv::ser::serializers::{
+        AlignedSerializer, AllocScratch, CompositeSerializer, SharedSerializeMap,
+    };
     use rkyv::{
-        archived_root, archived_unsized_root,
-        ser::{serializers::BufferSerializer, Serializer},
-        Aligned, Deserialize, Serialize, SerializeUnsized,
+        archived_root, archived_unsized_root, ser::Serializer, Deserialize, Serialize,
+        SerializeUnsized,
     };
     #[cfg(feature = "std")]
     use rkyv::{
         de::{adapters::SharedDeserializerAdapter, deserializers::AllocDeserializer},
-        ser::adapters::SharedSerializerAdapter,
+        AlignedVec,
     };
+    #[cfg(not(feature = "std"))]
+    use rkyv::{ser::serializers::BufferSerializer, Aligned};
 
     pub const BUFFER_SIZE: usize = 256;
+    #[cfg(all(feature = "std", feature = "const_generics"))]
+    pub const SCRATCH_SIZE: usize = 64;
 
-    #[cfg(feature = "std")]
+    #[cfg(all(feature = "std", feature = "const_generics"))]
+    pub type DefaultSerializer = AllocSerializer<SCRATCH_SIZE>;
+
+    #[cfg(all(feature = "std", not(feature = "const_generics")))]
     pub type DefaultSerializer =
-        SharedSerializerAdapter<BufferSerializer<Aligned<[u8; BUFFER_SIZE]>>>;
+        CompositeSerializer<AlignedSerializer<AlignedVec>, AllocScratch, SharedSerializeMap>;
 
     #[cfg(feature = "std")]
     pub fn make_default_serializer() -> DefaultSerializer {
-        SharedSerializerAdapter::new(BufferSerializer::new(Aligned([0u8; BUFFER_SIZE])))
+        DefaultSerializer::default()
     }
 
     #[cfg(feature = "std")]
-    pub fn unwrap_default_serializer(s: DefaultSerializer) -> Aligned<[u8; BUFFER_SIZE]> {
-        s.into_inner().into_inner()
+    pub fn unwrap_default_serializer(s: DefaultSerializer) -> AlignedVec {
+        s.into_serializer().into_inner()
     }
 
     #[cfg(feature = "std")]
@@ -121,6 +133,30 @@ mod util {
         let archived_ref = unsafe { archived_root::<T>(&buffer.as_ref()[0..len]) };
         assert!(archived_ref.deref() == value.deref());
     }
+
+    /// Polls a future to completion, spinning while it's pending.
+    #[cfg(any(feature = "futures_io", feature = "tokio_io"))]
+    pub fn block_on<F: core::future::Future>(future: F) -> F::Output {
+        use core::{
+            ptr,
+            task::{Context, Poll, RawWaker, RawWakerVTable, Waker},
+        };
+
+        fn clone(_: *const ()) -> RawWaker {
+            RawWaker::new(ptr::null(), &VTABLE)
+        }
+        fn noop(_: *const ()) {}
+        static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+
+        let waker = unsafe { Waker::from_raw(clone(ptr::null())) };
+        let mut context = Context::from_waker(&waker);
+        let mut future = Box::pin(future);
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+                return output;
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -186,8 +222,8 @@ mod tests {
         archived_root, archived_root_mut,
         de::{adapters::SharedDeserializerAdapter, deserializers::AllocDeserializer, Deserializer},
         ser::{
-            adapters::SharedSerializerAdapter,
-            serializers::{AlignedSerializer, BufferSerializer},
+            adapters::{ScratchSpaceAdapter, SharedSerializerAdapter},
+            serializers::{AlignedSerializer, AllocScratch, BufferSerializer},
             SeekSerializer, Serializer,
         },
         AlignedVec, Archive, Archived, Deserialize, Serialize,
@@ -348,6 +384,119 @@ mod tests {
         }
     }
 
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn scratch_space() {
+        use core::alloc::Layout;
+        use rkyv::ser::{
+            adapters::{LimitSerializer, ScratchSpaceAdapterError},
+            serializers::{
+                AllocScratchError, BufferScratch, FallbackScratch, FixedSizeScratchError,
+            },
+            ScratchSpace,
+        };
+        use std::collections::HashMap;
+
+        fn assert_all_popped(scratch: &mut BufferScratch<[u8; 4096]>) {
+            unsafe {
+                let layout = Layout::new::<u8>();
+                let ptr = scratch.push_scratch(layout).unwrap();
+                scratch.clear();
+                assert_eq!(scratch.push_scratch(layout).unwrap(), ptr);
+                scratch.clear();
+            }
+        }
+
+        let mut value = HashMap::new();
+        for i in 0..16 {
+            value.insert(i.to_string(), vec![i.to_string(); i]);
+        }
+
+        // All scratch space must be returned to the buffer after serializing
+        let mut serializer = ScratchSpaceAdapter::new(
+            AlignedSerializer::new(AlignedVec::new()),
+            BufferScratch::new([0u8; 4096]),
+        );
+        serializer
+            .serialize_value(&value)
+            .expect("failed to archive value");
+        let (serializer, mut scratch) = serializer.into_parts();
+        assert_all_popped(&mut scratch);
+        let buf = serializer.into_inner();
+        let archived_value = unsafe { archived_root::<HashMap<String, Vec<String>>>(buf.as_ref()) };
+        assert_eq!(archived_value.len(), value.len());
+        for (key, value) in value.iter() {
+            assert_eq!(&archived_value[key.as_str()], value);
+        }
+
+        // Scratch space is also returned when serializing fails partway through
+        let mut serializer = LimitSerializer::new(
+            ScratchSpaceAdapter::new(
+                AlignedSerializer::new(AlignedVec::new()),
+                BufferScratch::new([0u8; 4096]),
+            ),
+            64,
+        );
+        assert!(serializer.serialize_value(&value).is_err());
+        let (_, mut scratch) = serializer.into_inner().into_parts();
+        assert_all_popped(&mut scratch);
+
+        // Only the last allocation can be popped
+        let mut scratch = BufferScratch::new([0u8; 64]);
+        unsafe {
+            let layout = Layout::new::<[u32; 2]>();
+            let first = scratch.push_scratch(layout).unwrap();
+            let second = scratch.push_scratch(layout).unwrap();
+            match scratch.pop_scratch(first.cast(), layout) {
+                Err(FixedSizeScratchError::NotPoppedInReverseOrder { .. }) => (),
+                result => panic!("expected not popped in reverse order error, got {:?}", result),
+            }
+            let tail = Layout::new::<u32>();
+            match scratch.pop_scratch(second.cast::<u32>().add(1).cast(), tail) {
+                Err(FixedSizeScratchError::NotPoppedInReverseOrder { .. }) => (),
+                result => panic!("expected not popped in reverse order error, got {:?}", result),
+            }
+            scratch.pop_scratch(second.cast(), layout).unwrap();
+            scratch.pop_scratch(first.cast(), layout).unwrap();
+        }
+
+        // A buffer that's too small fails
+        let mut serializer = ScratchSpaceAdapter::new(
+            AlignedSerializer::new(AlignedVec::new()),
+            BufferScratch::new([0u8; 4]),
+        );
+        match serializer.serialize_value(&value) {
+            Err(ScratchSpaceAdapterError::ScratchSpace(FixedSizeScratchError::OutOfSpace {
+                ..
+            })) => (),
+            result => panic!("expected out of space error, got {:?}", result),
+        }
+
+        // Falling back to allocations succeeds
+        let mut serializer = ScratchSpaceAdapter::new(
+            AlignedSerializer::new(AlignedVec::new()),
+            FallbackScratch::new(BufferScratch::new([0u8; 4]), AllocScratch::new()),
+        );
+        serializer
+            .serialize_value(&value)
+            .expect("failed to archive value");
+        let buf = serializer.into_inner().into_inner();
+        let archived_value = unsafe { archived_root::<HashMap<String, Vec<String>>>(buf.as_ref()) };
+        assert_eq!(archived_value.len(), value.len());
+
+        // Allocations past the limit fail
+        let mut serializer = ScratchSpaceAdapter::new(
+            AlignedSerializer::new(AlignedVec::new()),
+            AllocScratch::with_limit(4),
+        );
+        match serializer.serialize_value(&value) {
+            Err(ScratchSpaceAdapterError::ScratchSpace(AllocScratchError::ExceededLimit {
+                ..
+            })) => (),
+            result => panic!("expected exceeded limit error, got {:?}", result),
+        }
+    }
+
     #[test]
     #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
     fn archive_unit_struct() {
@@ -1179,194 +1328,1883 @@ mod tests {
 
     #[test]
     #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
-    fn archive_more_std() {
-        use core::{
-            num::NonZeroU8,
-            ops::Range,
-            sync::atomic::{AtomicU32, Ordering},
+    fn composite_serializer() {
+        use rkyv::{
+            archived_value,
+            ser::{
+                serializers::{CompositeSerializer, SharedSerializeMap, SharedSerializeMapError},
+                SharedSerializeRegistry,
+            },
+            Aligned,
         };
+        use std::rc::Rc;
 
-        #[derive(Archive, Serialize, Deserialize)]
+        #[derive(Archive, Serialize)]
         struct Test {
-            a: AtomicU32,
-            b: Range<i32>,
-            c: NonZeroU8,
-        }
-
-        impl PartialEq for Test {
-            fn eq(&self, other: &Self) -> bool {
-                self.a.load(Ordering::Relaxed) == other.a.load(Ordering::Relaxed)
-                    && self.b == other.b
-                    && self.c == other.c
-            }
-        }
-
-        // Can't derive PartialEq automatically because AtomicU32 doesn't implement PartialEq
-        impl PartialEq<Test> for Archived<Test> {
-            fn eq(&self, other: &Test) -> bool {
-                self.a.load(Ordering::Relaxed) == other.a.load(Ordering::Relaxed)
-                    && self.b == other.b
-                    && self.c == other.c
-            }
+            a: Rc<String>,
+            b: Rc<String>,
+            c: Vec<String>,
         }
 
+        let shared = Rc::new("shared".to_string());
         let value = Test {
-            a: AtomicU32::new(42),
-            b: Range { start: 14, end: 46 },
-            c: NonZeroU8::new(8).unwrap(),
+            a: shared.clone(),
+            b: shared,
+            c: vec!["hello".to_string(), "world".to_string()],
         };
 
-        test_archive(&value);
+        // Seeking, scratch space and shared pointers are all supported at once
+        let mut serializer = CompositeSerializer::new(
+            BufferSerializer::new(Aligned([0u8; BUFFER_SIZE])),
+            AllocScratch::new(),
+            SharedSerializeMap::new(),
+        );
+        let pos = serializer
+            .serialize_front(&value)
+            .expect("failed to archive value");
+        assert_eq!(pos, 0);
+        let buffer = serializer.into_serializer().into_inner();
+        let archived = unsafe { archived_value::<Test>(buffer.as_ref(), pos) };
+        assert_eq!(*archived.a, "shared");
+        assert!(core::ptr::eq(&*archived.a, &*archived.b));
+        assert_eq!(archived.c.as_slice(), value.c.as_slice());
+
+        // The shared serializer adapter forwards seeking
+        let mut serializer =
+            SharedSerializerAdapter::new(BufferSerializer::new(Aligned([0u8; BUFFER_SIZE])));
+        let pos = serializer
+            .serialize_front(&value)
+            .expect("failed to archive value");
+        assert_eq!(pos, 0);
+
+        let mut map = SharedSerializeMap::new();
+        let ptr = (&value as *const Test).cast::<u8>();
+        assert_eq!(map.get_shared_ptr(ptr), None);
+        map.add_shared_ptr(ptr, 16).unwrap();
+        assert_eq!(map.get_shared_ptr(ptr), Some(16));
+        match map.add_shared_ptr(ptr, 32) {
+            Err(SharedSerializeMapError::DuplicateSharedPointer(p)) => assert_eq!(p, ptr),
+            result => panic!("expected duplicate shared pointer error, got {:?}", result),
+        }
     }
 
     #[test]
     #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
-    fn archive_net() {
-        use std::net::{Ipv4Addr, Ipv6Addr, IpAddr, SocketAddrV4, SocketAddrV6, SocketAddr};
+    fn archived_size() {
+        use rkyv::{
+            archived_size_of,
+            ser::{
+                serializers::{AllocSizeSerializer, SizeSerializer},
+                SharedSerializer,
+            },
+        };
+        use std::{collections::HashMap, rc::Rc};
 
-        #[derive(Archive, Serialize, Deserialize, PartialEq)]
-        #[archive(compare(PartialEq))]
-        struct TestNet {
-            ipv4: Ipv4Addr,
-            ipv6: Ipv6Addr,
-            ip: IpAddr,
-            sockv4: SocketAddrV4,
-            sockv6: SocketAddrV6,
-            sock: SocketAddr,
+        #[derive(Archive, Serialize)]
+        struct Test {
+            a: u8,
+            b: Rc<String>,
+            c: Rc<String>,
+            d: Vec<Option<Box<u64>>>,
+            e: HashMap<String, Vec<u16>>,
         }
 
-        let value = TestNet {
-            ipv4: Ipv4Addr::new(31, 41, 59, 26),
-            ipv6: Ipv6Addr::new(31, 41, 59, 26, 53, 58, 97, 93),
-            ip: IpAddr::V4(Ipv4Addr::new(31, 41, 59, 26)),
-            sockv4: SocketAddrV4::new(Ipv4Addr::new(31, 41, 59, 26), 5358),
-            sockv6: SocketAddrV6::new(
-                Ipv6Addr::new(31, 31, 59, 26, 53, 58, 97, 93),
-                2384,
-                0,
-                0,
-            ),
-            sock: SocketAddr::V6(SocketAddrV6::new(
-                Ipv6Addr::new(31, 31, 59, 26, 53, 58, 97, 93),
-                2384,
-                0,
-                0,
-            )),
-        };
+        fn check<T: Serialize<DefaultSerializer> + Serialize<AllocSizeSerializer>>(value: &T) {
+            let size = archived_size_of(value).expect("failed to compute archived size");
+            let vec = AlignedVec::with_capacity_for(value).expect("failed to compute capacity");
+            assert_eq!(vec.capacity(), size);
 
-        test_archive(&value);
+            let mut serializer = make_default_serializer();
+            serializer
+                .serialize_value(value)
+                .expect("failed to archive value");
+            assert_eq!(unwrap_default_serializer(serializer).len(), size);
+        }
+
+        check(&());
+        check(&"hello world".to_string());
+        check(&vec![1u8, 2, 3]);
+        let shared = Rc::new("shared".to_string());
+        let mut e = HashMap::new();
+        e.insert("hello".to_string(), vec![1, 2, 3]);
+        e.insert("world".to_string(), Vec::new());
+        check(&Test {
+            a: 1,
+            b: shared.clone(),
+            c: shared.clone(),
+            d: vec![Some(Box::new(42)), None, Some(Box::new(7))],
+            e,
+        });
+
+        // Shared values are only counted once
+        let mut serializer = SizeSerializer::new();
+        let first = serializer.serialize_shared(&*shared).unwrap();
+        let pos = serializer.pos();
+        assert_eq!(serializer.serialize_shared(&*shared).unwrap(), first);
+        assert_eq!(serializer.pos(), pos);
     }
 
     #[test]
     #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
-    fn archive_shared_ptr() {
-        use std::rc::Rc;
+    fn limit_serializer() {
+        use rkyv::{
+            ser::{
+                adapters::{LimitSerializer, LimitSerializerError},
+                serializers::{BufferSerializerError, WriteSerializer},
+            },
+            Aligned,
+        };
+        use std::io;
 
-        #[derive(Archive, Deserialize, Serialize, Eq, PartialEq)]
-        #[archive(compare(PartialEq))]
-        struct Test {
-            a: Rc<u32>,
-            b: Rc<u32>,
+        #[derive(Archive, Serialize)]
+        #[archive(
+            bound(serialize = "__S: Serializer"),
+            no_check
+        )]
+        enum Node {
+            Nil,
+            Cons(String, #[omit_bounds] Box<Node>),
         }
 
-        impl ArchivedTest {
-            fn a(self: Pin<&mut Self>) -> Pin<&mut Archived<Rc<u32>>> {
-                unsafe { self.map_unchecked_mut(|s| &mut s.a) }
-            }
-
-            fn b(self: Pin<&mut Self>) -> Pin<&mut Archived<Rc<u32>>> {
-                unsafe { self.map_unchecked_mut(|s| &mut s.b) }
-            }
+        fn list(len: usize) -> Node {
+            (0..len).fold(Node::Nil, |node, i| {
+                Node::Cons(i.to_string(), Box::new(node))
+            })
         }
 
-        let shared = Rc::new(10);
-        let value = Test {
-            a: shared.clone(),
-            b: shared.clone(),
-        };
+        let value = list(3);
 
-        let mut serializer =
-            SharedSerializerAdapter::new(AlignedSerializer::new(AlignedVec::new()));
+        // Values within the limits are archived normally
+        let mut serializer = LimitSerializer::new(make_default_serializer(), 1024)
+            .with_max_slice_len(8)
+            .with_max_depth(4);
         serializer
             .serialize_value(&value)
             .expect("failed to archive value");
-        let mut buf = serializer.into_inner().into_inner();
-
-        let archived = unsafe { archived_root::<Test>(buf.as_ref()) };
-        assert!(archived == &value);
-
-        let mut mutable_archived =
-            unsafe { archived_root_mut::<Test>(Pin::new_unchecked(buf.as_mut())) };
-        unsafe {
-            *mutable_archived.as_mut().a().get_pin_unchecked() = 42;
+        let buf = unwrap_default_serializer(serializer.into_inner());
+        let archived = unsafe { archived_root::<Node>(buf.as_ref()) };
+        match archived {
+            ArchivedNode::Cons(s, _) => assert_eq!(s.as_str(), "2"),
+            ArchivedNode::Nil => panic!("expected cons"),
         }
 
-        let archived = unsafe { archived_root::<Test>(buf.as_ref()) };
-        assert_eq!(*archived.a, 42);
-        assert_eq!(*archived.b, 42);
-
-        let mut mutable_archived =
-            unsafe { archived_root_mut::<Test>(Pin::new_unchecked(buf.as_mut())) };
-        unsafe {
-            *mutable_archived.as_mut().b().get_pin_unchecked() = 17;
+        // Size limit
+        let mut serializer = LimitSerializer::new(make_default_serializer(), 16);
+        match serializer.serialize_value(&value) {
+            Err(LimitSerializerError::SizeLimitExceeded { limit: 16, size }) => assert!(size > 16),
+            result => panic!("expected size limit error, got {:?}", result.map(|_| ())),
         }
 
-        let archived = unsafe { archived_root::<Test>(buf.as_ref()) };
-        assert_eq!(*archived.a, 17);
-        assert_eq!(*archived.b, 17);
+        // The innermost string is behind three boxes
+        let mut serializer =
+            LimitSerializer::new(make_default_serializer(), 1024).with_max_depth(3);
+        serializer
+            .serialize_value(&value)
+            .expect("failed to archive value");
+        let mut serializer =
+            LimitSerializer::new(make_default_serializer(), 1024).with_max_depth(2);
+        match serializer.serialize_value(&value) {
+            Err(LimitSerializerError::DepthLimitExceeded { limit: 2 }) => (),
+            result => panic!("expected depth limit error, got {:?}", result.map(|_| ())),
+        }
 
-        let mut deserializer = SharedDeserializerAdapter::new(AllocDeserializer);
-        let deserialized = archived.deserialize(&mut deserializer).unwrap();
+        // Leaving nested values because of an error restores the depth
+        serializer
+            .serialize_value(&list(2))
+            .expect("failed to archive value");
 
-        assert_eq!(*deserialized.a, 17);
-        assert_eq!(*deserialized.b, 17);
-        assert_eq!(
-            &*deserialized.a as *const u32,
-            &*deserialized.b as *const u32
-        );
-        assert_eq!(Rc::strong_count(&deserialized.a), 3);
-        assert_eq!(Rc::strong_count(&deserialized.b), 3);
-        assert_eq!(Rc::weak_count(&deserialized.a), 0);
-        assert_eq!(Rc::weak_count(&deserialized.b), 0);
+        // Slice length limit
+        let mut serializer =
+            LimitSerializer::new(make_default_serializer(), 1024).with_max_slice_len(4);
+        serializer
+            .serialize_value(&"four".to_string())
+            .expect("failed to archive value");
+        match serializer.serialize_value(&"hello".to_string()) {
+            Err(LimitSerializerError::SliceLengthLimitExceeded { limit: 4, len: 5 }) => (),
+            result => panic!(
+                "expected slice length limit error, got {:?}",
+                result.map(|_| ())
+            ),
+        }
 
-        core::mem::drop(deserializer);
+        // Errors from the inner serializer are passed through
+        let mut serializer = LimitSerializer::new(BufferSerializer::new(Aligned([0u8; 16])), 1024);
+        match serializer.serialize_value(&"hello world, this is a long string".to_string()) {
+            Err(LimitSerializerError::Inner(BufferSerializerError::Overflow { .. })) => (),
+            result => panic!("expected buffer overflow, got {:?}", result.map(|_| ())),
+        }
 
-        assert_eq!(*deserialized.a, 17);
-        assert_eq!(*deserialized.b, 17);
-        assert_eq!(
-            &*deserialized.a as *const u32,
-            &*deserialized.b as *const u32
-        );
-        assert_eq!(Rc::strong_count(&deserialized.a), 2);
-        assert_eq!(Rc::strong_count(&deserialized.b), 2);
-        assert_eq!(Rc::weak_count(&deserialized.a), 0);
-        assert_eq!(Rc::weak_count(&deserialized.b), 0);
+        let mut buf = [0u8; 16];
+        let mut serializer = LimitSerializer::new(WriteSerializer::new(&mut buf[..]), 1024);
+        match serializer.serialize_value(&"hello world, this is a long string".to_string()) {
+            Err(LimitSerializerError::Inner(e)) => assert_eq!(e.kind(), io::ErrorKind::WriteZero),
+            result => panic!("expected io error, got {:?}", result.map(|_| ())),
+        }
     }
 
     #[test]
     #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
-    fn archive_unsized_shared_ptr() {
-        use std::rc::Rc;
+    fn checksum_serializer() {
+        use core::hash::Hasher;
+        use rkyv::{
+            checksum::{verify_checksum, ChecksumError, Crc32c, StreamingHasher, CHECKSUM_SIZE},
+            ser::{
+                adapters::ChecksumSerializer,
+                serializers::{AllocScratch, CompositeSerializer, WriteSerializer},
+            },
+            Infallible,
+        };
 
-        #[derive(Archive, Serialize, Deserialize, PartialEq)]
-        #[archive(compare(PartialEq))]
+        #[derive(Archive, Serialize)]
         struct Test {
-            a: Rc<[String]>,
-            b: Rc<[String]>,
+            name: String,
+            values: Vec<f32>,
+            nested: Option<Box<u64>>,
         }
 
-        let rc_slice =
-            Rc::<[String]>::from(vec!["hello".to_string(), "world".to_string()].into_boxed_slice());
-        let value = Test {
-            a: rc_slice.clone(),
-            b: rc_slice,
-        };
+        fn test_checksum<H: StreamingHasher + Default>() {
+            let value = Test {
+                name: "checksum".to_string(),
+                values: vec![1.0, 2.5, -3.75],
+                nested: Some(Box::new(42)),
+            };
 
-        test_archive(&value);
+            // Streaming to a writer produces the same checksum as serializing to a buffer
+            let mut serializer = ChecksumSerializer::<_, H>::new(make_default_serializer());
+            serializer
+                .serialize_value(&value)
+                .expect("failed to archive value");
+            let checksum = serializer.checksum();
+            let serializer = serializer.finish().expect("failed to write checksum");
+            let mut buf = unwrap_default_serializer(serializer);
+
+            let mut writer = ChecksumSerializer::<_, H>::new(CompositeSerializer::new(
+                WriteSerializer::new(Vec::new()),
+                AllocScratch::new(),
+                Infallible,
+            ));
+            writer
+                .serialize_value(&value)
+                .expect("failed to archive value");
+            assert_eq!(writer.checksum(), checksum);
+            let written = writer
+                .finish()
+                .expect("failed to write checksum")
+                .into_serializer()
+                .into_inner();
+            assert_eq!(written.as_slice(), buf.as_ref());
+
+            let len = buf.as_ref().len();
+            assert_eq!(
+                &buf.as_ref()[len - CHECKSUM_SIZE..],
+                &checksum.to_le_bytes()[..]
+            );
+            let archive = verify_checksum::<H>(buf.as_ref()).expect("failed to verify checksum");
+            assert_eq!(archive.len(), len - CHECKSUM_SIZE);
+            let archived = unsafe { archived_root::<Test>(archive) };
+            assert_eq!(archived.name, "checksum");
+            assert_eq!(archived.values.as_slice(), &[1.0, 2.5, -3.75]);
+            assert_eq!(archived.nested.as_ref().map(|n| **n), Some(42));
+
+            // Corrupting any byte is detected
+            buf.as_mut()[len - CHECKSUM_SIZE - 1] ^= 0x80;
+            match verify_checksum::<H>(buf.as_ref()) {
+                Err(ChecksumError::ChecksumMismatch { expected, actual }) => {
+                    assert_eq!(expected, checksum);
+                    assert_ne!(actual, checksum);
+                }
+                result => panic!("expected checksum mismatch, got {:?}", result),
+            }
+
+            match verify_checksum::<H>(&[0; CHECKSUM_SIZE - 1]) {
+                Err(ChecksumError::MissingTrailer { len }) => assert_eq!(len, CHECKSUM_SIZE - 1),
+                result => panic!("expected missing trailer, got {:?}", result),
+            }
+        }
+
+        // The standard check value, which is the same however the input is split up
+        let mut hasher = Crc32c::default();
+        hasher.write(b"123456789");
+        assert_eq!(hasher.finish(), 0xe306_9283);
+        let mut hasher = Crc32c::default();
+        hasher.write(b"1234");
+        hasher.write(b"");
+        hasher.write(b"56789");
+        assert_eq!(hasher.finish(), 0xe306_9283);
+
+        test_checksum::<Crc32c>();
+        #[cfg(feature = "xxh3")]
+        test_checksum::<rkyv::checksum::Xxh3>();
     }
 
     #[test]
-    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    #[cfg(feature = "futures_io")]
+    fn async_write_serializer() {
+        use core::{
+            pin::Pin,
+            task::{Context, Poll},
+        };
+        use rkyv::{
+            archived_value,
+            ser::serializers::{AsyncWriteSerializer, CompositeSerializer},
+            Infallible,
+        };
+        use std::io;
+
+        // Accepts a few bytes at a time and is only ready every other poll
+        struct SlowSink {
+            bytes: Vec<u8>,
+            ready: bool,
+            flushes: usize,
+        }
+
+        impl futures_io::AsyncWrite for SlowSink {
+            fn poll_write(
+                mut self: Pin<&mut Self>,
+                cx: &mut Context<'_>,
+                buf: &[u8],
+            ) -> Poll<io::Result<usize>> {
+                self.ready = !self.ready;
+                if self.ready {
+                    let len = buf.len().min(7);
+                    self.bytes.extend_from_slice(&buf[..len]);
+                    Poll::Ready(Ok(len))
+                } else {
+                    cx.waker().wake_by_ref();
+                    Poll::Pending
+                }
+            }
+
+            fn poll_flush(mut self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+                self.flushes += 1;
+                Poll::Ready(Ok(()))
+            }
+
+            fn poll_close(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+                Poll::Ready(Ok(()))
+            }
+        }
+
+        #[derive(Archive, Serialize)]
+        struct Test {
+            name: String,
+            values: Vec<u32>,
+        }
+
+        let parts = (0..4)
+            .map(|i| Test {
+                name: format!("part {}", i),
+                values: (0..i * 10).collect(),
+            })
+            .collect::<Vec<_>>();
+
+        let mut expected = make_default_serializer();
+        let expected_positions = parts
+            .iter()
+            .map(|part| expected.serialize_value(part).unwrap())
+            .collect::<Vec<_>>();
+        let expected = unwrap_default_serializer(expected);
+
+        // Each part is flushed before the next is serialized
+        let mut serializer = CompositeSerializer::new(
+            AsyncWriteSerializer::new(SlowSink {
+                bytes: Vec::new(),
+                ready: false,
+                flushes: 0,
+            }),
+            AllocScratch::new(),
+            Infallible,
+        );
+        let mut positions = Vec::new();
+        for part in parts.iter() {
+            positions.push(block_on(serializer.serialize_value_async(part)).unwrap());
+        }
+        let serializer = serializer.into_serializer();
+        assert!(serializer.buffered().is_empty());
+        let sink = serializer.into_inner();
+        assert_eq!(sink.flushes, parts.len());
+        assert_eq!(positions, expected_positions);
+        assert_eq!(sink.bytes.as_slice(), expected.as_ref());
+
+        let mut buf = AlignedVec::new();
+        buf.extend_from_slice(&sink.bytes);
+        for (part, pos) in parts.iter().zip(positions) {
+            let archived = unsafe { archived_value::<Test>(buf.as_ref(), pos) };
+            assert_eq!(archived.name, part.name);
+            assert_eq!(archived.values.as_slice(), part.values.as_slice());
+        }
+
+        // Bytes stay buffered until flushed
+        let mut serializer = AsyncWriteSerializer::new(Vec::new());
+        serializer.serialize_value(&42u32).unwrap();
+        assert_eq!(serializer.buffered(), &42u32.to_le_bytes());
+        block_on(serializer.flush()).unwrap();
+        assert!(serializer.buffered().is_empty());
+        assert_eq!(serializer.into_inner(), 42u32.to_le_bytes());
+
+        // Past the flush threshold, bytes reach ready sinks while serializing
+        let value = "0123456789".repeat(100);
+        let mut serializer = AsyncWriteSerializer::new(Vec::new()).with_flush_threshold(16);
+        let pos = serializer.serialize_value(&value).unwrap();
+        assert!(serializer.buffered().len() <= 16);
+        block_on(serializer.flush()).unwrap();
+        let mut buf = AlignedVec::new();
+        buf.extend_from_slice(&serializer.into_inner());
+        let archived = unsafe { archived_value::<String>(buf.as_ref(), pos) };
+        assert_eq!(archived, &value);
+
+        // Sinks that aren't ready get the rest of the bytes when flushed
+        let mut serializer = AsyncWriteSerializer::new(SlowSink {
+            bytes: Vec::new(),
+            ready: false,
+            flushes: 0,
+        })
+        .with_flush_threshold(16);
+        for part in parts.iter() {
+            serializer.serialize_value(part).unwrap();
+        }
+        assert!(serializer.buffered().len() < expected.len());
+        block_on(serializer.flush()).unwrap();
+        assert_eq!(serializer.into_inner().bytes.as_slice(), expected.as_ref());
+
+        // Waiting for slow sinks to be ready between values keeps the buffer small
+        let mut serializer = AsyncWriteSerializer::new(SlowSink {
+            bytes: Vec::new(),
+            ready: false,
+            flushes: 0,
+        })
+        .with_flush_threshold(16);
+        for part in parts.iter() {
+            block_on(serializer.ready()).unwrap();
+            assert!(serializer.buffered().len() <= 16);
+            serializer.serialize_value(part).unwrap();
+        }
+        block_on(serializer.flush()).unwrap();
+        let sink = serializer.into_inner();
+        assert_eq!(sink.flushes, 1);
+        assert_eq!(sink.bytes.as_slice(), expected.as_ref());
+    }
+
+    #[test]
+    #[cfg(feature = "tokio_io")]
+    fn tokio_write_serializer() {
+        use rkyv::{
+            archived_value,
+            ser::serializers::{AsyncWriteSerializer, TokioSink},
+        };
+
+        let value = "hello async world".to_string();
+        let mut serializer = AsyncWriteSerializer::new(TokioSink(Vec::new()));
+        let pos = block_on(serializer.serialize_value_async(&value)).unwrap();
+        let bytes = serializer.into_inner().into_inner();
+
+        let mut buf = AlignedVec::new();
+        buf.extend_from_slice(&bytes);
+        let archived = unsafe { archived_value::<String>(buf.as_ref(), pos) };
+        assert_eq!(archived, &value);
+    }
+
+    #[test]
+    #[cfg(feature = "tokio_codec")]
+    fn frame_codec() {
+        use bytes::BytesMut;
+        use rkyv::frame::{ArchiveFrameCodec, FrameError, FRAME_HEADER_SIZE};
+        use tokio_util::codec::{Decoder, Encoder};
+
+        #[derive(Archive, Serialize)]
+        struct Message {
+            id: u32,
+            text: String,
+        }
+
+        let messages = (0..3)
+            .map(|id| Message {
+                id,
+                text: "message ".repeat(id as usize),
+            })
+            .collect::<Vec<_>>();
+
+        let mut codec = ArchiveFrameCodec::new();
+        let mut encoded = BytesMut::new();
+        for message in messages.iter() {
+            codec.encode(message, &mut encoded).unwrap();
+        }
+
+        // Frames are only decoded once all of their bytes have arrived
+        let mut src = BytesMut::new();
+        let mut decoded = Vec::new();
+        for byte in encoded.iter() {
+            src.extend_from_slice(&[*byte]);
+            if let Some(frame) = codec.decode(&mut src).unwrap() {
+                decoded.push(frame);
+            }
+        }
+        assert!(src.is_empty());
+        assert_eq!(decoded.len(), messages.len());
+        for (frame, message) in decoded.iter().zip(messages.iter()) {
+            let archived = unsafe { archived_root::<Message>(frame.as_ref()) };
+            assert_eq!(archived.id, message.id);
+            assert_eq!(archived.text, message.text);
+        }
+
+        // Headers are checked before the rest of the frame arrives
+        let mut codec = ArchiveFrameCodec::new().with_max_len(16);
+        let mut src = BytesMut::new();
+        src.extend_from_slice(&encoded[..FRAME_HEADER_SIZE]);
+        src[..4].copy_from_slice(&17u32.to_le_bytes());
+        match codec.decode(&mut src) {
+            Err(FrameError::FrameTooLarge {
+                len: 17,
+                max_len: 16,
+            }) => (),
+            result => panic!("expected frame too large, got {:?}", result),
+        }
+
+        src[..4].copy_from_slice(&0u32.to_le_bytes());
+        src[4..FRAME_HEADER_SIZE].copy_from_slice(&32u32.to_le_bytes());
+        match codec.decode(&mut src) {
+            Err(FrameError::UnsupportedAlignment { align: 32 }) => (),
+            result => panic!("expected unsupported alignment, got {:?}", result),
+        }
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn frame_reader_limits() {
+        use rkyv::frame::{
+            ArchiveFrameReader, ArchiveFrameWriter, FrameError, DEFAULT_MAX_FRAME_LEN,
+            FRAME_HEADER_SIZE, MAX_FRAME_LEN,
+        };
+        use std::io;
+
+        // Frames larger than the read chunk size are read whole
+        let payload = (0..=255u8).cycle().take(200_000).collect::<Vec<_>>();
+        let mut writer = ArchiveFrameWriter::new(Vec::new());
+        writer.write(&payload).unwrap();
+        let stream = writer.into_inner();
+        let mut reader = ArchiveFrameReader::new(stream.as_slice());
+        let archived = unsafe { reader.read_unchecked::<Vec<u8>>() }
+            .unwrap()
+            .unwrap();
+        assert_eq!(archived.as_slice(), payload.as_slice());
+
+        // Headers can't ask for more than the default limit
+        let mut header = [0u8; FRAME_HEADER_SIZE];
+        header[..4].copy_from_slice(&u32::MAX.to_le_bytes());
+        header[4..].copy_from_slice(&1u32.to_le_bytes());
+        let mut reader = ArchiveFrameReader::new(&header[..]);
+        match reader.read_frame() {
+            Err(FrameError::FrameTooLarge { len, max_len }) => {
+                assert_eq!(len, u32::MAX as usize);
+                assert_eq!(max_len, DEFAULT_MAX_FRAME_LEN);
+            }
+            result => panic!("expected frame too large, got {:?}", result),
+        }
+
+        // Even without a limit, memory is only allocated for bytes that arrive
+        let mut stream = header.to_vec();
+        stream.extend_from_slice(&[1, 2, 3]);
+        let mut reader = ArchiveFrameReader::new(stream.as_slice()).with_max_len(MAX_FRAME_LEN);
+        match reader.read_frame() {
+            Err(FrameError::Io(e)) => assert_eq!(e.kind(), io::ErrorKind::UnexpectedEof),
+            result => panic!("expected unexpected eof, got {:?}", result),
+        }
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn vectored_serializer() {
+        use rkyv::{
+            ser::serializers::{CompositeSerializer, VectoredSerializer},
+            Infallible,
+        };
+        use std::io;
+
+        // Writes at most a few bytes at a time
+        struct ShortWriter(Vec<u8>);
+
+        impl io::Write for ShortWriter {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                let len = buf.len().min(5);
+                self.0.extend_from_slice(&buf[..len]);
+                Ok(len)
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        #[derive(Archive, Serialize)]
+        struct Test {
+            id: u16,
+            title: String,
+            body: String,
+            tags: Vec<String>,
+            data: Vec<u8>,
+        }
+
+        let value = Test {
+            id: 7,
+            title: "short".to_string(),
+            body: "0123456789".repeat(1000),
+            tags: vec!["a".to_string(), "much longer tag".to_string()],
+            data: (0..=255).cycle().take(4096).collect(),
+        };
+
+        let mut expected = make_default_serializer();
+        expected.serialize_value(&value).unwrap();
+        let expected = unwrap_default_serializer(expected);
+
+        let mut vectored = VectoredSerializer::new();
+        vectored.lend(value.body.as_bytes());
+        vectored.lend(value.tags[1].as_bytes());
+        vectored.lend(value.data.as_slice());
+        let mut serializer = CompositeSerializer::new(vectored, AllocScratch::new(), Infallible);
+        serializer.serialize_value(&value).unwrap();
+        let vectored = serializer.into_serializer();
+
+        let slices = vectored.io_slices();
+        let borrowed = [
+            value.body.as_bytes(),
+            value.tags[1].as_bytes(),
+            value.data.as_slice(),
+        ];
+        for bytes in borrowed.iter() {
+            assert!(slices
+                .iter()
+                .any(|slice| slice.as_ptr() == bytes.as_ptr() && slice.len() == bytes.len()));
+        }
+        let copied = slices.iter().map(|slice| slice.len()).sum::<usize>()
+            - borrowed.iter().map(|bytes| bytes.len()).sum::<usize>();
+        assert!(copied < 128);
+
+        let mut writer = ShortWriter(Vec::new());
+        vectored.write_to(&mut writer).unwrap();
+        assert_eq!(writer.0.as_slice(), expected.as_ref());
+
+        let mut buf = AlignedVec::new();
+        vectored.write_to(&mut buf).unwrap();
+        let archived = unsafe { archived_root::<Test>(buf.as_ref()) };
+        assert_eq!(archived.id, 7);
+        assert_eq!(archived.title, "short");
+        assert_eq!(archived.body, value.body);
+        assert_eq!(archived.tags[1], "much longer tag");
+        assert_eq!(archived.data.as_slice(), value.data.as_slice());
+    }
+
+    #[test]
+    #[cfg(feature = "arena")]
+    fn bump_deserializer() {
+        use bumpalo::Bump;
+        use rkyv::de::{adapters::SharedDeserializerAdapter, deserializers::BumpDeserializer};
+        use std::rc::Rc;
+
+        #[derive(Archive, Serialize, Deserialize)]
+        struct Test<'a> {
+            arena_name: bumpalo::collections::String<'a>,
+            arena_values: bumpalo::collections::Vec<'a, bumpalo::collections::String<'a>>,
+            arena_boxed: bumpalo::boxed::Box<'a, [u64]>,
+            name: String,
+            values: Vec<String>,
+            boxed: Box<[u64]>,
+            shared: Rc<u32>,
+            shared_again: Rc<u32>,
+        }
+
+        let arena = Bump::new();
+        let mut arena_values = bumpalo::collections::Vec::new_in(&arena);
+        for i in 0..10 {
+            arena_values.push(bumpalo::collections::String::from_str_in(
+                &format!("arena string #{}", i),
+                &arena,
+            ));
+        }
+        let shared = Rc::new(42);
+        let value = Test {
+            arena_name: bumpalo::collections::String::from_str_in("arena", &arena),
+            arena_values,
+            arena_boxed: bumpalo::vec![in &arena; 1, 2, 3].into_boxed_slice(),
+            name: "global".to_string(),
+            values: (0..10).map(|i| format!("string #{}", i)).collect(),
+            boxed: vec![4, 5, 6].into_boxed_slice(),
+            shared: shared.clone(),
+            shared_again: shared,
+        };
+
+        let mut serializer = make_default_serializer();
+        serializer
+            .serialize_value(&value)
+            .expect("failed to archive value");
+        let buf = unwrap_default_serializer(serializer);
+        let archived = unsafe { archived_root::<Test>(buf.as_ref()) };
+
+        let deserialized_arena = Bump::new();
+        let mut deserializer =
+            SharedDeserializerAdapter::new(BumpDeserializer::new(&deserialized_arena));
+        let deserialized: Test = archived
+            .deserialize(&mut deserializer)
+            .expect("failed to deserialize value");
+
+        // Arena types are allocated from the arena
+        assert!(deserialized_arena.allocated_bytes() > 0);
+        assert_eq!(deserialized.arena_name, "arena");
+        assert_eq!(deserialized.arena_values.len(), 10);
+        for (i, s) in deserialized.arena_values.iter().enumerate() {
+            assert_eq!(s, &format!("arena string #{}", i));
+        }
+        assert_eq!(&*deserialized.arena_boxed, &[1, 2, 3]);
+
+        // Other types are moved into the global allocator and freed normally when dropped
+        assert_eq!(deserialized.name, "global");
+        assert_eq!(deserialized.values, value.values);
+        assert_eq!(&*deserialized.boxed, &[4, 5, 6]);
+        assert_eq!(*deserialized.shared, 42);
+        assert!(Rc::ptr_eq(&deserialized.shared, &deserialized.shared_again));
+        drop(deserialized);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn limited_deserializer() {
+        use rkyv::de::adapters::{LimitedDeserializer, LimitedDeserializerError};
+        use std::{collections::HashMap, rc::Rc};
+
+        #[derive(Archive, Serialize, Deserialize)]
+        #[allow(clippy::redundant_allocation)]
+        struct Test {
+            names: Vec<String>,
+            shared: Rc<[u32; 16]>,
+            shared_again: Rc<[u32; 16]>,
+            nested: Box<Box<Box<u32>>>,
+        }
+
+        let shared = Rc::new([7; 16]);
+        let value = Test {
+            names: (0..4).map(|i| format!("name #{}", i)).collect(),
+            shared: shared.clone(),
+            shared_again: shared,
+            nested: Box::new(Box::new(Box::new(42))),
+        };
+
+        let mut serializer = make_default_serializer();
+        serializer
+            .serialize_value(&value)
+            .expect("failed to archive value");
+        let buf = unwrap_default_serializer(serializer);
+        let archived = unsafe { archived_root::<Test>(buf.as_ref()) };
+
+        // 4 strings, the names vec, one shared array and three boxes
+        let strings_size = value.names.iter().map(|s| s.len()).sum::<usize>();
+        let alloc_size = strings_size
+            + 4 * core::mem::size_of::<String>()
+            + core::mem::size_of::<[u32; 16]>()
+            + core::mem::size_of::<Box<Box<u32>>>()
+            + core::mem::size_of::<Box<u32>>()
+            + core::mem::size_of::<u32>();
+        let element_count = strings_size + 4 + 1 + 3;
+
+        let mut deserializer = SharedDeserializerAdapter::new(
+            LimitedDeserializer::new(AllocDeserializer, alloc_size)
+                .with_max_elements(element_count)
+                .with_max_depth(3),
+        );
+        let deserialized: Test = archived
+            .deserialize(&mut deserializer)
+            .expect("failed to deserialize value");
+        assert_eq!(deserialized.names, value.names);
+        assert!(Rc::ptr_eq(&deserialized.shared, &deserialized.shared_again));
+        assert_eq!(***deserialized.nested, 42);
+        // The shared array only counts against the budgets once
+        let deserializer = deserializer.into_inner();
+        assert_eq!(deserializer.allocated(), alloc_size);
+        assert_eq!(deserializer.elements(), element_count);
+
+        let mut deserializer = SharedDeserializerAdapter::new(LimitedDeserializer::new(
+            AllocDeserializer,
+            alloc_size - 1,
+        ));
+        match Deserialize::<Test, _>::deserialize(archived, &mut deserializer) {
+            Err(LimitedDeserializerError::AllocLimitExceeded { limit, requested }) => {
+                assert_eq!(limit, alloc_size - 1);
+                assert_eq!(requested, alloc_size);
+            }
+            result => panic!("expected alloc limit error, got {:?}", result.map(|_| ())),
+        }
+
+        let mut deserializer = SharedDeserializerAdapter::new(
+            LimitedDeserializer::new(AllocDeserializer, usize::MAX)
+                .with_max_elements(element_count - 1),
+        );
+        match Deserialize::<Test, _>::deserialize(archived, &mut deserializer) {
+            Err(LimitedDeserializerError::ElementLimitExceeded { limit, count }) => {
+                assert_eq!(limit, element_count - 1);
+                assert_eq!(count, element_count);
+            }
+            result => panic!("expected element limit error, got {:?}", result.map(|_| ())),
+        }
+
+        let mut deserializer = SharedDeserializerAdapter::new(
+            LimitedDeserializer::new(AllocDeserializer, usize::MAX).with_max_depth(2),
+        );
+        match Deserialize::<Test, _>::deserialize(archived, &mut deserializer) {
+            Err(LimitedDeserializerError::DepthLimitExceeded { limit: 2 }) => (),
+            result => panic!("expected depth limit error, got {:?}", result.map(|_| ())),
+        }
+        // Values that fail to deserialize still restore the depth
+        let names: Vec<String> = archived
+            .names
+            .deserialize(&mut deserializer)
+            .expect("failed to deserialize names after depth limit error");
+        assert_eq!(names, value.names);
+
+        // Hash maps allocate their own memory but still count against the budget
+        let map = (0..16u32).map(|i| (i, i * 2)).collect::<HashMap<_, _>>();
+        let mut serializer = make_default_serializer();
+        serializer
+            .serialize_value(&map)
+            .expect("failed to archive value");
+        let buf = unwrap_default_serializer(serializer);
+        let archived = unsafe { archived_root::<HashMap<u32, u32>>(buf.as_ref()) };
+        let map_size = 16 * core::mem::size_of::<(u32, u32)>();
+
+        let mut deserializer = LimitedDeserializer::new(AllocDeserializer, map_size);
+        let deserialized: HashMap<u32, u32> = archived
+            .deserialize(&mut deserializer)
+            .expect("failed to deserialize value");
+        assert_eq!(deserialized, map);
+        assert_eq!(deserializer.allocated(), map_size);
+        assert_eq!(deserializer.elements(), 16);
+
+        let mut deserializer = LimitedDeserializer::new(AllocDeserializer, map_size - 1);
+        match Deserialize::<HashMap<u32, u32>, _>::deserialize(archived, &mut deserializer) {
+            Err(LimitedDeserializerError::AllocLimitExceeded { limit, requested }) => {
+                assert_eq!(limit, map_size - 1);
+                assert_eq!(requested, map_size);
+            }
+            result => panic!("expected alloc limit error, got {:?}", result.map(|_| ())),
+        }
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn path_deserializer() {
+        use rkyv::de::{
+            adapters::{LimitedDeserializer, LimitedDeserializerError, PathDeserializer},
+            PathSegment,
+        };
+        use std::collections::{HashMap, HashSet};
+
+        #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+        #[archive(track_path)]
+        struct Item {
+            name: String,
+            count: u32,
+        }
+
+        #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+        #[archive(track_path)]
+        enum Event {
+            Say { message: String },
+            Pickup(Item),
+        }
+
+        #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+        #[archive(track_path)]
+        struct Player {
+            name: String,
+            inventory: Vec<Item>,
+            events: Vec<Event>,
+        }
+
+        fn item(name_len: usize) -> Item {
+            Item {
+                name: "x".repeat(name_len),
+                count: name_len as u32,
+            }
+        }
+
+        let value = Player {
+            name: "player".to_string(),
+            inventory: (0..12).map(|_| item(0)).chain(Some(item(100))).collect(),
+            events: Vec::new(),
+        };
+
+        let mut serializer = make_default_serializer();
+        serializer
+            .serialize_value(&value)
+            .expect("failed to archive value");
+        let buf = unwrap_default_serializer(serializer);
+        let archived = unsafe { archived_root::<Player>(buf.as_ref()) };
+
+        let mut deserializer = SharedDeserializerAdapter::new(PathDeserializer::new(
+            LimitedDeserializer::new(AllocDeserializer, usize::MAX),
+        ));
+        let deserialized: Player = archived
+            .deserialize(&mut deserializer)
+            .expect("failed to deserialize value");
+        assert_eq!(deserialized, value);
+
+        let mut deserializer = SharedDeserializerAdapter::new(PathDeserializer::new(
+            LimitedDeserializer::new(AllocDeserializer, usize::MAX).with_max_elements(50),
+        ));
+        match Deserialize::<Player, _>::deserialize(archived, &mut deserializer) {
+            Err(e) => {
+                assert!(matches!(
+                    e.inner(),
+                    LimitedDeserializerError::ElementLimitExceeded { limit: 50, .. }
+                ));
+                assert_eq!(e.path().to_string(), "Player.inventory[12].name");
+                assert!(e.to_string().ends_with(" at Player.inventory[12].name"));
+            }
+            result => panic!("expected element limit error, got {:?}", result.map(|_| ())),
+        }
+
+        let value = Player {
+            name: "player".to_string(),
+            inventory: Vec::new(),
+            events: vec![
+                Event::Say {
+                    message: "hello".to_string(),
+                },
+                Event::Pickup(item(100)),
+            ],
+        };
+
+        let mut serializer = make_default_serializer();
+        serializer
+            .serialize_value(&value)
+            .expect("failed to archive value");
+        let buf = unwrap_default_serializer(serializer);
+        let archived = unsafe { archived_root::<Player>(buf.as_ref()) };
+
+        let mut deserializer = PathDeserializer::new(
+            LimitedDeserializer::new(AllocDeserializer, usize::MAX).with_max_elements(50),
+        );
+        match Deserialize::<Player, _>::deserialize(archived, &mut deserializer) {
+            Err(e) => {
+                assert_eq!(e.path().to_string(), "Player.events[1]::Pickup.0.name");
+                assert_eq!(
+                    e.path().segments(),
+                    &[
+                        PathSegment::Type("Player"),
+                        PathSegment::Field("events"),
+                        PathSegment::Index(1),
+                        PathSegment::Type("Event"),
+                        PathSegment::Variant("Pickup"),
+                        PathSegment::Field("0"),
+                        PathSegment::Type("Item"),
+                        PathSegment::Field("name"),
+                    ]
+                );
+            }
+            result => panic!("expected element limit error, got {:?}", result.map(|_| ())),
+        }
+
+        // Hash map and hash set paths include the index of the entry and whether the key or value
+        // failed
+        let value = vec![
+            HashMap::new(),
+            Some(("key".to_string(), "x".repeat(100))).into_iter().collect(),
+        ];
+
+        let mut serializer = make_default_serializer();
+        serializer
+            .serialize_value(&value)
+            .expect("failed to archive value");
+        let buf = unwrap_default_serializer(serializer);
+        let archived = unsafe { archived_root::<Vec<HashMap<String, String>>>(buf.as_ref()) };
+
+        let mut deserializer = PathDeserializer::new(
+            LimitedDeserializer::new(AllocDeserializer, usize::MAX).with_max_elements(50),
+        );
+        match Deserialize::<Vec<HashMap<String, String>>, _>::deserialize(
+            archived,
+            &mut deserializer,
+        ) {
+            Err(e) => assert_eq!(e.path().to_string(), "[1][0].value"),
+            result => panic!("expected element limit error, got {:?}", result.map(|_| ())),
+        }
+
+        let value = vec![Some("x".repeat(100)).into_iter().collect::<HashSet<_>>()];
+
+        let mut serializer = make_default_serializer();
+        serializer
+            .serialize_value(&value)
+            .expect("failed to archive value");
+        let buf = unwrap_default_serializer(serializer);
+        let archived = unsafe { archived_root::<Vec<HashSet<String>>>(buf.as_ref()) };
+
+        let mut deserializer = PathDeserializer::new(
+            LimitedDeserializer::new(AllocDeserializer, usize::MAX).with_max_elements(50),
+        );
+        match Deserialize::<Vec<HashSet<String>>, _>::deserialize(archived, &mut deserializer) {
+            Err(e) => assert_eq!(e.path().to_string(), "[0][0]"),
+            result => panic!("expected element limit error, got {:?}", result.map(|_| ())),
+        }
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn deserialize_into() {
+        use rkyv::DeserializeInto;
+        use std::collections::HashMap;
+
+        #[derive(
+            Archive, Serialize, Deserialize, DeserializeInto, Clone, Copy, Debug, PartialEq,
+        )]
+        #[archive(copy)]
+        struct Position {
+            x: f32,
+            y: f32,
+        }
+
+        #[derive(Archive, Serialize, Deserialize, DeserializeInto, Clone, Debug, PartialEq)]
+        struct Marker;
+
+        #[derive(Archive, Serialize, Deserialize, DeserializeInto, Clone, Debug, PartialEq)]
+        struct Id(u32, Marker);
+
+        #[derive(Archive, Serialize, Deserialize, DeserializeInto, Clone, Debug, PartialEq)]
+        enum State {
+            Idle,
+            Moving { target: Position, path: Vec<u32> },
+            Named(String),
+        }
+
+        #[derive(Archive, Serialize, Deserialize, DeserializeInto, Clone, Debug, PartialEq)]
+        struct Entity {
+            id: Id,
+            name: String,
+            position: Position,
+            state: State,
+            parent: Option<Box<Position>>,
+        }
+
+        #[derive(Archive, Serialize, Deserialize, DeserializeInto, Clone, Debug, PartialEq)]
+        struct World {
+            tick: u64,
+            entities: Vec<Entity>,
+            groups: HashMap<String, Vec<u32>>,
+        }
+
+        fn entity(i: u32, state: State) -> Entity {
+            Entity {
+                id: Id(i, Marker),
+                name: format!("entity #{}", i),
+                position: Position {
+                    x: i as f32,
+                    y: -(i as f32),
+                },
+                state,
+                parent: Some(Box::new(Position {
+                    x: -(i as f32),
+                    y: i as f32,
+                })),
+            }
+        }
+
+        fn deserialize_into_world(value: &World, out: &mut World) {
+            let mut serializer = make_default_serializer();
+            serializer
+                .serialize_value(value)
+                .expect("failed to archive value");
+            let buf = unwrap_default_serializer(serializer);
+            let archived = unsafe { archived_root::<World>(buf.as_ref()) };
+            archived
+                .deserialize_into(out, &mut make_default_deserializer())
+                .expect("failed to deserialize value");
+            assert_eq!(out, value);
+        }
+
+        let mut groups = HashMap::new();
+        groups.insert("a".to_string(), vec![1, 2, 3]);
+        groups.insert("b".to_string(), vec![4, 5, 6]);
+        let mut value = World {
+            tick: 1,
+            entities: vec![
+                entity(
+                    0,
+                    State::Moving {
+                        target: Position { x: 1.0, y: 2.0 },
+                        path: vec![1, 2, 3, 4],
+                    },
+                ),
+                entity(1, State::Idle),
+                entity(2, State::Named("two".to_string())),
+            ],
+            groups,
+        };
+
+        let mut world = World {
+            tick: 0,
+            entities: Vec::new(),
+            groups: HashMap::new(),
+        };
+        deserialize_into_world(&value, &mut world);
+
+        // Same shape: every allocation is reused
+        let entities_ptr = world.entities.as_ptr();
+        let name_ptr = world.entities[0].name.as_ptr();
+        let path_ptr = match world.entities[0].state {
+            State::Moving { ref path, .. } => path.as_ptr(),
+            _ => unreachable!(),
+        };
+        let parent_ptr = &**world.entities[0].parent.as_ref().unwrap() as *const Position;
+        let group_ptr = world.groups["a"].as_ptr();
+
+        value.tick = 2;
+        value.entities[0].name = "renamed".to_string();
+        value.entities[0].state = State::Moving {
+            target: Position { x: 3.0, y: 4.0 },
+            path: vec![5, 6],
+        };
+        value.groups.get_mut("a").unwrap().truncate(1);
+        deserialize_into_world(&value, &mut world);
+
+        assert_eq!(world.entities.as_ptr(), entities_ptr);
+        assert_eq!(world.entities[0].name.as_ptr(), name_ptr);
+        match world.entities[0].state {
+            State::Moving { ref path, .. } => assert_eq!(path.as_ptr(), path_ptr),
+            _ => unreachable!(),
+        }
+        assert_eq!(
+            &**world.entities[0].parent.as_ref().unwrap() as *const Position,
+            parent_ptr
+        );
+        assert_eq!(world.groups["a"].as_ptr(), group_ptr);
+
+        // Different shapes: variants change, entities are removed and keys are replaced
+        value.entities[0].state = State::Named("zero".to_string());
+        value.entities[1].state = State::Moving {
+            target: Position { x: 0.0, y: 0.0 },
+            path: Vec::new(),
+        };
+        value.entities[1].parent = None;
+        value.entities.pop();
+        value.groups.remove("b");
+        value.groups.insert("c".to_string(), vec![7]);
+        deserialize_into_world(&value, &mut world);
+        assert_eq!(world.entities.as_ptr(), entities_ptr);
+
+        // Growing reuses existing elements and deserializes the rest
+        value.entities.push(entity(3, State::Idle));
+        value.entities.push(entity(4, State::Idle));
+        value.groups.clear();
+        deserialize_into_world(&value, &mut world);
+        assert_eq!(world.entities[0].name.as_ptr(), name_ptr);
+    }
+
+    #[test]
+    fn partial_deserialize() {
+        use rkyv::lazy::Lazy;
+
+        #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+        #[archive(deserialize_fields)]
+        struct Item<T> {
+            name: String,
+            value: T,
+        }
+
+        #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+        #[archive(deserialize_fields)]
+        struct Pair(u32, Vec<u32>);
+
+        #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+        #[archive(deserialize_fields)]
+        struct Player {
+            name: String,
+            pair: Pair,
+            inventory: Vec<Item<u32>>,
+            history: Lazy<Vec<String>>,
+        }
+
+        let value = Player {
+            name: "player".to_string(),
+            pair: Pair(1, vec![2, 3]),
+            inventory: vec![
+                Item {
+                    name: "sword".to_string(),
+                    value: 10,
+                },
+                Item {
+                    name: "shield".to_string(),
+                    value: 20,
+                },
+            ],
+            history: Lazy::new(vec!["joined".to_string(), "left".to_string()]),
+        };
+
+        let mut serializer = make_default_serializer();
+        serializer
+            .serialize_value(&value)
+            .expect("failed to archive value");
+        let buf = unwrap_default_serializer(serializer);
+        let archived = unsafe { archived_root::<Player>(buf.as_ref()) };
+        let mut deserializer = make_default_deserializer();
+
+        assert_eq!(
+            archived.deserialize_name(&mut deserializer).unwrap(),
+            "player"
+        );
+        assert_eq!(
+            archived.pair.deserialize_1(&mut deserializer).unwrap(),
+            vec![2, 3]
+        );
+        assert_eq!(
+            archived.deserialize_inventory(&mut deserializer).unwrap(),
+            value.inventory
+        );
+        assert_eq!(
+            archived.inventory[1]
+                .deserialize_name(&mut deserializer)
+                .unwrap(),
+            "shield"
+        );
+
+        // Lazy subtrees can be read from the archive and loaded on demand
+        let archived_history = archived.history.get().unwrap();
+        assert_eq!(archived_history.len(), 2);
+        assert_eq!(archived_history[0], "joined");
+
+        let mut history = Lazy::<Vec<String>>::unloaded();
+        assert!(!history.is_loaded());
+        history
+            .load(&archived.history, &mut deserializer)
+            .unwrap()
+            .unwrap()
+            .push("rejoined".to_string());
+        assert_eq!(history.get().unwrap().len(), 3);
+
+        // Deserializing loads lazy subtrees so that they survive a round trip
+        let deserialized: Player = archived.deserialize(&mut deserializer).unwrap();
+        assert_eq!(deserialized, value);
+
+        let mut serializer = make_default_serializer();
+        serializer
+            .serialize_value(&deserialized)
+            .expect("failed to archive value");
+        let buf = unwrap_default_serializer(serializer);
+        let archived = unsafe { archived_root::<Player>(buf.as_ref()) };
+        let archived_history = archived.history.get().unwrap();
+        assert_eq!(archived_history.len(), 2);
+        assert_eq!(archived_history[1], "left");
+
+        // Unloaded subtrees stay unloaded through a round trip
+        let unloaded = Player {
+            history: Lazy::unloaded(),
+            ..value
+        };
+        let mut serializer = make_default_serializer();
+        serializer
+            .serialize_value(&unloaded)
+            .expect("failed to archive value");
+        let buf = unwrap_default_serializer(serializer);
+        let archived = unsafe { archived_root::<Player>(buf.as_ref()) };
+        assert!(archived.history.get().is_none());
+        let mut history = Lazy::<Vec<String>>::unloaded();
+        assert!(history
+            .load(&archived.history, &mut deserializer)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn archived_serde() {
+        use core::time::Duration;
+        use rkyv::lazy::Lazy;
+        use std::{collections::HashMap, net::Ipv4Addr};
+
+        macro_rules! assert_json {
+            ($ty:ty, $value:expr, $expected:expr) => {{
+                let mut serializer = make_default_serializer();
+                serializer
+                    .serialize_value(&$value)
+                    .expect("failed to archive value");
+                let buf = unwrap_default_serializer(serializer);
+                let archived = unsafe { archived_root::<$ty>(buf.as_ref()) };
+                assert_eq!(serde_json::to_string(archived).unwrap(), $expected);
+            }};
+        }
+
+        // Builtin archived types serialize the same way as their unarchived counterparts
+        let value = vec![Some("a".to_string()), None];
+        assert_json!(
+            Vec<Option<String>>,
+            value,
+            serde_json::to_string(&value).unwrap()
+        );
+        let mut value = HashMap::new();
+        value.insert("key".to_string(), vec![1u32, 2, 3]);
+        assert_json!(
+            HashMap<String, Vec<u32>>,
+            value,
+            serde_json::to_string(&value).unwrap()
+        );
+        let value = (
+            Duration::new(5, 6),
+            1u32..4,
+            Box::<str>::from("boxed"),
+            Ipv4Addr::new(127, 0, 0, 1),
+        );
+        assert_json!(
+            (Duration, core::ops::Range<u32>, Box<str>, Ipv4Addr),
+            value,
+            serde_json::to_string(&value).unwrap()
+        );
+        assert_json!(
+            (Lazy<u32>, Lazy<u32>),
+            (Lazy::new(1u32), Lazy::<u32>::unloaded()),
+            "[1,null]"
+        );
+
+        #[derive(Archive, Serialize)]
+        #[archive(serde)]
+        struct Unit;
+
+        #[derive(Archive, Serialize)]
+        #[archive(serde)]
+        struct Newtype(String);
+
+        #[derive(Archive, Serialize)]
+        #[archive(serde)]
+        struct Tuple(u32, String);
+
+        #[derive(Archive, Serialize)]
+        #[archive(serde)]
+        enum Shape {
+            Empty,
+            Circle(f32),
+            Rect(f32, f32),
+            Polygon { points: Vec<(f32, f32)> },
+        }
+
+        #[derive(Archive, Serialize)]
+        #[archive(serde)]
+        struct Drawing<T> {
+            name: String,
+            shapes: Vec<Shape>,
+            extra: T,
+        }
+
+        assert_json!(Unit, Unit, "null");
+        assert_json!(Newtype, Newtype("hello".to_string()), "\"hello\"");
+        assert_json!(Tuple, Tuple(1, "one".to_string()), "[1,\"one\"]");
+        assert_json!(
+            Drawing<(Unit, Newtype, Tuple)>,
+            Drawing {
+                name: "drawing".to_string(),
+                shapes: vec![
+                    Shape::Empty,
+                    Shape::Circle(1.0),
+                    Shape::Rect(2.0, 3.0),
+                    Shape::Polygon {
+                        points: vec![(0.0, 0.0), (1.0, 1.0)],
+                    },
+                ],
+                extra: (Unit, Newtype("a".to_string()), Tuple(2, "b".to_string())),
+            },
+            concat!(
+                r#"{"name":"drawing","shapes":["Empty",{"Circle":1.0},{"Rect":[2.0,3.0]},"#,
+                r#"{"Polygon":{"points":[[0.0,0.0],[1.0,1.0]]}}],"#,
+                r#""extra":[null,"a",[2,"b"]]}"#,
+            )
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn convert_from_serde() {
+        use rkyv::{
+            convert::{serialize_from, write_frames_from, ConvertError},
+            frame::{ArchiveFrameReader, ArchiveFrameWriter},
+        };
+        use std::collections::HashMap;
+
+        type Inventory = HashMap<String, Vec<Option<u32>>>;
+
+        let json = r#"{"sword":[1,null,3]}"#;
+        let mut serializer = make_default_serializer();
+        serialize_from::<Inventory, _, _>(
+            &mut serde_json::Deserializer::from_str(json),
+            &mut serializer,
+        )
+        .unwrap();
+        let buf = unwrap_default_serializer(serializer);
+        let archived = unsafe { archived_root::<Inventory>(buf.as_ref()) };
+        assert_eq!(archived.len(), 1);
+        assert_eq!(
+            archived.get("sword").unwrap().as_slice(),
+            &[Some(1), None, Some(3)]
+        );
+        // Archived values serialize back to the same JSON
+        assert_eq!(serde_json::to_string(archived).unwrap(), json);
+
+        let mut serializer = make_default_serializer();
+        let result = serialize_from::<Inventory, _, _>(
+            &mut serde_json::Deserializer::from_str(r#"{"sword":"none"}"#),
+            &mut serializer,
+        );
+        assert!(matches!(result, Err(ConvertError::Deserialize(_))));
+
+        // Sequences are streamed into one frame per element
+        let json = r#"[{"a":[1]},{},{"b":[null],"c":[2,3]}]"#;
+        let mut writer = ArchiveFrameWriter::new(Vec::new());
+        let count = write_frames_from::<Inventory, _, _>(
+            &mut serde_json::Deserializer::from_str(json),
+            &mut writer,
+        )
+        .unwrap();
+        assert_eq!(count, 3);
+
+        let stream = writer.into_inner();
+        let mut reader = ArchiveFrameReader::new(stream.as_slice());
+        let mut lens = Vec::new();
+        while let Some(inventory) = unsafe { reader.read_unchecked::<Inventory>() }.unwrap() {
+            lens.push(inventory.len());
+        }
+        assert_eq!(lens, vec![1, 0, 2]);
+
+        let mut writer = ArchiveFrameWriter::new(Vec::new());
+        let result = write_frames_from::<Inventory, _, _>(
+            &mut serde_json::Deserializer::from_str(r#"[{"a":[1]},{"b":"c"}]"#),
+            &mut writer,
+        );
+        assert!(matches!(result, Err(ConvertError::Deserialize(_))));
+    }
+
+    #[test]
+    #[cfg(feature = "mmap")]
+    fn mmap_serializer() {
+        use rkyv::{
+            archived_value,
+            ser::serializers::{CompositeSerializer, MmapSerializer, SharedSerializeMap},
+            MmapArchive,
+        };
+        use std::fs::{self, OpenOptions};
+
+        #[derive(Archive, Serialize)]
+        #[archive(compare(PartialEq))]
+        struct Test {
+            a: i32,
+            b: Vec<String>,
+            c: Option<Box<[u64]>>,
+        }
+
+        let value = Test {
+            a: 42,
+            b: (0..100).map(|i| format!("string #{}", i)).collect(),
+            c: Some((0..100).collect::<Vec<_>>().into_boxed_slice()),
+        };
+
+        let path = std::env::temp_dir().join(format!("rkyv_test_mmap_{}", std::process::id()));
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&path)
+            .expect("failed to open file");
+
+        // Start with a tiny map so the file has to grow several times
+        let mut serializer = CompositeSerializer::new(
+            MmapSerializer::with_capacity(file, 16).expect("failed to map file"),
+            AllocScratch::new(),
+            SharedSerializeMap::new(),
+        );
+        let pos = serializer
+            .serialize_front(&value)
+            .expect("failed to archive value");
+        assert_eq!(pos, 0);
+        let serializer = serializer.into_serializer();
+        let capacity = serializer.capacity();
+        assert!(capacity > 16);
+        let file = serializer.finish().expect("failed to finish serializer");
+
+        // The file is truncated to the end of the archive, not to the last written position
+        let len = file.metadata().unwrap().len() as usize;
+        assert!(len > core::mem::size_of::<Archived<Test>>() && len <= capacity);
+
+        let archive = unsafe { MmapArchive::map(&file).expect("failed to map file") };
+        assert_eq!(archive.len(), len);
+        let archived = unsafe { archived_value::<Test>(&archive, pos) };
+        assert!(*archived == value);
+
+        drop(archive);
+        drop(file);
+        fs::remove_file(&path).expect("failed to remove file");
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn archive_more_std() {
+        use core::{
+            num::NonZeroU8,
+            ops::Range,
+            sync::atomic::{AtomicU32, Ordering},
+        };
+
+        #[derive(Archive, Serialize, Deserialize)]
+        struct Test {
+            a: AtomicU32,
+            b: Range<i32>,
+            c: NonZeroU8,
+        }
+
+        impl PartialEq for Test {
+            fn eq(&self, other: &Self) -> bool {
+                self.a.load(Ordering::Relaxed) == other.a.load(Ordering::Relaxed)
+                    && self.b == other.b
+                    && self.c == other.c
+            }
+        }
+
+        // Can't derive PartialEq automatically because AtomicU32 doesn't implement PartialEq
+        impl PartialEq<Test> for Archived<Test> {
+            fn eq(&self, other: &Test) -> bool {
+                self.a.load(Ordering::Relaxed) == other.a.load(Ordering::Relaxed)
+                    && self.b == other.b
+                    && self.c == other.c
+            }
+        }
+
+        let value = Test {
+            a: AtomicU32::new(42),
+            b: Range { start: 14, end: 46 },
+            c: NonZeroU8::new(8).unwrap(),
+        };
+
+        test_archive(&value);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn archive_net() {
+        use std::net::{Ipv4Addr, Ipv6Addr, IpAddr, SocketAddrV4, SocketAddrV6, SocketAddr};
+
+        #[derive(Archive, Serialize, Deserialize, PartialEq)]
+        #[archive(compare(PartialEq))]
+        struct TestNet {
+            ipv4: Ipv4Addr,
+            ipv6: Ipv6Addr,
+            ip: IpAddr,
+            sockv4: SocketAddrV4,
+            sockv6: SocketAddrV6,
+            sock: SocketAddr,
+        }
+
+        let value = TestNet {
+            ipv4: Ipv4Addr::new(31, 41, 59, 26),
+            ipv6: Ipv6Addr::new(31, 41, 59, 26, 53, 58, 97, 93),
+            ip: IpAddr::V4(Ipv4Addr::new(31, 41, 59, 26)),
+            sockv4: SocketAddrV4::new(Ipv4Addr::new(31, 41, 59, 26), 5358),
+            sockv6: SocketAddrV6::new(
+                Ipv6Addr::new(31, 31, 59, 26, 53, 58, 97, 93),
+                2384,
+                0,
+                0,
+            ),
+            sock: SocketAddr::V6(SocketAddrV6::new(
+                Ipv6Addr::new(31, 31, 59, 26, 53, 58, 97, 93),
+                2384,
+                0,
+                0,
+            )),
+        };
+
+        test_archive(&value);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn archive_shared_ptr() {
+        use std::rc::Rc;
+
+        #[derive(Archive, Deserialize, Serialize, Eq, PartialEq)]
+        #[archive(compare(PartialEq))]
+        struct Test {
+            a: Rc<u32>,
+            b: Rc<u32>,
+        }
+
+        impl ArchivedTest {
+            fn a(self: Pin<&mut Self>) -> Pin<&mut Archived<Rc<u32>>> {
+                unsafe { self.map_unchecked_mut(|s| &mut s.a) }
+            }
+
+            fn b(self: Pin<&mut Self>) -> Pin<&mut Archived<Rc<u32>>> {
+                unsafe { self.map_unchecked_mut(|s| &mut s.b) }
+            }
+        }
+
+        let shared = Rc::new(10);
+        let value = Test {
+            a: shared.clone(),
+            b: shared.clone(),
+        };
+
+        let mut serializer =
+            SharedSerializerAdapter::new(AlignedSerializer::new(AlignedVec::new()));
+        serializer
+            .serialize_value(&value)
+            .expect("failed to archive value");
+        let mut buf = serializer.into_inner().into_inner();
+
+        let archived = unsafe { archived_root::<Test>(buf.as_ref()) };
+        assert!(archived == &value);
+
+        let mut mutable_archived =
+            unsafe { archived_root_mut::<Test>(Pin::new_unchecked(buf.as_mut())) };
+        unsafe {
+            *mutable_archived.as_mut().a().get_pin_unchecked() = 42;
+        }
+
+        let archived = unsafe { archived_root::<Test>(buf.as_ref()) };
+        assert_eq!(*archived.a, 42);
+        assert_eq!(*archived.b, 42);
+
+        let mut mutable_archived =
+            unsafe { archived_root_mut::<Test>(Pin::new_unchecked(buf.as_mut())) };
+        unsafe {
+            *mutable_archived.as_mut().b().get_pin_unchecked() = 17;
+        }
+
+        let archived = unsafe { archived_root::<Test>(buf.as_ref()) };
+        assert_eq!(*archived.a, 17);
+        assert_eq!(*archived.b, 17);
+
+        let mut deserializer = SharedDeserializerAdapter::new(AllocDeserializer);
+        let deserialized = archived.deserialize(&mut deserializer).unwrap();
+
+        assert_eq!(*deserialized.a, 17);
+        assert_eq!(*deserialized.b, 17);
+        assert_eq!(
+            &*deserialized.a as *const u32,
+            &*deserialized.b as *const u32
+        );
+        assert_eq!(Rc::strong_count(&deserialized.a), 3);
+        assert_eq!(Rc::strong_count(&deserialized.b), 3);
+        assert_eq!(Rc::weak_count(&deserialized.a), 0);
+        assert_eq!(Rc::weak_count(&deserialized.b), 0);
+
+        core::mem::drop(deserializer);
+
+        assert_eq!(*deserialized.a, 17);
+        assert_eq!(*deserialized.b, 17);
+        assert_eq!(
+            &*deserialized.a as *const u32,
+            &*deserialized.b as *const u32
+        );
+        assert_eq!(Rc::strong_count(&deserialized.a), 2);
+        assert_eq!(Rc::strong_count(&deserialized.b), 2);
+        assert_eq!(Rc::weak_count(&deserialized.a), 0);
+        assert_eq!(Rc::weak_count(&deserialized.b), 0);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn archive_unsized_shared_ptr() {
+        use std::rc::Rc;
+
+        #[derive(Archive, Serialize, Deserialize, PartialEq)]
+        #[archive(compare(PartialEq))]
+        struct Test {
+            a: Rc<[String]>,
+            b: Rc<[String]>,
+        }
+
+        let rc_slice =
+            Rc::<[String]>::from(vec!["hello".to_string(), "world".to_string()].into_boxed_slice());
+        let value = Test {
+            a: rc_slice.clone(),
+            b: rc_slice,
+        };
+
+        test_archive(&value);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn archive_unsized_struct() {
+        #[derive(Archive, Serialize, Deserialize)]
+        #[archive(debug)]
+        #[repr(C)]
+        struct Block<H> {
+            head: H,
+            tail: [i32],
+        }
+
+        #[derive(Archive, Serialize, Deserialize)]
+        #[archive(debug)]
+        #[repr(C)]
+        struct Label(u8, str);
+
+        #[derive(Archive, Serialize, Deserialize)]
+        struct Test {
+            block: Box<Block<String>>,
+            label: Box<Label>,
+        }
+
+        // Unsized structs have to be built from sized structs with the same layout
+        #[repr(C)]
+        struct SizedBlock<H> {
+            head: H,
+            tail: [i32; 4],
+        }
+
+        #[repr(C)]
+        struct SizedLabel(u8, [u8; 5]);
+
+        let block = Box::new(SizedBlock {
+            head: "Numbers 1-4".to_string(),
+            tail: [1, 2, 3, 4],
+        });
+        let label = Box::new(SizedLabel(42, *b"hello"));
+        let value = unsafe {
+            Test {
+                block: Box::from_raw(ptr_meta::from_raw_parts_mut(
+                    Box::into_raw(block).cast(),
+                    4,
+                )),
+                label: Box::from_raw(ptr_meta::from_raw_parts_mut(
+                    Box::into_raw(label).cast(),
+                    5,
+                )),
+            }
+        };
+
+        let mut serializer = AlignedSerializer::new(AlignedVec::new());
+        serializer
+            .serialize_value(&value)
+            .expect("failed to archive value");
+        let buf = serializer.into_inner();
+
+        let archived = unsafe { archived_root::<Test>(buf.as_ref()) };
+        assert_eq!(archived.block.head, "Numbers 1-4");
+        assert_eq!(archived.block.tail, [1, 2, 3, 4]);
+        assert_eq!(archived.label.0, 42);
+        assert_eq!(&archived.label.1, "hello");
+        assert_eq!(
+            format!("{:?}", archived.block),
+            "Block { head: \"Numbers 1-4\", tail: [1, 2, 3, 4] }"
+        );
+        assert_eq!(format!("{:?}", archived.label), "Label(42, \"hello\")");
+
+        let deserialized = archived.deserialize(&mut AllocDeserializer).unwrap();
+        assert_eq!(deserialized.block.head, "Numbers 1-4");
+        assert_eq!(deserialized.block.tail, [1, 2, 3, 4]);
+        assert_eq!(deserialized.label.0, 42);
+        assert_eq!(&deserialized.label.1, "hello");
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn deserialize_unsized_struct_error() {
+        use core::{alloc::Layout, cell::Cell};
+        use rkyv::Fallible;
+
+        thread_local! {
+            static DROPPED: Cell<usize> = const { Cell::new(0) };
+        }
+
+        #[derive(Archive, Serialize, Deserialize)]
+        struct Item(Box<u32>);
+
+        impl Drop for Item {
+            fn drop(&mut self) {
+                DROPPED.with(|d| d.set(d.get() + 1));
+            }
+        }
+
+        #[derive(Archive, Serialize, Deserialize)]
+        #[repr(C)]
+        struct Block {
+            head: Item,
+            tail: [Item],
+        }
+
+        #[repr(C)]
+        struct SizedBlock {
+            head: Item,
+            tail: [Item; 3],
+        }
+
+        // Fails once it has made `allocs` allocations
+        struct FailingDeserializer {
+            allocs: usize,
+            deallocs: usize,
+        }
+
+        impl Fallible for FailingDeserializer {
+            type Error = ();
+        }
+
+        impl Deserializer for FailingDeserializer {
+            unsafe fn alloc(&mut self, layout: Layout) -> Result<*mut u8, Self::Error> {
+                if self.allocs == 0 {
+                    Err(())
+                } else {
+                    self.allocs -= 1;
+                    Ok(std::alloc::alloc(layout))
+                }
+            }
+
+            unsafe fn dealloc(&mut self, ptr: *mut u8, layout: Layout) {
+                self.deallocs += 1;
+                std::alloc::dealloc(ptr, layout);
+            }
+        }
+
+        let block = Box::new(SizedBlock {
+            head: Item(Box::new(1)),
+            tail: [Item(Box::new(2)), Item(Box::new(3)), Item(Box::new(4))],
+        });
+        let value: Box<Block> =
+            unsafe { Box::from_raw(ptr_meta::from_raw_parts_mut(Box::into_raw(block).cast(), 3)) };
+
+        let mut serializer = AlignedSerializer::new(AlignedVec::new());
+        serializer
+            .serialize_value(&value)
+            .expect("failed to archive value");
+        let buf = serializer.into_inner();
+        drop(value);
+
+        let archived = unsafe { archived_root::<Box<Block>>(buf.as_ref()) };
+
+        // The block, the head and the first two tail items allocate before the third item fails
+        DROPPED.with(|d| d.set(0));
+        let mut deserializer = FailingDeserializer {
+            allocs: 4,
+            deallocs: 0,
+        };
+        let result: Result<Box<Block>, ()> = archived.deserialize(&mut deserializer);
+        assert!(result.is_err());
+        assert_eq!(DROPPED.with(|d| d.get()), 3);
+        assert_eq!(deserializer.deallocs, 1);
+
+        // A failing head frees the block without dropping anything
+        DROPPED.with(|d| d.set(0));
+        let mut deserializer = FailingDeserializer {
+            allocs: 1,
+            deallocs: 0,
+        };
+        let result: Result<Box<Block>, ()> = archived.deserialize(&mut deserializer);
+        assert!(result.is_err());
+        assert_eq!(DROPPED.with(|d| d.get()), 0);
+        assert_eq!(deserializer.deallocs, 1);
+
+        let mut deserializer = FailingDeserializer {
+            allocs: 5,
+            deallocs: 0,
+        };
+        let result: Result<Box<Block>, ()> = archived.deserialize(&mut deserializer);
+        assert_eq!(*result.unwrap().tail[2].0, 4);
+        assert_eq!(deserializer.deallocs, 0);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
     fn archive_weak_ptr() {
         use std::rc::{Rc, Weak};
 
@@ -1461,6 +3299,203 @@ mod tests {
         assert_eq!(Weak::weak_count(&deserialized.b), 1);
     }
 
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn archive_debug() {
+        use core::time::Duration;
+        use std::{
+            collections::{HashMap, HashSet},
+            net::{IpAddr, Ipv4Addr, SocketAddr},
+            rc::Rc,
+        };
+
+        #[derive(Archive, Serialize, Debug)]
+        #[archive(debug)]
+        struct Test {
+            a: i32,
+            b: String,
+            c: Vec<Option<u8>>,
+            d: HashMap<String, u32>,
+            e: HashSet<i8>,
+            f: Box<Tuple>,
+            g: Rc<str>,
+            h: Duration,
+            i: SocketAddr,
+            j: IpAddr,
+            k: (Enum, Enum, Enum),
+            l: Unit,
+        }
+
+        #[derive(Archive, Serialize, Debug)]
+        #[archive(debug)]
+        struct Tuple(u16, Option<String>);
+
+        #[derive(Archive, Serialize, Debug)]
+        #[archive(debug)]
+        enum Enum {
+            A,
+            B(u32, String),
+            C { value: Vec<i32> },
+        }
+
+        #[derive(Archive, Serialize, Debug)]
+        #[archive(debug)]
+        struct Unit;
+
+        let mut d = HashMap::new();
+        d.insert("hello".to_string(), 42);
+        let mut e = HashSet::new();
+        e.insert(-3);
+
+        let value = Test {
+            a: 42,
+            b: "hello world".to_string(),
+            c: vec![Some(1), None, Some(3)],
+            d,
+            e,
+            f: Box::new(Tuple(7, Some("seven".to_string()))),
+            g: Rc::from("shared"),
+            h: Duration::new(1, 500_000_000),
+            i: SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8080),
+            j: IpAddr::V4(Ipv4Addr::new(192, 168, 0, 1)),
+            k: (
+                Enum::A,
+                Enum::B(3, "three".to_string()),
+                Enum::C {
+                    value: vec![1, 2, 3],
+                },
+            ),
+            l: Unit,
+        };
+
+        let mut serializer = make_default_serializer();
+        serializer
+            .serialize_value(&value)
+            .expect("failed to archive value");
+        let buf = unwrap_default_serializer(serializer);
+        let archived = unsafe { archived_root::<Test>(buf.as_ref()) };
+
+        assert_eq!(format!("{:?}", archived), format!("{:?}", value));
+        assert_eq!(format!("{:#?}", archived), format!("{:#?}", value));
+        assert_eq!(format!("{}", archived.b), "hello world");
+        assert_eq!(format!("{}", archived.i), "127.0.0.1:8080");
+    }
+
+    mod framework {
+        pub use ::rkyv as serialization;
+        pub use ::rkyv_dyn as dyn_serialization;
+        pub use ::rkyv_typename as type_names;
+    }
+
+    // The crates are only reachable through the framework in this module, so code generated with
+    // the default crate paths doesn't compile here
+    #[cfg(not(feature = "wasm"))]
+    mod crate_path {
+        mod rkyv {}
+        mod rkyv_dyn {}
+        mod rkyv_typename {}
+
+        use super::framework::{
+            dyn_serialization::archive_dyn,
+            serialization::{
+                archived_root, de::deserializers::AllocDeserializer, ser::Serializer, Archive,
+                Archived, Deserialize, Serialize,
+            },
+            type_names::TypeName,
+        };
+        use crate::util::*;
+
+        #[test]
+        fn derive_crate_path() {
+            #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+            #[archive(crate = "crate::tests::framework::serialization", compare(PartialEq))]
+            struct Test {
+                a: i32,
+                b: Option<String>,
+                c: Inner,
+            }
+
+            #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+            #[archive(crate = "crate::tests::framework::serialization", compare(PartialEq))]
+            enum Inner {
+                A,
+                B(u32),
+                C { value: Vec<i32> },
+            }
+
+            let value = Test {
+                a: 42,
+                b: Some("hello world".to_string()),
+                c: Inner::C {
+                    value: vec![1, 2, 3],
+                },
+            };
+            test_archive(&value);
+
+            #[archive_dyn(
+                crate = "crate::tests::framework::dyn_serialization",
+                serialize = "STestTrait",
+                deserialize = "DTestTrait"
+            )]
+            pub trait TestTrait {
+                fn get_id(&self) -> i32;
+            }
+
+            #[derive(Archive, Serialize, Deserialize)]
+            #[archive(crate = "crate::tests::framework::serialization")]
+            pub struct DynTest {
+                id: i32,
+            }
+
+            // Derives on the archived type can't be given a crate path
+            impl TypeName for Archived<DynTest> {
+                fn build_type_name<F: FnMut(&str)>(mut f: F) {
+                    f("ArchivedDynTest");
+                }
+            }
+
+            #[archive_dyn(
+                crate = "crate::tests::framework::dyn_serialization",
+                serialize = "STestTrait",
+                deserialize = "DTestTrait"
+            )]
+            impl TestTrait for DynTest {
+                fn get_id(&self) -> i32 {
+                    self.id
+                }
+            }
+
+            impl TestTrait for Archived<DynTest> {
+                fn get_id(&self) -> i32 {
+                    self.id
+                }
+            }
+
+            let value: Box<dyn STestTrait> = Box::new(DynTest { id: 42 });
+
+            let mut serializer = make_default_serializer();
+            serializer
+                .serialize_value(&value)
+                .expect("failed to archive value");
+            let buf = unwrap_default_serializer(serializer);
+            let archived_value = unsafe { archived_root::<Box<dyn STestTrait>>(buf.as_ref()) };
+            assert_eq!(value.get_id(), archived_value.get_id());
+
+            let deserialized_value: Box<dyn STestTrait> =
+                archived_value.deserialize(&mut AllocDeserializer).unwrap();
+            assert_eq!(value.get_id(), deserialized_value.get_id());
+
+            #[derive(TypeName)]
+            #[typename = "Named"]
+            #[typename(crate = "crate::tests::framework::type_names")]
+            struct Named<T>(T);
+
+            let mut name = String::new();
+            Named::<i32>::build_type_name(|s| name.push_str(s));
+            assert_eq!(name, "Named<i32>");
+        }
+    }
+
     #[test]
     #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
     fn derive_attributes() {
@@ -1544,6 +3579,143 @@ mod tests {
         }
     }
 
+    #[test]
+    #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+    fn compare_archived() {
+        use core::hash::{Hash, Hasher};
+        use rkyv::compare::ArchiveKey;
+        use std::collections::{hash_map::DefaultHasher, HashMap, HashSet};
+
+        #[derive(Archive, Serialize, Hash, PartialEq, Eq, PartialOrd, Ord)]
+        #[archive(compare(PartialEq, Eq, Ord, Hash))]
+        struct Test {
+            a: String,
+            b: Vec<u32>,
+            c: Option<Box<i32>>,
+            d: Kind,
+            e: Tuple,
+        }
+
+        #[derive(Archive, Serialize, Hash, PartialEq, Eq, PartialOrd, Ord)]
+        #[archive(compare(PartialEq, Eq, Ord, Hash))]
+        #[repr(u8)]
+        enum Kind {
+            A = 3,
+            B(u8),
+            C { value: String } = 10,
+            D,
+        }
+
+        #[derive(Archive, Serialize, Hash, PartialEq, Eq, PartialOrd, Ord)]
+        #[archive(compare(PartialEq, Eq, Ord, Hash))]
+        struct Tuple(i16, bool);
+
+        fn hash<T: Hash + ?Sized>(value: &T) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            value.hash(&mut hasher);
+            hasher.finish()
+        }
+
+        fn archive(value: &Test) -> AlignedVec {
+            let mut serializer = make_default_serializer();
+            serializer
+                .serialize_value(value)
+                .expect("failed to archive value");
+            unwrap_default_serializer(serializer)
+        }
+
+        let values = [
+            Test {
+                a: "hello".to_string(),
+                b: vec![1, 2, 3],
+                c: Some(Box::new(42)),
+                d: Kind::A,
+                e: Tuple(-1, true),
+            },
+            Test {
+                a: "hello".to_string(),
+                b: vec![1, 2, 3],
+                c: None,
+                d: Kind::B(7),
+                e: Tuple(-1, true),
+            },
+            Test {
+                a: "hello".to_string(),
+                b: vec![1, 2, 3],
+                c: None,
+                d: Kind::C {
+                    value: "world".to_string(),
+                },
+                e: Tuple(2, false),
+            },
+            Test {
+                a: "world".to_string(),
+                b: vec![],
+                c: None,
+                d: Kind::D,
+                e: Tuple(0, false),
+            },
+        ];
+
+        let first = values.iter().map(archive).collect::<Vec<_>>();
+        let second = values.iter().map(archive).collect::<Vec<_>>();
+        let first = first
+            .iter()
+            .map(|buf| unsafe { archived_root::<Test>(buf.as_ref()) })
+            .collect::<Vec<_>>();
+        let second = second
+            .iter()
+            .map(|buf| unsafe { archived_root::<Test>(buf.as_ref()) })
+            .collect::<Vec<_>>();
+
+        for (i, value) in values.iter().enumerate() {
+            assert_eq!(hash(first[i]), hash(value));
+            assert_eq!(hash(&first[i].e), hash(&value.e));
+            for (j, other) in values.iter().enumerate() {
+                assert_eq!(first[i] == second[j], value == other);
+                assert_eq!(first[i].cmp(second[j]), value.cmp(other));
+                assert_eq!(first[i].d.cmp(&second[j].d), value.d.cmp(&other.d));
+            }
+        }
+
+        let set = first.iter().copied().collect::<HashSet<_>>();
+        assert!(second.iter().all(|value| set.contains(value)));
+
+        // Archived and unarchived values look up entries in maps keyed by the other
+        let unarchived_keys = values.iter().collect::<HashSet<_>>();
+        let archived_keys = first.iter().copied().collect::<HashSet<_>>();
+        for (value, archived) in values.iter().zip(second.iter()) {
+            assert!(unarchived_keys.contains(*archived as &dyn ArchiveKey<Test>));
+            assert!(archived_keys.contains(value as &dyn ArchiveKey<Test>));
+        }
+        let missing = Test {
+            a: "missing".to_string(),
+            b: vec![],
+            c: None,
+            d: Kind::D,
+            e: Tuple(0, false),
+        };
+        assert!(!archived_keys.contains(&missing as &dyn ArchiveKey<Test>));
+
+        let mut map = HashMap::new();
+        map.insert(Tuple(1, true), 10u32);
+        map.insert(Tuple(-1, false), 20u32);
+        let mut serializer = AlignedSerializer::new(AlignedVec::new());
+        serializer
+            .serialize_value(&map)
+            .expect("failed to archive value");
+        let buf = serializer.into_inner();
+        let archived_map = unsafe { archived_root::<HashMap<Tuple, u32>>(buf.as_ref()) };
+        assert_eq!(
+            archived_map.get(&Tuple(-1, false) as &dyn ArchiveKey<Tuple>),
+            Some(&20)
+        );
+        assert_eq!(
+            archived_map.get(&Tuple(-1, true) as &dyn ArchiveKey<Tuple>),
+            None
+        );
+    }
+
     #[test]
     #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
     fn default_type_parameters() {
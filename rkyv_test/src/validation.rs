@@ -1,5 +1,4 @@
 use bytecheck::CheckBytes;
-use core::fmt;
 use rkyv::{
     check_archived_root, check_archived_value,
     ser::{
@@ -7,13 +6,14 @@ use rkyv::{
         serializers::{AlignedSerializer, BufferSerializer},
         Serializer,
     },
-    validation::DefaultArchiveValidator,
+    validation::{
+        check_archived_value_with_context, ArchiveBoundsValidator, ArchiveDedupValidator,
+        ArchiveValidator, CheckTypeError, DefaultArchiveDedupValidator, DefaultArchiveValidator,
+        SharedArchiveValidator,
+    },
     Aligned, AlignedVec, Archive, Serialize,
 };
-use std::{
-    collections::{HashMap, HashSet},
-    error::Error,
-};
+use std::collections::{HashMap, HashSet};
 
 #[cfg(feature = "wasm")]
 use wasm_bindgen_test::*;
@@ -32,6 +32,29 @@ where
     check_archived_root::<T>(buf.as_ref()).unwrap();
 }
 
+fn check_archived_value_dedup<T: Archive>(
+    buf: &[u8],
+    pos: usize,
+) -> Result<&T::Archived, CheckTypeError<T::Archived, DefaultArchiveDedupValidator>>
+where
+    T::Archived: CheckBytes<DefaultArchiveDedupValidator>,
+{
+    let mut validator = SharedArchiveValidator::new(ArchiveDedupValidator::new(
+        ArchiveValidator::new(ArchiveBoundsValidator::new(buf)),
+    ));
+    check_archived_value_with_context::<T, DefaultArchiveDedupValidator>(buf, pos, &mut validator)
+}
+
+fn check_archived_root_dedup<T: Archive>(
+    buf: &[u8],
+) -> Result<&T::Archived, CheckTypeError<T::Archived, DefaultArchiveDedupValidator>>
+where
+    T::Archived: CheckBytes<DefaultArchiveDedupValidator>,
+{
+    let pos = buf.len() - core::mem::size_of::<T::Archived>();
+    check_archived_value_dedup::<T>(buf, pos)
+}
+
 #[test]
 #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
 fn basic_functionality() {
@@ -124,6 +147,7 @@ fn invalid_tags() {
 #[test]
 #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
 fn overlapping_claims() {
+    #[cfg(not(feature = "size_64"))]
     // Invalid archive (overlapping claims)
     let synthetic_buf = Aligned([
         // First string
@@ -136,16 +160,299 @@ fn overlapping_claims() {
         0x48, 0x65, 0x6c, 0x6c, 0x6f, 0x20, 0x77, 0x6f, 0x72, 0x6c, 0x64,
     ]);
 
+    #[cfg(feature = "size_64")]
+    // Invalid archive (overlapping claims)
+    let synthetic_buf = Aligned([
+        // First string
+        32u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, // points 32 bytes forward
+        11u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, // string is 11 characters long
+        // Second string
+        16u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, // points 16 bytes forward
+        11u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, // string is 11 characters long
+        // "Hello world"
+        0x48, 0x65, 0x6c, 0x6c, 0x6f, 0x20, 0x77, 0x6f, 0x72, 0x6c, 0x64,
+    ]);
+
+    #[cfg(not(feature = "size_64"))]
+    let expected = "memory claim overlap: current [0x10..0x1b] overlaps previous [0x0..0x1b]";
+    #[cfg(feature = "size_64")]
+    let expected = "memory claim overlap: current [0x20..0x2b] overlaps previous [0x0..0x2b]";
+
+    // Overlapping claims are reported by their positions in the archive, and the root and first
+    // string are merged into one claim
+    let error = check_archived_value::<[String; 2]>(synthetic_buf.as_ref(), 0).unwrap_err();
+    assert!(error.to_string().contains(expected), "{}", error);
+}
+
+#[test]
+#[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+fn claim_bytes_out_of_order() {
+    use rkyv::validation::{
+        ArchiveBoundsValidator, ArchiveMemoryContext, ArchiveMemoryError, ArchiveValidator,
+    };
+
+    let buf = Aligned([0u8; 64]);
+    let base = buf.as_ref().as_ptr();
+    let mut validator = ArchiveValidator::new(ArchiveBoundsValidator::new(buf.as_ref()));
+    // Returns the positions of the previous and current claims if they overlap
+    let mut claim = |pos: usize, len: usize| unsafe {
+        validator
+            .claim_bytes(base.add(pos), len)
+            .map_err(|e| match e {
+                ArchiveMemoryError::ClaimOverlap { previous, current } => (previous, current),
+                other => panic!("expected claim overlap error, got {}", other),
+            })
+    };
+
+    // Runs of adjacent claims in both directions
+    claim(16, 4).unwrap();
+    claim(20, 4).unwrap();
+    claim(12, 4).unwrap();
+    // Claims on either side of the run
+    claim(40, 8).unwrap();
+    claim(0, 4).unwrap();
+    // Claims that extend an earlier run
+    claim(24, 8).unwrap();
+    // Claiming nothing never overlaps
+    claim(20, 0).unwrap();
+
+    assert_eq!(claim(30, 4), Err((12..32, 30..34)));
+    assert_eq!(claim(44, 8), Err((40..48, 44..52)));
+    assert_eq!(claim(2, 1), Err((0..4, 2..3)));
+
+    // The gaps between claims can still be claimed, and filling them merges the claims around them
+    claim(32, 8).unwrap();
+    claim(4, 8).unwrap();
+    assert_eq!(claim(47, 2), Err((0..48, 47..49)));
+    claim(48, 16).unwrap();
+    assert_eq!(claim(0, 64), Err((0..64, 0..64)));
+}
+
+#[test]
+#[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+fn claim_many_bytes_out_of_order() {
+    use rkyv::validation::{
+        ArchiveBoundsValidator, ArchiveMemoryContext, ArchiveMemoryError, ArchiveValidator,
+    };
+
+    const COUNT: usize = 4096;
+
+    let buf = vec![0u8; 8 * COUNT];
+    let base = buf.as_ptr();
+    let mut validator = ArchiveValidator::new(ArchiveBoundsValidator::new(buf.as_ref()));
+    let mut claim = |pos: usize, len: usize| unsafe {
+        validator
+            .claim_bytes(base.add(pos), len)
+            .map_err(|e| match e {
+                ArchiveMemoryError::ClaimOverlap { previous, current } => (previous, current),
+                other => panic!("expected claim overlap error, got {}", other),
+            })
+    };
+
+    // Visits every slot once in a scrambled order
+    let order = (0..COUNT).map(|i| (i * 1531) % COUNT).collect::<Vec<_>>();
+
+    // Claims that leave gaps between them can't be merged
+    for &i in order.iter() {
+        claim(8 * i, 4).unwrap();
+    }
+    for &i in order.iter() {
+        assert_eq!(claim(8 * i + 2, 4), Err((8 * i..8 * i + 4, 8 * i + 2..8 * i + 6)));
+    }
+
+    // Filling the gaps merges all of the claims
+    for &i in order.iter() {
+        claim(8 * i + 4, 4).unwrap();
+    }
+    assert_eq!(claim(0, 1), Err((0..8 * COUNT, 0..1)));
+}
+
+#[test]
+#[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+fn shared_read_only_claims() {
+    use rkyv::ser::{adapters::DedupSerializer, serializers::AllocSerializer};
+    use std::rc::Rc;
+
+    #[cfg(not(feature = "size_64"))]
+    // Strings may share the exact same bytes
+    let synthetic_buf = Aligned([
+        // First string
+        16u8, 0u8, 0u8, 0u8, // points 16 bytes forward
+        11u8, 0u8, 0u8, 0u8, // string is 11 characters long
+        // Second string
+        8u8, 0u8, 0u8, 0u8, // points 8 bytes forward
+        11u8, 0u8, 0u8, 0u8, // string is 11 characters long
+        // "Hello world"
+        0x48, 0x65, 0x6c, 0x6c, 0x6f, 0x20, 0x77, 0x6f, 0x72, 0x6c, 0x64,
+    ]);
+
+    #[cfg(feature = "size_64")]
+    // Strings may share the exact same bytes
+    let synthetic_buf = Aligned([
+        // First string
+        32u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, // points 32 bytes forward
+        11u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, // string is 11 characters long
+        // Second string
+        16u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, // points 16 bytes forward
+        11u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, // string is 11 characters long
+        // "Hello world"
+        0x48, 0x65, 0x6c, 0x6c, 0x6f, 0x20, 0x77, 0x6f, 0x72, 0x6c, 0x64,
+    ]);
+
+    // The default validator never allows bytes to be shared
     check_archived_value::<[String; 2]>(synthetic_buf.as_ref(), 0).unwrap_err();
+
+    let archived = check_archived_value_dedup::<[String; 2]>(synthetic_buf.as_ref(), 0).unwrap();
+    assert_eq!(archived[0], "Hello world");
+    assert_eq!(archived[1], "Hello world");
+
+    #[cfg(not(feature = "size_64"))]
+    // Plain bytes may be shared between strings and slices of primitives
+    let synthetic_buf = Aligned([
+        // String
+        16u8, 0u8, 0u8, 0u8, // points 16 bytes forward
+        11u8, 0u8, 0u8, 0u8, // string is 11 characters long
+        // Vec
+        8u8, 0u8, 0u8, 0u8, // points 8 bytes forward
+        11u8, 0u8, 0u8, 0u8, // vec is 11 elements long
+        // "Hello world"
+        0x48, 0x65, 0x6c, 0x6c, 0x6f, 0x20, 0x77, 0x6f, 0x72, 0x6c, 0x64,
+    ]);
+
+    #[cfg(feature = "size_64")]
+    // Plain bytes may be shared between strings and slices of primitives
+    let synthetic_buf = Aligned([
+        // String
+        32u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, // points 32 bytes forward
+        11u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, // string is 11 characters long
+        // Vec
+        16u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, // points 16 bytes forward
+        11u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, // vec is 11 elements long
+        // "Hello world"
+        0x48, 0x65, 0x6c, 0x6c, 0x6f, 0x20, 0x77, 0x6f, 0x72, 0x6c, 0x64,
+    ]);
+
+    let archived =
+        check_archived_value_dedup::<(String, Vec<u8>)>(synthetic_buf.as_ref(), 0).unwrap();
+    assert_eq!(archived.0.as_bytes(), archived.1.as_slice());
+
+    #[cfg(not(feature = "size_64"))]
+    // Invalid archive (shared bytes that contain a pointer)
+    let synthetic_buf = Aligned([
+        // First box
+        8u8, 0u8, 0u8, 0u8, // points 8 bytes forward
+        // Second box
+        4u8, 0u8, 0u8, 0u8, // points 4 bytes forward
+        // String
+        8u8, 0u8, 0u8, 0u8, // points 8 bytes forward
+        11u8, 0u8, 0u8, 0u8, // string is 11 characters long
+        // "Hello world"
+        0x48, 0x65, 0x6c, 0x6c, 0x6f, 0x20, 0x77, 0x6f, 0x72, 0x6c, 0x64,
+    ]);
+
+    #[cfg(feature = "size_64")]
+    // Invalid archive (shared bytes that contain a pointer)
+    let synthetic_buf = Aligned([
+        // First box
+        16u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, // points 16 bytes forward
+        // Second box
+        8u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, // points 8 bytes forward
+        // String
+        16u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, // points 16 bytes forward
+        11u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, // string is 11 characters long
+        // "Hello world"
+        0x48, 0x65, 0x6c, 0x6c, 0x6f, 0x20, 0x77, 0x6f, 0x72, 0x6c, 0x64,
+    ]);
+
+    #[cfg(not(feature = "size_64"))]
+    let expected = "memory claim overlap: current [0x8..0x10] overlaps previous [0x0..0x1b]";
+    #[cfg(feature = "size_64")]
+    let expected = "memory claim overlap: current [0x10..0x20] overlaps previous [0x0..0x2b]";
+
+    let error =
+        check_archived_value_dedup::<[Box<String>; 2]>(synthetic_buf.as_ref(), 0).unwrap_err();
+    assert!(error.to_string().contains(expected), "{}", error);
+
+    #[derive(Archive, Serialize)]
+    #[archive(derive(CheckBytes))]
+    struct Test {
+        a: Vec<String>,
+        b: Box<str>,
+        c: Rc<str>,
+        d: Rc<str>,
+        e: Option<String>,
+    }
+
+    let shared = Rc::<str>::from("hello");
+    let value = Test {
+        a: vec!["hello".to_string(), "world".to_string(), "hello".to_string()],
+        b: "world".into(),
+        c: shared.clone(),
+        d: shared,
+        e: Some("hello".to_string()),
+    };
+
+    let mut serializer = DedupSerializer::new(AllocSerializer::<256>::default());
+    serializer
+        .serialize_value(&value)
+        .expect("failed to archive value");
+    let buf = serializer.into_inner().into_serializer().into_inner();
+
+    assert!(check_archived_root::<Test>(buf.as_ref()).is_err());
+    let archived = check_archived_root_dedup::<Test>(buf.as_ref()).unwrap();
+    assert_eq!(archived.a[0].as_ptr(), archived.a[2].as_ptr());
+    assert_eq!(archived.a[1].as_ptr(), archived.b.as_ptr());
+    assert_eq!(archived.a[0].as_ptr(), archived.c.as_ptr());
+    assert_eq!(archived.e.as_ref().unwrap().as_ptr(), archived.a[0].as_ptr());
+}
+
+#[test]
+#[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+fn shared_copy_slices() {
+    use rkyv::ser::{adapters::DedupSerializer, serializers::AllocSerializer};
+
+    #[derive(Archive, Serialize)]
+    #[archive(derive(CheckBytes))]
+    struct Test {
+        a: Vec<u8>,
+        b: Vec<u8>,
+        c: Box<[u8]>,
+        d: Vec<u32>,
+        e: Box<[u32]>,
+    }
+
+    let value = Test {
+        a: vec![1, 2, 3, 4],
+        b: vec![1, 2, 3, 4],
+        c: vec![1, 2, 3, 4].into_boxed_slice(),
+        d: vec![5, 6, 7],
+        e: vec![5, 6, 7].into_boxed_slice(),
+    };
+
+    let mut serializer = DedupSerializer::new(AllocSerializer::<256>::default());
+    serializer
+        .serialize_value(&value)
+        .expect("failed to archive value");
+    let buf = serializer.into_inner().into_serializer().into_inner();
+
+    assert!(check_archived_root::<Test>(buf.as_ref()).is_err());
+    let archived = check_archived_root_dedup::<Test>(buf.as_ref()).unwrap();
+    assert_eq!(archived.a.as_slice(), &[1, 2, 3, 4]);
+    assert_eq!(archived.a.as_ptr(), archived.b.as_ptr());
+    assert_eq!(archived.a.as_ptr(), archived.c.as_ptr());
+    assert_eq!(archived.d.as_slice(), &[5, 6, 7]);
+    assert_eq!(archived.d.as_ptr(), archived.e.as_ptr());
 }
 
 #[test]
 #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
 fn cycle_detection() {
+    use core::fmt;
     use rkyv::{
         validation::{ArchiveBoundsContext, ArchiveMemoryContext},
         Archived,
     };
+    use std::error::Error;
 
     #[derive(Archive)]
     #[archive(derive(Debug))]
@@ -153,7 +460,7 @@ fn cycle_detection() {
 
     #[allow(dead_code)]
     #[derive(Archive)]
-    #[archive(derive(Debug))]
+    #[archive(derive(Debug), no_check)]
     enum Node {
         Nil,
         Cons(#[omit_bounds] Box<Node>),
@@ -199,7 +506,7 @@ fn cycle_detection() {
                 0 => (),
                 1 => {
                     <Archived<Box<Node>> as CheckBytes<C>>::check_bytes(
-                        bytes.add(4).cast(),
+                        bytes.add(core::mem::align_of::<Archived<Box<Node>>>()).cast(),
                         context,
                     )
                     .map_err(|e| NodeError(e.into()))?;
@@ -210,6 +517,7 @@ fn cycle_detection() {
         }
     }
 
+    #[cfg(not(feature = "size_64"))]
     // Invalid archive (cyclic claims)
     let synthetic_buf = Aligned([
         // First node
@@ -220,6 +528,17 @@ fn cycle_detection() {
         244u8, 255u8, 255u8, 255u8, // Node is 12 bytes back
     ]);
 
+    #[cfg(feature = "size_64")]
+    // Invalid archive (cyclic claims)
+    let synthetic_buf = Aligned([
+        // First node
+        1u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, // Cons
+        8u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, // Node is 8 bytes forward
+        // Second string
+        1u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, // Cons
+        232u8, 255u8, 255u8, 255u8, 255u8, 255u8, 255u8, 255u8, // Node is 24 bytes back
+    ]);
+
     check_archived_value::<Node>(synthetic_buf.as_ref(), 0).unwrap_err();
 }
 
@@ -284,6 +603,182 @@ fn derive_enum() {
     ])));
 }
 
+#[test]
+#[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+fn automatic_check_bytes() {
+    #[derive(Archive, Serialize)]
+    struct Unit;
+
+    #[derive(Archive, Serialize)]
+    struct Struct<T> {
+        a: u32,
+        b: T,
+        c: Option<Box<[String]>>,
+    }
+
+    #[derive(Archive, Serialize)]
+    struct Tuple(u32, String, Option<Box<Unit>>);
+
+    #[derive(Archive, Serialize)]
+    enum Enum {
+        A,
+        B(u32, String),
+        C { a: Struct<String>, b: Tuple },
+    }
+
+    serialize_and_check(&Unit);
+    serialize_and_check(&Struct {
+        a: 42,
+        b: "hello world".to_string(),
+        c: Some(vec!["yes".to_string(), "no".to_string()].into_boxed_slice()),
+    });
+    serialize_and_check(&Tuple(42, "hello world".to_string(), Some(Box::new(Unit))));
+    serialize_and_check(&Enum::A);
+    serialize_and_check(&Enum::B(42, "hello world".to_string()));
+    serialize_and_check(&Enum::C {
+        a: Struct {
+            a: 42,
+            b: "hello world".to_string(),
+            c: None,
+        },
+        b: Tuple(1, "yes".to_string(), None),
+    });
+}
+
+#[test]
+#[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+fn automatic_check_bytes_recursive() {
+    #[derive(Archive, Serialize)]
+    #[archive(bound(serialize = "__S: Serializer"))]
+    enum List {
+        Nil,
+        Cons(u32, #[omit_bounds] Box<List>),
+    }
+
+    serialize_and_check(&List::Cons(
+        1,
+        Box::new(List::Cons(2, Box::new(List::Cons(3, Box::new(List::Nil))))),
+    ));
+}
+
+#[test]
+#[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+fn automatic_check_bytes_unsized() {
+    #[derive(Archive, Serialize)]
+    #[repr(C)]
+    struct Block<H> {
+        head: H,
+        tail: [String],
+    }
+
+    #[derive(Archive, Serialize)]
+    #[repr(C)]
+    struct Label(u8, str);
+
+    #[derive(Archive, Serialize)]
+    struct Test {
+        block: Box<Block<u16>>,
+        label: Box<Label>,
+    }
+
+    // Unsized structs have to be built from sized structs with the same layout
+    #[repr(C)]
+    struct SizedBlock<H> {
+        head: H,
+        tail: [String; 2],
+    }
+
+    #[repr(C)]
+    struct SizedLabel(u8, [u8; 5]);
+
+    let block = Box::new(SizedBlock {
+        head: 42u16,
+        tail: ["hello".to_string(), "world".to_string()],
+    });
+    let label = Box::new(SizedLabel(42, *b"label"));
+    let value = unsafe {
+        Test {
+            block: Box::from_raw(ptr_meta::from_raw_parts_mut(
+                Box::into_raw(block).cast(),
+                2,
+            )),
+            label: Box::from_raw(ptr_meta::from_raw_parts_mut(
+                Box::into_raw(label).cast(),
+                5,
+            )),
+        }
+    };
+
+    let mut serializer = AlignedSerializer::new(AlignedVec::new());
+    serializer
+        .serialize_value(&value)
+        .expect("failed to archive value");
+    let mut buf = serializer.into_inner();
+
+    let archived = check_archived_root::<Test>(buf.as_ref()).unwrap();
+    assert_eq!(archived.block.head, 42);
+    assert_eq!(archived.block.tail[1], "world");
+    assert_eq!(&archived.label.1, "label");
+
+    // The string tail is checked as UTF-8
+    let pos = buf
+        .as_ref()
+        .windows(5)
+        .position(|w| w == b"label")
+        .unwrap();
+    buf.as_mut()[pos] = 0xff;
+    assert!(check_archived_root::<Test>(buf.as_ref()).is_err());
+    buf.as_mut()[pos] = b'l';
+
+    // A block length too large to compute the layout of fails to check instead of panicking
+    let root_pos = buf.len() - core::mem::size_of::<rkyv::Archived<Test>>();
+    let len_pos = root_pos + core::mem::size_of::<rkyv::ArchivedIsize>();
+    let len_size = core::mem::size_of::<rkyv::ArchivedUsize>();
+    for b in buf.as_mut()[len_pos..len_pos + len_size].iter_mut() {
+        *b = 0xff;
+    }
+    assert!(check_archived_root::<Test>(buf.as_ref()).is_err());
+}
+
+#[test]
+#[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+fn invalid_enum_tag() {
+    use rkyv::validation::{CheckArchiveError, EnumCheckError};
+
+    #[allow(dead_code)]
+    #[derive(Archive)]
+    #[archive(debug)]
+    enum Test {
+        A,
+        B(u32),
+        C { value: u32 },
+    }
+
+    let synthetic_buf = Aligned([
+        3u8, 0u8, 0u8, 0u8, // invalid tag + padding
+        42u8, 0u8, 0u8, 0u8, // value
+    ]);
+
+    match check_archived_value::<Test>(synthetic_buf.as_ref(), 0) {
+        Err(CheckArchiveError::CheckBytesError(ref e @ EnumCheckError::InvalidTag { .. })) => {
+            assert_eq!(e.to_string(), "invalid tag 3 for enum `Test`");
+        }
+        other => panic!("expected invalid tag error, got {:?}", other),
+    }
+
+    // Valid tags are accepted
+    let synthetic_buf = Aligned([
+        2u8, 0u8, 0u8, 0u8, // tag + padding
+        42u8, 0u8, 0u8, 0u8, // value
+    ]);
+
+    let archived = check_archived_value::<Test>(synthetic_buf.as_ref(), 0).unwrap();
+    match archived {
+        ArchivedTest::C { value } => assert_eq!(*value, 42),
+        _ => panic!("expected variant C"),
+    }
+}
+
 #[test]
 #[cfg_attr(feature = "wasm", wasm_bindgen_test)]
 fn hashmap() {
@@ -340,7 +835,7 @@ fn check_dyn() {
     serialize_and_check(&value);
 
     #[derive(Archive, Serialize)]
-    #[archive(derive(TypeName))]
+    #[archive(derive(TypeName), no_check)]
     pub struct TestUnchecked {
         id: i32,
     }
@@ -400,3 +895,340 @@ fn check_shared_ptr() {
 
     check_archived_value::<Test>(buffer.as_ref(), pos).unwrap();
 }
+
+#[test]
+#[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+fn from_bytes() {
+    use rkyv::{ser::serializers::AllocSerializer, validation::FromBytesError, Deserialize};
+    use std::rc::Rc;
+
+    #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+    #[archive(derive(CheckBytes))]
+    struct Test {
+        name: String,
+        shared_a: Rc<u32>,
+        shared_b: Rc<u32>,
+        values: Option<Vec<u8>>,
+    }
+
+    let shared = Rc::new(42);
+    let value = Test {
+        name: "hello world".to_string(),
+        shared_a: shared.clone(),
+        shared_b: shared,
+        values: Some(vec![1, 2, 3, 4]),
+    };
+
+    let mut serializer = AllocSerializer::<256>::default();
+    serializer
+        .serialize_value(&value)
+        .expect("failed to archive value");
+    let mut buf = serializer.into_serializer().into_inner();
+
+    let deserialized = rkyv::from_bytes::<Test>(buf.as_ref()).unwrap();
+    assert_eq!(deserialized, value);
+    assert!(Rc::ptr_eq(&deserialized.shared_a, &deserialized.shared_b));
+
+    // Buffers too short to hold the root fail validation
+    match rkyv::from_bytes::<Test>(&buf.as_ref()[..2]) {
+        Err(FromBytesError::CheckArchiveError(_)) => (),
+        result => panic!("expected check archive error, got {:?}", result),
+    }
+
+    // Corrupt the tag of the root's option
+    let tag_pos =
+        buf.len() - core::mem::size_of::<ArchivedTest>() + rkyv::offset_of!(ArchivedTest, values);
+    buf.as_mut_slice()[tag_pos] = 2;
+    match rkyv::from_bytes::<Test>(buf.as_ref()) {
+        Err(FromBytesError::CheckArchiveError(_)) => (),
+        result => panic!("expected check archive error, got {:?}", result),
+    }
+}
+
+#[test]
+#[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+fn check_with_path() {
+    use rkyv::{
+        check_archived_root_with_path,
+        de::PathSegment,
+        ser::serializers::AllocSerializer,
+        std_impl::ArchivedString,
+        validation::{
+            check_archived_root_with_context, ArchiveBoundsContext, ArchiveBoundsValidator,
+            ArchiveValidator, CheckArchiveError, SharedArchiveValidator,
+        },
+    };
+
+    #[derive(Archive, Serialize)]
+    #[archive(track_path)]
+    struct Item {
+        name: String,
+        count: u32,
+    }
+
+    #[derive(Archive, Serialize)]
+    #[archive(track_path)]
+    enum Event {
+        Pickup(Item),
+        Drop { slot: u32 },
+        Quit,
+    }
+
+    #[derive(Archive, Serialize)]
+    #[archive(track_path)]
+    struct Player {
+        name: String,
+        inventory: Vec<Item>,
+        events: Vec<Event>,
+    }
+
+    let value = Player {
+        name: "alice".to_string(),
+        inventory: (0..5)
+            .map(|i| Item {
+                name: format!("item {}", i),
+                count: i,
+            })
+            .collect(),
+        events: vec![
+            Event::Pickup(Item {
+                name: "sword".to_string(),
+                count: 1,
+            }),
+            Event::Drop { slot: 2 },
+            Event::Quit,
+        ],
+    };
+
+    let mut serializer = AllocSerializer::<256>::default();
+    serializer
+        .serialize_value(&value)
+        .expect("failed to archive value");
+    let buf = serializer.into_serializer().into_inner();
+    check_archived_root_with_path::<Player>(buf.as_ref()).unwrap();
+
+    let archived = unsafe { rkyv::archived_root::<Player>(buf.as_ref()) };
+    let pos_of = |ptr: *const u8| ptr as usize - buf.as_ptr() as usize;
+    let name_pos = pos_of((&archived.inventory[3].name as *const ArchivedString).cast());
+    let tag_pos = pos_of((&archived.events[1] as *const ArchivedEvent).cast());
+
+    // Point the name of an item out of bounds
+    let mut broken = buf.clone();
+    broken.as_mut_slice()[name_pos..name_pos + 4].copy_from_slice(&[0x7f; 4]);
+    let error = check_archived_root_with_path::<Player>(broken.as_ref())
+        .err()
+        .unwrap();
+    assert_eq!(
+        error.path().segments(),
+        &[
+            PathSegment::Type("Player"),
+            PathSegment::Field("inventory"),
+            PathSegment::Index(3),
+            PathSegment::Type("Item"),
+            PathSegment::Field("name"),
+        ]
+    );
+    let message = error.to_string();
+    assert!(message.starts_with("`Player.inventory[3].name`: "));
+    assert!(message.contains(&format!(
+        "relative pointer at offset {:#x} out of bounds",
+        name_pos
+    )));
+
+    // Corrupt the tag of an event
+    let mut broken = buf.clone();
+    broken.as_mut_slice()[tag_pos] = 7;
+    let error = check_archived_root_with_path::<Player>(broken.as_ref())
+        .err()
+        .unwrap();
+    assert_eq!(error.path().to_string(), "Player.events[1]");
+    match error.into_inner() {
+        CheckArchiveError::CheckBytesError(_) => (),
+        other => panic!("expected check bytes error, got {:?}", other),
+    }
+
+    // Paths are only tracked when asked for
+    let mut validator =
+        SharedArchiveValidator::new(ArchiveValidator::new(ArchiveBoundsValidator::new(&broken)));
+    check_archived_root_with_context::<Player, DefaultArchiveValidator>(&broken, &mut validator)
+        .err()
+        .unwrap();
+    assert!(validator.path().is_empty());
+}
+
+#[test]
+#[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+fn check_with_checksum() {
+    use rkyv::{
+        checksum::{ChecksumError, Crc32c, CHECKSUM_SIZE},
+        ser::adapters::ChecksumSerializer,
+        validation::{check_archived_root_with_checksum, CheckChecksumError},
+    };
+
+    #[derive(Archive, Serialize)]
+    #[archive(derive(CheckBytes))]
+    struct Test {
+        name: String,
+        value: f64,
+    }
+
+    let mut serializer =
+        ChecksumSerializer::<_, Crc32c>::new(AlignedSerializer::new(AlignedVec::new()));
+    serializer
+        .serialize_value(&Test {
+            name: "checked".to_string(),
+            value: 1.5,
+        })
+        .expect("failed to archive value");
+    let mut buf = serializer
+        .finish()
+        .expect("failed to write checksum")
+        .into_inner();
+
+    let archived = check_archived_root_with_checksum::<Test, Crc32c>(buf.as_ref()).unwrap();
+    assert_eq!(archived.name, "checked");
+    assert_eq!(archived.value, 1.5);
+
+    // A corrupted float is still structurally valid
+    let len = buf.len();
+    buf[len - CHECKSUM_SIZE - 1] ^= 0x01;
+    check_archived_root::<Test>(&buf[..len - CHECKSUM_SIZE]).unwrap();
+    match check_archived_root_with_checksum::<Test, Crc32c>(buf.as_ref()) {
+        Err(CheckChecksumError::ChecksumError(ChecksumError::ChecksumMismatch { .. })) => (),
+        _ => panic!("expected checksum mismatch"),
+    }
+
+    // A valid checksum of an invalid archive still fails validation
+    let mut serializer =
+        ChecksumSerializer::<_, Crc32c>::new(AlignedSerializer::new(AlignedVec::new()));
+    serializer
+        .serialize_value(&Test {
+            name: "checked".to_string(),
+            value: 1.5,
+        })
+        .expect("failed to archive value");
+    let mut buf = serializer.into_inner().into_inner();
+    // Point the string out of bounds and checksum the broken archive
+    let len = buf.len();
+    let root = len - core::mem::size_of::<ArchivedTest>();
+    buf[root] = 0x7f;
+    let mut hasher = Crc32c::default();
+    core::hash::Hasher::write(&mut hasher, buf.as_ref());
+    let checksum = core::hash::Hasher::finish(&hasher);
+    buf.extend_from_slice(&checksum.to_le_bytes());
+    match check_archived_root_with_checksum::<Test, Crc32c>(buf.as_ref()) {
+        Err(CheckChecksumError::CheckArchiveError(_)) => (),
+        _ => panic!("expected check archive error"),
+    }
+}
+
+#[test]
+#[cfg(feature = "size_64")]
+#[cfg_attr(feature = "wasm", wasm_bindgen_test)]
+fn invalid_layout() {
+    use rkyv::frame::{ArchiveFrameReader, ArchiveFrameWriter, FrameError};
+
+    // A slice length that's too large to compute the layout of is reported as a bounds issue
+    let mut serializer = AlignedSerializer::new(AlignedVec::new());
+    serializer
+        .serialize_value(&vec![1u64, 2, 3].into_boxed_slice())
+        .expect("failed to archive value");
+    let mut buf = serializer.into_inner();
+    let len = buf.len();
+    for b in buf.as_mut()[len - 8..].iter_mut() {
+        *b = 0xff;
+    }
+    let error = check_archived_root::<Box<[u64]>>(buf.as_ref())
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("invalid layout for value at offset 0x0"), "{}", error);
+
+    // Frames full of 0xff have the same problem, and are rejected instead of panicking
+    #[derive(Archive, Serialize)]
+    #[archive(derive(CheckBytes))]
+    struct Message {
+        id: u32,
+        tags: Vec<u64>,
+    }
+
+    let mut writer = ArchiveFrameWriter::new(Vec::new());
+    writer
+        .write(&[0xffu8; core::mem::size_of::<ArchivedMessage>()])
+        .unwrap();
+    let frames = writer.into_inner();
+
+    let mut reader = ArchiveFrameReader::new(frames.as_slice());
+    match reader.read::<Message>() {
+        Err(FrameError::ArchiveError(_)) => (),
+        result => panic!("expected archive error, got {:?}", result.map(|_| ())),
+    }
+}
+
+#[test]
+#[cfg(unix)]
+fn frames_over_unix_stream() {
+    use rkyv::frame::{ArchiveFrameReader, ArchiveFrameWriter, FrameError};
+    use std::{os::unix::net::UnixStream, thread};
+
+    #[derive(Archive, Serialize)]
+    #[archive(derive(CheckBytes))]
+    struct Message {
+        id: u32,
+        tags: Vec<String>,
+        payload: Option<Box<[u8]>>,
+    }
+
+    fn message(id: u32) -> Message {
+        Message {
+            id,
+            tags: (0..id % 4).map(|i| format!("tag {}", i)).collect(),
+            payload: if id & 1 == 0 {
+                Some(vec![id as u8; id as usize * 100].into_boxed_slice())
+            } else {
+                None
+            },
+        }
+    }
+
+    const MESSAGES: u32 = 32;
+
+    let (sender, receiver) = UnixStream::pair().unwrap();
+    let writer = thread::spawn(move || {
+        let mut writer = ArchiveFrameWriter::new(sender);
+        for id in 0..MESSAGES {
+            writer.write(&message(id)).unwrap();
+        }
+        writer.flush().unwrap();
+        // Send frames that aren't valid `Message`s before closing the stream
+        writer
+            .write(&[0xffu8; core::mem::size_of::<ArchivedMessage>()])
+            .unwrap();
+        writer.write(&0u8).unwrap();
+    });
+
+    let mut reader = ArchiveFrameReader::new(receiver);
+    for id in 0..MESSAGES {
+        let expected = message(id);
+        let archived = reader.read::<Message>().unwrap().unwrap();
+        assert_eq!(archived.id, expected.id);
+        assert_eq!(archived.tags.len(), expected.tags.len());
+        for (archived, tag) in archived.tags.iter().zip(expected.tags.iter()) {
+            assert_eq!(archived, tag);
+        }
+        assert_eq!(
+            archived.payload.as_ref().map(|p| &**p),
+            expected.payload.as_deref()
+        );
+    }
+    match reader.read::<Message>() {
+        Err(FrameError::ArchiveError(_)) => (),
+        result => panic!("expected archive error, got {:?}", result.map(|_| ())),
+    }
+    match reader.read::<Message>() {
+        Err(FrameError::FrameTooSmall { len: 1, .. }) => (),
+        result => panic!("expected frame too small, got {:?}", result.map(|_| ())),
+    }
+    assert!(reader.read::<Message>().unwrap().is_none());
+
+    writer.join().unwrap();
+}
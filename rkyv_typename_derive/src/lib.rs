@@ -4,15 +4,22 @@ extern crate proc_macro;
 
 use proc_macro2::TokenStream;
 use quote::{quote, quote_spanned};
-use syn::{parse_macro_input, spanned::Spanned, AttrStyle, DeriveInput, Error, Lit, Meta};
+use syn::{
+    parse_macro_input, parse_quote, spanned::Spanned, AttrStyle, DeriveInput, Error, Lit, Meta,
+    NestedMeta, Path,
+};
 
 struct Attributes {
     typename: Option<String>,
+    crate_path: Option<Path>,
 }
 
 impl Default for Attributes {
     fn default() -> Self {
-        Self { typename: None }
+        Self {
+            typename: None,
+            crate_path: None,
+        }
     }
 }
 
@@ -20,8 +27,8 @@ fn parse_attributes(input: &DeriveInput) -> Result<Attributes, TokenStream> {
     let mut result = Attributes::default();
     for a in input.attrs.iter() {
         if let AttrStyle::Outer = a.style {
-            if let Ok(Meta::NameValue(meta)) = a.parse_meta() {
-                if meta.path.is_ident("typename") {
+            match a.parse_meta() {
+                Ok(Meta::NameValue(meta)) if meta.path.is_ident("typename") => {
                     if result.typename.is_none() {
                         if let Lit::Str(ref lit_str) = meta.lit {
                             result.typename = Some(lit_str.value());
@@ -40,6 +47,41 @@ fn parse_attributes(input: &DeriveInput) -> Result<Attributes, TokenStream> {
                         .to_compile_error());
                     }
                 }
+                Ok(Meta::List(list)) if list.path.is_ident("typename") => {
+                    for nested in list.nested.iter() {
+                        match nested {
+                            NestedMeta::Meta(Meta::NameValue(meta))
+                                if meta.path.is_ident("crate") =>
+                            {
+                                if result.crate_path.is_some() {
+                                    return Err(Error::new(
+                                        meta.span(),
+                                        "crate attribute already specified",
+                                    )
+                                    .to_compile_error());
+                                }
+                                if let Lit::Str(ref lit_str) = meta.lit {
+                                    result.crate_path =
+                                        Some(lit_str.parse().map_err(|e| e.to_compile_error())?);
+                                } else {
+                                    return Err(Error::new(
+                                        meta.lit.span(),
+                                        "crate must be set to a string",
+                                    )
+                                    .to_compile_error());
+                                }
+                            }
+                            _ => {
+                                return Err(Error::new(
+                                    nested.span(),
+                                    "unrecognized typename parameter",
+                                )
+                                .to_compile_error())
+                            }
+                        }
+                    }
+                }
+                _ => (),
             }
         }
     }
@@ -49,6 +91,10 @@ fn parse_attributes(input: &DeriveInput) -> Result<Attributes, TokenStream> {
 /// Derives `TypeName` for the labeled type.
 ///
 /// A custom name can be set using the attribute `#[typename = "..."]`.
+///
+/// The path to the `rkyv_typename` crate used by the generated code can be set with
+/// `#[typename(crate = "...")]`. This is useful when `rkyv_typename` is only available through a
+/// re-export.
 #[proc_macro_derive(TypeName, attributes(typename))]
 pub fn type_name_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
@@ -84,9 +130,13 @@ fn derive_type_name_impl(input: &DeriveInput) -> TokenStream {
         None => quote! {},
     };
 
+    let rkyv_typename = attributes
+        .crate_path
+        .unwrap_or_else(|| parse_quote! { rkyv_typename });
+
     let type_wheres = input.generics.type_params().map(|p| {
         let name = &p.ident;
-        quote_spanned! { name.span() => #name: rkyv_typename::TypeName }
+        quote_spanned! { name.span() => #name: #rkyv_typename::TypeName }
     });
 
     let name = &input.ident;
@@ -124,7 +174,7 @@ fn derive_type_name_impl(input: &DeriveInput) -> TokenStream {
 
     quote! {
         const _: () = {
-            use rkyv_typename::TypeName;
+            use #rkyv_typename::TypeName;
 
             impl<#(#generic_params,)*> TypeName for #name<#(#generic_args,)*>
             where